@@ -181,7 +181,7 @@ async fn main() -> Result<()> {
                                 _ => "(non-text result)".to_string(),
                             };
                             let truncated = if result_text.len() > 200 {
-                                format!("{}...", &result_text[..200])
+                                format!("{}...", picrust::helpers::truncate_bytes_safe(&result_text, 200))
                             } else {
                                 result_text
                             };
@@ -195,7 +195,7 @@ async fn main() -> Result<()> {
                                     _ => "(non-text result)".to_string(),
                                 };
                                 let truncated = if result_text.len() > 300 {
-                                    format!("{}...", &result_text[..300])
+                                    format!("{}...", picrust::helpers::truncate_bytes_safe(&result_text, 300))
                                 } else {
                                     result_text
                                 };
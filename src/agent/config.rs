@@ -4,10 +4,54 @@
 
 use std::sync::Arc;
 
-use crate::helpers::InjectionChain;
+use crate::helpers::{InjectionChain, LongLines, SystemTemplate};
 use crate::hooks::HookRegistry;
-use crate::llm::{LlmProvider, ThinkingConfig};
-use crate::tools::ToolRegistry;
+use crate::llm::{LlmProvider, Message, MessageRequest, ReasoningEffort, ThinkingConfig, ToolDefinition};
+use crate::tools::{ToolRegistry, ToolResult};
+
+/// Callback invoked with each text delta from the model
+pub type TextDeltaCallback = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// Transform applied to each assistant text block before it is shown and saved
+pub type ResponsePostprocessor = Arc<dyn Fn(String) -> String + Send + Sync>;
+
+/// Callback invoked when a tool call starts or finishes
+pub type ToolEventCallback = Arc<dyn Fn(&ToolEvent) + Send + Sync>;
+
+/// Callback invoked with the tool definitions sent on each LLM request
+///
+/// Receives the final definitions and the JSON the provider transmits for them.
+pub type ToolDefinitionsCallback = Arc<dyn Fn(&[ToolDefinition], &serde_json::Value) + Send + Sync>;
+
+/// Function that selects the tools exposed to the model for a request
+///
+/// Receives every registered tool definition and the conversation history.
+pub type ToolFilter = Arc<dyn Fn(&[ToolDefinition], &[Message]) -> Vec<ToolDefinition> + Send + Sync>;
+
+/// Function that returns the names of the tools allowed for a request
+///
+/// Receives the conversation history and the agent's tool registry.
+pub type AllowedToolsFn = Arc<dyn Fn(&[Message], Option<&ToolRegistry>) -> Vec<String> + Send + Sync>;
+
+/// Callback that can edit each LLM request right before it is sent
+pub type RequestInterceptor = Arc<dyn Fn(&mut MessageRequest) + Send + Sync>;
+
+/// Tool lifecycle event passed to [`AgentConfig::with_on_tool_event`]
+#[derive(Debug, Clone)]
+pub enum ToolEvent {
+    /// The model requested a tool call (fired before hooks and permission checks)
+    Start {
+        id: String,
+        name: String,
+        input: serde_json::Value,
+    },
+    /// The tool call finished (including denied or failed calls)
+    End {
+        id: String,
+        name: String,
+        result: ToolResult,
+    },
+}
 
 /// Configuration for a StandardAgent
 ///
@@ -24,7 +68,9 @@ use crate::tools::ToolRegistry;
 /// ```
 ///
 /// The system prompt is no longer part of AgentConfig — it lives in the session's
-/// `system_prompt.md` file and is passed to `AgentSession::new()`.
+/// `system_prompt.md` file and is passed to `AgentSession::new()`. A
+/// [`SystemTemplate`] set with `with_system_template()` keeps that file in sync
+/// with a prompt template, and `with_system_blocks()` with a list of blocks.
 pub struct AgentConfig {
     /// Tool registry (optional - agent can work without tools)
     pub tools: Option<Arc<ToolRegistry>>,
@@ -46,6 +92,7 @@ pub struct AgentConfig {
 
     /// Extended thinking configuration (optional)
     /// When enabled, Claude will show its step-by-step reasoning process.
+    /// Off by default, since thinking tokens are billed as output.
     pub thinking: Option<ThinkingConfig>,
 
     /// Hooks for intercepting agent behavior
@@ -102,6 +149,11 @@ pub struct AgentConfig {
     /// This can be changed at runtime via `AgentHandle::set_dangerous_skip_permissions()`.
     pub dangerous_skip_permissions: bool,
 
+    /// Ask once for all mutating tool calls in a response (default: false)
+    ///
+    /// See `with_batch_permissions`.
+    pub batch_permissions: bool,
+
     /// Turn retry configuration.
     ///
     /// When a turn fails due to a network/streaming error, the agent will retry
@@ -111,9 +163,101 @@ pub struct AgentConfig {
     ///
     /// **Default: 3 retries, 15 seconds between attempts**
     pub turn_retry: TurnRetryConfig,
+
+    /// Text to prefill the assistant's response with (optional)
+    ///
+    /// Sent as a trailing partial assistant message so the model continues from it
+    /// (e.g. `{` to force JSON). Only supported by the Anthropic provider and not
+    /// compatible with extended thinking; ignored with a warning otherwise.
+    pub response_prefill: Option<String>,
+
+    /// Consecutive identical tool call limit (optional)
+    ///
+    /// When the model calls the same tool with the same input this many times in
+    /// a row, the call is not executed again. The model gets the previous result
+    /// back with a note asking it to change approach.
+    pub tool_loop_guard: Option<usize>,
+
+    /// Callback for each text delta (optional)
+    ///
+    /// Lets embedders forward output to their own sink without subscribing to
+    /// the output channel or depending on the CLI module.
+    pub on_text_delta: Option<TextDeltaCallback>,
+
+    /// Transform for assistant text before it is shown and saved (optional)
+    ///
+    /// While set, streamed text is sent once per completed block instead of
+    /// delta by delta.
+    pub response_postprocessor: Option<ResponsePostprocessor>,
+
+    /// Callback for tool start/end events (optional)
+    pub on_tool_event: Option<ToolEventCallback>,
+
+    /// Callback to inspect tool definitions right before each request (optional)
+    pub on_tool_definitions: Option<ToolDefinitionsCallback>,
+
+    /// Whether to checkpoint streamed responses to disk while they arrive
+    ///
+    /// If the process dies mid-stream, the partial response can be recovered
+    /// from the session on resume. Only applies when streaming is enabled.
+    pub checkpoint_streaming: bool,
+
+    /// Per-request tool selection (optional)
+    ///
+    /// When set, only the tools it returns are sent to the model. Tools that
+    /// are filtered out stay registered and can still be executed.
+    pub tool_filter: Option<ToolFilter>,
+
+    /// Per-request tool allow-list (optional)
+    ///
+    /// Unlike `tool_filter`, this is enforced: calls to tools outside the
+    /// list are rejected with an error result instead of being executed.
+    pub allowed_tools: Option<AllowedToolsFn>,
+
+    /// Whether to send compacted tool definitions
+    ///
+    /// Strips schema examples and collapses whitespace in descriptions. See
+    /// [`compact_tool_definitions`](crate::llm::compact_tool_definitions).
+    pub compact_tool_schemas: bool,
+
+    /// Whether to cut long tool descriptions once the model has seen them
+    ///
+    /// Only applies with `compact_tool_schemas`. The first request of a
+    /// session sends full descriptions; later requests send the first
+    /// sentence of any description over 200 characters.
+    pub trim_seen_tool_descriptions: bool,
+
+    /// End-user ID sent with each LLM request for attribution (optional)
+    ///
+    /// Sent as `metadata.user_id` to Anthropic and `user` to OpenAI.
+    pub user_id: Option<String>,
+
+    /// Callback to edit each request right before it is sent (optional)
+    pub request_interceptor: Option<RequestInterceptor>,
+
+    /// How long lines in attached text files are shown (default: truncate the end)
+    pub attachment_long_lines: LongLines,
+
+    /// Message written as the first user turn of a new session (optional)
+    pub session_preamble: Option<String>,
+
+    /// Template rendered into the session's system prompt each turn (optional)
+    pub system_template: Option<SystemTemplate>,
+
+    /// System prompt sent as separate blocks, each with a cache flag (optional)
+    pub system_blocks: Option<Vec<(String, bool)>>,
+
+    /// Most messages a single request may carry (optional)
+    pub max_request_messages: Option<usize>,
+
+    /// Largest serialized size in bytes a single request may have (optional)
+    pub max_request_bytes: Option<usize>,
 }
 
 /// Configuration for automatic turn retries on transient errors.
+///
+/// A retried turn sends new LLM requests with new idempotency keys, so a
+/// request that failed on the client but reached the provider may run twice.
 #[derive(Debug, Clone)]
 pub struct TurnRetryConfig {
     /// Whether retry is enabled. Default: true
@@ -144,7 +288,27 @@ impl AgentConfig {
             naming_llm: None,
             hook_short_circuit: false, // Safe default: all hooks run
             dangerous_skip_permissions: false, // Safe default: permissions enforced
+            batch_permissions: false,
             turn_retry: TurnRetryConfig::default(),
+            response_prefill: None,
+            tool_loop_guard: None,
+            on_text_delta: None,
+            response_postprocessor: None,
+            on_tool_event: None,
+            on_tool_definitions: None,
+            checkpoint_streaming: false,
+            tool_filter: None,
+            allowed_tools: None,
+            compact_tool_schemas: false,
+            trim_seen_tool_descriptions: false,
+            user_id: None,
+            request_interceptor: None,
+            attachment_long_lines: LongLines::default(),
+            session_preamble: None,
+            system_template: None,
+            system_blocks: None,
+            max_request_messages: None,
+            max_request_bytes: None,
         }
     }
 
@@ -169,6 +333,24 @@ impl AgentConfig {
         self
     }
 
+    /// Add an injection with a priority (higher runs first and wins the budget)
+    pub fn with_injection_priority<I: crate::helpers::ContextInjection + 'static>(
+        mut self,
+        injection: I,
+        priority: i32,
+    ) -> Self {
+        self.injections.add_with_priority(injection, priority);
+        self
+    }
+
+    /// Limit the total bytes of text the injections may add per LLM call
+    ///
+    /// Lower-priority injections that would exceed the budget are skipped.
+    pub fn with_injection_budget(mut self, max_bytes: usize) -> Self {
+        self.injections.set_budget(Some(max_bytes));
+        self
+    }
+
     /// Add a function-based injection
     pub fn with_injection_fn<F>(mut self, name: impl Into<String>, func: F) -> Self
     where
@@ -232,6 +414,15 @@ impl AgentConfig {
         self
     }
 
+    /// Enable thinking at a provider-neutral effort level
+    ///
+    /// Shorthand for `with_thinking(effort.budget_tokens())`; see
+    /// [`ReasoningEffort`] for the budgets.
+    pub fn with_reasoning_effort(mut self, effort: ReasoningEffort) -> Self {
+        self.thinking = Some(ThinkingConfig::with_effort(effort));
+        self
+    }
+
     /// Set the hook registry for intercepting agent behavior
     ///
     /// Hooks allow you to:
@@ -362,6 +553,20 @@ impl AgentConfig {
         self
     }
 
+    /// Ask for permission once per response instead of once per tool call
+    ///
+    /// **Default: false** (each call gets its own `PermissionRequest`)
+    ///
+    /// When a response contains two or more mutating (not read-only) tool
+    /// calls that no rule allows or denies, the agent sends a single
+    /// `OutputChunk::BatchPermissionRequest` listing them all, and waits for
+    /// an `InputMessage::BatchPermissionResponse` naming the approved calls.
+    /// Calls left out are denied. Other calls are asked about as usual.
+    pub fn with_batch_permissions(mut self, enabled: bool) -> Self {
+        self.batch_permissions = enabled;
+        self
+    }
+
     /// Configure turn retry behavior for transient errors
     ///
     /// When a turn fails due to a network/streaming error, the agent will retry
@@ -383,6 +588,355 @@ impl AgentConfig {
         self
     }
 
+    /// Prefill the start of every assistant response
+    ///
+    /// The model continues from the given text, which is then included at the
+    /// start of the response text shown to subscribers and saved in history.
+    /// Trailing whitespace is trimmed since the API rejects it.
+    ///
+    /// Only the Anthropic provider supports prefill, and it cannot be combined
+    /// with extended thinking. In other cases it is skipped with a warning.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // Force a JSON object response
+    /// let config = AgentConfig::new().with_response_prefill("{");
+    /// ```
+    pub fn with_response_prefill(mut self, prefill: impl Into<String>) -> Self {
+        self.response_prefill = Some(prefill.into());
+        self
+    }
+
+    /// Guard against the model repeating the exact same tool call
+    ///
+    /// After `n` consecutive calls with the same tool name and input (minimum 2),
+    /// further repeats are short-circuited: the tool is not executed and the
+    /// previous result is returned with a note telling the model it is looping
+    /// and that the result may be out of date.
+    /// This breaks read loops well before `max_tool_iterations` is hit.
+    pub fn with_tool_loop_guard(mut self, n: usize) -> Self {
+        self.tool_loop_guard = Some(n.max(2));
+        self
+    }
+
+    /// Set a callback invoked with each text delta from the model
+    ///
+    /// With streaming enabled this fires for every delta as it arrives; without
+    /// streaming it fires once per text block. The callback runs inline in the
+    /// agent loop, so it should return quickly.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let config = AgentConfig::new()
+    ///     .with_streaming(true)
+    ///     .with_on_text_delta(move |delta| {
+    ///         let _ = sse_tx.send(delta.to_string());
+    ///     });
+    /// ```
+    pub fn with_on_text_delta<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        self.on_text_delta = Some(Arc::new(callback));
+        self
+    }
+
+    /// Transform each assistant text block before it is shown and saved
+    ///
+    /// Runs on the complete text of every text block, e.g. to strip markers,
+    /// cap the length or append a footer. The result is what subscribers,
+    /// `on_text_delta` and the session history see. Text filter hooks
+    /// (`AssistantTextDelta`) still see the model's raw output.
+    ///
+    /// A block can only be transformed once it is complete, so with streaming
+    /// enabled each text block arrives as a single delta rather than token by
+    /// token. A block transformed to an empty string is dropped. Thinking and
+    /// tool calls are not affected.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let config = AgentConfig::new()
+    ///     .with_response_postprocessor(|text| text.replace("<scratch>", ""));
+    /// ```
+    pub fn with_response_postprocessor<F>(mut self, postprocessor: F) -> Self
+    where
+        F: Fn(String) -> String + Send + Sync + 'static,
+    {
+        self.response_postprocessor = Some(Arc::new(postprocessor));
+        self
+    }
+
+    /// Set a callback invoked when each tool call starts and ends
+    pub fn with_on_tool_event<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&ToolEvent) + Send + Sync + 'static,
+    {
+        self.on_tool_event = Some(Arc::new(callback));
+        self
+    }
+
+    /// Set a callback invoked with the tool definitions sent on each LLM request
+    ///
+    /// The callback sees the definitions after cache control is applied, along
+    /// with the provider's wire-format JSON for them. Useful for debugging a
+    /// tool the model ignores, e.g. because of a malformed schema.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let config = AgentConfig::new()
+    ///     .with_on_tool_definitions(|_defs, json| {
+    ///         tracing::debug!("tools sent: {}", json);
+    ///     });
+    /// ```
+    pub fn with_on_tool_definitions<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&[ToolDefinition], &serde_json::Value) + Send + Sync + 'static,
+    {
+        self.on_tool_definitions = Some(Arc::new(callback));
+        self
+    }
+
+    /// Enable or disable checkpointing of streamed responses
+    ///
+    /// While a response streams, the completed blocks and partial text are
+    /// saved to the session directory (at most about once a second) and removed
+    /// when the response finishes. After a crash, use
+    /// `AgentSession::recover_interrupted_response()` or
+    /// `AgentSession::discard_interrupted_response()` on resume.
+    pub fn with_streaming_checkpoints(mut self, enabled: bool) -> Self {
+        self.checkpoint_streaming = enabled;
+        self
+    }
+
+    /// Set a filter that picks which tools to expose on each LLM request
+    ///
+    /// The filter runs before every request with all registered tool
+    /// definitions and the current history. Changing the tool set between
+    /// requests invalidates the prompt cache, so prefer filters that are
+    /// stable across a turn.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let in_repo = std::path::Path::new(".git").exists();
+    /// let config = AgentConfig::new()
+    ///     .with_tools(tools)
+    ///     .with_tool_filter(move |tools, _history| {
+    ///         tools
+    ///             .iter()
+    ///             .filter(|t| in_repo || !t.name().starts_with("git_"))
+    ///             .cloned()
+    ///             .collect()
+    ///     });
+    /// ```
+    pub fn with_tool_filter<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(&[ToolDefinition], &[Message]) -> Vec<ToolDefinition> + Send + Sync + 'static,
+    {
+        self.tool_filter = Some(Arc::new(filter));
+        self
+    }
+
+    /// Restrict each LLM request to the tools named by `allowed`
+    ///
+    /// The function runs before every request with the current history. Only
+    /// the named tools are sent to the model, and if the model calls any other
+    /// tool anyway, the call is not executed; the model gets an error listing
+    /// the tools it may use. Applied after [`with_tool_filter`](Self::with_tool_filter).
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // Read-only until the user approves the plan
+    /// let config = AgentConfig::new()
+    ///     .with_tools(tools)
+    ///     .with_allowed_tools_per_turn(|history| {
+    ///         if plan_approved(history) {
+    ///             vec!["Read".into(), "Edit".into(), "Bash".into()]
+    ///         } else {
+    ///             vec!["Read".into(), "Glob".into(), "Grep".into()]
+    ///         }
+    ///     });
+    /// ```
+    pub fn with_allowed_tools_per_turn<F>(mut self, allowed: F) -> Self
+    where
+        F: Fn(&[Message]) -> Vec<String> + Send + Sync + 'static,
+    {
+        self.allowed_tools = Some(Arc::new(move |history, _| allowed(history)));
+        self
+    }
+
+    /// Send compacted tool definitions to reduce input tokens
+    ///
+    /// Schema `examples`, `example` and `$comment` keywords are removed and
+    /// whitespace in descriptions is collapsed. The tools behave the same;
+    /// only the definitions sent to the model change. Savings depend on the
+    /// tools: the built-in tools are already terse (~2,310 tokens, under 1%
+    /// saved), while tools with schema examples or indented multi-line
+    /// descriptions save much more.
+    pub fn with_compact_tool_schemas(mut self, enabled: bool) -> Self {
+        self.compact_tool_schemas = enabled;
+        self
+    }
+
+    /// Also cut long tool descriptions after the first request of a session
+    ///
+    /// Once the model has seen the full descriptions, later requests send only
+    /// the first sentence of descriptions over 200 characters. For the
+    /// built-in tools this cuts the definitions from ~2,310 to ~1,860 tokens
+    /// (about 20%) on every later request, but changes the tool definitions once, which invalidates the
+    /// prompt cache for that request. Enables
+    /// [`with_compact_tool_schemas`](Self::with_compact_tool_schemas).
+    pub fn with_trimmed_tool_descriptions(mut self, enabled: bool) -> Self {
+        self.trim_seen_tool_descriptions = enabled;
+        if enabled {
+            self.compact_tool_schemas = true;
+        }
+        self
+    }
+
+    /// Set the end-user ID sent with each LLM request
+    ///
+    /// Anthropic recommends this for abuse monitoring. Use an opaque value such
+    /// as a UUID or hash; don't send names, emails, or other personal data.
+    pub fn with_user_id(mut self, user_id: impl Into<String>) -> Self {
+        self.user_id = Some(user_id.into());
+        self
+    }
+
+    /// Set a callback that can edit each LLM request right before it is sent
+    ///
+    /// The callback gets the fully built request: history with injections and
+    /// prefill applied, system prompt, tools and thinking config, all with
+    /// cache control already set. Changes to `messages`, `system`, `tools`,
+    /// `tool_choice`, `thinking` and `metadata` are sent as is. `model` and
+    /// `max_tokens` are informational (`max_tokens` is 0, meaning the
+    /// provider's default); changes to them, `temperature` and `stream` are
+    /// ignored. Nothing is validated, so an edited request can fail at the API.
+    ///
+    /// The history stored in the session is not affected.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // Hide the Bash tool once the conversation gets long
+    /// let config = AgentConfig::new()
+    ///     .with_tools(tools)
+    ///     .with_request_interceptor(|request| {
+    ///         if request.messages.len() > 40 {
+    ///             if let Some(ref mut tools) = request.tools {
+    ///                 tools.retain(|t| t.name() != "Bash");
+    ///             }
+    ///         }
+    ///     });
+    /// ```
+    pub fn with_request_interceptor<F>(mut self, interceptor: F) -> Self
+    where
+        F: Fn(&mut MessageRequest) + Send + Sync + 'static,
+    {
+        self.request_interceptor = Some(Arc::new(interceptor));
+        self
+    }
+
+    /// Set how lines over 2000 characters in attached text files are shown
+    ///
+    /// Use `ReadTool::with_long_lines` for the same choice in the Read tool.
+    pub fn with_attachment_long_lines(mut self, long_lines: LongLines) -> Self {
+        self.attachment_long_lines = long_lines;
+        self
+    }
+
+    /// Seed new sessions with a one-time message (e.g. project onboarding context)
+    ///
+    /// When the first message of a new session is sent, the preamble is added
+    /// to it as a leading text block. It is part of the history from then on,
+    /// so it is sent with every request but never added again, including when
+    /// the session is resumed. Unlike the system prompt it is part of a user
+    /// turn, and unlike injections it is not recomputed per request. Conversation
+    /// naming and `ReplayHarness` leave it out of what the user said.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let onboarding = std::fs::read_to_string("docs/ONBOARDING.md")?;
+    /// let config = AgentConfig::new().with_session_preamble(onboarding);
+    /// ```
+    pub fn with_session_preamble(mut self, preamble: impl Into<String>) -> Self {
+        self.session_preamble = Some(preamble.into());
+        self
+    }
+
+    /// Render the system prompt from a `PromptLibrary` template
+    ///
+    /// At the start of each turn the template is rendered and, if the result
+    /// differs, saved as the session's system prompt. With a hot-reloading
+    /// library, edits to the template file apply from the next turn. If the
+    /// template fails to render, the previous system prompt is kept.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let prompts = Arc::new(PromptLibrary::hot_reload("prompts"));
+    /// let config = AgentConfig::new().with_system_template(
+    ///     SystemTemplate::new(prompts, "system").with_var("project", "picrust"),
+    /// );
+    /// ```
+    pub fn with_system_template(mut self, template: SystemTemplate) -> Self {
+        self.system_template = Some(template);
+        self
+    }
+
+    /// Send the system prompt as several blocks, caching after the flagged ones
+    ///
+    /// Put stable instructions first and flag them `true`, and dynamic text
+    /// (dates, user details) after them, so changing the tail doesn't invalidate
+    /// the cached prefix. With prompt caching enabled, each flagged block gets a
+    /// cache breakpoint; only the first two are used, as the tools and the last
+    /// message take two of the four a request may have.
+    ///
+    /// The blocks replace the session's system prompt in requests, and their
+    /// text, joined by blank lines, is saved as the session's system prompt at
+    /// the start of each turn. Takes precedence over `with_system_template()`.
+    /// A prompt set with `AgentHandle::set_system_prompt` is sent instead
+    /// until the blocks change.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let config = AgentConfig::new().with_system_blocks(vec![
+    ///     (INSTRUCTIONS.to_string(), true),
+    ///     (format!("Today is {}.", today), false),
+    /// ]);
+    /// ```
+    pub fn with_system_blocks<S: Into<String>>(mut self, blocks: Vec<(S, bool)>) -> Self {
+        self.system_blocks = Some(blocks.into_iter().map(|(text, cache)| (text.into(), cache)).collect());
+        self
+    }
+
+    /// Refuse to send requests with more than `max` messages
+    ///
+    /// Checked after injections and the request interceptor, right before
+    /// the network call. A request over the limit fails the turn with an
+    /// error naming the limit instead of reaching the API.
+    pub fn with_max_request_messages(mut self, max: usize) -> Self {
+        self.max_request_messages = Some(max);
+        self
+    }
+
+    /// Refuse to send requests larger than `max` bytes
+    ///
+    /// The size is that of the request serialized as JSON (messages, system
+    /// prompt and tools), which is close to what goes over the wire. Checked
+    /// like [`with_max_request_messages`](Self::with_max_request_messages).
+    pub fn with_max_request_bytes(mut self, max: usize) -> Self {
+        self.max_request_bytes = Some(max);
+        self
+    }
+
     /// Get tool definitions (empty vec if no tools)
     pub fn tool_definitions(&self) -> Vec<crate::llm::ToolDefinition> {
         self.tools
@@ -423,7 +977,27 @@ impl std::fmt::Debug for AgentConfig {
             .field("naming_llm", &self.naming_llm.as_ref().map(|l| l.model()))
             .field("hook_short_circuit", &self.hook_short_circuit)
             .field("dangerous_skip_permissions", &self.dangerous_skip_permissions)
+            .field("batch_permissions", &self.batch_permissions)
             .field("turn_retry", &self.turn_retry)
+            .field("response_prefill", &self.response_prefill)
+            .field("tool_loop_guard", &self.tool_loop_guard)
+            .field("on_text_delta", &self.on_text_delta.is_some())
+            .field("response_postprocessor", &self.response_postprocessor.is_some())
+            .field("on_tool_event", &self.on_tool_event.is_some())
+            .field("on_tool_definitions", &self.on_tool_definitions.is_some())
+            .field("checkpoint_streaming", &self.checkpoint_streaming)
+            .field("tool_filter", &self.tool_filter.is_some())
+            .field("allowed_tools", &self.allowed_tools.is_some())
+            .field("compact_tool_schemas", &self.compact_tool_schemas)
+            .field("trim_seen_tool_descriptions", &self.trim_seen_tool_descriptions)
+            .field("user_id", &self.user_id)
+            .field("request_interceptor", &self.request_interceptor.is_some())
+            .field("attachment_long_lines", &self.attachment_long_lines)
+            .field("session_preamble", &self.session_preamble.is_some())
+            .field("system_template", &self.system_template.as_ref().map(|t| &t.name))
+            .field("system_blocks", &self.system_blocks.as_ref().map(|b| b.len()))
+            .field("max_request_messages", &self.max_request_messages)
+            .field("max_request_bytes", &self.max_request_bytes)
             .finish()
     }
 }
@@ -441,9 +1015,61 @@ mod tests {
         assert_eq!(config.max_tool_iterations, 100);
     }
 
+    #[test]
+    fn test_agent_config_thinking() {
+        assert!(AgentConfig::new().thinking.is_none());
+
+        let config = AgentConfig::new().with_reasoning_effort(ReasoningEffort::Medium);
+        assert_eq!(config.thinking.unwrap().budget_tokens, 8192);
+    }
+
     #[test]
     fn test_agent_config_with_debug() {
         let config = AgentConfig::new().with_debug(true);
         assert!(config.debug_enabled);
     }
+
+    #[test]
+    fn test_agent_config_with_tool_filter() {
+        use crate::llm::define_tool;
+
+        let config = AgentConfig::new().with_tool_filter(|tools, _history| {
+            tools
+                .iter()
+                .filter(|t| !t.name().starts_with("git_"))
+                .cloned()
+                .collect()
+        });
+
+        let tools = vec![
+            define_tool("Read", "Read a file", serde_json::json!({}), vec![]),
+            define_tool("git_status", "Show git status", serde_json::json!({}), vec![]),
+        ];
+        let filter = config.tool_filter.as_ref().unwrap();
+        let selected = filter(&tools, &[]);
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].name(), "Read");
+    }
+
+    #[test]
+    fn test_agent_config_with_allowed_tools_per_turn() {
+        let config = AgentConfig::new().with_allowed_tools_per_turn(|history| {
+            if history.is_empty() {
+                vec!["Read".to_string()]
+            } else {
+                vec!["Read".to_string(), "Edit".to_string()]
+            }
+        });
+
+        let allowed = config.allowed_tools.as_ref().unwrap();
+        assert_eq!(allowed(&[], None), vec!["Read".to_string()]);
+        assert_eq!(allowed(&[Message::user("Go ahead")], None).len(), 2);
+    }
+
+    #[test]
+    fn test_agent_config_trimmed_tool_descriptions_enable_compaction() {
+        let config = AgentConfig::new().with_trimmed_tool_descriptions(true);
+        assert!(config.compact_tool_schemas);
+        assert!(config.trim_seen_tool_descriptions);
+    }
 }
@@ -2,15 +2,35 @@
 //!
 //! Handles permission-aware tool execution with optional debug logging and hooks.
 
+use std::collections::HashMap;
+
 use serde_json::Value;
 
-use crate::core::InputMessage;
+use crate::core::{InputMessage, OutputChunk, PendingToolCall};
 use crate::helpers::Debugger;
 use crate::hooks::{HookContext, HookRegistry, PermissionDecision};
 use crate::permissions::{CheckResult, PermissionRule, PermissionScope};
 use crate::runtime::AgentInternals;
 use crate::tools::{ToolRegistry, ToolResult};
 
+/// A tool call after its PreToolUse hooks have run
+#[derive(Debug, Clone)]
+pub struct HookedToolCall {
+    /// The input to run the tool with, as rewritten by the hooks
+    pub input: Value,
+    /// The hooks' permission decision, if any
+    pub decision: Option<PermissionDecision>,
+    /// Reason given with the decision
+    pub reason: Option<String>,
+}
+
+impl HookedToolCall {
+    /// Whether the hooks left the decision to the permission system
+    pub fn needs_permission(&self) -> bool {
+        matches!(self.decision, Some(PermissionDecision::Ask) | None)
+    }
+}
+
 /// Handles tool execution with permission checking and hooks
 pub struct ToolExecutor;
 
@@ -24,6 +44,10 @@ impl ToolExecutor {
     /// 4. Wait for response
     /// 5. Execute if allowed, return error if denied
     /// 6. Run PostToolUse or PostToolUseFailure hooks
+    ///
+    /// Messages added by PreToolUse hooks (`HookResult::with_message`) are
+    /// appended to `hook_messages`, whatever the hooks decided.
+    #[allow(clippy::too_many_arguments)]
     pub async fn execute_with_permission(
         internals: &mut AgentInternals,
         tools: &ToolRegistry,
@@ -32,64 +56,86 @@ impl ToolExecutor {
         tool_id: &str,
         input: &Value,
         hook_short_circuit: bool,
+        hook_messages: &mut Vec<String>,
     ) -> ToolResult {
-        let mut current_input = input.clone();
+        let call = Self::run_pre_tool_use(internals, hooks, tool_name, tool_id, input, hook_short_circuit, hook_messages);
+        Self::execute_hooked(internals, tools, hooks, tool_name, tool_id, call, hook_short_circuit, None).await
+    }
 
-        // === Run PreToolUse hooks ===
-        if let Some(hooks) = hooks {
-            let mut ctx = HookContext::pre_tool_use(
-                internals,
-                tool_name,
-                &current_input,
-                tool_id,
-                hook_short_circuit,
-            );
-            let result = hooks.run(&mut ctx);
+    /// Run the PreToolUse hooks for a call (step 1 of `execute_with_permission`)
+    pub fn run_pre_tool_use(
+        internals: &mut AgentInternals,
+        hooks: Option<&HookRegistry>,
+        tool_name: &str,
+        tool_id: &str,
+        input: &Value,
+        hook_short_circuit: bool,
+        hook_messages: &mut Vec<String>,
+    ) -> HookedToolCall {
+        let Some(hooks) = hooks else {
+            return HookedToolCall {
+                input: input.clone(),
+                decision: None,
+                reason: None,
+            };
+        };
 
+        let mut ctx = HookContext::pre_tool_use(internals, tool_name, input, tool_id, hook_short_circuit);
+        let mut result = hooks.run(&mut ctx);
+        hook_messages.append(&mut result.messages);
+
+        HookedToolCall {
             // Hook may have modified tool_input
-            if let Some(modified_input) = ctx.tool_input {
-                current_input = modified_input;
-            }
+            input: ctx.tool_input.unwrap_or_else(|| input.clone()),
+            decision: result.decision,
+            reason: result.reason,
+        }
+    }
 
-            // Handle permission decision from hooks
-            match result.decision {
-                Some(PermissionDecision::Deny) => {
-                    let reason = result
-                        .reason
-                        .unwrap_or_else(|| "Blocked by hook".to_string());
-                    tracing::info!("[Executor] Hook denied {}: {}", tool_name, reason);
-                    return ToolResult::error(format!("Hook denied: {}", reason));
-                }
-                Some(PermissionDecision::Allow) => {
-                    // Skip permission check, execute directly
-                    tracing::info!("[Executor] Hook allowed {} (skipping permission check)", tool_name);
-                    return Self::execute_with_hooks(
-                        internals,
-                        tools,
-                        Some(hooks),
-                        tool_name,
-                        tool_id,
-                        &current_input,
-                        hook_short_circuit,
-                    )
-                    .await;
-                }
-                Some(PermissionDecision::Ask) | None => {
-                    // Fall through to normal permission check
-                }
+    /// Execute a call whose PreToolUse hooks have already run (steps 2-6 of `execute_with_permission`)
+    ///
+    /// `batch_decision` is the user's answer from `ask_batch_permission`, used
+    /// instead of asking again when no rule decides the call.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn execute_hooked(
+        internals: &mut AgentInternals,
+        tools: &ToolRegistry,
+        hooks: Option<&HookRegistry>,
+        tool_name: &str,
+        tool_id: &str,
+        call: HookedToolCall,
+        hook_short_circuit: bool,
+        batch_decision: Option<bool>,
+    ) -> ToolResult {
+        let current_input = call.input;
+
+        // Handle permission decision from hooks
+        match call.decision {
+            Some(PermissionDecision::Deny) => {
+                let reason = call.reason.unwrap_or_else(|| "Blocked by hook".to_string());
+                tracing::info!("[Executor] Hook denied {}: {}", tool_name, reason);
+                return ToolResult::error(format!("Hook denied: {}", reason));
+            }
+            Some(PermissionDecision::Allow) => {
+                // Skip permission check, execute directly
+                tracing::info!("[Executor] Hook allowed {} (skipping permission check)", tool_name);
+                return Self::execute_with_hooks(
+                    internals,
+                    tools,
+                    hooks,
+                    tool_name,
+                    tool_id,
+                    &current_input,
+                    hook_short_circuit,
+                )
+                .await;
+            }
+            Some(PermissionDecision::Ask) | None => {
+                // Fall through to normal permission check
             }
         }
 
-        // Check if dangerous_skip_permissions is enabled (from session metadata)
-        let should_skip_permissions = {
-            let session = internals.session.read().await;
-            session
-                .get_custom("dangerous_skip_permissions")
-                .and_then(|v| v.as_bool())
-                .unwrap_or(false)
-        };
-
-        if should_skip_permissions {
+        if Self::skips_permissions(internals).await {
             tracing::warn!(
                 "[Executor] DANGEROUS: Skipping permission check for {} (dangerous_skip_permissions enabled)",
                 tool_name
@@ -136,6 +182,25 @@ impl ToolExecutor {
                 ToolResult::error(format!("Permission denied for tool: {}", tool_name))
             }
 
+            CheckResult::AskUser if batch_decision == Some(false) => {
+                tracing::info!("[Executor] User denied {} in batch", tool_name);
+                ToolResult::error(format!("User denied permission for: {}", tool_name))
+            }
+
+            CheckResult::AskUser if batch_decision == Some(true) => {
+                tracing::info!("[Executor] User allowed {} in batch", tool_name);
+                Self::execute_with_hooks(
+                    internals,
+                    tools,
+                    hooks,
+                    tool_name,
+                    tool_id,
+                    &current_input,
+                    hook_short_circuit,
+                )
+                .await
+            }
+
             CheckResult::AskUser => {
                 tracing::info!("[Executor] Asking user for permission: {}", tool_name);
                 Self::ask_and_execute(
@@ -154,6 +219,93 @@ impl ToolExecutor {
         }
     }
 
+    /// Whether dangerous_skip_permissions is enabled (from session metadata)
+    async fn skips_permissions(internals: &AgentInternals) -> bool {
+        let session = internals.session.read().await;
+        session
+            .get_custom("dangerous_skip_permissions")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Ask once about every mutating call in `calls` that no rule decides
+    ///
+    /// `calls` are `(id, name, input)` from one response, with the input as
+    /// rewritten by the PreToolUse hooks; leave out calls the hooks decided.
+    /// When at least two of them need asking, sends a single
+    /// `BatchPermissionRequest` and returns the user's decision for each
+    /// asked call by tool use ID; otherwise returns an empty map and the calls
+    /// are asked about one by one. If the wait ends without an answer, each
+    /// asked call maps to the error to give it instead.
+    pub async fn ask_batch_permission(
+        internals: &mut AgentInternals,
+        tools: &ToolRegistry,
+        calls: &[(&str, &str, &Value)],
+    ) -> HashMap<String, Result<bool, ToolResult>> {
+        if Self::skips_permissions(internals).await {
+            return HashMap::new();
+        }
+
+        let pending: Vec<PendingToolCall> = calls
+            .iter()
+            .filter(|(_, name, input)| {
+                !tools.is_read_only(name)
+                    && matches!(internals.check_permission(name, &input.to_string()), CheckResult::AskUser)
+            })
+            .map(|(id, name, input)| {
+                let tool_info = tools.get_tool_info(name, input);
+                PendingToolCall {
+                    id: id.to_string(),
+                    tool_name: name.to_string(),
+                    action: tool_info
+                        .as_ref()
+                        .map(|i| i.action_description.clone())
+                        .unwrap_or_else(|| format!("Execute {}", name)),
+                    input: input.to_string(),
+                    details: tool_info.and_then(|i| i.details),
+                }
+            })
+            .collect();
+        if pending.len() < 2 {
+            return HashMap::new();
+        }
+
+        tracing::info!("[Executor] Asking user for permission for {} tool calls", pending.len());
+        let ids: Vec<String> = pending.iter().map(|call| call.id.clone()).collect();
+        internals.send(OutputChunk::BatchPermissionRequest { calls: pending });
+        internals.set_waiting_for_permission().await;
+
+        let stopped = match internals.receive().await {
+            Some(InputMessage::BatchPermissionResponse { approved }) => {
+                tracing::info!("[Executor] User approved {} of {} tool calls", approved.len(), ids.len());
+                return ids
+                    .into_iter()
+                    .map(|id| {
+                        let allowed = approved.contains(&id);
+                        (id, Ok(allowed))
+                    })
+                    .collect();
+            }
+            Some(InputMessage::Interrupt) => {
+                tracing::info!("[Executor] Interrupted while waiting for batch permission");
+                ToolResult::error("Interrupted")
+            }
+            Some(InputMessage::Shutdown) => {
+                tracing::info!("[Executor] Shutdown while waiting for batch permission");
+                ToolResult::error("Shutdown")
+            }
+            None => {
+                tracing::info!("[Executor] Channel closed while waiting for batch permission");
+                ToolResult::error("Channel closed")
+            }
+            _ => {
+                tracing::warn!("[Executor] Unexpected message while waiting for batch permission");
+                ToolResult::error("Unexpected message during permission request")
+            }
+        };
+        ids.into_iter().map(|id| (id, Err(stopped.clone()))).collect()
+    }
+
     /// Ask user for permission and execute if granted
     async fn ask_and_execute(
         internals: &mut AgentInternals,
@@ -31,6 +31,9 @@ mod config;
 mod executor;
 mod standard_loop;
 
-pub use config::{AgentConfig, TurnRetryConfig};
-pub use executor::ToolExecutor;
+pub use config::{
+    AgentConfig, AllowedToolsFn, RequestInterceptor, ResponsePostprocessor, TextDeltaCallback,
+    ToolDefinitionsCallback, ToolEvent, ToolEventCallback, ToolFilter, TurnRetryConfig,
+};
+pub use executor::{HookedToolCall, ToolExecutor};
 pub use standard_loop::StandardAgent;
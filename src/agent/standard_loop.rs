@@ -8,24 +8,64 @@
 //! - Streaming responses (when enabled)
 //! - Automatic conversation naming (after first turn)
 
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
 use futures::StreamExt;
 use serde_json::Value;
 
-use crate::core::{FrameworkResult, InputMessage};
-use crate::helpers::{process_attachments, ConversationNamer, Debugger};
-use crate::hooks::HookContext;
+use crate::core::{FrameworkResult, InputMessage, OutputChunk, TurnEndReason, TurnOutcome};
+use crate::helpers::{
+    process_attachments_with, session_preamble_block, truncate_bytes_safe, ConversationNamer, Debugger,
+    TodoListManager,
+};
+use crate::hooks::{HookContext, HookEvent, PermissionDecision};
 use crate::llm::{
-    CacheControl, ContentBlock, ContentBlockStart, ContentDelta, LlmProvider, Message,
-    StopReason, StreamEvent, SystemBlock, SystemPrompt,
+    collect::PartialBlock, compact_tool_definitions, estimate_tool_tokens, CacheControl, ContentBlock,
+    ContentDelta, DeltaUsage, GuardedStream, LlmProvider, Message, MessageContent, MessageRequest, RequestMetadata,
+    RequestOptions, StopReason, StreamEvent, SystemBlock, SystemPrompt, ToolInputError, Usage,
 };
 use crate::runtime::AgentInternals;
+use crate::session::{AgentSession, SessionStorage};
 use crate::tools::{ToolResult, ToolResultData};
 
-use super::config::AgentConfig;
-use super::executor::ToolExecutor;
+use super::config::{AgentConfig, ToolEvent};
+use super::executor::{HookedToolCall, ToolExecutor};
+
+/// Cache breakpoints allowed on system blocks
+///
+/// Anthropic accepts four per request; the tools and the last message use two.
+const MAX_SYSTEM_CACHE_BREAKPOINTS: usize = 2;
+
+/// Minimum time between checkpoints of a response that is still streaming
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// System prompt for `StandardAgent::summarize_session`
+const SUMMARY_SYSTEM_PROMPT: &str = "You summarize agent sessions for handoff. The text that follows is the \
+conversation between a user and an AI agent, including the tools the agent used. Write one paragraph \
+(at most 5 sentences) covering what the user asked for, what the agent did, and the outcome, including \
+anything left unfinished. Respond with ONLY the summary.";
+
+/// Longest transcript `summarize_session` sends, in bytes (about 25k tokens)
+const MAX_SUMMARY_TRANSCRIPT_BYTES: usize = 100_000;
+
+/// Malformed streamed tool inputs, by tool use ID
+type ToolInputErrors = HashMap<String, ToolInputError>;
+
+/// Response from one LLM call
+struct LlmCall {
+    content_blocks: Vec<ContentBlock>,
+    stop_reason: Option<StopReason>,
+    usage: Usage,
+    /// Malformed streamed tool inputs
+    input_errors: ToolInputErrors,
+    /// Why an `AssistantTextDelta` hook stopped the response, if one did
+    filtered: Option<String>,
+}
 
 /// Standard agent that handles the full agent loop
 ///
@@ -57,7 +97,47 @@ impl StandardAgent {
     /// This is the main entry point - pass this to `runtime.spawn()`.
     pub async fn run(self, mut internals: AgentInternals) -> FrameworkResult<()> {
         tracing::info!("[StandardAgent] Started, waiting for input...");
+        self.start(&mut internals).await;
+
+        // Signal we're ready for input
+        internals.set_idle().await;
+
+        loop {
+            // Wait for next message
+            match internals.receive().await {
+                Some(InputMessage::UserInput(text)) => {
+                    self.step(&mut internals, &text).await;
+                }
+
+                Some(InputMessage::Interrupt) => {
+                    tracing::info!("[StandardAgent] Interrupted");
+                    internals.send_status("Interrupted");
+                    internals.set_done().await;
+                    break;
+                }
+
+                Some(InputMessage::Shutdown) | None => {
+                    tracing::info!("[StandardAgent] Shutting down");
+                    internals.set_done().await;
+                    break;
+                }
+
+                _ => {
+                    // Ignore other message types
+                    internals.next_turn();
+                }
+            }
+        }
+
+        Ok(())
+    }
 
+    /// Prepare the session and context before the first turn
+    ///
+    /// Records the model and provider, restores the todo list of a resumed
+    /// session and sets up the debugger. `run()` calls this itself; call it
+    /// once before the first `step()` when driving the agent directly.
+    pub async fn start(&self, internals: &mut AgentInternals) {
         // Write initial model/provider info into session metadata
         {
             let mut session = internals.session.write().await;
@@ -75,6 +155,16 @@ impl StandardAgent {
             );
         }
 
+        // Repopulate the todo list from history when resuming a session
+        if let Some(todos) = internals.context.get_resource::<TodoListManager>() {
+            if todos.is_empty() {
+                let session = internals.session.read().await;
+                if todos.restore_from_history(session.history()) {
+                    tracing::info!("[StandardAgent] Restored {} todos from session history", todos.len());
+                }
+            }
+        }
+
         // Initialize debugger if enabled
         if self.config.debug_enabled {
             let session = internals.session.read().await;
@@ -94,140 +184,182 @@ impl StandardAgent {
                 }
             }
         }
+    }
 
-        loop {
-            // Signal we're ready for input
-            internals.set_idle().await;
-
-            // Wait for next message
-            match internals.receive().await {
-                Some(InputMessage::UserInput(text)) => {
-                    tracing::info!("[StandardAgent] Received: {}", text);
-                    internals.set_processing().await;
-
-                    // Run UserPromptSubmit hooks
-                    let mut current_text = text.clone();
-                    let mut should_process = true;
-
-                    if let Some(ref hooks) = self.config.hooks {
-                        let mut ctx = HookContext::user_prompt_submit(
-                            &mut internals,
-                            &text,
-                            self.config.hook_short_circuit,
-                        );
-                        let result = hooks.run(&mut ctx);
-
-                        // Hook may have modified the prompt
-                        if let Some(modified) = ctx.user_prompt {
-                            current_text = modified;
-                        }
-
-                        // Check if hook denied the prompt
-                        if let Some(crate::hooks::PermissionDecision::Deny) = result.decision {
-                            let reason = result.reason.unwrap_or_else(|| "Blocked by hook".to_string());
-                            tracing::info!("[StandardAgent] UserPromptSubmit hook denied: {}", reason);
-                            internals.send_error(format!("Prompt blocked: {}", reason));
-                            should_process = false;
-                        }
-                    }
+    /// Process one user message, including all of its tool calls, and return
+    ///
+    /// Does everything `run()` does for a `UserInput` message: prompt hooks,
+    /// retries of transient errors, conversation naming, `TurnComplete` and
+    /// `Done` output, saving the session, and advancing the turn counter. It
+    /// leaves the agent idle. Interrupts sent through the agent's input
+    /// channel still stop the turn.
+    ///
+    /// Use this to drive the agent from your own event loop instead of
+    /// `run()`:
+    ///
+    /// ```ignore
+    /// agent.start(&mut internals).await;
+    /// while let Some(message) = queue.next().await {
+    ///     let outcome = agent.step(&mut internals, &message).await;
+    ///     if outcome.reason == TurnEndReason::Error {
+    ///         break;
+    ///     }
+    /// }
+    /// ```
+    pub async fn step(&self, internals: &mut AgentInternals, text: &str) -> TurnOutcome {
+        tracing::info!("[StandardAgent] Received: {}", text);
+        internals.set_processing().await;
+        self.refresh_system_prompt(internals).await;
+
+        // Run UserPromptSubmit hooks
+        let mut current_text = text.to_string();
+        let mut should_process = true;
+
+        if let Some(ref hooks) = self.config.hooks {
+            let mut ctx = HookContext::user_prompt_submit(
+                internals,
+                text,
+                self.config.hook_short_circuit,
+            );
+            let result = hooks.run(&mut ctx);
 
-                    tracing::info!("[StandardAgent] Should process: {}", should_process);
-
-                    // Process the user message (if not blocked by hook)
-                    if should_process {
-                        let retry_config = &self.config.turn_retry;
-                        let mut attempt = 0u32;
-                        let mut first_attempt = true;
-                        loop {
-                            match self.process_turn(&mut internals, &current_text, first_attempt).await {
-                                Ok(()) => break,
-                                Err(e) => {
-                                    first_attempt = false;
-                                    attempt += 1;
-                                    let err_msg = e.to_string();
-                                    let is_transient = err_msg.contains("error decoding response body")
-                                        || err_msg.contains("connection")
-                                        || err_msg.contains("timeout")
-                                        || err_msg.contains("broken pipe")
-                                        || err_msg.contains("reset by peer")
-                                        || err_msg.contains("stream")
-                                        || err_msg.contains("hyper")
-                                        || err_msg.contains("io error");
-
-                                    if retry_config.enabled && is_transient && attempt < retry_config.max_retries {
-                                        tracing::warn!(
-                                            "[StandardAgent] Transient error on attempt {}/{}: {}. Retrying in {}s...",
-                                            attempt, retry_config.max_retries, e, retry_config.retry_delay_secs
-                                        );
-                                        internals.send_status(format!(
-                                            "Connection issue, retrying... (attempt {}/{})",
-                                            attempt, retry_config.max_retries
-                                        ));
-                                        tokio::time::sleep(std::time::Duration::from_secs(retry_config.retry_delay_secs)).await;
-                                        continue;
-                                    }
+            // Hook may have modified the prompt
+            if let Some(modified) = ctx.user_prompt {
+                current_text = modified;
+            }
 
-                                    tracing::error!("[StandardAgent] Error processing turn: {}", e);
-                                    internals.send_error(format!("Error: {}", e));
-                                    break;
-                                }
-                            }
-                        }
+            // Check if hook denied the prompt
+            if let Some(crate::hooks::PermissionDecision::Deny) = result.decision {
+                let reason = result.reason.unwrap_or_else(|| "Blocked by hook".to_string());
+                tracing::info!("[StandardAgent] UserPromptSubmit hook denied: {}", reason);
+                internals.send_error(format!("Prompt blocked: {}", reason));
+                should_process = false;
+            }
+        }
 
-                        if self.config.auto_name_conversation && internals.context.current_turn == 0
-                        {
-                            let session_id = {
-                                let session = internals.session.read().await;
-                                session.session_id().to_string()
-                            };
-                            let has_name = internals.session.read().await.has_conversation_name();
-                            if !has_name {
-                                self.generate_conversation_name(&mut internals, Some(&session_id)).await;
-                            }
+        tracing::info!("[StandardAgent] Should process: {}", should_process);
+
+        // Process the user message (if not blocked by hook)
+        let outcome = if should_process {
+            let retry_config = &self.config.turn_retry;
+            let mut attempt = 0u32;
+            let mut first_attempt = true;
+            let outcome = loop {
+                match self.process_turn(internals, &current_text, first_attempt).await {
+                    Ok(outcome) => break outcome,
+                    Err(e) => {
+                        first_attempt = false;
+                        attempt += 1;
+                        let err_msg = e.to_string();
+                        let is_transient = err_msg.contains("error decoding response body")
+                            || err_msg.contains("connection")
+                            || err_msg.contains("timeout")
+                            || err_msg.contains("broken pipe")
+                            || err_msg.contains("reset by peer")
+                            || err_msg.contains("stream")
+                            || err_msg.contains("hyper")
+                            || err_msg.contains("io error");
+
+                        if retry_config.enabled && is_transient && attempt < retry_config.max_retries {
+                            tracing::warn!(
+                                "[StandardAgent] Transient error on attempt {}/{}: {}. Retrying in {}s...",
+                                attempt, retry_config.max_retries, e, retry_config.retry_delay_secs
+                            );
+                            internals.send_status(format!(
+                                "Connection issue, retrying... (attempt {}/{})",
+                                attempt, retry_config.max_retries
+                            ));
+                            tokio::time::sleep(std::time::Duration::from_secs(retry_config.retry_delay_secs)).await;
+                            continue;
                         }
-                    }
-                    // Run TurnComplete hooks
-                    if let Some(ref hooks) = self.config.hooks {
-                        let mut ctx = HookContext::turn_complete(
-                            &mut internals,
-                            self.config.hook_short_circuit,
-                        );
-                        let _result = hooks.run(&mut ctx);
-                    }
 
-                    // Signal turn complete
-                    internals.send_done();
-
-                    // Persist session if configured
-                    if self.config.auto_save_session {
-                        if let Err(e) = internals.session.write().await.save() {
-                            tracing::error!("[StandardAgent] Failed to save session: {}", e);
-                        }
+                        tracing::error!("[StandardAgent] Error processing turn: {}", e);
+                        internals.send_error(format!("Error: {}", e));
+                        break TurnOutcome::new(TurnEndReason::Error);
                     }
                 }
+            };
 
-                Some(InputMessage::Interrupt) => {
-                    tracing::info!("[StandardAgent] Interrupted");
-                    internals.send_status("Interrupted");
-                    internals.set_done().await;
-                    break;
+            if self.config.auto_name_conversation && internals.context.current_turn == 0
+            {
+                let session_id = {
+                    let session = internals.session.read().await;
+                    session.session_id().to_string()
+                };
+                let has_name = internals.session.read().await.has_conversation_name();
+                if !has_name {
+                    self.generate_conversation_name(internals, Some(&session_id)).await;
                 }
+            }
 
-                Some(InputMessage::Shutdown) | None => {
-                    tracing::info!("[StandardAgent] Shutting down");
-                    internals.set_done().await;
-                    break;
-                }
+            outcome
+        } else {
+            TurnOutcome::new(TurnEndReason::Blocked)
+        };
+        // Run TurnComplete hooks
+        if let Some(ref hooks) = self.config.hooks {
+            let mut ctx = HookContext::turn_complete(
+                internals,
+                self.config.hook_short_circuit,
+            );
+            let _result = hooks.run(&mut ctx);
+        }
 
-                _ => {
-                    // Ignore other message types
-                }
+        // Signal turn complete
+        tracing::info!(
+            "[StandardAgent] Turn ended: {:?} ({} LLM calls, {} tool calls)",
+            outcome.reason,
+            outcome.llm_calls,
+            outcome.tool_calls
+        );
+        internals.send_turn_complete(outcome.clone());
+        internals.send_done();
+
+        // Persist session if configured, otherwise write out any buffered history
+        if self.config.auto_save_session {
+            if let Err(e) = internals.session.write().await.save() {
+                tracing::error!("[StandardAgent] Failed to save session: {}", e);
             }
-
-            internals.next_turn();
+        } else if let Err(e) = internals.session.read().await.flush() {
+            tracing::error!("[StandardAgent] Failed to flush session history: {}", e);
         }
 
-        Ok(())
+        internals.next_turn();
+        internals.set_idle().await;
+        outcome
+    }
+
+    /// Summarize what happened in a session in one paragraph
+    ///
+    /// Sends a condensed transcript (text, tool names and the start of tool
+    /// results) to the agent's LLM. Transcripts over 100 KB keep their first
+    /// quarter and the most recent part, so long sessions still fit the
+    /// context window. The summary is returned, not stored; use
+    /// `AgentSession::set_summary` to keep it in the session metadata.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let summary = agent.summarize_session(&session).await?;
+    /// session.set_summary(&summary)?;
+    /// ```
+    pub async fn summarize_session(&self, session: &AgentSession) -> Result<String> {
+        if session.history().is_empty() {
+            anyhow::bail!("Cannot summarize an empty session");
+        }
+        let transcript = summary_transcript(session.history(), MAX_SUMMARY_TRANSCRIPT_BYTES);
+        tracing::debug!(
+            "[StandardAgent] Summarizing session {} ({} messages)",
+            session.session_id(),
+            session.history().len()
+        );
+
+        let request_session_id = format!("session-summary-{}", session.session_id());
+        let response = self
+            .llm
+            .send_message(&transcript, &[], Some(SUMMARY_SYSTEM_PROMPT), Some(&request_session_id))
+            .await?;
+        Ok(response.trim().to_string())
     }
 
     /// Generate a conversation name using the ConversationNamer helper
@@ -259,11 +391,59 @@ impl StandardAgent {
         }
     }
 
+    /// Save the system blocks or rendered system template as the system prompt if it changed
+    ///
+    /// A prompt set with `AgentHandle::set_system_prompt` is kept until the
+    /// blocks or the rendered template change, which replaces it again.
+    async fn refresh_system_prompt(&self, internals: &AgentInternals) {
+        let (prompt, source) = if let Some(ref blocks) = self.config.system_blocks {
+            let texts: Vec<&str> = blocks.iter().map(|(text, _)| text.as_str()).collect();
+            (texts.join("\n\n"), "system blocks".to_string())
+        } else if let Some(ref template) = self.config.system_template {
+            match template.render() {
+                Ok(prompt) => (prompt, format!("template '{}'", template.name)),
+                Err(e) => {
+                    tracing::error!("[StandardAgent] Failed to render system template: {:#}", e);
+                    return;
+                }
+            }
+        } else {
+            return;
+        };
+        let mut hasher = DefaultHasher::new();
+        prompt.hash(&mut hasher);
+        let config_hash = format!("{:x}", hasher.finish());
+
+        let mut session = internals.session.write().await;
+        let last_hash = session
+            .get_custom("system_prompt_config_hash")
+            .and_then(|v| v.as_str())
+            .map(str::to_string);
+        session.set_custom("system_prompt_config_hash", config_hash.as_str());
+        let overridden = session
+            .get_custom("system_prompt_overridden")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        if overridden {
+            if last_hash.is_none_or(|last| last == config_hash) {
+                return;
+            }
+            tracing::info!("[StandardAgent] {} changed, replacing the system prompt set at runtime", source);
+            session.set_custom("system_prompt_overridden", false);
+        }
+        if session.system_prompt() != prompt {
+            tracing::info!("[StandardAgent] Updating system prompt from {}", source);
+            if let Err(e) = session.update_system_prompt(prompt) {
+                tracing::error!("[StandardAgent] Failed to save system prompt: {}", e);
+            }
+        }
+    }
+
     /// Process a single user turn (may involve multiple LLM calls for tool use)
     ///
     /// `add_user_message`: true on the first attempt, false on retries to avoid
     /// duplicating the user message in session history.
-    async fn process_turn(&self, internals: &mut AgentInternals, user_input: &str, add_user_message: bool) -> Result<()> {
+    async fn process_turn(&self, internals: &mut AgentInternals, user_input: &str, add_user_message: bool) -> Result<TurnOutcome> {
         // Only add the user message on the first attempt (not on retries)
         if add_user_message {
             // Check if input contains attachment tags and process them
@@ -277,7 +457,8 @@ impl StandardAgent {
                     .to_string();
 
                 // Process attachments
-                let attachment_blocks = process_attachments(user_input, &base_dir);
+                let attachment_blocks =
+                    process_attachments_with(user_input, &base_dir, self.config.attachment_long_lines);
 
                 // Build message blocks: original text first, then attachments
                 let mut blocks = vec![ContentBlock::Text {
@@ -292,6 +473,20 @@ impl StandardAgent {
                 Message::user(user_input)
             };
 
+            // Open a new session with the preamble (never repeated on resume)
+            let user_message = match self.config.session_preamble {
+                Some(ref preamble) if internals.session.read().await.history().is_empty() => {
+                    tracing::info!("[StandardAgent] Adding session preamble to the first message");
+                    let mut blocks = vec![session_preamble_block(preamble)];
+                    match user_message.content {
+                        MessageContent::Text(text) => blocks.push(ContentBlock::text(text)),
+                        MessageContent::Blocks(rest) => blocks.extend(rest),
+                    }
+                    Message::user_with_blocks(blocks)
+                }
+                _ => user_message,
+            };
+
             // Add user message to history
             internals.session.write().await.add_message(user_message)?;
         }
@@ -299,7 +494,31 @@ impl StandardAgent {
         // Get tool definitions
         let tool_definitions = self.config.tool_definitions();
 
+        // Compacted definitions: full descriptions for the first request of a
+        // session, trimmed ones once the model has seen them
+        let (tool_definitions, seen_tool_definitions) = if self.config.compact_tool_schemas {
+            let compact = compact_tool_definitions(&tool_definitions, false);
+            let trimmed = self
+                .config
+                .trim_seen_tool_descriptions
+                .then(|| compact_tool_definitions(&tool_definitions, true));
+            tracing::debug!(
+                "[StandardAgent] Compact tool schemas: ~{} -> ~{} tokens (~{} after first request)",
+                estimate_tool_tokens(&tool_definitions),
+                estimate_tool_tokens(&compact),
+                estimate_tool_tokens(trimmed.as_ref().unwrap_or(&compact))
+            );
+            (compact, trimmed)
+        } else {
+            (tool_definitions, None)
+        };
+
         let mut iterations = 0;
+        let mut outcome = TurnOutcome::new(TurnEndReason::EndTurn);
+
+        // Track consecutive identical tool calls across iterations (loop guard)
+        let mut last_tool_call: Option<(String, ToolResult)> = None;
+        let mut repeat_count = 0usize;
 
         // LLM loop - continues until no more tool calls
         loop {
@@ -310,6 +529,7 @@ impl StandardAgent {
                     self.config.max_tool_iterations
                 );
                 internals.send_status("Max tool iterations reached");
+                outcome.reason = TurnEndReason::MaxIterations;
                 break;
             }
 
@@ -319,16 +539,51 @@ impl StandardAgent {
                 (session.history().to_vec(), session.system_prompt().to_string())
             };
 
+            // Narrow the tools for this request if a filter is configured
+            let seen = messages.iter().any(|message| message.role == "assistant");
+            let base_tools = match seen_tool_definitions {
+                Some(ref trimmed) if seen => trimmed,
+                _ => &tool_definitions,
+            };
+            let request_tools = match self.config.tool_filter {
+                Some(ref filter) => filter(base_tools, &messages),
+                None => base_tools.clone(),
+            };
+
+            // Restrict the turn to an allow-list if one is configured
+            let allowed_tools = self
+                .config
+                .allowed_tools
+                .as_ref()
+                .map(|allowed| allowed(&messages, self.config.tools.as_deref()));
+            let request_tools = match allowed_tools {
+                Some(ref allowed) => request_tools
+                    .into_iter()
+                    .filter(|t| allowed.iter().any(|name| name == t.name()))
+                    .collect(),
+                None => request_tools,
+            };
+
             // IMPORTANT: Apply cache control BEFORE injections
             // This ensures we cache the stable message content (without dynamic injections)
             // The injections will be added AFTER the cache breakpoint, so they're sent but not cached
             // This allows the cache to match across turns even though injections are dynamic
             let (tools_with_cache, system_with_cache, mut messages_with_cache) =
-                self.apply_cache_control(&system_prompt_text, tool_definitions.to_vec(), messages);
+                self.apply_cache_control(&system_prompt_text, request_tools, messages);
 
             // Apply context injections AFTER cache control
             messages_with_cache = self.config.injections.apply(internals, messages_with_cache);
 
+            // Prefill the assistant turn last so the model continues from it
+            let prefill = self.active_prefill();
+            if let Some(ref prefill) = prefill {
+                messages_with_cache.push(Message::assistant(prefill.as_str()));
+            }
+
+            // Give the request interceptor the last word on what is sent
+            let request = self.build_request(messages_with_cache, system_with_cache, tools_with_cache);
+            self.check_request_size(&request)?;
+
             // Update session metadata with current model/provider (may change via SwappableLlmProvider)
             {
                 let mut session = internals.session.write().await;
@@ -338,19 +593,26 @@ impl StandardAgent {
 
             tracing::info!(
                 "[StandardAgent] Calling LLM with {} messages (iteration {})",
-                messages_with_cache.len(),
+                request.messages.len(),
                 iterations
             );
 
+            // Let the embedder inspect exactly which tools this request carries
+            let request_tools = request.tools.as_deref().unwrap_or_default();
+            if let Some(ref callback) = self.config.on_tool_definitions {
+                let wire_json = self.llm.tool_definitions_json(request_tools);
+                callback(request_tools, &wire_json);
+            }
+
             // Log API request if debugger is enabled (with cache_control included)
             if let Some(debugger) = internals.context.get_resource::<Debugger>() {
-                let tool_defs: Vec<serde_json::Value> = tools_with_cache
+                let tool_defs: Vec<serde_json::Value> = request_tools
                     .iter()
                     .map(|t| serde_json::to_value(t).unwrap_or_default())
                     .collect();
 
                 // Convert SystemPrompt to string for logging (or serialize as-is)
-                let system_str = match &system_with_cache {
+                let system_str = match &request.system {
                     Some(SystemPrompt::Text(s)) => Some(s.as_str()),
                     Some(SystemPrompt::Blocks(_)) => {
                         // For blocks, we'll serialize them so cache_control is visible
@@ -360,11 +622,11 @@ impl StandardAgent {
                 };
 
                 // If we have system blocks, we need to log them differently
-                if let Some(SystemPrompt::Blocks(_)) = &system_with_cache {
+                if let Some(SystemPrompt::Blocks(_)) = &request.system {
                     // Log the full request with SystemPrompt blocks
                     if let Err(e) = debugger.log_api_request_full(
-                        &messages_with_cache,
-                        system_with_cache.clone(),
+                        &request.messages,
+                        request.system.clone(),
                         Some(&tool_defs),
                     ) {
                         tracing::warn!("[StandardAgent] Failed to log API request: {}", e);
@@ -372,7 +634,7 @@ impl StandardAgent {
                 } else {
                     // Legacy path for simple string system prompt
                     if let Err(e) = debugger.log_api_request(
-                        &messages_with_cache,
+                        &request.messages,
                         system_str,
                         Some(&tool_defs),
                     ) {
@@ -383,42 +645,87 @@ impl StandardAgent {
 
             // Choose streaming or non-streaming based on config
             // Pass the already-cache-controlled data
-            let (content_blocks, stop_reason) = if self.config.streaming_enabled {
-                self.call_llm_streaming_with_cache(
-                    internals,
-                    messages_with_cache,
-                    tools_with_cache,
-                    system_with_cache,
-                )
-                .await?
+            let LlmCall {
+                content_blocks,
+                stop_reason,
+                usage,
+                input_errors,
+                filtered,
+            } = if self.config.streaming_enabled {
+                self.call_llm_streaming_with_cache(internals, request, prefill.as_deref())
+                    .await?
             } else {
-                self.call_llm_non_streaming_with_cache(
-                    internals,
-                    messages_with_cache,
-                    tools_with_cache,
-                    system_with_cache,
-                )
-                .await?
+                self.call_llm_non_streaming_with_cache(internals, request, prefill.as_deref())
+                    .await?
             };
 
             tracing::info!(
                 "[StandardAgent] LLM response: stop_reason={:?}",
                 stop_reason
             );
+            outcome.llm_calls += 1;
+            outcome.usage.accumulate(&usage);
+
+            if let Some(reason) = filtered {
+                tracing::warn!("[StandardAgent] Response stopped by text filter: {}", reason);
+                internals.send_status(format!("Response stopped: {}", reason));
+                outcome.reason = TurnEndReason::Filtered;
+            }
 
             // Process tool use blocks and execute tools
             let mut tool_results: Vec<(String, ToolResult)> = Vec::new();
 
+            // Messages from PreToolUse hooks, sent after the tool results
+            let mut hook_messages: Vec<String> = Vec::new();
+
             // Track recent tool calls for loop detection
             let mut tool_call_set = std::collections::HashSet::new();
 
+            // With batch permissions, run the PreToolUse hooks up front, then ask once
+            // for the calls left to the user, showing the input the hooks produced
+            let mut hooked: HashMap<String, HookedToolCall> = HashMap::new();
+            let mut batch = HashMap::new();
+            if let (Some(tools), true) = (self.config.tools.as_ref(), self.config.batch_permissions) {
+                let hooks = self.config.hooks.as_deref();
+                for block in &content_blocks {
+                    if let ContentBlock::ToolUse { id, name, input, .. } = block {
+                        let runnable = !input_errors.contains_key(id)
+                            && allowed_tools.as_ref().is_none_or(|allowed| allowed.contains(name));
+                        if runnable {
+                            let call = ToolExecutor::run_pre_tool_use(
+                                internals,
+                                hooks,
+                                name,
+                                id,
+                                input,
+                                self.config.hook_short_circuit,
+                                &mut hook_messages,
+                            );
+                            hooked.insert(id.clone(), call);
+                        }
+                    }
+                }
+                let calls: Vec<(&str, &str, &Value)> = content_blocks
+                    .iter()
+                    .filter_map(|block| match block {
+                        ContentBlock::ToolUse { id, name, .. } => hooked
+                            .get(id)
+                            .filter(|call| call.needs_permission())
+                            .map(|call| (id.as_str(), name.as_str(), &call.input)),
+                        _ => None,
+                    })
+                    .collect();
+                batch = ToolExecutor::ask_batch_permission(internals, tools, &calls).await;
+            }
+
             for (index, block) in content_blocks.iter().enumerate() {
                 if let ContentBlock::ToolUse { id, name, input, .. } = block {
                     tracing::info!("[StandardAgent] Tool use: {} ({})", name, id);
+                    outcome.tool_calls += 1;
 
                     // Loop detection: Check if this exact tool call was already made in this turn
                     let call_signature = format!("{}:{}", name, input);
-                    if !tool_call_set.insert(call_signature) {
+                    if !tool_call_set.insert(call_signature.clone()) {
                         tracing::warn!(
                             "[StandardAgent] Loop detected: duplicate tool call {} with same args",
                             name
@@ -428,22 +735,87 @@ impl StandardAgent {
                             name
                         ));
                         // Stop processing further tools and exit the turn
-                        return Ok(());
+                        outcome.reason = TurnEndReason::LoopDetected;
+                        return Ok(outcome);
                     }
 
+                    self.emit_tool_event(ToolEvent::Start {
+                        id: id.clone(),
+                        name: name.clone(),
+                        input: input.clone(),
+                    });
+
+                    // Loop guard: count repeats of the previous call across iterations
+                    let cached = match &last_tool_call {
+                        Some((signature, result)) if *signature == call_signature => {
+                            repeat_count += 1;
+                            Some(result.clone())
+                        }
+                        _ => {
+                            repeat_count = 1;
+                            None
+                        }
+                    };
+
+                    let guard_tripped = self
+                        .config
+                        .tool_loop_guard
+                        .is_some_and(|limit| repeat_count >= limit);
+
                     // Execute tool with permission check (if tools configured)
-                    let result = if let Some(ref tools) = self.config.tools {
-                        let hooks = self.config.hooks.as_deref();
-                        ToolExecutor::execute_with_permission(
-                            internals,
-                            tools,
-                            hooks,
+                    let disallowed = allowed_tools
+                        .as_ref()
+                        .filter(|allowed| !allowed.contains(name));
+
+                    let result = if let Some(error) = input_errors.get(id) {
+                        tracing::warn!("[StandardAgent] Not running {}: malformed input ({})", name, error);
+                        ToolResult::error(format!(
+                            "The input for tool '{}' was not valid JSON ({}), so the tool was not run. Call it again with complete, valid JSON arguments.",
+                            name, error
+                        ))
+                    } else if let Some(allowed) = disallowed {
+                        tracing::warn!("[StandardAgent] Rejected call to {}: not allowed on this turn", name);
+                        disallowed_tool_result(name, allowed)
+                    } else if let (true, Some(previous)) = (guard_tripped, cached) {
+                        tracing::warn!(
+                            "[StandardAgent] Loop guard: {} called {} times in a row with identical input",
                             name,
-                            id,
-                            input,
-                            self.config.hook_short_circuit,
-                        )
-                        .await
+                            repeat_count
+                        );
+                        loop_guard_result(name, repeat_count, previous)
+                    } else if let Some(Err(stopped)) = batch.get(id) {
+                        stopped.clone()
+                    } else if let Some(ref tools) = self.config.tools {
+                        let hooks = self.config.hooks.as_deref();
+                        match hooked.remove(id) {
+                            Some(call) => {
+                                let batch_decision = batch.get(id).and_then(|decision| decision.as_ref().ok().copied());
+                                ToolExecutor::execute_hooked(
+                                    internals,
+                                    tools,
+                                    hooks,
+                                    name,
+                                    id,
+                                    call,
+                                    self.config.hook_short_circuit,
+                                    batch_decision,
+                                )
+                                .await
+                            }
+                            None => {
+                                ToolExecutor::execute_with_permission(
+                                    internals,
+                                    tools,
+                                    hooks,
+                                    name,
+                                    id,
+                                    input,
+                                    self.config.hook_short_circuit,
+                                    &mut hook_messages,
+                                )
+                                .await
+                            }
+                        }
                     } else {
                         ToolResult::error(format!(
                             "No tools configured, cannot execute: {}",
@@ -451,6 +823,17 @@ impl StandardAgent {
                         ))
                     };
 
+                    // Keep the original result cached so repeated notes don't pile up
+                    if self.config.tool_loop_guard.is_some() && !guard_tripped {
+                        last_tool_call = Some((call_signature, result.clone()));
+                    }
+
+                    self.emit_tool_event(ToolEvent::End {
+                        id: id.clone(),
+                        name: name.clone(),
+                        result: result.clone(),
+                    });
+
                     tool_results.push((id.clone(), result));
 
                     // Check if user interrupted after tool execution (non-blocking check)
@@ -513,6 +896,7 @@ impl StandardAgent {
                             _ => vec![]
                         }
                     })
+                    .chain(hook_messages.into_iter().map(ContentBlock::text))
                     .collect();
 
                 internals
@@ -529,6 +913,7 @@ impl StandardAgent {
                     .add_message(Message::assistant("<vibe-working-agent-system>User interrupted this message</vibe-working-agent-system>"))?;
 
                 // Break out of the loop
+                outcome.reason = TurnEndReason::Interrupted;
                 break;
             }
 
@@ -539,8 +924,14 @@ impl StandardAgent {
                 let tool_result_blocks: Vec<ContentBlock> = tool_results
                     .into_iter()
                     .flat_map(|(id, result)| {
+                        let suggestions_note = result.suggestions_note();
                         match result.content {
                             ToolResultData::Text(text) => {
+                                // Tell the model what the tool suggests doing next
+                                let text = match suggestions_note {
+                                    Some(note) => format!("{}\n{}", text, note),
+                                    None => text,
+                                };
                                 vec![ContentBlock::tool_result(&id, &text, result.is_error)]
                             }
                             ToolResultData::Image { data, media_type } => {
@@ -548,12 +939,9 @@ impl StandardAgent {
                                 use base64::Engine;
                                 let base64_data = base64::engine::general_purpose::STANDARD.encode(&data);
 
-                                vec![ContentBlock::ToolResult {
-                                    tool_use_id: id,
-                                    content: None,
-                                    is_error: if result.is_error { Some(true) } else { None },
-                                    cache_control: None,
-                                }, ContentBlock::image(base64_data, media_type)]
+                                // The image is the tool's result; providers that can't
+                                // nest images in tool results move it to a user message
+                                vec![ContentBlock::tool_result_image(id, base64_data, media_type, result.is_error)]
                             }
                             ToolResultData::Document {
                                 data,
@@ -572,6 +960,8 @@ impl StandardAgent {
                             }
                         }
                     })
+                    // Hook messages go last: tool results must lead the message
+                    .chain(hook_messages.into_iter().map(ContentBlock::text))
                     .collect();
 
                 internals
@@ -596,22 +986,156 @@ impl StandardAgent {
                 }
                 Some(StopReason::MaxTokens) => {
                     internals.send_status("Response truncated (max tokens)");
+                    outcome.reason = TurnEndReason::MaxTokens;
                     break;
                 }
                 Some(StopReason::PauseTurn) => {
                     // Model paused, wait for next input
+                    outcome.reason = TurnEndReason::Paused;
                     break;
                 }
                 Some(StopReason::Refusal) => {
                     internals.send_status("Model refused to respond");
+                    outcome.reason = TurnEndReason::Refusal;
                     break;
                 }
             }
         }
 
+        Ok(outcome)
+    }
+
+    /// Run `AssistantTextDelta` hooks, returning the reason if one denies the delta
+    fn filter_text_delta(
+        &self,
+        internals: &mut AgentInternals,
+        delta: &str,
+        text_so_far: &str,
+    ) -> Option<String> {
+        let hooks = self.config.hooks.as_ref()?;
+        if !hooks.has_hooks(HookEvent::AssistantTextDelta) {
+            return None;
+        }
+
+        let mut ctx = HookContext::assistant_text_delta(
+            internals,
+            delta,
+            text_so_far,
+            self.config.hook_short_circuit,
+        );
+        let result = hooks.run(&mut ctx);
+        match result.decision {
+            Some(PermissionDecision::Deny) => {
+                Some(result.reason.unwrap_or_else(|| "blocked by a hook".to_string()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Apply the configured response postprocessor to a text block
+    fn postprocess(&self, text: String) -> String {
+        match self.config.response_postprocessor {
+            Some(ref postprocessor) => postprocessor(text),
+            None => text,
+        }
+    }
+
+    /// Send a text delta to subscribers and the `on_text_delta` callback
+    fn emit_text(&self, internals: &AgentInternals, text: &str) {
+        internals.send_text(text);
+        if let Some(ref callback) = self.config.on_text_delta {
+            callback(text);
+        }
+    }
+
+    /// Save the response streamed so far so it can be recovered after a crash
+    async fn save_checkpoint(
+        &self,
+        internals: &AgentInternals,
+        blocks: &[ContentBlock],
+        partial_text: &str,
+    ) {
+        let mut blocks = blocks.to_vec();
+        if !partial_text.is_empty() {
+            blocks.push(ContentBlock::text(partial_text));
+        }
+
+        let message = Message::assistant_with_blocks(blocks);
+        if let Err(e) = Self::on_storage(internals, move |storage, session_id| {
+            storage.save_partial_response(session_id, &message)
+        })
+        .await
+        {
+            tracing::warn!(
+                "[StandardAgent] Failed to checkpoint streamed response: {}",
+                e
+            );
+        }
+    }
+
+    /// Remove the streamed response checkpoint, if checkpointing is enabled
+    async fn clear_checkpoint(&self, internals: &AgentInternals) {
+        if !self.config.checkpoint_streaming {
+            return;
+        }
+        if let Err(e) = Self::on_storage(internals, |storage, session_id| {
+            storage.clear_partial_response(session_id)
+        })
+        .await
+        {
+            tracing::warn!(
+                "[StandardAgent] Failed to clear streamed response checkpoint: {}",
+                e
+            );
+        }
+    }
+
+    /// Run a file operation on the session's storage without blocking the runtime
+    async fn on_storage(
+        internals: &AgentInternals,
+        op: impl FnOnce(&SessionStorage, &str) -> FrameworkResult<()> + Send + 'static,
+    ) -> Result<()> {
+        let (storage, session_id) = {
+            let session = internals.session.read().await;
+            (session.storage().clone(), session.session_id().to_string())
+        };
+        tokio::task::spawn_blocking(move || op(&storage, &session_id)).await??;
         Ok(())
     }
 
+    /// Pass a tool event to the `on_tool_event` callback, if set
+    fn emit_tool_event(&self, event: ToolEvent) {
+        if let Some(ref callback) = self.config.on_tool_event {
+            callback(&event);
+        }
+    }
+
+    /// Get the response prefill to use for the next LLM call, if any
+    ///
+    /// Prefill is only sent to Anthropic and never alongside extended thinking.
+    /// Trailing whitespace is trimmed because the API rejects it.
+    fn active_prefill(&self) -> Option<String> {
+        let prefill = self.config.response_prefill.as_deref()?.trim_end();
+        if prefill.is_empty() {
+            return None;
+        }
+
+        if self.config.thinking.is_some() {
+            tracing::warn!("[StandardAgent] Response prefill is not compatible with extended thinking, skipping");
+            return None;
+        }
+
+        if self.llm.provider_name() != "anthropic" {
+            tracing::warn!(
+                "[StandardAgent] Response prefill is not supported by provider '{}', skipping",
+                self.llm.provider_name()
+            );
+            return None;
+        }
+
+        Some(prefill.to_string())
+    }
+
     /// Apply cache control to tools, system prompt, and messages (if enabled)
     fn apply_cache_control(
         &self,
@@ -620,12 +1144,8 @@ impl StandardAgent {
         mut messages: Vec<Message>,
     ) -> (Vec<crate::llm::ToolDefinition>, Option<SystemPrompt>, Vec<Message>) {
         if !self.config.enable_prompt_caching {
-            // Caching disabled - return system prompt as simple text
-            return (
-                tool_definitions,
-                Some(SystemPrompt::Text(system_prompt_text.to_string())),
-                messages,
-            );
+            // Caching disabled - return system prompt without breakpoints
+            return (tool_definitions, Some(self.system_prompt(system_prompt_text, false)), messages);
         }
 
         // IMPORTANT: Strip ALL existing cache_control from messages first
@@ -652,10 +1172,7 @@ impl StandardAgent {
         }
 
         // 2. Create system prompt with cache control
-        let system_prompt = Some(SystemPrompt::Blocks(vec![SystemBlock::new(
-            system_prompt_text.to_string(),
-        )
-        .with_cache_control(CacheControl::ephemeral())]));
+        let system_prompt = Some(self.system_prompt(system_prompt_text, true));
 
         // 3. Add cache control to the last content block of the LAST message
         // This caches everything including the current user input, creating a stable growing cache
@@ -683,29 +1200,119 @@ impl StandardAgent {
         (tool_definitions, system_prompt, messages)
     }
 
+    /// System prompt for a request: the configured blocks, or the session's prompt
+    ///
+    /// With `caching`, the session's prompt gets one breakpoint, and configured
+    /// blocks get one each where flagged (at most `MAX_SYSTEM_CACHE_BREAKPOINTS`).
+    fn system_prompt(&self, session_prompt: &str, caching: bool) -> SystemPrompt {
+        // Blocks stand in for the session prompt only while it is their text,
+        // not after it was replaced at runtime
+        let blocks = self.config.system_blocks.as_ref().filter(|blocks| {
+            let texts: Vec<&str> = blocks.iter().map(|(text, _)| text.as_str()).collect();
+            texts.join("\n\n") == session_prompt
+        });
+        let Some(blocks) = blocks else {
+            return if caching {
+                SystemPrompt::Blocks(vec![
+                    SystemBlock::new(session_prompt.to_string()).with_cache_control(CacheControl::ephemeral())
+                ])
+            } else {
+                SystemPrompt::Text(session_prompt.to_string())
+            };
+        };
+
+        let mut breakpoints = 0;
+        SystemPrompt::Blocks(
+            blocks
+                .iter()
+                .map(|(text, cache)| {
+                    let block = SystemBlock::new(text.clone());
+                    if caching && *cache && breakpoints < MAX_SYSTEM_CACHE_BREAKPOINTS {
+                        breakpoints += 1;
+                        block.with_cache_control(CacheControl::ephemeral())
+                    } else {
+                        block
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Build the request for an LLM call and pass it to the request interceptor
+    fn build_request(
+        &self,
+        messages: Vec<Message>,
+        system: Option<SystemPrompt>,
+        tools: Vec<crate::llm::ToolDefinition>,
+    ) -> MessageRequest {
+        let mut request = MessageRequest {
+            model: self.llm.model(),
+            max_tokens: 0,
+            messages,
+            system,
+            tools: Some(tools),
+            tool_choice: None,
+            thinking: self.config.thinking.clone(),
+            temperature: None,
+            stream: Some(self.config.streaming_enabled),
+            metadata: RequestMetadata::for_user(self.config.user_id.as_deref()),
+        };
+        if let Some(ref interceptor) = self.config.request_interceptor {
+            interceptor(&mut request);
+        }
+        request
+    }
+
+    /// Fail if the request exceeds the configured message count or size
+    fn check_request_size(&self, request: &MessageRequest) -> Result<()> {
+        if let Some(max) = self.config.max_request_messages {
+            if request.messages.len() > max {
+                anyhow::bail!(
+                    "Request not sent: it has {} messages, more than the limit of {} (max_request_messages)",
+                    request.messages.len(),
+                    max
+                );
+            }
+        }
+        if let Some(max) = self.config.max_request_bytes {
+            let size = serde_json::to_vec(request)?.len();
+            if size > max {
+                anyhow::bail!(
+                    "Request not sent: it is {} bytes, more than the limit of {} (max_request_bytes)",
+                    size,
+                    max
+                );
+            }
+        }
+        Ok(())
+    }
+
     /// Call LLM without streaming (with pre-applied cache control)
     async fn call_llm_non_streaming_with_cache(
         &self,
         internals: &mut AgentInternals,
-        messages: Vec<Message>,
-        tools: Vec<crate::llm::ToolDefinition>,
-        system: Option<SystemPrompt>,
-    ) -> Result<(Vec<ContentBlock>, Option<StopReason>)> {
+        request: MessageRequest,
+        prefill: Option<&str>,
+    ) -> Result<LlmCall> {
         // Get session ID
         let session_id = {
             let session = internals.session.read().await;
             session.session_id().to_string()
         };
+        let options = RequestOptions {
+            session_id: Some(session_id),
+            user_id: request.metadata.and_then(|m| m.user_id),
+        };
 
-        let response = self
+        let mut response = self
             .llm
-            .send_with_tools_and_system(
-                messages,
-                system,
-                tools,
-                None,
-                self.config.thinking.clone(),
-                Some(&session_id),
+            .send_with_options(
+                request.messages,
+                request.system,
+                request.tools.unwrap_or_default(),
+                request.tool_choice,
+                request.thinking,
+                &options,
             )
             .await?;
 
@@ -718,11 +1325,47 @@ impl StandardAgent {
             }
         }
 
+        // The response continues from the prefill, so stitch it back on
+        if let Some(prefill) = prefill {
+            prepend_prefill(&mut response.content, prefill);
+        }
+
+        // Filter hooks see each text block as a single delta before it is shown
+        let mut filtered = None;
+        for (index, block) in response.content.iter().enumerate() {
+            if let ContentBlock::Text { text, .. } = block {
+                if let Some(reason) = self.filter_text_delta(internals, text, text) {
+                    response.content.truncate(index);
+                    filtered = Some(reason);
+                    break;
+                }
+            }
+        }
+
+        // Only the model's own text is postprocessed, as when streaming, so
+        // this runs before the filter note is added
+        if self.config.response_postprocessor.is_some() {
+            for block in response.content.iter_mut() {
+                if let ContentBlock::Text { text, .. } = block {
+                    *text = self.postprocess(std::mem::take(text));
+                }
+            }
+            // The API rejects empty text blocks
+            response
+                .content
+                .retain(|block| !matches!(block, ContentBlock::Text { text, .. } if text.is_empty()));
+        }
+
+        if let Some(ref reason) = filtered {
+            stop_filtered_response(&mut response.content, reason);
+            response.stop_reason = None;
+        }
+
         // Send text and thinking content to output
         for block in &response.content {
             match block {
                 ContentBlock::Text { text, .. } => {
-                    internals.send_text(text);
+                    self.emit_text(internals, text);
                     internals.send_text_complete(text);
                 }
                 ContentBlock::Thinking { thinking, .. } => {
@@ -733,38 +1376,50 @@ impl StandardAgent {
             }
         }
 
-        Ok((response.content, response.stop_reason))
+        Ok(LlmCall {
+            content_blocks: response.content,
+            stop_reason: response.stop_reason,
+            usage: response.usage,
+            input_errors: ToolInputErrors::new(),
+            filtered,
+        })
     }
 
     /// Call LLM with streaming (with pre-applied cache control) - sends deltas in real-time
     async fn call_llm_streaming_with_cache(
         &self,
         internals: &mut AgentInternals,
-        messages: Vec<Message>,
-        tools: Vec<crate::llm::ToolDefinition>,
-        system: Option<SystemPrompt>,
-    ) -> Result<(Vec<ContentBlock>, Option<StopReason>)> {
+        request: MessageRequest,
+        prefill: Option<&str>,
+    ) -> Result<LlmCall> {
         // Get session ID
         let session_id = {
             let session = internals.session.read().await;
             session.session_id().to_string()
         };
+        let options = RequestOptions {
+            session_id: Some(session_id),
+            user_id: request.metadata.and_then(|m| m.user_id),
+        };
 
-        let mut stream = self
+        // Guard the stream so a truncated response fails the turn instead of
+        // committing a partial assistant message
+        let stream = self
             .llm
-            .stream_with_tools_and_system(
-                messages,
-                system,
-                tools,
-                None,
-                self.config.thinking.clone(),
-                Some(&session_id),
+            .stream_with_options(
+                request.messages,
+                request.system,
+                request.tools.unwrap_or_default(),
+                request.tool_choice,
+                request.thinking,
+                &options,
             )
             .await?;
+        let mut stream = GuardedStream::new(stream);
 
-        // Track content blocks as they're built
+        // Completed content blocks, and the one being streamed with its index
         let mut content_blocks: Vec<ContentBlock> = Vec::new();
-        let mut current_block_index: Option<usize> = None;
+        let mut current_block: Option<(usize, PartialBlock)> = None;
         let mut stop_reason: Option<StopReason> = None;
 
         // Track message metadata for logging
@@ -772,17 +1427,26 @@ impl StandardAgent {
         let mut model: Option<String> = None;
         let mut initial_usage: Option<crate::llm::Usage> = None;
         let mut output_tokens: u32 = 0;
+        let mut final_usage = DeltaUsage::default();
+
+        let mut input_errors = ToolInputErrors::new();
 
-        // Accumulators for building content blocks
-        let mut text_accum = String::new();
-        let mut thinking_accum = String::new();
-        let mut thinking_signature = String::new();
-        let mut tool_input_accum = String::new();
-        let mut current_tool_id = String::new();
-        let mut current_tool_name = String::new();
-        let mut current_tool_signature: Option<String> = None;
+        // Prefill is emitted as the start of the first text block
+        let mut pending_prefill = prefill;
+
+        // Throttle for on-disk checkpoints of the partial response
+        let mut last_checkpoint = Instant::now();
+
+        // Set when an AssistantTextDelta hook stops the response
+        let mut filtered: Option<String> = None;
+
+        // Postprocessed text is sent once each block is complete
+        let postprocessing = self.config.response_postprocessor.is_some();
 
         loop {
+            // With OutputBackpressure::Wait, let slow subscribers catch up first
+            internals.wait_for_output_capacity().await;
+
             tokio::select! {
                 event_result = stream.next() => {
                     let event_result = match event_result {
@@ -802,91 +1466,113 @@ impl StandardAgent {
                                 }
 
                         StreamEvent::ContentBlockStart(block_start) => {
-                            current_block_index = Some(block_start.index);
-
-                            match &block_start.content_block {
-                                ContentBlockStart::Text { .. } => {
-                                    text_accum.clear();
-                                }
-                                ContentBlockStart::Thinking { .. } => {
-                                    thinking_accum.clear();
-                                    thinking_signature.clear();
-                                }
-                                ContentBlockStart::ToolUse { id, name, signature, .. } => {
-                                    tool_input_accum.clear();
-                                    current_tool_id = id.clone();
-                                    current_tool_name = name.clone();
-                                    current_tool_signature = signature.clone();
+                            let mut block = PartialBlock::start(block_start.content_block);
+                            if let PartialBlock::Text(ref mut text) = block {
+                                if let Some(prefill) = pending_prefill.take() {
+                                    text.insert_str(0, prefill);
+                                    if !postprocessing {
+                                        self.emit_text(internals, prefill);
+                                    }
                                 }
                             }
+                            current_block = Some((block_start.index, block));
                         }
 
                         StreamEvent::ContentBlockDelta(delta) => {
-                            match &delta.delta {
-                                ContentDelta::TextDelta { text } => {
-                                    text_accum.push_str(text);
-                                    // Stream text to output immediately
-                                    internals.send_text(text);
+                            let Some((_, ref mut block)) = current_block else {
+                                tracing::warn!(
+                                    "[StandardAgent] Delta for content block {} before its start",
+                                    delta.index
+                                );
+                                continue;
+                            };
+
+                            // Report malformed input on the first bad fragment
+                            if let Err(error) = block.push(&delta.delta) {
+                                if let PartialBlock::ToolUse { ref id, ref name, .. } = block {
+                                    report_input_error(internals, &mut input_errors, id, name, error);
                                 }
-                                ContentDelta::ThinkingDelta { thinking } => {
-                                    thinking_accum.push_str(thinking);
+                            }
+
+                            match (&delta.delta, &*block) {
+                                (ContentDelta::TextDelta { text }, PartialBlock::Text(text_so_far)) => {
+                                    // Filter hooks see each delta before it is shown
+                                    if let Some(reason) = self.filter_text_delta(internals, text, text_so_far) {
+                                        filtered = Some(reason);
+                                        break;
+                                    }
+
+                                    // Stream text to output immediately, unless it must be
+                                    // postprocessed as a whole first
+                                    if !postprocessing {
+                                        self.emit_text(internals, text);
+                                    }
+
+                                    if self.config.checkpoint_streaming
+                                        && last_checkpoint.elapsed() >= CHECKPOINT_INTERVAL
+                                    {
+                                        self.save_checkpoint(internals, &content_blocks, text_so_far).await;
+                                        last_checkpoint = Instant::now();
+                                    }
+                                }
+                                (ContentDelta::ThinkingDelta { thinking }, _) => {
                                     // Stream thinking to output immediately
                                     internals.send_thinking(thinking);
                                 }
-                                ContentDelta::SignatureDelta { signature } => {
-                                    thinking_signature.push_str(signature);
-                                }
-                                ContentDelta::InputJsonDelta { partial_json } => {
-                                    tool_input_accum.push_str(partial_json);
-                                }
+                                _ => {}
                             }
                         }
 
                         StreamEvent::ContentBlockStop(block_stop) => {
-                            if current_block_index == Some(block_stop.index) {
-                                // Finalize the content block
-                                if !text_accum.is_empty() {
-                                    // Send text complete signal to CLI
-                                    internals.send_text_complete(&text_accum);
-                                    content_blocks.push(ContentBlock::Text {
-                                        text: text_accum.clone(),
-                                        cache_control: None,
-                                    });
-                                    text_accum.clear();
-                                } else if !thinking_accum.is_empty() {
-                                    // Send thinking complete signal to CLI
-                                    internals.send_thinking_complete(&thinking_accum);
-                                    content_blocks.push(ContentBlock::Thinking {
-                                        thinking: thinking_accum.clone(),
-                                        signature: thinking_signature.clone(),
-                                    });
-                                    thinking_accum.clear();
-                                    thinking_signature.clear();
-                                } else if !tool_input_accum.is_empty()
-                                    || !current_tool_name.is_empty()
-                                {
-                                    // Parse accumulated JSON
-                                    let input: Value =
-                                        serde_json::from_str(&tool_input_accum).unwrap_or_default();
-                                    content_blocks.push(ContentBlock::ToolUse {
-                                        id: current_tool_id.clone(),
-                                        name: current_tool_name.clone(),
-                                        input,
-                                        signature: current_tool_signature.clone(),
-                                    });
-                                    tool_input_accum.clear();
-                                    current_tool_id.clear();
-                                    current_tool_name.clear();
-                                    current_tool_signature = None;
+                            if let Some((_, block)) = current_block.take_if(|(index, _)| *index == block_stop.index) {
+                                match block {
+                                    PartialBlock::Text(text) if !text.is_empty() => {
+                                        let text = if postprocessing {
+                                            let text = self.postprocess(text);
+                                            self.emit_text(internals, &text);
+                                            text
+                                        } else {
+                                            text
+                                        };
+                                        // A postprocessor may empty the block, which the API would reject
+                                        if !text.is_empty() {
+                                            // Send text complete signal to CLI
+                                            internals.send_text_complete(&text);
+                                            content_blocks.push(ContentBlock::text(text));
+                                        }
+                                    }
+                                    PartialBlock::Text(_) => {}
+                                    PartialBlock::Thinking { ref thinking, .. } if thinking.is_empty() => {}
+                                    block => {
+                                        // A malformed call is kept with empty input so its
+                                        // tool_use/tool_result pair stays valid, but it is never executed.
+                                        let (block, error) = block.finish();
+                                        match (&block, error) {
+                                            (ContentBlock::ToolUse { id, name, .. }, Some(error)) => {
+                                                report_input_error(internals, &mut input_errors, id, name, error);
+                                            }
+                                            (ContentBlock::Thinking { thinking, .. }, _) => {
+                                                // Send thinking complete signal to CLI
+                                                internals.send_thinking_complete(thinking);
+                                            }
+                                            _ => {}
+                                        }
+                                        content_blocks.push(block);
+                                    }
+                                }
+
+                                if self.config.checkpoint_streaming {
+                                    self.save_checkpoint(internals, &content_blocks, "").await;
+                                    last_checkpoint = Instant::now();
                                 }
-                                current_block_index = None;
                             }
                         }
 
                         StreamEvent::MessageDelta(msg_delta) => {
                             stop_reason = msg_delta.delta.stop_reason;
-                            // Capture final output tokens
+                            // Capture final output tokens (and input tokens, if reported at the end)
                             output_tokens = msg_delta.usage.output_tokens;
+                            final_usage = msg_delta.usage;
                         }
 
                         StreamEvent::MessageStop => {
@@ -912,6 +1598,7 @@ impl StandardAgent {
                 }
                 Err(e) => {
                     tracing::error!("[StandardAgent] Stream error: {}", e);
+                    self.clear_checkpoint(internals).await;
                     return Err(e);
                 }
             }
@@ -923,11 +1610,20 @@ impl StandardAgent {
                         tracing::info!("[StandardAgent] Interrupt received");
 
                         // Finalize any in-progress text content block
-                        if !text_accum.is_empty() {
-                            content_blocks.push(ContentBlock::Text {
-                                text: text_accum.clone(),
-                                cache_control: None,
-                            });
+                        if let Some((_, PartialBlock::Text(text))) = current_block.take() {
+                            let text = if postprocessing && !text.is_empty() {
+                                let text = self.postprocess(text);
+                                self.emit_text(internals, &text);
+                                text
+                            } else {
+                                text
+                            };
+                            if !text.is_empty() {
+                                content_blocks.push(ContentBlock::Text {
+                                    text,
+                                    cache_control: None,
+                                });
+                            }
                         }
                         // Discard incomplete thinking blocks (signature may be incomplete)
                         // Discard partial tool calls (don't add them to content_blocks)
@@ -947,6 +1643,33 @@ impl StandardAgent {
             }
         }
 
+        // Drop the block being filtered and any tool calls; completed text stays
+        if let Some(ref reason) = filtered {
+            stop_filtered_response(&mut content_blocks, reason);
+            stop_reason = None;
+        }
+
+        // The response is complete (or interrupted and finalized) - no recovery needed
+        self.clear_checkpoint(internals).await;
+
+        // No text block was streamed - keep the prefill as part of the turn
+        if let Some(prefill) = pending_prefill {
+            prepend_prefill(&mut content_blocks, prefill);
+        }
+
+        // Input and cache usage arrive in message_start, output tokens in message_delta.
+        // Providers that only know the input tokens at the end send them in message_delta.
+        let mut usage = Usage {
+            output_tokens,
+            ..initial_usage.clone().unwrap_or_default()
+        };
+        if let Some(input_tokens) = final_usage.input_tokens {
+            usage.input_tokens = input_tokens;
+        }
+        if final_usage.thoughts_token_count.is_some() {
+            usage.thoughts_token_count = final_usage.thoughts_token_count;
+        }
+
         // Log the assembled response if debugger is enabled
         if let Some(debugger) = internals.context.get_resource::<Debugger>() {
             // Construct a response object similar to MessageResponse for logging
@@ -960,7 +1683,7 @@ impl StandardAgent {
             });
 
             // Add usage information if we captured it
-            if let Some(usage) = initial_usage {
+            if initial_usage.is_some() {
                 let usage_obj = serde_json::json!({
                     "input_tokens": usage.input_tokens,
                     "output_tokens": output_tokens,
@@ -975,6 +1698,791 @@ impl StandardAgent {
             }
         }
 
-        Ok((content_blocks, stop_reason))
+        Ok(LlmCall {
+            content_blocks,
+            stop_reason,
+            usage,
+            input_errors,
+            filtered,
+        })
+    }
+}
+
+/// Format `history` for `summarize_session`, cut to about `max_bytes`
+///
+/// The start, where the task is usually stated, and the end are kept.
+fn summary_transcript(history: &[Message], max_bytes: usize) -> String {
+    let transcript = ConversationNamer::format_messages(history);
+    if transcript.len() <= max_bytes {
+        return transcript;
+    }
+
+    let head = truncate_bytes_safe(&transcript, max_bytes / 4);
+    let mut tail_start = transcript.len() - (max_bytes - head.len());
+    while !transcript.is_char_boundary(tail_start) {
+        tail_start += 1;
+    }
+    format!(
+        "{}\n[... {} bytes omitted ...]\n{}",
+        head,
+        tail_start - head.len(),
+        &transcript[tail_start..]
+    )
+}
+
+/// Prepend a response prefill to the first text block, or add one if there is none
+fn prepend_prefill(blocks: &mut Vec<ContentBlock>, prefill: &str) {
+    let first_text = blocks
+        .iter_mut()
+        .find_map(|block| match block {
+            ContentBlock::Text { text, .. } => Some(text),
+            _ => None,
+        });
+
+    match first_text {
+        Some(text) => text.insert_str(0, prefill),
+        None => blocks.insert(0, ContentBlock::text(prefill)),
+    }
+}
+
+/// Finish a response stopped by a text filter
+///
+/// Tool calls are removed so nothing from the stopped response runs, and a
+/// note tells the model on later turns why its response ended.
+fn stop_filtered_response(blocks: &mut Vec<ContentBlock>, reason: &str) {
+    blocks.retain(|block| !matches!(block, ContentBlock::ToolUse { .. }));
+    blocks.push(ContentBlock::Text {
+        text: format!(
+            "<vibe-working-agent-system>This response was stopped by a content filter: {}</vibe-working-agent-system>",
+            reason
+        ),
+        cache_control: None,
+    });
+}
+
+/// Record a malformed tool input and notify subscribers, once per tool call
+fn report_input_error(
+    internals: &AgentInternals,
+    input_errors: &mut ToolInputErrors,
+    id: &str,
+    name: &str,
+    error: ToolInputError,
+) {
+    if input_errors.contains_key(id) {
+        return;
+    }
+    tracing::warn!("[StandardAgent] Malformed input for {}: {}", name, error);
+    internals.send(OutputChunk::ToolInputError {
+        id: id.to_string(),
+        name: name.to_string(),
+        error: error.to_string(),
+    });
+    input_errors.insert(id.to_string(), error);
+}
+
+/// Build the corrective error for a call to a tool outside the turn's allow-list
+fn disallowed_tool_result(tool_name: &str, allowed: &[String]) -> ToolResult {
+    if allowed.is_empty() {
+        return ToolResult::error(format!(
+            "Tool '{}' is not available on this turn. No tools are available; respond without calling tools.",
+            tool_name
+        ));
+    }
+    ToolResult::error(format!(
+        "Tool '{}' is not available on this turn. Available tools: {}",
+        tool_name,
+        allowed.join(", ")
+    ))
+}
+
+/// Build the short-circuited result for a repeated tool call
+///
+/// Text results are replayed with a note appended; other results are replaced
+/// by the note alone.
+fn loop_guard_result(tool_name: &str, repeat_count: usize, previous: ToolResult) -> ToolResult {
+    let repeated = format!(
+        "Tool '{}' has been called {} times in a row with identical input, so it was not executed again.",
+        tool_name, repeat_count
+    );
+
+    match previous.content {
+        ToolResultData::Text(text) => ToolResult {
+            content: ToolResultData::Text(format!(
+                "{}\n\n<vibe-working-agent-system>{} The result above is from the previous call \
+and may be out of date; use it or try a different approach.</vibe-working-agent-system>",
+                text, repeated
+            )),
+            ..previous
+        },
+        _ => ToolResult::error(format!(
+            "<vibe-working-agent-system>{} Use the result of the previous call or try a different \
+approach.</vibe-working-agent-system>",
+            repeated
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::OutputChunk;
+    use crate::runtime::internals::test_internals;
+    use crate::testing::MockLlmProvider;
+
+    #[tokio::test]
+    async fn test_step_runs_one_turn() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (mut internals, mut output_rx, _) = test_internals(temp_dir.path());
+
+        let llm = MockLlmProvider::new(vec![vec![ContentBlock::text("Hi")]]);
+        let agent = StandardAgent::new(AgentConfig::new().with_auto_name(false), Arc::new(llm));
+        agent.start(&mut internals).await;
+        let outcome = agent.step(&mut internals, "Hello").await;
+
+        assert_eq!(outcome.reason, TurnEndReason::EndTurn);
+        assert_eq!(outcome.llm_calls, 1);
+        assert_eq!(internals.session.read().await.history().len(), 2);
+        assert_eq!(internals.context.current_turn, 1);
+
+        let mut chunks = Vec::new();
+        while let Ok(chunk) = output_rx.try_recv() {
+            chunks.push(chunk);
+        }
+        assert!(chunks.iter().any(|chunk| matches!(chunk, OutputChunk::Done)));
+    }
+
+    #[tokio::test]
+    async fn test_system_template_reloaded_each_turn() {
+        use crate::helpers::{PromptLibrary, SystemTemplate};
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let prompts = temp_dir.path().join("prompts");
+        std::fs::create_dir(&prompts).unwrap();
+        std::fs::write(prompts.join("system.md"), "You help with {{project}}.").unwrap();
+
+        let template = SystemTemplate::new(Arc::new(PromptLibrary::hot_reload(&prompts)), "system")
+            .with_var("project", "picrust");
+        let llm = MockLlmProvider::new(vec![vec![ContentBlock::text("Hi")], vec![ContentBlock::text("Hi")]]);
+        let config = AgentConfig::new().with_auto_name(false).with_system_template(template);
+        let agent = StandardAgent::new(config, Arc::new(llm));
+        let (mut internals, _output_rx, _) = test_internals(temp_dir.path());
+
+        agent.step(&mut internals, "Hello").await;
+        assert_eq!(internals.session.read().await.system_prompt(), "You help with picrust.");
+
+        std::fs::write(prompts.join("system.md"), "You review {{project}}.").unwrap();
+        agent.step(&mut internals, "Again").await;
+        assert_eq!(internals.session.read().await.system_prompt(), "You review picrust.");
+    }
+
+    /// A handle on the test session, as the runtime would hand out
+    fn test_handle(internals: &AgentInternals) -> crate::runtime::AgentHandle {
+        let (input_tx, _, output_tx) = crate::runtime::channels::create_agent_channels();
+        let state = Arc::new(tokio::sync::RwLock::new(crate::core::AgentState::Idle));
+        crate::runtime::AgentHandle::new("test-session", internals.session.clone(), input_tx, output_tx, state)
+    }
+
+    /// Text of the system prompt sent with a recorded request
+    fn sent_system(request: &crate::testing::RecordedRequest) -> String {
+        match request.system.as_ref().unwrap() {
+            SystemPrompt::Text(text) => text.clone(),
+            SystemPrompt::Blocks(blocks) => {
+                blocks.iter().map(|b| b.text.as_str()).collect::<Vec<_>>().join("\n\n")
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_runtime_system_prompt_kept_until_template_changes() {
+        use crate::helpers::{PromptLibrary, SystemTemplate};
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let prompts = temp_dir.path().join("prompts");
+        std::fs::create_dir(&prompts).unwrap();
+        std::fs::write(prompts.join("system.md"), "You help with {{project}}.").unwrap();
+
+        let template = SystemTemplate::new(Arc::new(PromptLibrary::hot_reload(&prompts)), "system")
+            .with_var("project", "picrust");
+        let llm = Arc::new(MockLlmProvider::new(vec![
+            vec![ContentBlock::text("Hi")],
+            vec![ContentBlock::text("Hi")],
+            vec![ContentBlock::text("Hi")],
+        ]));
+        let config = AgentConfig::new().with_auto_name(false).with_system_template(template);
+        let agent = StandardAgent::new(config, llm.clone());
+        let (mut internals, _output_rx, _) = test_internals(temp_dir.path());
+        let handle = test_handle(&internals);
+
+        agent.step(&mut internals, "Hello").await;
+        handle.set_system_prompt("Be terse.").await.unwrap();
+
+        // The unchanged template does not overwrite the prompt set at runtime
+        agent.step(&mut internals, "Again").await;
+        assert_eq!(internals.session.read().await.system_prompt(), "Be terse.");
+        assert_eq!(sent_system(&llm.requests()[1]), "Be terse.");
+
+        // Editing the template replaces it
+        std::fs::write(prompts.join("system.md"), "You review {{project}}.").unwrap();
+        agent.step(&mut internals, "Once more").await;
+        assert_eq!(internals.session.read().await.system_prompt(), "You review picrust.");
+        assert_eq!(sent_system(&llm.requests()[2]), "You review picrust.");
+    }
+
+    #[tokio::test]
+    async fn test_runtime_system_prompt_replaces_blocks() {
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (mut internals, _output_rx, _) = test_internals(temp_dir.path());
+        let llm = Arc::new(MockLlmProvider::new(vec![vec![ContentBlock::text("Hi")]]));
+        let config = AgentConfig::new()
+            .with_auto_name(false)
+            .with_system_blocks(vec![("Stable", true), ("Today", false)]);
+        let agent = StandardAgent::new(config, llm.clone());
+        let handle = test_handle(&internals);
+
+        handle.set_system_prompt("Be terse.").await.unwrap();
+        agent.step(&mut internals, "Hi").await;
+
+        assert_eq!(sent_system(&llm.requests()[0]), "Be terse.");
+    }
+
+    #[tokio::test]
+    async fn test_summarize_session() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (mut internals, _output_rx, _) = test_internals(temp_dir.path());
+        let llm = MockLlmProvider::new(vec![vec![ContentBlock::text("Hi")]]);
+        let agent = StandardAgent::new(AgentConfig::new().with_auto_name(false), Arc::new(llm));
+
+        let empty = internals.session.read().await;
+        assert!(agent.summarize_session(&empty).await.is_err());
+        drop(empty);
+
+        agent.step(&mut internals, "Hello").await;
+        let mut session = internals.session.write().await;
+        let summary = agent.summarize_session(&session).await.unwrap();
+        assert!(!summary.is_empty());
+        session.set_summary(&summary).unwrap();
+        assert_eq!(session.summary(), Some(summary.as_str()));
+    }
+
+    #[test]
+    fn test_summary_transcript_is_bounded() {
+        let history = vec![
+            Message::user("Fix the login bug"),
+            Message::assistant_with_blocks(vec![ContentBlock::tool_use("toolu_1", "Read", serde_json::json!({}))]),
+        ];
+        let transcript = summary_transcript(&history, 1000);
+        assert_eq!(transcript, "User: Fix the login bug\nAssistant: [Using tool: Read]\n");
+
+        let mut history = vec![Message::user("Fix the login bug")];
+        for i in 0..200 {
+            history.push(Message::assistant(format!("Step {} done ✓", i)));
+            history.push(Message::user("Continue"));
+        }
+        history.push(Message::assistant("All fixed"));
+        let transcript = summary_transcript(&history, 1000);
+        assert!(transcript.len() <= 1000 + 40);
+        assert!(transcript.starts_with("User: Fix the login bug\n"));
+        assert!(transcript.contains(" bytes omitted ...]"));
+        assert!(transcript.ends_with("Assistant: All fixed\n"));
+    }
+
+    #[tokio::test]
+    async fn test_allowed_tools_rejects_other_tools() {
+        use crate::tools::{FnTool, ToolRegistry};
+        use serde_json::json;
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let edited = Arc::new(AtomicBool::new(false));
+        let flag = edited.clone();
+        let mut tools = ToolRegistry::new();
+        tools.register(
+            FnTool::new("Read", "Read", json!({}), vec![], |_| async { Ok(ToolResult::success("read")) })
+                .with_permission(false),
+        );
+        tools.register(
+            FnTool::new("Edit", "Edit", json!({}), vec![], move |_| {
+                flag.store(true, Ordering::SeqCst);
+                async { Ok(ToolResult::success("edited")) }
+            })
+            .with_permission(false),
+        );
+        let llm = Arc::new(MockLlmProvider::new(vec![
+            vec![ContentBlock::tool_use("toolu_1", "Edit", json!({}))],
+            vec![ContentBlock::text("Done")],
+        ]));
+        let config = AgentConfig::new()
+            .with_auto_name(false)
+            .with_tools(Arc::new(tools))
+            .with_allowed_tools_per_turn(|_| vec!["Read".to_string()]);
+        let agent = StandardAgent::new(config, llm.clone());
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (mut internals, _output_rx, _) = test_internals(temp_dir.path());
+        agent.step(&mut internals, "Edit the file").await;
+
+        assert!(!edited.load(Ordering::SeqCst));
+        assert_eq!(llm.requests()[0].tool_names, vec!["Read"]);
+        let session = internals.session.read().await;
+        let ContentBlock::ToolResult { content, is_error, .. } = &session.history()[2].content.blocks()[0] else {
+            panic!("expected a tool result");
+        };
+        assert_eq!(*is_error, Some(true));
+        assert!(content.as_ref().unwrap().text().contains("Available tools: Read"));
+    }
+
+    #[tokio::test]
+    async fn test_tool_loop_guard() {
+        use crate::tools::{FnTool, ToolRegistry};
+        use serde_json::json;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let runs = Arc::new(AtomicUsize::new(0));
+        let counter = runs.clone();
+        let mut tools = ToolRegistry::new();
+        tools.register(
+            FnTool::new("Read", "Read", json!({}), vec![], move |input| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                async move { Ok(ToolResult::success(format!("contents of {}", input["file"]))) }
+            })
+            .with_permission(false),
+        );
+        let read = |id: &str, file: &str| vec![ContentBlock::tool_use(id, "Read", json!({ "file": file }))];
+        let llm = MockLlmProvider::new(vec![
+            read("toolu_1", "a.rs"),
+            read("toolu_2", "a.rs"),
+            read("toolu_3", "a.rs"),
+            read("toolu_4", "b.rs"),
+            vec![ContentBlock::text("Done")],
+        ]);
+        let config = AgentConfig::new()
+            .with_auto_name(false)
+            .with_tools(Arc::new(tools))
+            .with_tool_loop_guard(2)
+            .with_dangerous_skip_permissions(true);
+        let agent = StandardAgent::new(config, Arc::new(llm));
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (mut internals, _output_rx, _) = test_internals(temp_dir.path());
+        agent.start(&mut internals).await;
+        agent.step(&mut internals, "Read a.rs").await;
+
+        // Repeats of a.rs are answered from the first result; b.rs runs again
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+        let session = internals.session.read().await;
+        let result_text = |index: usize| match &session.history()[index].content.blocks()[0] {
+            ContentBlock::ToolResult { content, .. } => content.as_ref().unwrap().text(),
+            _ => panic!("expected a tool result"),
+        };
+        assert_eq!(result_text(2), "contents of \"a.rs\"");
+        assert!(result_text(4).starts_with("contents of \"a.rs\""));
+        assert!(result_text(4).contains("called 2 times in a row"));
+        assert!(result_text(4).contains("from the previous call"));
+        assert!(result_text(6).contains("called 3 times in a row"));
+        assert_eq!(result_text(8), "contents of \"b.rs\"");
+    }
+
+    #[tokio::test]
+    async fn test_request_interceptor_sees_each_request() {
+        use crate::tools::{FnTool, ToolRegistry};
+        use serde_json::json;
+        use std::sync::Mutex;
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let seen_by_interceptor = seen.clone();
+        let mut tools = ToolRegistry::new();
+        tools.register(
+            FnTool::new("Echo", "Echo", json!({}), vec![], |_| async { Ok(ToolResult::success("echoed")) })
+                .with_permission(false),
+        );
+        let llm = Arc::new(MockLlmProvider::new(vec![
+            vec![ContentBlock::tool_use("toolu_1", "Echo", json!({}))],
+            vec![ContentBlock::text("Done")],
+        ]));
+        let config = AgentConfig::new()
+            .with_auto_name(false)
+            .with_tools(Arc::new(tools))
+            .with_request_interceptor(move |request| {
+                let tools = request.tools.as_deref().unwrap_or_default();
+                seen_by_interceptor
+                    .lock()
+                    .unwrap()
+                    .push((request.messages.len(), tools.len()));
+                request.messages.push(Message::user("Intercepted note"));
+            });
+        let agent = StandardAgent::new(config, llm.clone());
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (mut internals, _output_rx, _) = test_internals(temp_dir.path());
+        agent.step(&mut internals, "Echo something").await;
+
+        assert_eq!(*seen.lock().unwrap(), vec![(1, 1), (3, 1)]);
+        // Edits apply to the outgoing request only, not the stored history
+        let requests = llm.requests();
+        assert_eq!(requests[1].messages.last().unwrap().text(), Some("Intercepted note"));
+        assert_eq!(internals.session.read().await.history().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_pre_tool_use_hook_messages() {
+        use crate::hooks::{HookEvent, HookRegistry, HookResult};
+        use crate::tools::{FnTool, ToolRegistry};
+        use serde_json::json;
+
+        let mut tools = ToolRegistry::new();
+        tools.register(
+            FnTool::new("Echo", "Echo", json!({}), vec![], |_| async { Ok(ToolResult::success("echoed")) })
+                .with_permission(false),
+        );
+        let mut hooks = HookRegistry::new();
+        hooks
+            .add_with_pattern(HookEvent::PreToolUse, "Echo", |_: &mut HookContext| {
+                HookResult::none().with_message("Reminder: echo responsibly.")
+            })
+            .unwrap();
+        let llm = MockLlmProvider::new(vec![
+            vec![ContentBlock::tool_use("toolu_1", "Echo", json!({}))],
+            vec![ContentBlock::text("Done")],
+        ]);
+        let config = AgentConfig::new()
+            .with_auto_name(false)
+            .with_tools(Arc::new(tools))
+            .with_hooks(hooks);
+        let agent = StandardAgent::new(config, Arc::new(llm));
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (mut internals, _output_rx, _) = test_internals(temp_dir.path());
+        agent.step(&mut internals, "Echo something").await;
+
+        let session = internals.session.read().await;
+        let blocks = session.history()[2].content.blocks();
+        assert!(matches!(blocks[0], ContentBlock::ToolResult { .. }));
+        assert_eq!(blocks[1].as_text(), Some("Reminder: echo responsibly."));
+    }
+
+    #[tokio::test]
+    async fn test_text_delta_hook_stops_response() {
+        use crate::hooks::{HookEvent, HookRegistry, HookResult};
+        use crate::tools::{FnTool, ToolRegistry};
+        use serde_json::json;
+
+        let mut tools = ToolRegistry::new();
+        tools.register(
+            FnTool::new("Echo", "Echo", json!({}), vec![], |_| async { Ok(ToolResult::success("echoed")) })
+                .with_permission(false),
+        );
+        let mut hooks = HookRegistry::new();
+        hooks.add(HookEvent::AssistantTextDelta, |ctx: &mut HookContext| {
+            match ctx.assistant_text {
+                Some(text) if text.contains("secret") => HookResult::deny("leaked a secret"),
+                _ => HookResult::none(),
+            }
+        });
+        let llm = MockLlmProvider::new(vec![vec![
+            ContentBlock::text("The secret is 42"),
+            ContentBlock::tool_use("toolu_1", "Echo", json!({})),
+        ]]);
+        let config = AgentConfig::new()
+            .with_auto_name(false)
+            .with_tools(Arc::new(tools))
+            .with_hooks(hooks)
+            .with_streaming(true);
+        let agent = StandardAgent::new(config, Arc::new(llm));
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (mut internals, mut output_rx, _) = test_internals(temp_dir.path());
+        agent.step(&mut internals, "Tell me").await;
+
+        // The tool call after the blocked text never runs
+        while let Ok(chunk) = output_rx.try_recv() {
+            assert!(!matches!(chunk, OutputChunk::ToolStart { .. }), "A tool ran");
+        }
+        let session = internals.session.read().await;
+        let history = session.history();
+        assert_eq!(history.len(), 2);
+        assert!(!history[1].has_tool_use());
+        assert!(history[1].content.text().contains("stopped by a content filter: leaked a secret"));
+    }
+
+    #[tokio::test]
+    async fn test_batch_permissions() {
+        use crate::hooks::{HookEvent, HookRegistry, HookResult};
+        use crate::tools::{FnTool, ToolRegistry};
+        use serde_json::json;
+
+        /// Runs one response with Write, Delete and Format calls, answering the batch with `answer`
+        async fn run(answer: InputMessage) -> (Vec<Vec<crate::core::PendingToolCall>>, Vec<(bool, String)>) {
+            let mut tools = ToolRegistry::new();
+            for name in ["Write", "Delete", "Format"] {
+                tools.register(FnTool::new(name, name, json!({}), vec![], |input| async move {
+                    Ok(ToolResult::success(input.to_string()))
+                }));
+            }
+            let mut hooks = HookRegistry::new();
+            hooks
+                .add_with_pattern(HookEvent::PreToolUse, "Write", |ctx: &mut HookContext| {
+                    ctx.tool_input = Some(json!({"path": "sandbox/a.txt"}));
+                    HookResult::none()
+                })
+                .unwrap();
+            hooks
+                .add_with_pattern(HookEvent::PreToolUse, "Format", |_: &mut HookContext| HookResult::allow())
+                .unwrap();
+            let llm = MockLlmProvider::new(vec![
+                vec![
+                    ContentBlock::tool_use("toolu_1", "Write", json!({"path": "a.txt"})),
+                    ContentBlock::tool_use("toolu_2", "Delete", json!({"path": "b.txt"})),
+                    ContentBlock::tool_use("toolu_3", "Format", json!({})),
+                ],
+                vec![ContentBlock::text("Done")],
+            ]);
+            let config = AgentConfig::new()
+                .with_auto_name(false)
+                .with_tools(Arc::new(tools))
+                .with_hooks(hooks)
+                .with_batch_permissions(true);
+            let agent = StandardAgent::new(config, Arc::new(llm));
+
+            let temp_dir = tempfile::TempDir::new().unwrap();
+            let (mut internals, mut output_rx, input_tx) = test_internals(temp_dir.path());
+            input_tx.send(answer).await.unwrap();
+            agent.step(&mut internals, "Tidy up").await;
+
+            let mut requests = Vec::new();
+            while let Ok(chunk) = output_rx.try_recv() {
+                match chunk {
+                    OutputChunk::BatchPermissionRequest { calls } => requests.push(calls),
+                    OutputChunk::PermissionRequest { tool_name, .. } => panic!("Asked separately for {}", tool_name),
+                    _ => {}
+                }
+            }
+            let session = internals.session.read().await;
+            let results = session.history()[2]
+                .content
+                .blocks()
+                .iter()
+                .map(|block| match block {
+                    ContentBlock::ToolResult { is_error, content, .. } => {
+                        (is_error.unwrap_or(false), serde_json::to_string(content).unwrap())
+                    }
+                    other => panic!("Expected a tool result, got {:?}", other),
+                })
+                .collect();
+            (requests, results)
+        }
+
+        let (requests, results) = run(InputMessage::batch_permission(["toolu_1"])).await;
+        // Format was allowed by its hook, so only Write and Delete are asked about,
+        // and Write is shown and run with the input its hook produced
+        assert_eq!(requests.len(), 1);
+        let ids: Vec<&str> = requests[0].iter().map(|call| call.id.as_str()).collect();
+        assert_eq!(ids, ["toolu_1", "toolu_2"]);
+        assert!(requests[0][0].input.contains("sandbox/a.txt"));
+        assert_eq!(results.iter().map(|(is_error, _)| *is_error).collect::<Vec<_>>(), [false, true, false]);
+        assert!(results[0].1.contains("sandbox/a.txt"));
+
+        // A batch that ends without an answer only fails the calls in it
+        let (_, results) = run(InputMessage::Shutdown).await;
+        assert_eq!(results.iter().map(|(is_error, _)| *is_error).collect::<Vec<_>>(), [true, true, false]);
+        assert!(results[1].1.contains("Shutdown"));
+    }
+
+    #[tokio::test]
+    async fn test_response_postprocessor() {
+        for streaming in [true, false] {
+            let temp_dir = tempfile::TempDir::new().unwrap();
+            let (mut internals, mut output_rx, _) = test_internals(temp_dir.path());
+            let llm = MockLlmProvider::new(vec![vec![ContentBlock::text("Hello there")]]);
+            let config = AgentConfig::new()
+                .with_auto_name(false)
+                .with_streaming(streaming)
+                .with_response_postprocessor(|text| format!("{} -- bot", text.to_uppercase()));
+            let agent = StandardAgent::new(config, Arc::new(llm));
+            agent.step(&mut internals, "Hi").await;
+
+            let session = internals.session.read().await;
+            assert_eq!(session.history()[1].content.text(), "HELLO THERE -- bot");
+
+            let mut deltas = Vec::new();
+            while let Ok(chunk) = output_rx.try_recv() {
+                if let OutputChunk::TextDelta(text) = chunk {
+                    deltas.push(text);
+                }
+            }
+            assert_eq!(deltas, vec!["HELLO THERE -- bot"], "streaming: {}", streaming);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_postprocessor_skips_filter_note() {
+        use crate::hooks::{HookEvent, HookRegistry, HookResult};
+
+        for streaming in [true, false] {
+            let mut hooks = HookRegistry::new();
+            hooks.add(HookEvent::AssistantTextDelta, |ctx: &mut HookContext| {
+                match ctx.text_delta {
+                    Some(text) if text.contains("secret") => HookResult::deny("leaked a secret"),
+                    _ => HookResult::none(),
+                }
+            });
+            let llm = MockLlmProvider::new(vec![vec![
+                ContentBlock::text("<scratch>"),
+                ContentBlock::text("Intro"),
+                ContentBlock::text("The secret is 42"),
+            ]]);
+            let config = AgentConfig::new()
+                .with_auto_name(false)
+                .with_streaming(streaming)
+                .with_hooks(hooks)
+                .with_response_postprocessor(|text| text.replace("<scratch>", "").to_uppercase());
+            let agent = StandardAgent::new(config, Arc::new(llm));
+
+            let temp_dir = tempfile::TempDir::new().unwrap();
+            let (mut internals, _output_rx, _) = test_internals(temp_dir.path());
+            agent.step(&mut internals, "Tell me").await;
+
+            let session = internals.session.read().await;
+            let texts: Vec<_> = session.history()[1]
+                .content
+                .blocks()
+                .iter()
+                .filter_map(|block| block.as_text())
+                .collect();
+            assert_eq!(texts.len(), 2, "streaming: {}", streaming);
+            assert_eq!(texts[0], "INTRO");
+            assert!(texts[1].contains("stopped by a content filter: leaked a secret"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_response_prefill() {
+        for streaming in [true, false] {
+            let temp_dir = tempfile::TempDir::new().unwrap();
+            let (mut internals, _output_rx, _) = test_internals(temp_dir.path());
+            let llm = Arc::new(
+                MockLlmProvider::new(vec![vec![ContentBlock::text("\"ok\": true}")]]).with_provider_name("anthropic"),
+            );
+            let config = AgentConfig::new()
+                .with_auto_name(false)
+                .with_streaming(streaming)
+                .with_response_prefill("{");
+            let agent = StandardAgent::new(config, llm.clone());
+            agent.step(&mut internals, "Reply in JSON").await;
+
+            // The prefill is sent as the start of the assistant turn and kept in the history
+            let sent = &llm.requests()[0].messages;
+            assert_eq!(sent.last().unwrap().role, "assistant", "streaming: {}", streaming);
+            assert_eq!(sent.last().unwrap().text(), Some("{"));
+            let session = internals.session.read().await;
+            assert_eq!(session.history().len(), 2);
+            assert_eq!(session.history()[1].content.text(), "{\"ok\": true}", "streaming: {}", streaming);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_response_prefill_skipped_without_support() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (mut internals, _output_rx, _) = test_internals(temp_dir.path());
+        let llm = Arc::new(MockLlmProvider::new(vec![vec![ContentBlock::text("Hi")]]));
+        let config = AgentConfig::new().with_auto_name(false).with_response_prefill("{");
+        let agent = StandardAgent::new(config, llm.clone());
+        agent.step(&mut internals, "Hello").await;
+
+        assert_eq!(llm.requests()[0].messages.last().unwrap().role, "user");
+        let session = internals.session.read().await;
+        assert_eq!(session.history()[1].content.text(), "Hi");
+    }
+
+    #[tokio::test]
+    async fn test_user_id_reaches_provider() {
+        for streaming in [true, false] {
+            let temp_dir = tempfile::TempDir::new().unwrap();
+            let (mut internals, _output_rx, _) = test_internals(temp_dir.path());
+            let llm = Arc::new(MockLlmProvider::new(vec![vec![ContentBlock::text("Hi")]]));
+            let config = AgentConfig::new()
+                .with_auto_name(false)
+                .with_streaming(streaming)
+                .with_user_id("user-1234");
+            let agent = StandardAgent::new(config, llm.clone());
+            agent.step(&mut internals, "Hi").await;
+
+            assert_eq!(llm.requests()[0].user_id.as_deref(), Some("user-1234"), "streaming: {}", streaming);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_system_blocks() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (mut internals, _output_rx, _) = test_internals(temp_dir.path());
+        let llm = Arc::new(MockLlmProvider::new(vec![vec![ContentBlock::text("Hi")]]));
+        let config = AgentConfig::new().with_auto_name(false).with_system_blocks(vec![
+            ("Stable", true),
+            ("Also stable", true),
+            ("More", true),
+            ("Today", false),
+        ]);
+        let agent = StandardAgent::new(config, llm.clone());
+        agent.step(&mut internals, "Hi").await;
+
+        let Some(SystemPrompt::Blocks(ref blocks)) = llm.requests()[0].system else {
+            panic!("expected system blocks");
+        };
+        let cached: Vec<bool> = blocks.iter().map(|b| b.cache_control.is_some()).collect();
+        assert_eq!(cached, vec![true, true, false, false]);
+        assert_eq!(blocks[3].text, "Today");
+        assert_eq!(
+            internals.session.read().await.system_prompt(),
+            "Stable\n\nAlso stable\n\nMore\n\nToday"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_max_request_messages() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (mut internals, _output_rx, _) = test_internals(temp_dir.path());
+        let llm = Arc::new(MockLlmProvider::new(vec![vec![ContentBlock::text("Hi")]]));
+        let config = AgentConfig::new().with_auto_name(false).with_max_request_messages(2);
+        let agent = StandardAgent::new(config, llm.clone());
+
+        assert_eq!(agent.step(&mut internals, "One").await.reason, TurnEndReason::EndTurn);
+        assert_eq!(agent.step(&mut internals, "Two").await.reason, TurnEndReason::Error);
+        assert_eq!(llm.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_session_preamble_written_once() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let agent = |responses| {
+            let config = AgentConfig::new()
+                .with_auto_name(false)
+                .with_session_preamble("Project uses tabs.");
+            StandardAgent::new(config, Arc::new(MockLlmProvider::new(responses)))
+        };
+
+        let (mut internals, _output_rx, _) = test_internals(temp_dir.path());
+        let first = agent(vec![vec![ContentBlock::text("Noted")]]);
+        first.start(&mut internals).await;
+        first.step(&mut internals, "Hello").await;
+        drop(internals);
+
+        // Resuming the session does not add the preamble again
+        let (mut internals, _output_rx, _) = test_internals(temp_dir.path());
+        let second = agent(vec![vec![ContentBlock::text("Done")]]);
+        second.start(&mut internals).await;
+        second.step(&mut internals, "Again").await;
+        let session = internals.session.read().await;
+        let history = session.history();
+        assert_eq!(history.len(), 4);
+
+        // It leads the first user message instead of being a turn of its own
+        let first = history[0].blocks().unwrap();
+        assert_eq!(first.len(), 2);
+        assert!(crate::helpers::is_session_preamble(&first[0]));
+        assert!(first[0].as_text().unwrap().contains("Project uses tabs."));
+        assert_eq!(first[1].as_text(), Some("Hello"));
+        assert_eq!(history[2].text(), Some("Again"));
+
+        // Consumers of what the user said leave it out
+        assert_eq!(ConversationNamer::format_messages(&history[..2]), "User: Hello\nAssistant: Noted\n");
     }
 }
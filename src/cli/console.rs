@@ -1,50 +1,135 @@
 use colored::*;
-use std::io::{self, Write};
+use std::io;
 use std::sync::Arc;
+use tokio::sync::oneshot;
 
-use crate::helpers::{TodoItem, TodoListManager, TodoStatus};
+use crate::core::PendingToolCall;
+use crate::helpers::{truncate_bytes_safe, TodoItem, TodoListManager, TodoStatus};
 use crate::permissions::{PermissionDecision, PermissionRequest};
 
+use super::sink::{ConsoleSink, StdoutSink};
+
+/// Colors used by the console for each kind of output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConsoleTheme {
+    /// User label and input prompt
+    pub user: Color,
+    /// Assistant label and response text
+    pub assistant: Color,
+    /// System messages and permission prompts
+    pub system: Color,
+    /// Errors and denials
+    pub error: Color,
+    /// Tool names
+    pub tool: Color,
+    /// Thinking block headers
+    pub thinking: Color,
+    /// Secondary text: tool output, thinking content, separators
+    pub muted: Color,
+}
+
+impl Default for ConsoleTheme {
+    fn default() -> Self {
+        Self {
+            user: Color::Cyan,
+            assistant: Color::Green,
+            system: Color::Yellow,
+            error: Color::Red,
+            tool: Color::Magenta,
+            thinking: Color::BrightBlue,
+            muted: Color::BrightBlack,
+        }
+    }
+}
+
 /// Console handles all terminal I/O with colored formatting
-pub struct Console {
-    user_color: Color,
-    assistant_color: Color,
-    tool_color: Color,
+///
+/// Output goes through a [`ConsoleSink`]; the default writes to the terminal.
+/// Colors are used only when the sink supports them (for stdout: a terminal
+/// with `NO_COLOR` unset), unless overridden with [`with_color`](Console::with_color).
+pub struct Console<S: ConsoleSink = StdoutSink> {
+    theme: ConsoleTheme,
+    /// Whether to emit ANSI colors
+    color_enabled: bool,
     /// Optional todo list manager for display
     todo_manager: Option<Arc<TodoListManager>>,
+    /// Where output is written and input is read from
+    sink: Arc<S>,
 }
 
 impl Console {
     /// Create a new Console with default colors
     pub fn new() -> Self {
-        Self {
-            user_color: Color::Cyan,
-            assistant_color: Color::Green,
-            tool_color: Color::Magenta,
-            todo_manager: None,
-        }
+        Self::with_sink(StdoutSink)
     }
 
     /// Create a new Console with a TodoListManager
     pub fn with_todo_manager(manager: Arc<TodoListManager>) -> Self {
-        Self {
-            user_color: Color::Cyan,
-            assistant_color: Color::Green,
-            tool_color: Color::Magenta,
-            todo_manager: Some(manager),
-        }
+        let mut console = Self::new();
+        console.todo_manager = Some(manager);
+        console
     }
 
     /// Create a new Console with custom colors
+    ///
+    /// Use [`with_theme`](Console::with_theme) to set the other colors.
     pub fn with_colors(user_color: Color, assistant_color: Color, tool_color: Color) -> Self {
+        Self::new().with_theme(ConsoleTheme {
+            user: user_color,
+            assistant: assistant_color,
+            tool: tool_color,
+            ..ConsoleTheme::default()
+        })
+    }
+}
+
+impl<S: ConsoleSink> Console<S> {
+    /// Create a new Console with default colors that writes to `sink`
+    pub fn with_sink(sink: S) -> Self {
         Self {
-            user_color,
-            assistant_color,
-            tool_color,
+            theme: ConsoleTheme::default(),
+            color_enabled: sink.supports_color(),
             todo_manager: None,
+            sink: Arc::new(sink),
+        }
+    }
+
+    /// Set the colors for each kind of output
+    pub fn with_theme(mut self, theme: ConsoleTheme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Force colors on or off, overriding the sink's detection
+    pub fn with_color(mut self, enabled: bool) -> Self {
+        self.color_enabled = enabled;
+        self
+    }
+
+    /// Get the current theme
+    pub fn theme(&self) -> &ConsoleTheme {
+        &self.theme
+    }
+
+    /// Whether ANSI colors are emitted
+    pub fn color_enabled(&self) -> bool {
+        self.color_enabled
+    }
+
+    /// Drop the styling from `text` when colors are disabled
+    fn paint(&self, text: ColoredString) -> ColoredString {
+        if self.color_enabled {
+            text
+        } else {
+            text.clear()
         }
     }
 
+    /// Get the sink output is written to
+    pub fn sink(&self) -> &S {
+        &self.sink
+    }
+
     /// Set the todo manager
     pub fn set_todo_manager(&mut self, manager: Arc<TodoListManager>) {
         self.todo_manager = Some(manager);
@@ -52,95 +137,119 @@ impl Console {
 
     /// Print a user message with colored formatting
     pub fn print_user(&self, message: &str) {
-        println!("{} {}", "User:".color(self.user_color).bold(), message);
+        self.sink.write_line(&format!("{} {}", self.paint("User:".color(self.theme.user).bold()), message));
     }
 
     /// Print an assistant message prefix (without newline)
     pub fn print_assistant_prefix(&self) {
-        print!("{} ", "Assistant:".color(self.assistant_color).bold());
-        io::stdout().flush().unwrap();
+        self.sink.prompt(&format!("{} ", self.paint("Assistant:".color(self.theme.assistant).bold())));
     }
 
     /// Print a chunk of assistant response (for streaming)
     pub fn print_assistant_chunk(&self, chunk: &str) {
-        print!("{}", chunk.color(self.assistant_color));
-        io::stdout().flush().unwrap();
+        self.sink.prompt(&self.paint(chunk.color(self.theme.assistant)).to_string());
     }
 
     /// Print a complete assistant message with colored formatting
     pub fn print_assistant(&self, message: &str) {
-        println!(
+        self.sink.write_line(&format!(
             "{} {}",
-            "Assistant:".color(self.assistant_color).bold(),
-            message.color(self.assistant_color)
-        );
+            self.paint("Assistant:".color(self.theme.assistant).bold()),
+            self.paint(message.color(self.theme.assistant))
+        ));
     }
 
     /// Print a newline
     pub fn println(&self) {
-        println!();
+        self.sink.write_line("");
     }
 
     /// Print a system message (errors, info, etc.)
     pub fn print_system(&self, message: &str) {
-        println!("{} {}", "System:".yellow().bold(), message);
+        self.sink.write_line(&format!("{} {}", self.paint("System:".color(self.theme.system).bold()), message));
     }
 
     /// Print an error message
     pub fn print_error(&self, error: &str) {
-        eprintln!("{} {}", "Error:".red().bold(), error);
+        self.sink.write_error_line(&format!("{} {}", self.paint("Error:".color(self.theme.error).bold()), error));
     }
 
     /// Read a line of input from the user
     pub fn read_input(&self) -> io::Result<String> {
-        print!("{} ", ">".color(self.user_color).bold());
-        io::stdout().flush()?;
+        self.sink.prompt(&format!("{} ", self.paint(">".color(self.theme.user).bold())));
 
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
+        let input = self.sink.read_line()?;
         Ok(input.trim().to_string())
     }
 
+    /// Show the input prompt and read the next line on a separate thread
+    ///
+    /// Lets the caller wait for input with a timeout. The read can't be
+    /// cancelled, so a timed-out receiver should be kept and awaited later
+    /// rather than starting a second read.
+    pub(crate) fn spawn_read_input(&self) -> oneshot::Receiver<io::Result<String>> {
+        self.sink.prompt(&format!("{} ", self.paint(">".color(self.theme.user).bold())));
+
+        let (tx, rx) = oneshot::channel();
+        let sink = self.sink.clone();
+        // A plain thread, so a pending read never holds up runtime shutdown
+        std::thread::spawn(move || {
+            let _ = tx.send(sink.read_line().map(|input| input.trim().to_string()));
+        });
+        rx
+    }
+
     /// Print a welcome banner
     pub fn print_banner(&self) {
-        println!("{}", "=".repeat(60).bright_blue());
-        println!(
-            "{}",
-            "  Coding Agent - Powered by Claude".bright_blue().bold()
-        );
-        println!("{}", "=".repeat(60).bright_blue());
-        println!();
-        println!("Type your message and press Enter. Type 'exit' or 'quit' to end the session.");
-        println!();
+        self.sink.write_line(&self.paint("=".repeat(60).bright_blue()).to_string());
+        self.sink.write_line(&self.paint("  Coding Agent - Powered by Claude".bright_blue().bold()).to_string());
+        self.sink.write_line(&self.paint("=".repeat(60).bright_blue()).to_string());
+        self.sink.write_line("");
+        self.sink.write_line("Type your message and press Enter. Type 'exit' or 'quit' to end the session.");
+        self.sink.write_line("");
     }
 
     /// Print a separator line
     pub fn print_separator(&self) {
-        println!("{}", "-".repeat(60).bright_black());
+        self.sink.write_line(&self.paint("-".repeat(60).color(self.theme.muted)).to_string());
     }
 
     /// Print a tool action message
     pub fn print_tool_action(&self, tool_name: &str, action: &str) {
-        println!(
+        self.sink.write_line(&format!(
             "{} {} {}",
-            "Tool:".color(self.tool_color).bold(),
-            format!("[{}]", tool_name).color(self.tool_color),
+            self.paint("Tool:".color(self.theme.tool).bold()),
+            self.paint(format!("[{}]", tool_name).color(self.theme.tool)),
             action
-        );
+        ));
+    }
+
+    /// Print streamed tool output as-is
+    pub fn print_tool_progress(&self, output: &str) {
+        self.sink.prompt(output);
     }
 
     /// Print a tool result
     pub fn print_tool_result(&self, result: &str, is_error: bool) {
         if is_error {
-            println!("{} {}", "Tool Error:".red().bold(), result);
+            self.sink.write_line(&format!("{} {}", self.paint("Tool Error:".color(self.theme.error).bold()), result));
         } else {
             // Truncate long output
             let display = if result.len() > 500 {
-                format!("{}...\n(output truncated)", &result[..500])
+                format!("{}...\n(output truncated)", truncate_bytes_safe(result, 500))
             } else {
                 result.to_string()
             };
-            println!("{}", display.bright_black());
+            self.sink.write_line(&self.paint(display.color(self.theme.muted)).to_string());
+        }
+    }
+
+    /// Print subagent activity, labelled with its session and indented by nesting depth
+    pub fn print_subagent(&self, session_id: &str, depth: usize, message: &str) {
+        let indent = "  ".repeat(depth);
+        let label = self.paint(format!("[{}]", session_id).color(self.theme.tool));
+        for line in message.lines() {
+            self.sink.write_line(&format!("{}{} {}", indent, label, self.paint(line.color(self.theme.muted))));
         }
     }
 
@@ -148,31 +257,28 @@ impl Console {
     ///
     /// Returns the user's decision: Allow, Deny, AlwaysAllow, or AlwaysDeny
     pub fn ask_permission(&self, request: &PermissionRequest) -> io::Result<PermissionDecision> {
-        println!();
-        println!("{}", "─".repeat(60).yellow());
-        println!(
+        self.sink.write_line("");
+        self.sink.write_line(&self.paint("─".repeat(60).color(self.theme.system)).to_string());
+        self.sink.write_line(&format!(
             "{} The agent wants to use tool: {}",
-            "Permission Required".yellow().bold(),
-            request.tool_name.color(self.tool_color).bold()
-        );
-        println!();
-        println!("  {}", request.action_description);
+            self.paint("Permission Required".color(self.theme.system).bold()),
+            self.paint(request.tool_name.color(self.theme.tool).bold())
+        ));
+        self.sink.write_line("");
+        self.sink.write_line(&format!("  {}", request.action_description));
         if let Some(ref details) = request.details {
-            println!("  {}", details.bright_black());
+            self.sink.write_line(&format!("  {}", self.paint(details.color(self.theme.muted))));
         }
-        println!();
-        println!("{}", "Options:".yellow());
-        println!("  [y] Allow this action");
-        println!("  [n] Deny this action");
-        println!("  [a] Always allow this tool");
-        println!("  [d] Always deny this tool");
-        println!("{}", "─".repeat(60).yellow());
-        print!("{} ", "Your choice (y/n/a/d):".yellow().bold());
-        io::stdout().flush()?;
-
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-        let input = input.trim().to_lowercase();
+        self.sink.write_line("");
+        self.sink.write_line(&self.paint("Options:".color(self.theme.system)).to_string());
+        self.sink.write_line("  [y] Allow this action");
+        self.sink.write_line("  [n] Deny this action");
+        self.sink.write_line("  [a] Always allow this tool");
+        self.sink.write_line("  [d] Always deny this tool");
+        self.sink.write_line(&self.paint("─".repeat(60).color(self.theme.system)).to_string());
+        self.sink.prompt(&format!("{} ", self.paint("Your choice (y/n/a/d):".color(self.theme.system).bold())));
+
+        let input = self.sink.read_line()?.trim().to_lowercase();
 
         let decision = match input.as_str() {
             "y" | "yes" => PermissionDecision::Allow,
@@ -180,7 +286,7 @@ impl Console {
             "a" | "always" => PermissionDecision::AlwaysAllow,
             "d" | "deny" | "never" => PermissionDecision::AlwaysDeny,
             _ => {
-                println!("{}", "Invalid choice. Defaulting to Deny.".red());
+                self.sink.write_line(&self.paint("Invalid choice. Defaulting to Deny.".color(self.theme.error)).to_string());
                 PermissionDecision::Deny
             }
         };
@@ -188,78 +294,134 @@ impl Console {
         // Print confirmation
         match decision {
             PermissionDecision::Allow => {
-                println!("{}", "✓ Allowed".green());
+                self.sink.write_line(&self.paint("✓ Allowed".green()).to_string());
             }
             PermissionDecision::Deny => {
-                println!("{}", "✗ Denied".red());
+                self.sink.write_line(&self.paint("✗ Denied".color(self.theme.error)).to_string());
             }
             PermissionDecision::AlwaysAllow => {
-                println!(
-                    "{}",
-                    format!("✓ Always allowing tool: {}", request.tool_name).green()
-                );
+                self.sink.write_line(&self.paint(format!("✓ Always allowing tool: {}", request.tool_name).green()).to_string());
             }
             PermissionDecision::AlwaysDeny => {
-                println!(
-                    "{}",
-                    format!("✗ Always denying tool: {}", request.tool_name).red()
-                );
+                self.sink.write_line(&self.paint(format!("✗ Always denying tool: {}", request.tool_name).color(self.theme.error)).to_string());
             }
         }
-        println!();
+        self.sink.write_line("");
 
         Ok(decision)
     }
 
+    /// Ask for permission to execute several tool calls at once
+    ///
+    /// The user allows all, denies all, or lists the numbers of the calls to
+    /// allow. Returns the IDs of the allowed calls.
+    pub fn ask_batch_permission(&self, calls: &[PendingToolCall]) -> io::Result<Vec<String>> {
+        self.sink.write_line("");
+        self.sink.write_line(&self.paint("─".repeat(60).color(self.theme.system)).to_string());
+        self.sink.write_line(&format!(
+            "{} The agent wants to run {} tool calls:",
+            self.paint("Permission Required".color(self.theme.system).bold()),
+            calls.len()
+        ));
+        self.sink.write_line("");
+        for (i, call) in calls.iter().enumerate() {
+            self.sink.write_line(&format!(
+                "  {}. {} {}",
+                i + 1,
+                self.paint(format!("[{}]", call.tool_name).color(self.theme.tool)),
+                call.action
+            ));
+            if let Some(ref details) = call.details {
+                self.sink.write_line(&format!("     {}", self.paint(details.color(self.theme.muted))));
+            }
+        }
+        self.sink.write_line("");
+        self.sink.write_line(&self.paint("Options:".color(self.theme.system)).to_string());
+        self.sink.write_line("  [y] Allow all");
+        self.sink.write_line("  [n] Deny all");
+        self.sink.write_line("  [1,3] Allow only the listed calls");
+        self.sink.write_line(&self.paint("─".repeat(60).color(self.theme.system)).to_string());
+        self.sink.prompt(&format!("{} ", self.paint("Your choice (y/n/numbers):".color(self.theme.system).bold())));
+
+        let input = self.sink.read_line()?.trim().to_lowercase();
+
+        let selected: Vec<usize> = match input.as_str() {
+            "y" | "yes" => (1..=calls.len()).collect(),
+            "n" | "no" => Vec::new(),
+            _ => {
+                let numbers: Option<Vec<usize>> = input
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|part| !part.is_empty())
+                    .map(|part| part.parse().ok().filter(|n| (1..=calls.len()).contains(n)))
+                    .collect();
+                numbers.unwrap_or_else(|| {
+                    self.sink.write_line(&self.paint("Invalid choice. Defaulting to Deny.".color(self.theme.error)).to_string());
+                    Vec::new()
+                })
+            }
+        };
+        let approved: Vec<String> = calls
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| selected.contains(&(i + 1)))
+            .map(|(_, call)| call.id.clone())
+            .collect();
+
+        // Print confirmation
+        if approved.is_empty() {
+            self.sink.write_line(&self.paint("✗ Denied".color(self.theme.error)).to_string());
+        } else {
+            self.sink.write_line(&self.paint(format!("✓ Allowed {} of {}", approved.len(), calls.len()).green()).to_string());
+        }
+        self.sink.write_line("");
+
+        Ok(approved)
+    }
+
     /// Print a thinking indicator
     pub fn print_thinking(&self) {
-        print!("{}", "Thinking...".bright_black());
-        io::stdout().flush().unwrap();
+        self.sink.prompt(&self.paint("Thinking...".color(self.theme.muted)).to_string());
     }
 
     /// Clear the thinking indicator
     pub fn clear_thinking(&self) {
-        print!("\r{}\r", " ".repeat(20));
-        io::stdout().flush().unwrap();
+        self.sink.prompt(&format!("\r{}\r", " ".repeat(20)));
     }
 
     /// Print a thinking block (extended thinking content) - all at once
     pub fn print_thinking_block(&self, thinking: &str) {
-        println!();
-        println!("{}", "─".repeat(60).bright_blue());
-        println!("{}", "💭 Agent Thinking:".bright_blue().bold());
-        println!("{}", "─".repeat(60).bright_blue());
+        self.sink.write_line("");
+        self.sink.write_line(&self.paint("─".repeat(60).color(self.theme.thinking)).to_string());
+        self.sink.write_line(&self.paint("💭 Agent Thinking:".color(self.theme.thinking).bold()).to_string());
+        self.sink.write_line(&self.paint("─".repeat(60).color(self.theme.thinking)).to_string());
 
         // Display the thinking content with some formatting
         for line in thinking.lines() {
-            println!("  {}", line.bright_black().italic());
+            self.sink.write_line(&format!("  {}", self.paint(line.color(self.theme.muted).italic())));
         }
 
-        println!("{}", "─".repeat(60).bright_blue());
-        println!();
+        self.sink.write_line(&self.paint("─".repeat(60).color(self.theme.thinking)).to_string());
+        self.sink.write_line("");
     }
 
     /// Print thinking prefix (header) for streaming thinking
     pub fn print_thinking_prefix(&self) {
-        println!();
-        println!("{}", "─".repeat(60).bright_blue());
-        println!("{}", "💭 Agent Thinking:".bright_blue().bold());
-        println!("{}", "─".repeat(60).bright_blue());
-        io::stdout().flush().unwrap();
+        self.sink.write_line("");
+        self.sink.write_line(&self.paint("─".repeat(60).color(self.theme.thinking)).to_string());
+        self.sink.write_line(&self.paint("💭 Agent Thinking:".color(self.theme.thinking).bold()).to_string());
+        self.sink.write_line(&self.paint("─".repeat(60).color(self.theme.thinking)).to_string());
     }
 
     /// Print a chunk of thinking content (for streaming)
     pub fn print_thinking_chunk(&self, chunk: &str) {
-        print!("{}", chunk.bright_black().italic());
-        io::stdout().flush().unwrap();
+        self.sink.prompt(&self.paint(chunk.color(self.theme.muted).italic()).to_string());
     }
 
     /// Print thinking suffix (footer) after streaming thinking completes
     pub fn print_thinking_suffix(&self) {
-        println!();
-        println!("{}", "─".repeat(60).bright_blue());
-        println!();
-        io::stdout().flush().unwrap();
+        self.sink.write_line("");
+        self.sink.write_line(&self.paint("─".repeat(60).color(self.theme.thinking)).to_string());
+        self.sink.write_line("");
     }
 
     /// Print the todo list status
@@ -273,13 +435,13 @@ impl Console {
                 return;
             }
 
-            println!();
-            println!("{}", "─".repeat(60).bright_black());
-            println!(
+            self.sink.write_line("");
+            self.sink.write_line(&self.paint("─".repeat(60).color(self.theme.muted)).to_string());
+            self.sink.write_line(&format!(
                 "{} · {}",
-                "Todos".bright_white().bold(),
-                "ctrl+t to hide todos".bright_black()
-            );
+                self.paint("Todos".bright_white().bold()),
+                self.paint("ctrl+t to hide todos".color(self.theme.muted))
+            ));
 
             for todo in todos.iter() {
                 let (icon, style) = match todo.status {
@@ -295,10 +457,10 @@ impl Console {
                     &todo.content
                 };
 
-                println!("  {} {}", icon.color(style), text.color(style));
+                self.sink.write_line(&format!("  {} {}", self.paint(icon.color(style)), self.paint(text.color(style))));
             }
 
-            println!("{}", "─".repeat(60).bright_black());
+            self.sink.write_line(&self.paint("─".repeat(60).color(self.theme.muted)).to_string());
         }
     }
 
@@ -310,13 +472,13 @@ impl Console {
             return;
         }
 
-        println!();
-        println!("{}", "─".repeat(60).bright_black());
-        println!(
+        self.sink.write_line("");
+        self.sink.write_line(&self.paint("─".repeat(60).color(self.theme.muted)).to_string());
+        self.sink.write_line(&format!(
             "{} · {}",
-            "Todos".bright_white().bold(),
-            "ctrl+t to hide todos".bright_black()
-        );
+            self.paint("Todos".bright_white().bold()),
+            self.paint("ctrl+t to hide todos".color(self.theme.muted))
+        ));
 
         for todo in todos.iter() {
             let (icon, style) = match todo.status {
@@ -332,10 +494,10 @@ impl Console {
                 &todo.content
             };
 
-            println!("  {} {}", icon.color(style), text.color(style));
+            self.sink.write_line(&format!("  {} {}", self.paint(icon.color(style)), self.paint(text.color(style))));
         }
 
-        println!("{}", "─".repeat(60).bright_black());
+        self.sink.write_line(&self.paint("─".repeat(60).color(self.theme.muted)).to_string());
     }
 
     /// Refresh the todo display (clear and reprint)
@@ -351,3 +513,76 @@ impl Default for Console {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::BufferSink;
+
+    #[test]
+    fn test_ask_permission_with_buffer_sink() {
+        let sink = BufferSink::with_input(["a"]);
+        let console = Console::with_sink(sink.clone());
+
+        let request = PermissionRequest {
+            tool_name: "Bash".to_string(),
+            action_description: "Run: ls".to_string(),
+            input: "ls".to_string(),
+            details: None,
+        };
+
+        let decision = console.ask_permission(&request).unwrap();
+        assert_eq!(decision, PermissionDecision::AlwaysAllow);
+
+        let output = sink.output();
+        assert!(output.contains("Run: ls"));
+        assert!(output.contains("Always allowing tool: Bash"));
+
+        // Queued input is exhausted
+        assert!(console.read_input().is_err());
+    }
+
+    #[test]
+    fn test_ask_batch_permission() {
+        let calls: Vec<PendingToolCall> = ["Write", "Bash", "FileOps"]
+            .iter()
+            .enumerate()
+            .map(|(i, tool)| PendingToolCall {
+                id: format!("call_{}", i + 1),
+                tool_name: tool.to_string(),
+                action: format!("Run {}", tool),
+                input: "{}".to_string(),
+                details: None,
+            })
+            .collect();
+        let sink = BufferSink::with_input(["1, 3", "y", "2,9"]);
+        let console = Console::with_sink(sink.clone());
+
+        assert_eq!(console.ask_batch_permission(&calls).unwrap(), ["call_1", "call_3"]);
+        assert!(sink.take_output().contains("3. [FileOps] Run FileOps"));
+        assert_eq!(console.ask_batch_permission(&calls).unwrap().len(), 3);
+        assert!(console.ask_batch_permission(&calls).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_color_disabled_for_buffer_sink() {
+        colored::control::set_override(true);
+
+        let sink = BufferSink::new();
+        let console = Console::with_sink(sink.clone());
+        assert!(!console.color_enabled());
+        console.print_system("plain");
+        assert_eq!(sink.take_output(), "System: plain\n");
+
+        let console = Console::with_sink(sink.clone())
+            .with_color(true)
+            .with_theme(ConsoleTheme {
+                system: Color::Blue,
+                ..ConsoleTheme::default()
+            });
+        console.print_system("colored");
+        assert!(sink.take_output().contains('\x1b'));
+
+        colored::control::unset_override();
+    }
+}
@@ -1,5 +1,7 @@
 pub mod console;
 pub mod renderer;
+pub mod sink;
 
-pub use console::Console;
-pub use renderer::ConsoleRenderer;
+pub use console::{Console, ConsoleTheme};
+pub use renderer::{ConsoleRenderer, IdleAction};
+pub use sink::{BufferSink, ConsoleSink, StdoutSink};
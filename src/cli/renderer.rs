@@ -8,15 +8,29 @@
 //!
 //! This can be replaced with other renderers (Tauri UI, Web UI, etc.)
 
-use std::io::{self, Write};
+use std::io;
 use std::sync::Arc;
+use std::time::Duration;
 
-use crate::core::{InputMessage, OutputChunk};
+use tokio::sync::{broadcast, oneshot};
+
+use crate::core::{AgentState, InputMessage, OutputChunk};
 use crate::helpers::TodoListManager;
+use crate::mcp::MCPServerManager;
 use crate::permissions::PermissionDecision;
 use crate::runtime::AgentHandle;
 
 use super::console::Console;
+use super::sink::{ConsoleSink, StdoutSink};
+
+/// What the renderer does when the idle timeout expires
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleAction {
+    /// Shut down the agent and return from `run()`
+    Exit,
+    /// Keep waiting; MCP servers reconnect on their next use
+    Hibernate,
+}
 
 /// Console renderer that subscribes to an agent and handles terminal I/O
 ///
@@ -27,18 +41,30 @@ use super::console::Console;
 /// let renderer = ConsoleRenderer::new(handle);
 /// renderer.run().await?;
 /// ```
-pub struct ConsoleRenderer {
+///
+/// To capture or redirect output, build it with a [`Console`] over another
+/// [`ConsoleSink`] via [`with_console`](ConsoleRenderer::with_console).
+pub struct ConsoleRenderer<S: ConsoleSink = StdoutSink> {
     /// The agent handle to communicate with
     handle: AgentHandle,
 
     /// The console for formatted output
-    console: Console,
+    console: Console<S>,
 
     /// Whether to show thinking blocks
     show_thinking: bool,
 
     /// Whether to show tool execution details
     show_tools: bool,
+
+    /// How long to wait for input before going idle (None = forever)
+    idle_timeout: Option<Duration>,
+
+    /// What to do when idle
+    idle_action: IdleAction,
+
+    /// MCP servers to disconnect when idle
+    mcp_manager: Option<Arc<MCPServerManager>>,
 }
 
 impl ConsoleRenderer {
@@ -49,16 +75,24 @@ impl ConsoleRenderer {
             console: Console::new(),
             show_thinking: true,
             show_tools: true,
+            idle_timeout: None,
+            idle_action: IdleAction::Hibernate,
+            mcp_manager: None,
         }
     }
+}
 
+impl<S: ConsoleSink> ConsoleRenderer<S> {
     /// Create a renderer with a custom console
-    pub fn with_console(handle: AgentHandle, console: Console) -> Self {
+    pub fn with_console(handle: AgentHandle, console: Console<S>) -> Self {
         Self {
             handle,
             console,
             show_thinking: true,
             show_tools: true,
+            idle_timeout: None,
+            idle_action: IdleAction::Hibernate,
+            mcp_manager: None,
         }
     }
 
@@ -74,6 +108,23 @@ impl ConsoleRenderer {
         self
     }
 
+    /// Go idle after `timeout` without user input (off by default)
+    ///
+    /// When idle, the session is saved and the MCP connections set with
+    /// [`with_mcp_manager`](Self::with_mcp_manager) are closed, then `action`
+    /// decides whether to exit or keep waiting for input.
+    pub fn with_idle_timeout(mut self, timeout: Duration, action: IdleAction) -> Self {
+        self.idle_timeout = Some(timeout);
+        self.idle_action = action;
+        self
+    }
+
+    /// Set the MCP servers to disconnect when the session goes idle
+    pub fn with_mcp_manager(mut self, manager: Arc<MCPServerManager>) -> Self {
+        self.mcp_manager = Some(manager);
+        self
+    }
+
     /// Set the todo manager for displaying task progress
     pub fn with_todo_manager(mut self, manager: Arc<TodoListManager>) -> Self {
         self.console.set_todo_manager(manager);
@@ -94,7 +145,13 @@ impl ConsoleRenderer {
 
         loop {
             // Read user input
-            let input = self.console.read_input()?;
+            let input = match self.idle_timeout {
+                Some(timeout) => match self.read_input_or_idle(timeout).await? {
+                    Some(input) => input,
+                    None => break,
+                },
+                None => self.console.read_input()?,
+            };
 
             // Check for exit commands
             if input.eq_ignore_ascii_case("exit") || input.eq_ignore_ascii_case("quit") {
@@ -125,6 +182,42 @@ impl ConsoleRenderer {
         Ok(())
     }
 
+    /// Read input, going idle if none arrives within `timeout`
+    ///
+    /// Returns `None` if the renderer should exit.
+    async fn read_input_or_idle(&self, timeout: Duration) -> io::Result<Option<String>> {
+        let mut pending = self.console.spawn_read_input();
+
+        if let Ok(result) = tokio::time::timeout(timeout, &mut pending).await {
+            return received_input(result).map(Some);
+        }
+
+        tracing::info!("[ConsoleRenderer] No input for {:?}, going idle", timeout);
+        if let Err(e) = self.handle.save_session().await {
+            tracing::warn!("[ConsoleRenderer] Failed to save idle session: {}", e);
+        }
+        if let Some(ref manager) = self.mcp_manager {
+            manager.disconnect_all().await;
+        }
+
+        match self.idle_action {
+            IdleAction::Exit => {
+                self.console.println();
+                self.console.print_system("Session idle, shutting down...");
+                let _ = self.handle.shutdown().await;
+                Ok(None)
+            }
+            IdleAction::Hibernate => {
+                self.console.println();
+                self.console.print_system("Session idle. Type a message to resume.");
+                // The read started above is still waiting for the next line
+                let input = received_input(pending.await)?;
+                tracing::info!("[ConsoleRenderer] Resuming from idle");
+                Ok(Some(input))
+            }
+        }
+    }
+
     /// Run a single turn - send input and render response
     ///
     /// Use this for programmatic interaction instead of the full loop.
@@ -144,10 +237,21 @@ impl ConsoleRenderer {
         let mut rx = self.handle.subscribe();
         let mut in_text = false;
         let mut in_thinking = false;
+        // Set after a lag until a prompt arrives, which proves none was lost
+        let mut unresolved_lag = false;
 
         loop {
             match rx.recv().await {
                 Ok(chunk) => {
+                    if matches!(
+                        chunk,
+                        OutputChunk::PermissionRequest { .. }
+                            | OutputChunk::BatchPermissionRequest { .. }
+                            | OutputChunk::AskUserQuestion { .. }
+                    ) {
+                        unresolved_lag = false;
+                    }
+
                     match chunk {
                         // Text streaming
                         OutputChunk::TextDelta(text) => {
@@ -193,8 +297,7 @@ impl ConsoleRenderer {
                         }
                         OutputChunk::ToolProgress { output, .. } => {
                             if self.show_tools {
-                                print!("{}", output);
-                                io::stdout().flush()?;
+                                self.console.print_tool_progress(&output);
                             }
                         }
                         OutputChunk::ToolEnd { result, .. } => {
@@ -213,6 +316,12 @@ impl ConsoleRenderer {
                             }
                         }
 
+                        OutputChunk::ToolInputError { name, error, .. } => {
+                            if self.show_tools {
+                                self.console.print_tool_result(&format!("Malformed input for {}: {}", name, error), true);
+                            }
+                        }
+
                         // Permission requests
                         OutputChunk::PermissionRequest { tool_name, action, input, details } => {
                             if in_text {
@@ -243,6 +352,16 @@ impl ConsoleRenderer {
                             let _ = self.handle.send_permission_response(&tool_name, allowed, remember).await;
                         }
 
+                        OutputChunk::BatchPermissionRequest { calls } => {
+                            if in_text {
+                                self.console.println();
+                                in_text = false;
+                            }
+
+                            let approved = self.console.ask_batch_permission(&calls)?;
+                            let _ = self.handle.send(InputMessage::batch_permission(approved)).await;
+                        }
+
                         // User questions
                         OutputChunk::AskUserQuestion { request_id, questions } => {
                             if in_text {
@@ -281,6 +400,9 @@ impl ConsoleRenderer {
                         }
 
                         // Completion
+                        OutputChunk::TurnComplete(outcome) => {
+                            tracing::debug!("Turn outcome: {:?}", outcome);
+                        }
                         OutputChunk::Done => {
                             if in_text {
                                 self.console.println();
@@ -306,30 +428,223 @@ impl ConsoleRenderer {
                                 "Subagent {} completed: {:?}", session_id, result
                             ));
                         }
-                        OutputChunk::SubAgentOutput { chunk, .. } => {
-                            // Could recursively render subagent output
-                            tracing::debug!("Subagent output: {:?}", chunk);
+                        OutputChunk::SubAgentOutput { session_id, chunk } => {
+                            if in_text {
+                                self.console.println();
+                                in_text = false;
+                            }
+                            self.render_subagent_chunk(&session_id, &chunk, 1);
                         }
                     }
                 }
-                Err(e) => {
-                    // Channel closed or lagged
-                    tracing::warn!("Output channel error: {}", e);
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    // Fell behind the output buffer; keep rendering from here
+                    tracing::warn!("[ConsoleRenderer] Skipped {} output chunks", missed);
+                    self.console.print_system(&format!("({} output chunks skipped)", missed));
+                    unresolved_lag = true;
+                }
+                Err(broadcast::error::RecvError::Closed) => {
+                    tracing::warn!("[ConsoleRenderer] Output channel closed");
                     break;
                 }
             }
+
+            // Once caught up, an agent still waiting for an answer means its
+            // prompt was among the skipped chunks and would wait forever
+            if unresolved_lag && rx.is_empty() && waits_for_answer(&self.handle.state().await) {
+                tracing::warn!("[ConsoleRenderer] A prompt was skipped; interrupting the agent");
+                self.console
+                    .print_error("A permission request or question was skipped while catching up; interrupting");
+                let _ = self.handle.interrupt().await;
+                unresolved_lag = false;
+            }
         }
 
         Ok(())
     }
 
+    /// Render one forwarded chunk of subagent output, indented by `depth`
+    ///
+    /// Only complete text and tool activity are shown, so concurrent
+    /// subagents don't interleave mid-line. Permission requests and
+    /// questions are left to whoever handles the subagent itself.
+    fn render_subagent_chunk(&self, session_id: &str, chunk: &OutputChunk, depth: usize) {
+        match chunk {
+            OutputChunk::SubAgentOutput { session_id, chunk } => {
+                self.render_subagent_chunk(session_id, chunk, depth + 1)
+            }
+            OutputChunk::TextComplete(text) => self.console.print_subagent(session_id, depth, text),
+            OutputChunk::ThinkingComplete(_) if self.show_thinking => {
+                self.console.print_subagent(session_id, depth, "(thinking)")
+            }
+            OutputChunk::ToolStart { name, .. } if self.show_tools => {
+                self.console.print_subagent(session_id, depth, &format!("{} executing...", name))
+            }
+            OutputChunk::ToolEnd { result, .. } if self.show_tools && result.is_error => {
+                if let crate::tools::ToolResultData::Text(text) = &result.content {
+                    self.console.print_subagent(session_id, depth, &format!("Tool error: {}", text))
+                }
+            }
+            OutputChunk::SubAgentSpawned { session_id: child, agent_type } => {
+                self.console.print_subagent(session_id, depth, &format!("Spawned subagent: {} ({})", agent_type, child))
+            }
+            OutputChunk::Error(e) => self.console.print_subagent(session_id, depth, &format!("Error: {}", e)),
+            _ => {}
+        }
+    }
+
     /// Get the underlying agent handle
     pub fn handle(&self) -> &AgentHandle {
         &self.handle
     }
 
     /// Get the underlying console
-    pub fn console(&self) -> &Console {
+    pub fn console(&self) -> &Console<S> {
         &self.console
     }
 }
+
+/// Whether the agent is blocked on a permission prompt or question
+fn waits_for_answer(state: &AgentState) -> bool {
+    matches!(state, AgentState::WaitingForPermission | AgentState::WaitingForUserInput { .. })
+}
+
+/// Unwrap the result of a read started with `Console::spawn_read_input`
+fn received_input(
+    result: Result<io::Result<String>, oneshot::error::RecvError>,
+) -> io::Result<String> {
+    result.map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "input reader stopped"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::BufferSink;
+    use crate::runtime::AgentRuntime;
+    use crate::session::{AgentSession, SessionStorage};
+
+    #[tokio::test]
+    async fn test_interrupts_after_skipped_prompt() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = SessionStorage::with_dir(temp_dir.path());
+        let session = AgentSession::new_with_storage("lag", "test-agent", "Test", "", "", storage).unwrap();
+        let runtime = AgentRuntime::new().with_output_buffer(2);
+
+        let handle = runtime
+            .spawn(session, |mut internals| async move {
+                let Some(InputMessage::UserInput(_)) = internals.receive().await else {
+                    return Ok(());
+                };
+                while internals.subscriber_count() == 0 {
+                    tokio::task::yield_now().await;
+                }
+                // The request is pushed out of the buffer before the renderer reads it
+                internals.send_permission_request("Bash", "Run: ls", "ls", None);
+                for i in 0..8 {
+                    internals.send_status(format!("filler {}", i));
+                }
+                internals.set_waiting_for_permission().await;
+                let answer = internals.receive().await;
+                internals.send_status(format!("answer: {:?}", answer));
+                internals.send_done();
+                Ok(())
+            })
+            .await;
+
+        let sink = BufferSink::new();
+        let renderer = ConsoleRenderer::with_console(handle, Console::with_sink(sink.clone()));
+        tokio::time::timeout(Duration::from_secs(5), renderer.run_turn("go"))
+            .await
+            .expect("renderer waited for a prompt it never saw")
+            .unwrap();
+
+        let output = sink.output();
+        assert!(output.contains("output chunks skipped"));
+        assert!(output.contains("answer: Some(Interrupt)"));
+    }
+
+    /// Sink whose reads block until a line is sent, like a terminal
+    #[derive(Clone)]
+    struct WaitingSink {
+        output: BufferSink,
+        input: Arc<std::sync::Mutex<std::sync::mpsc::Receiver<String>>>,
+    }
+
+    impl ConsoleSink for WaitingSink {
+        fn write_line(&self, line: &str) {
+            self.output.write_line(line);
+        }
+
+        fn prompt(&self, text: &str) {
+            self.output.prompt(text);
+        }
+
+        fn read_line(&self) -> io::Result<String> {
+            self.input
+                .lock()
+                .unwrap()
+                .recv()
+                .map_err(|_| io::Error::new(io::ErrorKind::UnexpectedEof, "input closed"))
+        }
+    }
+
+    /// Renderer for an agent that waits for shutdown, and the sender for its input
+    async fn idle_renderer(
+        temp_dir: &std::path::Path,
+        action: IdleAction,
+    ) -> (ConsoleRenderer<WaitingSink>, BufferSink, std::sync::mpsc::Sender<String>) {
+        let storage = SessionStorage::with_dir(temp_dir);
+        let session = AgentSession::new_with_storage("idle", "test-agent", "Test", "", "", storage).unwrap();
+        let handle = AgentRuntime::new()
+            .spawn(session, |mut internals| async move {
+                while !matches!(internals.receive().await, None | Some(InputMessage::Shutdown)) {}
+                Ok(())
+            })
+            .await;
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let output = BufferSink::new();
+        let sink = WaitingSink {
+            output: output.clone(),
+            input: Arc::new(std::sync::Mutex::new(rx)),
+        };
+        let renderer = ConsoleRenderer::with_console(handle, Console::with_sink(sink))
+            .with_idle_timeout(Duration::from_millis(100), action);
+        (renderer, output, tx)
+    }
+
+    #[tokio::test]
+    async fn test_idle_timeout_exits() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (renderer, output, _tx) = idle_renderer(temp_dir.path(), IdleAction::Exit).await;
+
+        let started = std::time::Instant::now();
+        tokio::time::timeout(Duration::from_secs(5), renderer.run())
+            .await
+            .expect("renderer never went idle")
+            .unwrap();
+
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        assert!(output.output().contains("Session idle, shutting down..."));
+    }
+
+    #[tokio::test]
+    async fn test_idle_timeout_hibernates_until_input() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (renderer, output, tx) = idle_renderer(temp_dir.path(), IdleAction::Hibernate).await;
+
+        let run = tokio::spawn(async move { renderer.run().await });
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(output.output().contains("Session idle. Type a message to resume."));
+        assert!(!run.is_finished());
+
+        // The pending read picks up the next line
+        tx.send("exit".to_string()).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), run)
+            .await
+            .expect("renderer did not resume")
+            .unwrap()
+            .unwrap();
+        assert!(output.output().contains("Shutting down..."));
+    }
+}
@@ -0,0 +1,144 @@
+//! Output targets for the console
+//!
+//! `Console` formats everything it shows, then hands the text to a
+//! `ConsoleSink`. The default `StdoutSink` writes to the terminal; a
+//! `BufferSink` keeps output in memory and answers reads from a queue, so
+//! rendering can be captured in tests or routed to another UI.
+
+use std::collections::VecDeque;
+use std::io::{self, IsTerminal, Write};
+use std::sync::{Arc, Mutex};
+
+/// Destination for console output and source of user input
+pub trait ConsoleSink: Send + Sync + 'static {
+    /// Write a full line
+    fn write_line(&self, line: &str);
+
+    /// Write text without a newline and flush it immediately
+    ///
+    /// Used for input prompts and streamed chunks.
+    fn prompt(&self, text: &str);
+
+    /// Read a line of user input, without the trailing newline
+    fn read_line(&self) -> io::Result<String>;
+
+    /// Write a line of error output
+    ///
+    /// Defaults to `write_line`.
+    fn write_error_line(&self, line: &str) {
+        self.write_line(line);
+    }
+
+    /// Whether output may contain ANSI colors
+    ///
+    /// Defaults to `false`.
+    fn supports_color(&self) -> bool {
+        false
+    }
+}
+
+/// Sink that writes to the process's stdout/stderr and reads from stdin
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdoutSink;
+
+impl ConsoleSink for StdoutSink {
+    fn write_line(&self, line: &str) {
+        println!("{}", line);
+    }
+
+    fn prompt(&self, text: &str) {
+        print!("{}", text);
+        let _ = io::stdout().flush();
+    }
+
+    fn read_line(&self) -> io::Result<String> {
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        Ok(input.trim_end_matches(['\r', '\n']).to_string())
+    }
+
+    fn write_error_line(&self, line: &str) {
+        eprintln!("{}", line);
+    }
+
+    /// True for a terminal, unless `NO_COLOR` is set to a non-empty value
+    fn supports_color(&self) -> bool {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        !no_color && io::stdout().is_terminal()
+    }
+}
+
+/// In-memory sink that records output and replays queued input
+///
+/// Clones share the same buffers, so a test can keep one clone and pass
+/// another to the console.
+///
+/// # Example
+///
+/// ```ignore
+/// let sink = BufferSink::with_input(["y"]);
+/// let console = Console::with_sink(sink.clone());
+/// let decision = console.ask_permission(&request)?;
+/// assert!(sink.output().contains("Allowed"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct BufferSink {
+    output: Arc<Mutex<String>>,
+    input: Arc<Mutex<VecDeque<String>>>,
+}
+
+impl BufferSink {
+    /// Create an empty sink
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a sink that answers reads with `lines`, in order
+    pub fn with_input<I, S>(lines: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let sink = Self::new();
+        for line in lines {
+            sink.push_input(line);
+        }
+        sink
+    }
+
+    /// Queue a line to be returned by a later read
+    pub fn push_input(&self, line: impl Into<String>) {
+        self.input.lock().unwrap().push_back(line.into());
+    }
+
+    /// Everything written so far
+    pub fn output(&self) -> String {
+        self.output.lock().unwrap().clone()
+    }
+
+    /// Return everything written so far and clear the buffer
+    pub fn take_output(&self) -> String {
+        std::mem::take(&mut *self.output.lock().unwrap())
+    }
+}
+
+impl ConsoleSink for BufferSink {
+    fn write_line(&self, line: &str) {
+        let mut output = self.output.lock().unwrap();
+        output.push_str(line);
+        output.push('\n');
+    }
+
+    fn prompt(&self, text: &str) {
+        self.output.lock().unwrap().push_str(text);
+    }
+
+    /// Returns `UnexpectedEof` once the queued input runs out
+    fn read_line(&self) -> io::Result<String> {
+        self.input
+            .lock()
+            .unwrap()
+            .pop_front()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "no more queued input"))
+    }
+}
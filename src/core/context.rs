@@ -8,6 +8,8 @@ use std::fmt;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+use super::error::{FrameworkError, FrameworkResult};
+
 // ============================================================================
 // DangerousSkipPermissions - Runtime-changeable permission bypass flag
 // ============================================================================
@@ -45,16 +47,24 @@ impl DangerousSkipPermissions {
 /// This allows tools to access agent-specific state like TodoManager,
 /// FileWatcher, or any other Rust object that the agent needs.
 ///
+/// Resources are keyed by type, so there is at most one of each type; wrap
+/// values in a newtype to store two of the same type. They are stored as
+/// `Arc<T>` and shared by every clone of the context (tools get a clone), so
+/// a resource that tools modify needs interior mutability such as
+/// `Mutex` or `RwLock`.
+///
 /// # Example
 ///
 /// ```ignore
-/// // Insert a resource
-/// ctx.resources.insert(TodoManager::new());
+/// // Insert a resource when spawning the agent
+/// runtime.spawn(session, move |mut internals| {
+///     internals.context.insert_resource(Counter(Mutex::new(0)));
+///     agent.run(internals)
+/// });
 ///
-/// // Get a resource in a tool
-/// let todo = ctx.resources.get::<TodoManager>()
-///     .expect("TodoManager not available");
-/// todo.add_task("Fix bug");
+/// // Get it in a tool; the error names the missing type
+/// let counter = internals.context.require_resource::<Counter>()?;
+/// *counter.0.lock().unwrap() += 1;
 /// ```
 #[derive(Default, Clone)]
 pub struct ResourceMap {
@@ -92,6 +102,12 @@ impl ResourceMap {
             .and_then(|arc| arc.clone().downcast::<T>().ok())
     }
 
+    /// Get a resource by type, or an error naming the type if it is missing
+    pub fn require<T: Send + Sync + 'static>(&self) -> FrameworkResult<Arc<T>> {
+        self.get::<T>()
+            .ok_or(FrameworkError::MissingResource(std::any::type_name::<T>()))
+    }
+
     /// Check if a resource of the given type exists
     pub fn contains<T: Send + Sync + 'static>(&self) -> bool {
         self.map.contains_key(&TypeId::of::<T>())
@@ -161,6 +177,10 @@ pub struct AgentContext {
     /// The tool_use_id that spawned this agent (if subagent)
     pub parent_tool_use_id: Option<String>,
 
+    /// Project directory recorded for the session (used to resolve relative paths)
+    #[serde(default)]
+    pub working_dir: Option<String>,
+
     // --- Current Execution State ---
     /// Current turn number (increments each LLM call)
     pub current_turn: usize,
@@ -189,6 +209,7 @@ impl fmt::Debug for AgentContext {
             .field("description", &self.description)
             .field("parent_session_id", &self.parent_session_id)
             .field("parent_tool_use_id", &self.parent_tool_use_id)
+            .field("working_dir", &self.working_dir)
             .field("current_turn", &self.current_turn)
             .field("current_tool_use_id", &self.current_tool_use_id)
             .field("metadata", &self.metadata)
@@ -212,6 +233,7 @@ impl AgentContext {
             description: description.into(),
             parent_session_id: None,
             parent_tool_use_id: None,
+            working_dir: None,
             current_turn: 0,
             current_tool_use_id: None,
             metadata: HashMap::new(),
@@ -235,6 +257,7 @@ impl AgentContext {
             description: description.into(),
             parent_session_id: Some(parent_session_id.into()),
             parent_tool_use_id: Some(parent_tool_use_id.into()),
+            working_dir: None,
             current_turn: 0,
             current_tool_use_id: None,
             metadata: HashMap::new(),
@@ -329,6 +352,18 @@ impl AgentContext {
         self.resources.get::<T>()
     }
 
+    /// Get a resource by type, or an error naming the type if it is missing
+    ///
+    /// Use this in tools that can't work without the resource.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let todo = ctx.require_resource::<TodoManager>()?;
+    /// ```
+    pub fn require_resource<T: Send + Sync + 'static>(&self) -> FrameworkResult<Arc<T>> {
+        self.resources.require::<T>()
+    }
+
     /// Check if a resource of the given type exists
     pub fn has_resource<T: Send + Sync + 'static>(&self) -> bool {
         self.resources.contains::<T>()
@@ -461,6 +496,17 @@ mod tests {
         assert!(!resources.contains::<TestResource>());
     }
 
+    #[test]
+    fn test_require_missing_resource() {
+        let mut ctx = AgentContext::new("session", "test", "Test", "Test agent");
+
+        let error = ctx.require_resource::<TestResource>().unwrap_err();
+        assert!(error.to_string().starts_with("Resource not found: picrust::core::context::tests::TestResource."));
+
+        ctx.insert_resource(TestResource { value: 7 });
+        assert_eq!(ctx.require_resource::<TestResource>().unwrap().value, 7);
+    }
+
     #[test]
     fn test_resource_map_replace() {
         let mut resources = ResourceMap::new();
@@ -45,10 +45,18 @@ pub enum FrameworkError {
     #[error("Permission denied: {0}")]
     PermissionDenied(String),
 
+    /// A subagent could not be spawned (too deep, or its own ancestor)
+    #[error("Subagent not allowed: {0}")]
+    SubagentNotAllowed(String),
+
     /// Invalid configuration
     #[error("Invalid configuration: {0}")]
     InvalidConfig(String),
 
+    /// A resource expected in the agent context was not inserted
+    #[error("Resource not found: {0}. Insert it with context.insert_resource() before the agent runs")]
+    MissingResource(&'static str),
+
     /// Agent was interrupted
     #[error("Agent interrupted")]
     Interrupted,
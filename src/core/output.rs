@@ -5,6 +5,7 @@ use serde_json::Value;
 use std::collections::HashMap;
 
 use super::state::AgentState;
+use crate::llm::Usage;
 use crate::tools::ToolResult;
 
 /// A single question option
@@ -23,6 +24,77 @@ pub struct UserQuestion {
     pub multi_select: bool,
 }
 
+/// A tool call awaiting permission, listed in a `BatchPermissionRequest`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingToolCall {
+    /// Tool use ID, used to approve this call
+    pub id: String,
+    /// Tool name
+    pub tool_name: String,
+    /// Human-readable action description
+    pub action: String,
+    /// The actual input being executed
+    pub input: String,
+    /// Additional details
+    pub details: Option<String>,
+}
+
+/// Why a turn ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TurnEndReason {
+    /// The model finished its response
+    EndTurn,
+    /// The response was cut off at the output token limit
+    MaxTokens,
+    /// The tool loop hit `max_tool_iterations`
+    MaxIterations,
+    /// The model refused to respond
+    Refusal,
+    /// The model paused the turn (e.g. a long-running server tool)
+    Paused,
+    /// The user interrupted tool execution
+    Interrupted,
+    /// The same tool call was repeated within one response
+    LoopDetected,
+    /// A `UserPromptSubmit` hook blocked the prompt
+    Blocked,
+    /// An `AssistantTextDelta` hook stopped the response
+    Filtered,
+    /// The turn failed with an error (see the preceding `Error` chunk)
+    Error,
+}
+
+/// Summary of a finished turn, sent as `OutputChunk::TurnComplete` before `Done`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TurnOutcome {
+    /// Why the turn ended
+    pub reason: TurnEndReason,
+    /// Token usage summed over every LLM call in the turn
+    pub usage: Usage,
+    /// Number of LLM calls made
+    pub llm_calls: usize,
+    /// Number of tool calls made
+    pub tool_calls: usize,
+}
+
+impl TurnOutcome {
+    /// Create an outcome with no LLM or tool calls
+    pub fn new(reason: TurnEndReason) -> Self {
+        Self {
+            reason,
+            usage: Usage::default(),
+            llm_calls: 0,
+            tool_calls: 0,
+        }
+    }
+
+    /// Whether the model finished normally
+    pub fn is_complete(&self) -> bool {
+        self.reason == TurnEndReason::EndTurn
+    }
+}
+
 /// Messages that can be sent TO an agent
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum InputMessage {
@@ -47,6 +119,12 @@ pub enum InputMessage {
         remember: bool,
     },
 
+    /// Response to a BatchPermissionRequest
+    BatchPermissionResponse {
+        /// IDs of the approved tool calls; the others are denied
+        approved: Vec<String>,
+    },
+
     /// Subagent completed
     SubAgentComplete {
         /// Session ID of the completed subagent
@@ -114,6 +192,16 @@ pub enum OutputChunk {
         result: ToolResult,
     },
 
+    /// Streamed tool input was malformed; the call will not be executed
+    ToolInputError {
+        /// Tool use ID
+        id: String,
+        /// Tool name
+        name: String,
+        /// What was wrong with the input
+        error: String,
+    },
+
     // --- Permission ---
     /// Requesting permission from user
     PermissionRequest {
@@ -127,6 +215,12 @@ pub enum OutputChunk {
         details: Option<String>,
     },
 
+    /// Requesting permission for several tool calls from one response at once
+    BatchPermissionRequest {
+        /// The calls, in the order the model made them
+        calls: Vec<PendingToolCall>,
+    },
+
     // --- Subagent Events ---
     /// Subagent was spawned
     SubAgentSpawned {
@@ -172,6 +266,9 @@ pub enum OutputChunk {
     /// Error occurred
     Error(String),
 
+    /// Why the turn ended, with usage and call counts (sent just before `Done`)
+    TurnComplete(TurnOutcome),
+
     /// Agent completed this turn
     Done,
 }
@@ -237,6 +334,7 @@ impl OutputChunk {
             OutputChunk::ToolStart { .. }
                 | OutputChunk::ToolProgress { .. }
                 | OutputChunk::ToolEnd { .. }
+                | OutputChunk::ToolInputError { .. }
         )
     }
 }
@@ -255,6 +353,17 @@ impl InputMessage {
             remember,
         }
     }
+
+    /// Create a batch permission response approving `approved` (by tool use ID)
+    pub fn batch_permission<I, S>(approved: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        InputMessage::BatchPermissionResponse {
+            approved: approved.into_iter().map(Into::into).collect(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -275,6 +384,16 @@ mod tests {
         assert!(!OutputChunk::text("hello").is_thinking());
     }
 
+    #[test]
+    fn test_turn_outcome_serialization() {
+        let outcome = TurnOutcome::new(TurnEndReason::MaxIterations);
+        assert!(!outcome.is_complete());
+
+        let json = serde_json::to_value(OutputChunk::TurnComplete(outcome)).unwrap();
+        assert_eq!(json["TurnComplete"]["reason"], "max_iterations");
+        assert_eq!(json["TurnComplete"]["tool_calls"], 0);
+    }
+
     #[test]
     fn test_input_message_creation() {
         let msg = InputMessage::user_input("hello");
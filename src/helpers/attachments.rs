@@ -11,6 +11,8 @@ use std::path::Path;
 
 use crate::llm::ContentBlock;
 
+use super::long_lines::LongLines;
+
 /// Maximum file size for images (5MB per Claude docs)
 const MAX_IMAGE_SIZE: u64 = 5 * 1024 * 1024;
 /// Maximum file size for PDFs (32MB)
@@ -37,6 +39,11 @@ const MAX_LINE_LENGTH: usize = 2000;
 /// # Returns
 /// A vector of ContentBlocks, one for each attachment found (in order)
 pub fn process_attachments(input: &str, base_dir: &str) -> Vec<ContentBlock> {
+    process_attachments_with(input, base_dir, LongLines::default())
+}
+
+/// Process attachments, choosing how lines over 2000 characters are shown
+pub fn process_attachments_with(input: &str, base_dir: &str, long_lines: LongLines) -> Vec<ContentBlock> {
     let mut blocks = Vec::new();
     let mut processed_paths: HashSet<String> = HashSet::new();
 
@@ -68,7 +75,7 @@ pub fn process_attachments(input: &str, base_dir: &str) -> Vec<ContentBlock> {
             tracing::info!("[Attachments] Processing attachment: {}", file_path);
 
             // Read the file and convert to content blocks
-            match read_attachment(file_path, base_dir) {
+            match read_attachment(file_path, base_dir, long_lines) {
                 Ok(mut content_blocks) => {
                     processed_paths.insert(resolved_path);
                     blocks.append(&mut content_blocks);
@@ -93,7 +100,7 @@ pub fn process_attachments(input: &str, base_dir: &str) -> Vec<ContentBlock> {
 }
 
 /// Read a single attachment file and convert to ContentBlocks
-fn read_attachment(file_path: &str, base_dir: &str) -> Result<Vec<ContentBlock>> {
+fn read_attachment(file_path: &str, base_dir: &str, long_lines: LongLines) -> Result<Vec<ContentBlock>> {
     let resolved_path = resolve_path(file_path, base_dir);
     let path_obj = Path::new(&resolved_path);
 
@@ -115,7 +122,7 @@ fn read_attachment(file_path: &str, base_dir: &str) -> Result<Vec<ContentBlock>>
         Some("pdf") => read_pdf(&resolved_path, file_path),
         _ => {
             // Default to text reading for all other files
-            read_text_file(&resolved_path, file_path)
+            read_text_file(&resolved_path, file_path, long_lines)
         }
     }
 }
@@ -134,7 +141,7 @@ fn resolve_path(path: &str, base_dir: &str) -> String {
 }
 
 /// Read a text file with line numbers
-fn read_text_file(resolved_path: &str, original_path: &str) -> Result<Vec<ContentBlock>> {
+fn read_text_file(resolved_path: &str, original_path: &str, long_lines: LongLines) -> Result<Vec<ContentBlock>> {
     let content = fs::read_to_string(resolved_path)?;
 
     let lines: Vec<&str> = content.lines().collect();
@@ -145,13 +152,8 @@ fn read_text_file(resolved_path: &str, original_path: &str) -> Result<Vec<Conten
 
     for (i, line) in lines[..end].iter().enumerate() {
         let line_num = i + 1;
-        let display_line = if line.len() > MAX_LINE_LENGTH {
-            format!("{}...", &line[..MAX_LINE_LENGTH])
-        } else {
-            line.to_string()
-        };
         // Use cat -n format: right-aligned line number + tab + content
-        result.push_str(&format!("{:>6}\t{}\n", line_num, display_line));
+        long_lines.push_numbered(&mut result, line_num, line, MAX_LINE_LENGTH);
     }
 
     if end < total_lines {
@@ -247,22 +249,18 @@ fn read_pdf(resolved_path: &str, original_path: &str) -> Result<Vec<ContentBlock
     )])
 }
 
-/// Read a directory and list its contents
-fn read_directory(resolved_path: &str, original_path: &str) -> Result<Vec<ContentBlock>> {
-    let entries = fs::read_dir(resolved_path)?;
-
-    let mut result = format!("Directory: {}\n\n", original_path);
-    let mut items: Vec<(String, bool, u64)> = Vec::new(); // (name, is_dir, size)
+/// Entries of a directory as (name, is_dir, size)
+///
+/// Sorted with directories first, then files, alphabetically within each group.
+pub(crate) fn directory_entries(path: impl AsRef<Path>) -> Result<Vec<(String, bool, u64)>> {
+    let mut items = Vec::new();
 
     // Collect all entries
-    for entry in entries {
+    for entry in fs::read_dir(path)? {
         let entry = entry?;
         let metadata = entry.metadata()?;
         let name = entry.file_name().to_string_lossy().to_string();
-        let is_dir = metadata.is_dir();
-        let size = metadata.len();
-
-        items.push((name, is_dir, size));
+        items.push((name, metadata.is_dir(), metadata.len()));
     }
 
     // Sort: directories first, then files, alphabetically within each group
@@ -274,6 +272,26 @@ fn read_directory(resolved_path: &str, original_path: &str) -> Result<Vec<Conten
         }
     });
 
+    Ok(items)
+}
+
+/// Human-readable file size (e.g. "512 B", "1.5 KB")
+pub(crate) fn format_size(size: u64) -> String {
+    if size < 1024 {
+        format!("{} B", size)
+    } else if size < 1024 * 1024 {
+        format!("{:.1} KB", size as f64 / 1024.0)
+    } else if size < 1024 * 1024 * 1024 {
+        format!("{:.1} MB", size as f64 / 1024.0 / 1024.0)
+    } else {
+        format!("{:.1} GB", size as f64 / 1024.0 / 1024.0 / 1024.0)
+    }
+}
+
+/// Read a directory and list its contents
+fn read_directory(resolved_path: &str, original_path: &str) -> Result<Vec<ContentBlock>> {
+    let mut result = format!("Directory: {}\n\n", original_path);
+    let items = directory_entries(resolved_path)?;
     let item_count = items.len();
 
     // Format output
@@ -282,17 +300,7 @@ fn read_directory(resolved_path: &str, original_path: &str) -> Result<Vec<Conten
     } else {
         for (name, is_dir, size) in items {
             let type_marker = if is_dir { "DIR " } else { "    " };
-            let size_str = if is_dir {
-                String::new()
-            } else if size < 1024 {
-                format!("{:>8} B", size)
-            } else if size < 1024 * 1024 {
-                format!("{:>7.1} KB", size as f64 / 1024.0)
-            } else if size < 1024 * 1024 * 1024 {
-                format!("{:>7.1} MB", size as f64 / 1024.0 / 1024.0)
-            } else {
-                format!("{:>7.1} GB", size as f64 / 1024.0 / 1024.0 / 1024.0)
-            };
+            let size_str = if is_dir { String::new() } else { format!("{:>10}", format_size(size)) };
 
             result.push_str(&format!("{} {:>12}  {}\n", type_marker, size_str, name));
         }
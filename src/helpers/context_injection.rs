@@ -13,6 +13,9 @@
 //! - Injecting dynamic context based on agent state
 //! - Any message transformation based on `AgentInternals`
 //!
+//! `CallbackContextProvider` covers the common case of adding a line of
+//! dynamic text (from a closure or an environment variable) each turn.
+//!
 //! # Example
 //!
 //! ```ignore
@@ -33,7 +36,7 @@
 //! agent.add_injection(todo_injection);
 //! ```
 
-use crate::llm::Message;
+use crate::llm::{ContentBlock, Message, MessageContent};
 use crate::runtime::AgentInternals;
 
 /// Trait for context injection implementations
@@ -102,6 +105,66 @@ where
     }
 }
 
+/// Callback returning the context text for a turn, or `None` to add nothing
+type ContextCallback = Box<dyn Fn(&[Message]) -> Option<String> + Send + Sync>;
+
+/// A context injection whose text comes from a callback
+///
+/// The callback sees the messages about to be sent and is called before
+/// every LLM call; whatever it returns is added as a system reminder. Use it
+/// for one-off dynamic context that doesn't warrant its own injection type:
+///
+/// ```ignore
+/// let branch = CallbackContextProvider::new("branch", |_messages| {
+///     current_branch().map(|branch| format!("Current branch: {}", branch))
+/// });
+/// let ticket = CallbackContextProvider::from_env("TICKET_ID");
+/// ```
+pub struct CallbackContextProvider {
+    name: String,
+    callback: ContextCallback,
+}
+
+impl CallbackContextProvider {
+    /// Add the text returned by `callback` each turn
+    pub fn new<F>(name: impl Into<String>, callback: F) -> Self
+    where
+        F: Fn(&[Message]) -> Option<String> + Send + Sync + 'static,
+    {
+        Self {
+            name: name.into(),
+            callback: Box::new(callback),
+        }
+    }
+
+    /// Add `VAR: value` while the environment variable `var` is set and not empty
+    ///
+    /// The variable is read each turn, so changes apply from the next LLM call.
+    pub fn from_env(var: impl Into<String>) -> Self {
+        let var = var.into();
+        let name = format!("env:{}", var);
+        Self::new(name, move |_| {
+            std::env::var(&var)
+                .ok()
+                .filter(|value| !value.trim().is_empty())
+                .map(|value| format!("{}: {}", var, value.trim()))
+        })
+    }
+}
+
+impl ContextInjection for CallbackContextProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn inject(&self, _internals: &AgentInternals, mut messages: Vec<Message>) -> Vec<Message> {
+        if let Some(text) = (self.callback)(&messages) {
+            inject_system_reminder(&mut messages, &text);
+        }
+        messages
+    }
+}
+
 /// Boxed context injection for storing in collections
 pub type BoxedInjection = Box<dyn ContextInjection>;
 
@@ -114,8 +177,19 @@ pub type SharedInjection = std::sync::Arc<dyn ContextInjection>;
 /// `messages = injection1(messages)`
 /// `messages = injection2(messages)`
 /// etc.
+///
+/// Injections run in priority order, highest first; injections with the same
+/// priority run in the order they were added. The default priority is 0.
+///
+/// A budget caps how much text the chain may add in total. Injections claim
+/// it in priority order, and one whose text would exceed what is left is
+/// skipped for that call, so a verbose low-priority injection cannot crowd
+/// out an important one. A skipped injection is undone completely: the chain
+/// keeps a copy of the messages from before it ran and restores that copy.
 pub struct InjectionChain {
-    injections: Vec<SharedInjection>,
+    injections: Vec<(i32, SharedInjection)>,
+    /// Maximum bytes of text all injections together may add (None = no limit)
+    budget: Option<usize>,
 }
 
 impl InjectionChain {
@@ -123,17 +197,49 @@ impl InjectionChain {
     pub fn new() -> Self {
         Self {
             injections: Vec::new(),
+            budget: None,
         }
     }
 
+    /// Limit the total bytes of text the injections may add per LLM call
+    pub fn with_budget(mut self, max_bytes: usize) -> Self {
+        self.budget = Some(max_bytes);
+        self
+    }
+
+    /// Set or clear the budget
+    pub fn set_budget(&mut self, max_bytes: Option<usize>) {
+        self.budget = max_bytes;
+    }
+
+    /// Get the budget, if any
+    pub fn budget(&self) -> Option<usize> {
+        self.budget
+    }
+
     /// Add an injection to the chain
     pub fn add<I: ContextInjection + 'static>(&mut self, injection: I) {
-        self.injections.push(std::sync::Arc::new(injection));
+        self.add_shared_with_priority(std::sync::Arc::new(injection), 0);
+    }
+
+    /// Add an injection with a priority (higher runs first and wins the budget)
+    pub fn add_with_priority<I: ContextInjection + 'static>(&mut self, injection: I, priority: i32) {
+        self.add_shared_with_priority(std::sync::Arc::new(injection), priority);
     }
 
     /// Add a shared injection to the chain
     pub fn add_shared(&mut self, injection: SharedInjection) {
-        self.injections.push(injection);
+        self.add_shared_with_priority(injection, 0);
+    }
+
+    /// Add a shared injection with a priority
+    pub fn add_shared_with_priority(&mut self, injection: SharedInjection, priority: i32) {
+        let index = self
+            .injections
+            .iter()
+            .position(|(existing, _)| *existing < priority)
+            .unwrap_or(self.injections.len());
+        self.injections.insert(index, (priority, injection));
     }
 
     /// Add a function-based injection to the chain
@@ -146,11 +252,32 @@ impl InjectionChain {
 
     /// Apply all injections in order
     ///
-    /// Each injection receives the output of the previous one.
+    /// Each injection receives the output of the previous one. With a budget,
+    /// an injection that adds more text than is left is undone and skipped.
     pub fn apply(&self, internals: &AgentInternals, mut messages: Vec<Message>) -> Vec<Message> {
-        for injection in &self.injections {
+        let mut remaining = self.budget;
+        for (_, injection) in &self.injections {
             tracing::debug!("Applying context injection: {}", injection.name());
+            let Some(left) = remaining else {
+                messages = injection.inject(internals, messages);
+                continue;
+            };
+
+            let snapshot = messages.clone();
+            let before = text_len(&messages);
             messages = injection.inject(internals, messages);
+            let added = text_len(&messages).saturating_sub(before);
+            if added > left {
+                tracing::warn!(
+                    "Skipping context injection '{}': adds {} bytes, {} left in budget",
+                    injection.name(),
+                    added,
+                    left
+                );
+                messages = snapshot;
+                continue;
+            }
+            remaining = Some(left - added);
         }
         messages
     }
@@ -165,12 +292,32 @@ impl InjectionChain {
         self.injections.is_empty()
     }
 
-    /// Get the names of all injections in the chain
+    /// Get the names of all injections in the chain, in the order they run
     pub fn names(&self) -> Vec<&str> {
-        self.injections.iter().map(|i| i.name()).collect()
+        self.injections.iter().map(|(_, i)| i.name()).collect()
     }
 }
 
+/// Bytes of text in the messages, counting text blocks and tool results
+fn text_len(messages: &[Message]) -> usize {
+    messages
+        .iter()
+        .map(|message| match &message.content {
+            MessageContent::Text(text) => text.len(),
+            MessageContent::Blocks(blocks) => blocks
+                .iter()
+                .map(|block| match block {
+                    ContentBlock::Text { text, .. } => text.len(),
+                    ContentBlock::ToolResult { content, .. } => {
+                        content.as_ref().map_or(0, |c| c.text().len())
+                    }
+                    _ => 0,
+                })
+                .sum(),
+        })
+        .sum()
+}
+
 impl Default for InjectionChain {
     fn default() -> Self {
         Self::new()
@@ -214,6 +361,21 @@ pub fn prepend_to_first_user_message(messages: &mut Vec<Message>, text: &str) {
     }
 }
 
+/// Tag wrapping a session preamble inside the first user message
+const SESSION_PREAMBLE_TAG: &str = "vibe-working-agent-session-preamble";
+
+/// Build the block that carries a session preamble in the first user message
+pub(crate) fn session_preamble_block(preamble: &str) -> ContentBlock {
+    ContentBlock::text(format!("<{tag}>\n{}\n</{tag}>", preamble, tag = SESSION_PREAMBLE_TAG))
+}
+
+/// Whether `block` is a session preamble rather than something the user wrote
+pub(crate) fn is_session_preamble(block: &ContentBlock) -> bool {
+    block
+        .as_text()
+        .is_some_and(|text| text.strip_prefix('<').is_some_and(|rest| rest.starts_with(SESSION_PREAMBLE_TAG)))
+}
+
 /// Append text to the last message
 ///
 /// # Example
@@ -229,6 +391,7 @@ pub fn append_to_last_message(messages: &mut Vec<Message>, text: &str) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::runtime::internals::test_internals;
 
     #[test]
     fn test_injection_chain_empty() {
@@ -246,4 +409,82 @@ mod tests {
         assert_eq!(chain.len(), 2);
         assert_eq!(chain.names(), vec!["first", "second"]);
     }
+
+    #[test]
+    fn test_injection_priority_and_budget() {
+        let mut chain = InjectionChain::new().with_budget(20);
+        chain.add_fn("git_status", |_, mut m| {
+            append_to_last_message(&mut m, &"M file.rs\n".repeat(10));
+            m
+        });
+        chain.add_fn("turn", |_, mut m| {
+            append_to_last_message(&mut m, " [turn 1]");
+            m
+        });
+        chain.add_with_priority(
+            FnInjection::new("conventions", |_, mut m| {
+                append_to_last_message(&mut m, " Use tabs.");
+                m
+            }),
+            10,
+        );
+        assert_eq!(chain.names(), vec!["conventions", "git_status", "turn"]);
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let messages = chain.apply(&test_internals(temp_dir.path()).0, vec![Message::user("Hi")]);
+        assert_eq!(messages[0].text(), Some("Hi Use tabs. [turn 1]"));
+
+        // A skipped injection that also added a message leaves no trace
+        let mut chain = InjectionChain::new().with_budget(5);
+        chain.add_fn("verbose", |_, mut m| {
+            append_to_last_message(&mut m, " and more");
+            m.push(Message::user("Extra context"));
+            m
+        });
+        let messages = chain.apply(&test_internals(temp_dir.path()).0, vec![Message::user("Hi")]);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].text(), Some("Hi"));
+
+        // So does one that prepended to the first user message
+        let mut chain = InjectionChain::new().with_budget(5);
+        chain.add_fn("preamble", |_, mut m| {
+            prepend_to_first_user_message(&mut m, "Long preamble. ");
+            m
+        });
+        let history = vec![Message::user("Hi"), Message::assistant("Hello"), Message::user("Bye")];
+        let messages = chain.apply(&test_internals(temp_dir.path()).0, history);
+        assert_eq!(messages[0].text(), Some("Hi"));
+
+        // And one that edited a message in the middle of the history
+        let mut chain = InjectionChain::new().with_budget(5);
+        chain.add_fn("rewrite", |_, mut m| {
+            m[1] = Message::assistant("Hello, with a long note attached");
+            m
+        });
+        let history = vec![Message::user("Hi"), Message::assistant("Hello"), Message::user("Bye")];
+        let messages = chain.apply(&test_internals(temp_dir.path()).0, history);
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1].text(), Some("Hello"));
+    }
+
+    #[test]
+    fn test_callback_context_provider() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (internals, _, _) = test_internals(temp_dir.path());
+        let turns = CallbackContextProvider::new("turns", |messages| {
+            (messages.len() > 1).then(|| format!("{} messages so far", messages.len()))
+        });
+        let messages = turns.inject(&internals, vec![Message::user("Hi")]);
+        assert_eq!(messages[0].text(), Some("Hi"));
+        let messages = turns.inject(&internals, vec![Message::user("Hi"), Message::assistant("Hello")]);
+        assert!(messages[1].text().unwrap().contains("\n2 messages so far\n"));
+
+        let ticket = CallbackContextProvider::from_env("PICRUST_TEST_TICKET_ID");
+        assert_eq!(ticket.name(), "env:PICRUST_TEST_TICKET_ID");
+        let messages = ticket.inject(&internals, vec![Message::user("Hi")]);
+        assert_eq!(messages[0].text(), Some("Hi"));
+        std::env::set_var("PICRUST_TEST_TICKET_ID", "ENG-42");
+        let messages = ticket.inject(&internals, vec![Message::user("Hi")]);
+        assert!(messages[0].text().unwrap().contains("PICRUST_TEST_TICKET_ID: ENG-42"));
+    }
 }
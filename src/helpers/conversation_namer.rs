@@ -22,6 +22,8 @@
 use anyhow::Result;
 use std::sync::Arc;
 
+use super::is_session_preamble;
+use super::truncate::truncate_bytes_safe;
 use crate::llm::{ContentBlock, LlmProvider, Message, MessageContent};
 
 /// System prompt for generating conversation names
@@ -95,7 +97,7 @@ impl ConversationNamer {
     /// Assistant: I'd be happy to help! What do you need?
     /// User: How do I implement traits?
     /// ```
-    fn format_messages(messages: &[Message]) -> String {
+    pub(crate) fn format_messages(messages: &[Message]) -> String {
         let mut formatted = String::new();
 
         for message in messages {
@@ -125,6 +127,8 @@ impl ConversationNamer {
 
                 for block in blocks {
                     match block {
+                        // Context the user never typed
+                        _ if is_session_preamble(block) => {}
                         ContentBlock::Text { text, .. } => {
                             text_parts.push(text.clone());
                         }
@@ -134,12 +138,12 @@ impl ConversationNamer {
                         }
                         ContentBlock::ToolResult { content, .. } => {
                             // Include tool result summary if available
-                            if let Some(result) = content {
+                            if let Some(result) = content.as_ref().map(|c| c.text()) {
                                 // Truncate long results
                                 let summary = if result.len() > 200 {
-                                    format!("{}...", &result[..200])
+                                    format!("{}...", truncate_bytes_safe(&result, 200))
                                 } else {
-                                    result.clone()
+                                    result
                                 };
                                 text_parts.push(format!("[Tool result: {}]", summary));
                             }
@@ -0,0 +1,329 @@
+//! Git diff summary injection
+//!
+//! Gives code-review style agents concise context about the current working
+//! tree changes. Small diffs are injected as-is; diffs over a size threshold
+//! are summarized by an LLM and the summary is cached until the diff changes.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use picrust::helpers::DiffSummaryProvider;
+//!
+//! let diff_context = DiffSummaryProvider::new("/path/to/repo", naming_llm.clone())
+//!     .with_max_diff_bytes(8_000);
+//!
+//! let config = AgentConfig::new().with_injection(diff_context);
+//! ```
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use tokio::runtime::RuntimeFlavor;
+
+use crate::llm::{LlmProvider, Message};
+use crate::runtime::AgentInternals;
+
+use super::context_injection::{inject_system_reminder, ContextInjection};
+
+/// Default diff size above which the diff is summarized instead of injected raw
+const DEFAULT_MAX_DIFF_BYTES: usize = 4000;
+
+/// System prompt for summarizing diffs
+const SUMMARY_SYSTEM_PROMPT: &str = r#"You summarize git diffs for a coding agent that is reviewing or continuing work on a repository.
+
+Rules:
+- Group changes by file or area
+- Describe what changed and why it likely changed, not line-by-line edits
+- Mention added, removed, and renamed files
+- Keep it under 200 words
+- Respond with ONLY the summary
+
+The text that follows is the output of `git diff HEAD`."#;
+
+/// Cached summary for a specific repository state
+#[derive(Debug, Default)]
+struct DiffCache {
+    /// Key of the repository state the summary was built for (HEAD + diff hash)
+    state_key: Option<String>,
+    /// The context note for that state
+    note: Option<String>,
+    /// Whether a background summarization is running
+    in_flight: bool,
+    /// Session and turn the last injected note was computed for
+    turn: Option<(String, usize)>,
+    /// The note injected during that turn
+    turn_note: Option<String>,
+}
+
+/// Context injection that summarizes `git diff HEAD` for the agent
+///
+/// When used as an injection, summarization runs in the background the first
+/// time a large diff is seen; until it finishes, `git diff --stat` is injected
+/// instead. Call [`summary`](Self::summary) to compute it eagerly.
+///
+/// The injection runs git once per turn and reuses the note for the rest of
+/// that turn's LLM calls, so it reflects the working tree at the start of the
+/// turn rather than the agent's edits in between. On a multi-threaded runtime
+/// git runs under `block_in_place`, so a slow `git diff` does not stall other
+/// tasks on the same worker thread.
+pub struct DiffSummaryProvider {
+    base_dir: PathBuf,
+    llm: Arc<dyn LlmProvider>,
+    max_diff_bytes: usize,
+    cache: Arc<Mutex<DiffCache>>,
+}
+
+impl DiffSummaryProvider {
+    /// Create a new provider for the git repository at `base_dir`
+    pub fn new(base_dir: impl Into<PathBuf>, llm: Arc<dyn LlmProvider>) -> Self {
+        Self {
+            base_dir: base_dir.into(),
+            llm,
+            max_diff_bytes: DEFAULT_MAX_DIFF_BYTES,
+            cache: Arc::new(Mutex::new(DiffCache::default())),
+        }
+    }
+
+    /// Set the diff size (in bytes) above which the LLM summarizes the diff
+    pub fn with_max_diff_bytes(mut self, max_diff_bytes: usize) -> Self {
+        self.max_diff_bytes = max_diff_bytes;
+        self
+    }
+
+    /// Get the context note for the current working tree
+    ///
+    /// Returns `None` when there are no changes or `base_dir` is not a git repository.
+    /// Summaries are cached per HEAD commit and diff contents.
+    pub async fn summary(&self) -> Result<Option<String>> {
+        let Some(diff) = self.git(&["diff", "HEAD"]) else {
+            return Ok(None);
+        };
+        if diff.trim().is_empty() {
+            return Ok(None);
+        }
+
+        let state_key = self.state_key(&diff);
+        if let Some(note) = self.cached_note(&state_key) {
+            return Ok(Some(note));
+        }
+
+        let note = if diff.len() <= self.max_diff_bytes {
+            format_raw_note(&diff)
+        } else {
+            summarize(&self.llm, &diff).await?
+        };
+
+        let mut cache = self.cache.lock().unwrap();
+        cache.state_key = Some(state_key);
+        cache.note = Some(note.clone());
+        Ok(Some(note))
+    }
+
+    /// Compute the note to inject for a new turn
+    fn turn_note(&self) -> Option<String> {
+        let diff = self.git(&["diff", "HEAD"])?;
+        if diff.trim().is_empty() {
+            return None;
+        }
+
+        if diff.len() <= self.max_diff_bytes {
+            return Some(format_raw_note(&diff));
+        }
+        let state_key = self.state_key(&diff);
+        if let Some(note) = self.cached_note(&state_key) {
+            return Some(note);
+        }
+        self.spawn_summary(diff, state_key);
+        self.git(&["diff", "HEAD", "--stat"])
+            .map(|stat| format!("Uncommitted changes (diff stat):\n{}", stat.trim_end()))
+    }
+
+    /// Run a git command in `base_dir`, returning stdout on success
+    fn git(&self, args: &[&str]) -> Option<String> {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(&self.base_dir)
+            .output()
+            .ok()?;
+
+        if !output.status.success() {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// Build a cache key from the HEAD commit and the diff contents
+    fn state_key(&self, diff: &str) -> String {
+        let head = self.git(&["rev-parse", "HEAD"]).unwrap_or_default();
+        let mut hasher = DefaultHasher::new();
+        diff.hash(&mut hasher);
+        format!("{}:{:x}", head.trim(), hasher.finish())
+    }
+
+    /// Get the cached note if it was built for `state_key`
+    fn cached_note(&self, state_key: &str) -> Option<String> {
+        let cache = self.cache.lock().unwrap();
+        if cache.state_key.as_deref() == Some(state_key) {
+            cache.note.clone()
+        } else {
+            None
+        }
+    }
+
+    /// Start summarizing in the background if not already running
+    fn spawn_summary(&self, diff: String, state_key: String) {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+
+        {
+            let mut cache = self.cache.lock().unwrap();
+            if cache.in_flight {
+                return;
+            }
+            cache.in_flight = true;
+        }
+
+        let llm = self.llm.clone();
+        let cache = self.cache.clone();
+        runtime.spawn(async move {
+            let result = summarize(&llm, &diff).await;
+            let mut cache = cache.lock().unwrap();
+            cache.in_flight = false;
+            match result {
+                Ok(note) => {
+                    cache.state_key = Some(state_key);
+                    cache.note = Some(note);
+                }
+                Err(e) => {
+                    tracing::warn!("[DiffSummaryProvider] Failed to summarize diff: {}", e);
+                }
+            }
+        });
+    }
+}
+
+impl ContextInjection for DiffSummaryProvider {
+    fn name(&self) -> &str {
+        "diff_summary"
+    }
+
+    fn inject(&self, internals: &AgentInternals, mut messages: Vec<Message>) -> Vec<Message> {
+        let turn = (internals.session_id().to_string(), internals.context.current_turn);
+        let cached = {
+            let cache = self.cache.lock().unwrap();
+            (cache.turn.as_ref() == Some(&turn)).then(|| cache.turn_note.clone())
+        };
+        let note = match cached {
+            Some(note) => note,
+            None => {
+                let note = run_blocking(|| self.turn_note());
+                let mut cache = self.cache.lock().unwrap();
+                cache.turn = Some(turn);
+                cache.turn_note = note.clone();
+                note
+            }
+        };
+
+        if let Some(note) = note {
+            inject_system_reminder(&mut messages, &note);
+        }
+        messages
+    }
+}
+
+/// Run blocking work (here, git processes) from inside the injection
+///
+/// `inject` is called on the async runtime before LLM calls. On a
+/// multi-threaded runtime the worker thread is handed off with
+/// `block_in_place` so other tasks keep running while git works; elsewhere
+/// the work runs inline.
+fn run_blocking<T>(work: impl FnOnce() -> T) -> T {
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(work)
+        }
+        _ => work(),
+    }
+}
+
+/// Ask the LLM for a short summary of a diff
+async fn summarize(llm: &Arc<dyn LlmProvider>, diff: &str) -> Result<String> {
+    tracing::debug!("[DiffSummaryProvider] Summarizing {} byte diff", diff.len());
+    let summary = llm
+        .send_message(diff, &[], Some(SUMMARY_SYSTEM_PROMPT), None)
+        .await?;
+    Ok(format!("Summary of uncommitted changes:\n{}", summary.trim()))
+}
+
+/// Wrap a small diff so it can be injected directly
+fn format_raw_note(diff: &str) -> String {
+    format!("Uncommitted changes (git diff HEAD):\n```diff\n{}\n```", diff.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::internals::test_internals;
+    use crate::testing::MockLlmProvider;
+
+    fn git(dir: &std::path::Path, args: &[&str]) {
+        let status = Command::new("git").args(args).current_dir(dir).output().unwrap().status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    /// A repository with one commit and an uncommitted change to a.txt
+    fn changed_repo() -> tempfile::TempDir {
+        let repo = tempfile::TempDir::new().unwrap();
+        git(repo.path(), &["init", "-q"]);
+        std::fs::write(repo.path().join("a.txt"), "one\n").unwrap();
+        git(repo.path(), &["add", "."]);
+        git(repo.path(), &["-c", "user.name=t", "-c", "user.email=t@t", "commit", "-qm", "init"]);
+        std::fs::write(repo.path().join("a.txt"), "two\n").unwrap();
+        repo
+    }
+
+    #[test]
+    fn test_inject_runs_git_once_per_turn() {
+        let repo = changed_repo();
+
+        let session_dir = tempfile::TempDir::new().unwrap();
+        let (mut internals, _, _) = test_internals(session_dir.path());
+        let provider = DiffSummaryProvider::new(repo.path(), Arc::new(MockLlmProvider::new(vec![])));
+
+        let messages = provider.inject(&internals, vec![Message::user("Hi")]);
+        assert!(messages[0].text().unwrap().contains("+two"));
+
+        // Later calls in the same turn reuse the note
+        std::fs::write(repo.path().join("a.txt"), "three\n").unwrap();
+        let messages = provider.inject(&internals, vec![Message::user("Hi")]);
+        assert!(messages[0].text().unwrap().contains("+two"));
+
+        internals.next_turn();
+        let messages = provider.inject(&internals, vec![Message::user("Hi")]);
+        assert!(messages[0].text().unwrap().contains("+three"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_inject_on_multi_thread_runtime() {
+        let repo = changed_repo();
+
+        let session_dir = tempfile::TempDir::new().unwrap();
+        let (internals, _, _) = test_internals(session_dir.path());
+        let provider = DiffSummaryProvider::new(repo.path(), Arc::new(MockLlmProvider::new(vec![])));
+
+        let messages = provider.inject(&internals, vec![Message::user("Hi")]);
+        assert!(messages[0].text().unwrap().contains("+two"));
+    }
+
+    #[test]
+    fn test_format_raw_note() {
+        let note = format_raw_note("+added line\n-removed line\n");
+        assert!(note.starts_with("Uncommitted changes"));
+        assert!(note.contains("```diff\n+added line\n-removed line\n```"));
+    }
+}
@@ -0,0 +1,76 @@
+//! Long line handling for file contents shown to the model
+//!
+//! The Read tool and file attachments cap each line at a maximum number of
+//! characters. `LongLines` picks what happens to the rest of the line. Limits
+//! are counted in characters, so multi-byte text is never split mid-character.
+
+use super::truncate::truncate_chars;
+
+/// How lines longer than the limit are shown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LongLines {
+    /// Keep the start of the line and end it with `...` (default)
+    #[default]
+    Truncate,
+    /// Keep the start and the end of the line with ` ... ` between them
+    TruncateMiddle,
+    /// Split the line into several lines of at most the limit
+    Wrap,
+}
+
+impl LongLines {
+    /// Fit a line into `max_chars`, returning one or more display lines
+    ///
+    /// Only `Wrap` returns more than one line.
+    pub fn apply(&self, line: &str, max_chars: usize) -> Vec<String> {
+        let total = line.chars().count();
+        if total <= max_chars || max_chars == 0 {
+            return vec![line.to_string()];
+        }
+
+        match self {
+            LongLines::Truncate => {
+                vec![format!("{}...", truncate_chars(line, max_chars))]
+            }
+            LongLines::TruncateMiddle => {
+                let head = truncate_chars(line, max_chars.div_ceil(2));
+                let tail = &line[truncate_chars(line, total - max_chars / 2).len()..];
+                vec![format!("{} ... {}", head, tail)]
+            }
+            LongLines::Wrap => {
+                let chars: Vec<char> = line.chars().collect();
+                chars.chunks(max_chars).map(|chunk| chunk.iter().collect()).collect()
+            }
+        }
+    }
+
+    /// Append a line in `cat -n` format, wrapped lines without a number
+    pub(crate) fn push_numbered(&self, out: &mut String, line_num: usize, line: &str, max_chars: usize) {
+        for (i, part) in self.apply(line, max_chars).iter().enumerate() {
+            if i == 0 {
+                out.push_str(&format!("{:>6}\t{}\n", line_num, part));
+            } else {
+                out.push_str(&format!("{:>6}\t{}\n", "", part));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_long_lines_respect_char_boundaries() {
+        let line = "😀".repeat(6);
+
+        assert_eq!(LongLines::Truncate.apply(&line, 4), vec!["😀😀😀😀...".to_string()]);
+        assert_eq!(LongLines::TruncateMiddle.apply("abcdefghij", 5), vec!["abc ... ij".to_string()]);
+        assert_eq!(LongLines::Wrap.apply(&line, 4), vec!["😀😀😀😀".to_string(), "😀😀".to_string()]);
+        assert_eq!(LongLines::Truncate.apply("short", 10), vec!["short".to_string()]);
+
+        let mut out = String::new();
+        LongLines::Wrap.push_numbered(&mut out, 7, "abcdef", 4);
+        assert_eq!(out, "     7\tabcd\n      \tef\n");
+    }
+}
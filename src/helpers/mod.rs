@@ -5,21 +5,39 @@
 //! - `ContextInjection` - Modify messages before each LLM call
 //! - `Debugger` - Log API calls and tool executions for debugging
 //! - `ConversationNamer` - Generate descriptive names for conversations
+//! - `DiffSummaryProvider` - Inject a (summarized) git diff as context
 //! - `Attachments` - Process file attachments in user messages
+//! - `LongLines` - How long lines in file contents are shortened
+//! - `plan_then_execute` - Read-only planning phase before tools are unlocked
+//! - `PromptLibrary` - Prompt templates loaded from disk
+//! - `truncate_chars` / `truncate_bytes_safe` - Shorten strings without splitting characters
 
 mod attachments;
 mod context_injection;
 mod conversation_namer;
 mod debugger;
+mod diff_summary;
+mod long_lines;
+mod plan_mode;
+mod prompt_library;
 mod todo_manager;
+mod truncate;
 
-pub use attachments::process_attachments;
+pub(crate) use attachments::{directory_entries, format_size};
+pub(crate) use context_injection::{is_session_preamble, session_preamble_block};
+pub use attachments::{process_attachments, process_attachments_with};
 pub use context_injection::{
     append_to_last_message, inject_system_reminder, prepend_to_first_user_message,
-    BoxedInjection, ContextInjection, FnInjection, InjectionChain, SharedInjection,
+    BoxedInjection, CallbackContextProvider, ContextInjection, FnInjection, InjectionChain,
+    SharedInjection,
 };
 pub use conversation_namer::{generate_conversation_name, ConversationNamer};
 pub use debugger::{
     ApiRequestEvent, ApiResponseEvent, Debugger, EventType, ToolCallEvent, ToolResultEvent,
 };
+pub use diff_summary::DiffSummaryProvider;
+pub use long_lines::LongLines;
+pub use plan_mode::{plan_then_execute, plan_then_execute_with, PlanApproval, PlanThenExecute};
+pub use prompt_library::{render_template, PromptLibrary, SystemTemplate};
 pub use todo_manager::{TodoItem, TodoListManager, TodoStatus};
+pub use truncate::{truncate_bytes_safe, truncate_chars};
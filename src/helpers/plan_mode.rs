@@ -0,0 +1,162 @@
+//! Plan-then-execute flow
+//!
+//! Wraps an `AgentConfig` so the agent starts in a read-only planning phase:
+//! only tools whose `Tool::is_read_only` is true are available and each request reminds the model to
+//! investigate and propose a plan. Once the user approves, `PlanApproval`
+//! unlocks the remaining tools for the execution phase.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use picrust::helpers::plan_then_execute;
+//!
+//! let plan = plan_then_execute(AgentConfig::new().with_tools(tools));
+//! let approval = plan.approval.clone();
+//! let agent = StandardAgent::new(plan.config, llm);
+//!
+//! // ... the agent replies with a plan; the user accepts it ...
+//! approval.approve();
+//! handle.send_input("Looks good, go ahead.").await?;
+//! ```
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::agent::AgentConfig;
+
+use super::context_injection::{inject_system_reminder, FnInjection};
+
+/// Reminder added to each request while the plan is not approved
+const PLAN_MODE_REMINDER: &str = "You are in plan mode. Investigate with the available read-only tools, \
+then reply with a concise, numbered plan of the changes you intend to make. Do not try to modify files \
+or run commands yet; the user will review the plan and unlock the remaining tools once it is approved.";
+
+/// Shared switch between the planning and execution phases
+///
+/// Clones share the same state, so keep one next to the agent handle and
+/// call `approve()` when the user accepts the plan.
+#[derive(Debug, Clone, Default)]
+pub struct PlanApproval(Arc<AtomicBool>);
+
+impl PlanApproval {
+    /// Unlock all tools for the execution phase
+    pub fn approve(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Return to the planning phase (e.g. for the next task)
+    pub fn revoke(&self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+
+    /// Whether the plan has been approved
+    pub fn is_approved(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// A config set up for plan-then-execute, and the switch between the phases
+pub struct PlanThenExecute {
+    /// The wrapped config; pass it to `StandardAgent::new`
+    pub config: AgentConfig,
+    /// Starts unapproved (planning phase)
+    pub approval: PlanApproval,
+}
+
+/// Wrap `config` in a planning phase limited to its read-only tools
+///
+/// The planning tools are those in the agent's tool registry that report
+/// `Tool::is_read_only`. The registry is read on each request, so tools set
+/// with `with_tools` after wrapping are covered too.
+pub fn plan_then_execute(config: AgentConfig) -> PlanThenExecute {
+    wrap(config, None)
+}
+
+/// Wrap `config` in a planning phase limited to `read_only_tools`
+///
+/// Builds on the config's per-turn allow-list: while planning, calls to other
+/// tools are rejected even if the model tries them. An allow-list already set
+/// on `config` still applies in both phases. The plan-mode reminder is added
+/// as a context injection with the highest priority, so it claims an
+/// injection budget first; it is still skipped if the budget is smaller than
+/// the reminder itself.
+pub fn plan_then_execute_with(config: AgentConfig, read_only_tools: Vec<String>) -> PlanThenExecute {
+    wrap(config, Some(read_only_tools))
+}
+
+/// Install the phase-dependent allow-list and the plan-mode reminder
+///
+/// With `read_only_tools` unset, the registry's read-only tools are used.
+fn wrap(mut config: AgentConfig, read_only_tools: Option<Vec<String>>) -> PlanThenExecute {
+    let approval = PlanApproval::default();
+
+    let inner_allowed = config.allowed_tools.take();
+    let phase = approval.clone();
+    config.allowed_tools = Some(Arc::new(move |history, tools| {
+        let mut allowed: Vec<String> = match inner_allowed {
+            Some(ref allowed) => allowed(history, tools),
+            None => tools
+                .map(|tools| tools.tool_names().into_iter().map(str::to_string).collect())
+                .unwrap_or_default(),
+        };
+        if !phase.is_approved() {
+            match read_only_tools {
+                Some(ref read_only) => allowed.retain(|name| read_only.contains(name)),
+                None => {
+                    let read_only = tools.map(|tools| tools.read_only_tool_names()).unwrap_or_default();
+                    allowed.retain(|name| read_only.contains(&name.as_str()));
+                }
+            }
+        }
+        allowed
+    }));
+
+    let phase = approval.clone();
+    config.injections.add_with_priority(
+        FnInjection::new("plan_mode", move |_internals, mut messages| {
+            if !phase.is_approved() {
+                inject_system_reminder(&mut messages, PLAN_MODE_REMINDER);
+            }
+            messages
+        }),
+        i32::MAX,
+    );
+
+    PlanThenExecute { config, approval }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::{FnTool, ToolRegistry, ToolResult};
+    use serde_json::json;
+
+    #[test]
+    fn test_plan_then_execute_unlocks_tools() {
+        let mut tools = ToolRegistry::new();
+        for (name, read_only) in [("Read", true), ("Grep", true), ("Edit", false)] {
+            tools.register(
+                FnTool::new(name, name, json!({}), vec![], |_| async { Ok(ToolResult::success("")) })
+                    .with_read_only(read_only),
+            );
+        }
+        // Tools set after wrapping are picked up
+        let plan = plan_then_execute(AgentConfig::new());
+        let config = plan.config.with_tools(Arc::new(tools));
+        let allowed = config.allowed_tools.as_ref().unwrap();
+        let sorted = || {
+            let mut names = allowed(&[], config.tools.as_deref());
+            names.sort();
+            names
+        };
+
+        assert_eq!(sorted(), vec!["Grep", "Read"]);
+        assert_eq!(config.injections.names(), vec!["plan_mode"]);
+
+        plan.approval.approve();
+        assert_eq!(sorted(), vec!["Edit", "Grep", "Read"]);
+
+        plan.approval.revoke();
+        assert_eq!(sorted(), vec!["Grep", "Read"]);
+    }
+}
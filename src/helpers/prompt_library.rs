@@ -0,0 +1,228 @@
+//! Prompt templates loaded from disk
+//!
+//! A `PromptLibrary` holds named templates read from the `.md` and `.txt`
+//! files of a directory (the name is the file stem). Templates may contain
+//! `{{var}}` placeholders, filled in by `render`.
+//!
+//! `PromptLibrary::load` reads the files once, for production.
+//! `PromptLibrary::hot_reload` reads a template from disk every time it is
+//! used, so prompts can be tweaked during development without recompiling.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use picrust::helpers::{PromptLibrary, SystemTemplate};
+//!
+//! let prompts = Arc::new(if cfg!(debug_assertions) {
+//!     PromptLibrary::hot_reload("prompts")
+//! } else {
+//!     PromptLibrary::load("prompts")?
+//! });
+//!
+//! let config = AgentConfig::new().with_system_template(
+//!     SystemTemplate::new(prompts, "system").with_var("project", "picrust"),
+//! );
+//! ```
+
+use anyhow::{anyhow, Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// File extensions read as templates
+const TEMPLATE_EXTENSIONS: &[&str] = &["md", "txt"];
+
+/// Named prompt templates from a directory
+#[derive(Debug)]
+pub struct PromptLibrary {
+    dir: PathBuf,
+    /// Templates read at load time; `None` when reloading on each use
+    cache: Option<HashMap<String, String>>,
+}
+
+impl PromptLibrary {
+    /// Read every template in `dir` once
+    pub fn load(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        let mut templates = HashMap::new();
+        let entries = fs::read_dir(&dir)
+            .with_context(|| format!("Failed to read prompt directory {}", dir.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            let Some(name) = template_name(&path) else {
+                continue;
+            };
+            let template = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read prompt template {}", path.display()))?;
+            templates.insert(name, template);
+        }
+        tracing::debug!("[PromptLibrary] Loaded {} templates from {}", templates.len(), dir.display());
+        Ok(Self {
+            dir,
+            cache: Some(templates),
+        })
+    }
+
+    /// Read templates from `dir` each time they are used
+    pub fn hot_reload(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            cache: None,
+        }
+    }
+
+    /// Directory the templates come from
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Whether templates are re-read on each use
+    pub fn is_hot_reload(&self) -> bool {
+        self.cache.is_none()
+    }
+
+    /// Raw text of the template `name`
+    pub fn get(&self, name: &str) -> Result<String> {
+        if let Some(ref templates) = self.cache {
+            return templates
+                .get(name)
+                .cloned()
+                .ok_or_else(|| anyhow!("Prompt template '{}' not found in {}", name, self.dir.display()));
+        }
+        for extension in TEMPLATE_EXTENSIONS {
+            let path = self.dir.join(format!("{}.{}", name, extension));
+            if path.is_file() {
+                return fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read prompt template {}", path.display()));
+            }
+        }
+        Err(anyhow!("Prompt template '{}' not found in {}", name, self.dir.display()))
+    }
+
+    /// Template `name` with its `{{var}}` placeholders filled from `vars`
+    pub fn render(&self, name: &str, vars: &HashMap<String, String>) -> Result<String> {
+        render_template(&self.get(name)?, vars)
+            .with_context(|| format!("Failed to render prompt template '{}'", name))
+    }
+
+    /// Names of the available templates, sorted
+    pub fn names(&self) -> Result<Vec<String>> {
+        let mut names: Vec<String> = match self.cache {
+            Some(ref templates) => templates.keys().cloned().collect(),
+            None => fs::read_dir(&self.dir)
+                .with_context(|| format!("Failed to read prompt directory {}", self.dir.display()))?
+                .filter_map(|entry| template_name(&entry.ok()?.path()))
+                .collect(),
+        };
+        names.sort();
+        names.dedup();
+        Ok(names)
+    }
+}
+
+/// Fill the `{{var}}` placeholders in `template`
+///
+/// Whitespace inside the braces is ignored. A placeholder without a value
+/// is an error, so typos don't silently reach the model.
+pub fn render_template(template: &str, vars: &HashMap<String, String>) -> Result<String> {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + 2 + len].trim();
+        let value = vars
+            .get(name)
+            .ok_or_else(|| anyhow!("No value for template variable '{}'", name))?;
+        output.push_str(&rest[..start]);
+        output.push_str(value);
+        rest = &rest[start + 2 + len + 2..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+/// A library template used as the agent's system prompt
+///
+/// Set with `AgentConfig::with_system_template`. The template is rendered
+/// at the start of each turn and written to the session's system prompt
+/// when it changed.
+#[derive(Debug, Clone)]
+pub struct SystemTemplate {
+    /// Library holding the template
+    pub library: Arc<PromptLibrary>,
+    /// Template name
+    pub name: String,
+    /// Values for the template's placeholders
+    pub vars: HashMap<String, String>,
+}
+
+impl SystemTemplate {
+    /// Use template `name` from `library`
+    pub fn new(library: Arc<PromptLibrary>, name: impl Into<String>) -> Self {
+        Self {
+            library,
+            name: name.into(),
+            vars: HashMap::new(),
+        }
+    }
+
+    /// Set the value of a placeholder
+    pub fn with_var(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.vars.insert(name.into(), value.into());
+        self
+    }
+
+    /// Render the template
+    pub fn render(&self) -> Result<String> {
+        self.library.render(&self.name, &self.vars)
+    }
+}
+
+/// Template name for `path`, if it is a template file
+fn template_name(path: &Path) -> Option<String> {
+    let extension = path.extension()?.to_str()?;
+    if !path.is_file() || !TEMPLATE_EXTENSIONS.contains(&extension) {
+        return None;
+    }
+    Some(path.file_stem()?.to_str()?.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_render_template() {
+        let vars = HashMap::from([("name".to_string(), "picrust".to_string())]);
+        assert_eq!(
+            render_template("Hello {{name}}, {{ name }}!", &vars).unwrap(),
+            "Hello picrust, picrust!"
+        );
+        assert_eq!(render_template("Unclosed {{name", &vars).unwrap(), "Unclosed {{name");
+        assert!(render_template("{{missing}}", &vars).is_err());
+    }
+
+    #[test]
+    fn test_load_and_hot_reload() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("system.md"), "You help with {{project}}.").unwrap();
+        fs::write(dir.path().join("notes.json"), "{}").unwrap();
+
+        let loaded = PromptLibrary::load(dir.path()).unwrap();
+        let hot = PromptLibrary::hot_reload(dir.path());
+        assert_eq!(loaded.names().unwrap(), vec!["system"]);
+        assert_eq!(hot.names().unwrap(), vec!["system"]);
+
+        let template = SystemTemplate::new(Arc::new(hot), "system").with_var("project", "picrust");
+        assert_eq!(template.render().unwrap(), "You help with picrust.");
+
+        fs::write(dir.path().join("system.md"), "You review {{project}}.").unwrap();
+        assert_eq!(template.render().unwrap(), "You review picrust.");
+        assert_eq!(loaded.get("system").unwrap(), "You help with {{project}}.");
+        assert!(loaded.get("missing").is_err());
+    }
+}
@@ -12,8 +12,14 @@
 //! ```
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::sync::RwLock;
 
+use crate::llm::{ContentBlock, Message, MessageContent};
+
+/// Name of the tool whose calls carry the todo list
+const TODO_TOOL_NAME: &str = "TodoWrite";
+
 /// Status of a todo item
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
@@ -108,6 +114,53 @@ impl TodoListManager {
         state.last_updated_turn = turn;
     }
 
+    /// Restore the todo list from the last successful `TodoWrite` call in `history`
+    ///
+    /// Use this when resuming a session so the list matches what the model
+    /// last wrote. The update turn is reset to 0, since turn counting restarts
+    /// on resume. Returns `false` (leaving the list unchanged) if the history
+    /// has no usable `TodoWrite` call.
+    pub fn restore_from_history(&self, history: &[Message]) -> bool {
+        let failed_calls: HashSet<&str> = history
+            .iter()
+            .filter_map(|message| match &message.content {
+                MessageContent::Blocks(blocks) => Some(blocks),
+                MessageContent::Text(_) => None,
+            })
+            .flatten()
+            .filter_map(|block| match block {
+                ContentBlock::ToolResult { tool_use_id, is_error: Some(true), .. } => Some(tool_use_id.as_str()),
+                _ => None,
+            })
+            .collect();
+
+        let last_write = history
+            .iter()
+            .rev()
+            .filter(|message| message.role == "assistant")
+            .filter_map(|message| match &message.content {
+                MessageContent::Blocks(blocks) => Some(blocks),
+                MessageContent::Text(_) => None,
+            })
+            .flat_map(|blocks| blocks.iter().rev())
+            .find_map(|block| match block {
+                ContentBlock::ToolUse { id, name, input, .. }
+                    if name == TODO_TOOL_NAME && !failed_calls.contains(id.as_str()) =>
+                {
+                    serde_json::from_value::<Vec<TodoItem>>(input.get("todos")?.clone()).ok()
+                }
+                _ => None,
+            });
+
+        match last_write {
+            Some(items) => {
+                self.set_todos(items, 0);
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Get the turn number when todos were last updated
     pub fn last_updated_turn(&self) -> usize {
         self.state.read().unwrap().last_updated_turn
@@ -275,4 +328,29 @@ mod tests {
         assert!(current.is_some());
         assert_eq!(current.unwrap().content, "Task 2");
     }
+
+    #[test]
+    fn test_restore_from_history() {
+        use serde_json::json;
+
+        let todos = |status: &str| {
+            json!({"todos": [{"content": "Run tests", "status": status, "activeForm": "Running tests"}]})
+        };
+        let history = vec![
+            Message::user("Fix the build"),
+            Message::assistant_with_blocks(vec![ContentBlock::tool_use("t1", "TodoWrite", todos("in_progress"))]),
+            Message::user_with_blocks(vec![ContentBlock::tool_result("t1", "ok", false)]),
+            Message::assistant_with_blocks(vec![ContentBlock::tool_use("t2", "TodoWrite", todos("completed"))]),
+            Message::user_with_blocks(vec![ContentBlock::tool_result("t2", "failed", true)]),
+        ];
+
+        // The failed second call is skipped
+        let manager = TodoListManager::new();
+        assert!(manager.restore_from_history(&history));
+        assert_eq!(manager.len(), 1);
+        assert_eq!(manager.get_todos()[0].status, TodoStatus::InProgress);
+
+        assert!(!manager.restore_from_history(&history[..1]));
+        assert_eq!(manager.len(), 1);
+    }
 }
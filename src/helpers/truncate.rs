@@ -0,0 +1,44 @@
+//! UTF-8-safe string truncation
+//!
+//! Slicing a `&str` with `&s[..n]` panics when `n` falls inside a multi-byte
+//! character. These helpers cut at character boundaries instead, so output
+//! containing emoji or non-Latin text can be shortened safely.
+
+/// Longest prefix of `s` that is at most `max_bytes` bytes long
+///
+/// Cuts before any character that would straddle the limit.
+pub fn truncate_bytes_safe(s: &str, max_bytes: usize) -> &str {
+    if s.len() <= max_bytes {
+        return s;
+    }
+    let mut end = max_bytes;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+/// The first `max_chars` characters of `s`
+pub fn truncate_chars(s: &str, max_chars: usize) -> &str {
+    match s.char_indices().nth(max_chars) {
+        Some((end, _)) => &s[..end],
+        None => s,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_multi_byte() {
+        let s = "a😀b";
+        assert_eq!(truncate_bytes_safe(s, 3), "a");
+        assert_eq!(truncate_bytes_safe(s, 5), "a😀");
+        assert_eq!(truncate_bytes_safe(s, 100), s);
+
+        assert_eq!(truncate_chars(s, 2), "a😀");
+        assert_eq!(truncate_chars(s, 0), "");
+        assert_eq!(truncate_chars(s, 3), s);
+    }
+}
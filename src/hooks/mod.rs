@@ -68,6 +68,33 @@
 //! | `UserPromptSubmit` | When user sends prompt | `user_prompt`, messages |
 //! | `PostAssistantResponse` | After assistant generates response | messages (for logging) |
 //! | `TurnComplete` | After turn finishes, before agent suspends | session, metadata |
+//! | `AssistantTextDelta` | Before each streamed text delta is shown | `deny` stops the response |
+//!
+//! # Streaming Text Filters
+//!
+//! `AssistantTextDelta` hooks see `text_delta` (the new text) and
+//! `assistant_text` (the current text block so far) before the delta reaches
+//! any output. Returning `deny` stops the response at once: the stream is
+//! closed, the text block in progress and all tool calls are dropped, and the
+//! turn ends with `TurnEndReason::Filtered`. Without streaming, each text
+//! block is checked once as a single delta.
+//!
+//! These hooks run synchronously on the agent task for every delta, typically
+//! every few tokens, and the delta is held until all of them return. Keep them
+//! to in-memory checks; a hook that takes 5 ms adds 5 ms per delta, which
+//! quickly shows as stutter. For expensive classifiers, check `assistant_text`
+//! only every few hundred characters.
+//!
+//! ```ignore
+//! hooks.add(HookEvent::AssistantTextDelta, |ctx| {
+//!     let text = ctx.assistant_text.as_deref().unwrap_or("");
+//!     if blocklist.is_match(text) {
+//!         HookResult::deny("disallowed content")
+//!     } else {
+//!         HookResult::none()
+//!     }
+//! });
+//! ```
 //!
 //! # HookResult
 //!
@@ -195,6 +195,9 @@ impl HookRegistry {
     /// - Else if ANY hook said Allow → ALLOW
     /// - Else if ANY hook said Ask → ASK
     /// - Else (all said None) → NONE (continue normal flow)
+    ///
+    /// Messages from every hook that ran are kept, in registration order,
+    /// regardless of which decision wins.
     pub fn run(&self, ctx: &mut HookContext<'_>) -> HookResult {
         let event = ctx.event;
         let tool_name = ctx.tool_name.clone();
@@ -240,8 +243,17 @@ impl HookRegistry {
 
 /// Combine two hook results
 ///
-/// Priority: Deny > Allow > Ask > None
-fn combine_results(a: HookResult, b: HookResult) -> HookResult {
+/// Priority: Deny > Allow > Ask > None. Messages from both are kept, `a`'s first.
+fn combine_results(mut a: HookResult, mut b: HookResult) -> HookResult {
+    let mut messages = std::mem::take(&mut a.messages);
+    messages.append(&mut b.messages);
+    let mut combined = combine_decisions(a, b);
+    combined.messages = messages;
+    combined
+}
+
+/// The higher-priority of two hook results
+fn combine_decisions(a: HookResult, b: HookResult) -> HookResult {
     match (a.decision, b.decision) {
         // Deny always wins
         (Some(PermissionDecision::Deny), _) => a,
@@ -382,4 +394,18 @@ mod tests {
             Some(PermissionDecision::Allow)
         );
     }
+
+    #[test]
+    fn test_messages_kept_from_all_hooks() {
+        let result = combine_results(
+            HookResult::none().with_message("first"),
+            HookResult::deny("blocked").with_message("second"),
+        );
+        assert_eq!(result.decision, Some(PermissionDecision::Deny));
+        assert_eq!(result.messages, vec!["first", "second"]);
+
+        let result = combine_results(HookResult::allow().with_message("only"), HookResult::none());
+        assert_eq!(result.decision, Some(PermissionDecision::Allow));
+        assert_eq!(result.messages, vec!["only"]);
+    }
 }
@@ -29,6 +29,8 @@ pub enum HookEvent {
     PostAssistantResponse,
     /// After the full turn completes (agent about to suspend/go idle)
     TurnComplete,
+    /// Before each streamed text delta is shown - `deny` stops the response
+    AssistantTextDelta,
 }
 
 impl std::fmt::Display for HookEvent {
@@ -40,6 +42,7 @@ impl std::fmt::Display for HookEvent {
             HookEvent::UserPromptSubmit => write!(f, "UserPromptSubmit"),
             HookEvent::PostAssistantResponse => write!(f, "PostAssistantResponse"),
             HookEvent::TurnComplete => write!(f, "TurnComplete"),
+            HookEvent::AssistantTextDelta => write!(f, "AssistantTextDelta"),
         }
     }
 }
@@ -88,6 +91,15 @@ pub struct HookContext<'a> {
 
     /// Stop reason for the assistant's response
     pub stop_reason: Option<StopReason>,
+
+    // === Streamed text (for AssistantTextDelta) ===
+    /// The delta about to be shown
+    pub text_delta: Option<&'a str>,
+
+    /// Text of the current block so far, including the delta
+    ///
+    /// Borrowed, so long responses are not copied for every delta.
+    pub assistant_text: Option<&'a str>,
 }
 
 impl<'a> HookContext<'a> {
@@ -111,6 +123,8 @@ impl<'a> HookContext<'a> {
             user_prompt: None,
             assistant_content: None,
             stop_reason: None,
+            text_delta: None,
+            assistant_text: None,
         }
     }
 
@@ -135,6 +149,8 @@ impl<'a> HookContext<'a> {
             user_prompt: None,
             assistant_content: None,
             stop_reason: None,
+            text_delta: None,
+            assistant_text: None,
         }
     }
 
@@ -159,6 +175,8 @@ impl<'a> HookContext<'a> {
             user_prompt: None,
             assistant_content: None,
             stop_reason: None,
+            text_delta: None,
+            assistant_text: None,
         }
     }
 
@@ -180,6 +198,8 @@ impl<'a> HookContext<'a> {
             user_prompt: Some(prompt.to_string()),
             assistant_content: None,
             stop_reason: None,
+            text_delta: None,
+            assistant_text: None,
         }
     }
 
@@ -202,6 +222,32 @@ impl<'a> HookContext<'a> {
             user_prompt: None,
             assistant_content: Some(content_blocks.to_vec()),
             stop_reason,
+            text_delta: None,
+            assistant_text: None,
+        }
+    }
+
+    /// Create context for AssistantTextDelta hook
+    pub fn assistant_text_delta(
+        internals: &'a mut AgentInternals,
+        delta: &'a str,
+        text_so_far: &'a str,
+        short_circuit_on_deny: bool,
+    ) -> Self {
+        Self {
+            event: HookEvent::AssistantTextDelta,
+            internals,
+            short_circuit_on_deny,
+            tool_name: None,
+            tool_input: None,
+            tool_use_id: None,
+            tool_result: None,
+            error: None,
+            user_prompt: None,
+            assistant_content: None,
+            stop_reason: None,
+            text_delta: Some(delta),
+            assistant_text: Some(text_so_far),
         }
     }
 
@@ -219,6 +265,8 @@ impl<'a> HookContext<'a> {
             user_prompt: None,
             assistant_content: None,
             stop_reason: None,
+            text_delta: None,
+            assistant_text: None,
         }
     }
 
@@ -290,13 +338,20 @@ pub enum PermissionDecision {
 ///
 /// For most hooks, just return `HookResult::none()` or `HookResult::default()`.
 /// For PreToolUse hooks that want to control permissions, use `allow()`, `deny()`, or `ask()`.
+/// PreToolUse hooks can also add messages for the model with `with_message()`.
+///
+/// Build results with the constructors above; new fields may be added.
 #[derive(Debug, Clone, Default)]
+#[non_exhaustive]
 pub struct HookResult {
     /// Permission decision (mainly for PreToolUse)
     pub decision: Option<PermissionDecision>,
 
     /// Reason for the decision (shown in error message if denied)
     pub reason: Option<String>,
+
+    /// Text added to the conversation alongside the tool's result (PreToolUse only)
+    pub messages: Vec<String>,
 }
 
 impl HookResult {
@@ -305,6 +360,7 @@ impl HookResult {
         Self {
             decision: Some(PermissionDecision::Allow),
             reason: None,
+            messages: Vec::new(),
         }
     }
 
@@ -313,6 +369,7 @@ impl HookResult {
         Self {
             decision: Some(PermissionDecision::Deny),
             reason: Some(reason.into()),
+            messages: Vec::new(),
         }
     }
 
@@ -321,6 +378,7 @@ impl HookResult {
         Self {
             decision: Some(PermissionDecision::Ask),
             reason: None,
+            messages: Vec::new(),
         }
     }
 
@@ -334,4 +392,16 @@ impl HookResult {
         self.reason = Some(reason.into());
         self
     }
+
+    /// Add a message for the model, e.g. a policy reminder about this call
+    ///
+    /// In a PreToolUse hook, the text is added to the conversation as a text
+    /// block after the tool results of the current step, whatever the decision.
+    /// It can't go before the call: the model has already made it, and the
+    /// APIs require tool results to lead the message that answers it. The
+    /// model reads the text with the results, before its next step.
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.messages.push(message.into());
+        self
+    }
 }
@@ -20,3 +20,7 @@ pub mod hooks;
 
 // MCP (Model Context Protocol) support
 pub mod mcp;
+
+// Test support (mock LLM, session replay)
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
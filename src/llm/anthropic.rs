@@ -19,28 +19,48 @@
 //!     let jwt = refresh_token().await?;
 //!     Ok(AuthConfig::with_base_url(jwt, "https://proxy.example.com/v1/messages"))
 //! });
+//!
+//! // Route through a gateway and enable a beta feature
+//! let llm = AnthropicProvider::from_env()?
+//!     .with_base_url("https://gateway.example.com/v1/messages")
+//!     .with_beta("context-1m-2025-08-07");
 //! ```
 
 use anyhow::{Context, Result};
 use futures::stream::Stream;
 use futures::StreamExt;
 use reqwest::Client;
-use std::env;
+use serde::Deserialize;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::io::AsyncBufReadExt;
 use tokio_util::io::StreamReader;
 
 use super::auth::{auth_provider, AuthConfig, AuthProvider, AuthSource};
-use super::provider::LlmProvider;
+use super::batch::{
+    plain_request, with_defaults, BatchProvider, BatchRequest, BatchResult, BatchState,
+    BatchStatus,
+};
+use super::env_config::{require_model, ProviderEnvConfig};
+use super::headers::RequestHeaders;
+use super::http::{build_client, check_credentials, model_url, send_with_retries};
+use super::provider::{LlmProvider, RequestOptions};
 use super::types::{
     Message, MessageRequest, MessageResponse, RawStreamEvent, StreamEvent, SystemPrompt,
-    ThinkingConfig, ToolChoice, ToolDefinition,
+    RequestMetadata, ThinkingConfig, ToolChoice, ToolDefinition,
 };
 
 const DEFAULT_API_URL: &str = "https://api.anthropic.com/v1/messages";
 const ANTHROPIC_VERSION: &str = "2023-06-01";
+/// Beta features enabled on every provider
+const DEFAULT_BETAS: &[&str] = &["interleaved-thinking-2025-05-14"];
+
+/// Betas enabled on a new provider
+fn default_betas() -> Vec<String> {
+    DEFAULT_BETAS.iter().map(|b| b.to_string()).collect()
+}
 
 /// Anthropic LLM provider using direct HTTP calls
 ///
@@ -53,48 +73,53 @@ pub struct AnthropicProvider {
     auth: AuthSource,
     model: String,
     max_tokens: u32,
+    headers: RequestHeaders,
+    /// Endpoint URL overriding the one from auth (None = use auth or default)
+    base_url: Option<String>,
+    /// Values sent in the `anthropic-beta` header
+    betas: Vec<String>,
+    /// Retries for transient request failures
+    max_retries: u32,
 }
 
 impl AnthropicProvider {
     /// Create a new Anthropic provider from environment variables
     ///
-    /// Reads from:
+    /// Reads the `ANTHROPIC_*` variables described in
+    /// [`ProviderEnvConfig`](super::ProviderEnvConfig):
     /// - `ANTHROPIC_API_KEY` (required)
     /// - `ANTHROPIC_MODEL` (required)
     /// - `ANTHROPIC_BASE_URL` (optional, defaults to Anthropic API)
     /// - `ANTHROPIC_MAX_TOKENS` (optional, defaults to 32000)
+    /// - `ANTHROPIC_TIMEOUT_SECS` (optional, no timeout by default)
+    /// - `ANTHROPIC_MAX_RETRIES` (optional, defaults to 0)
     pub fn from_env() -> Result<Self> {
         tracing::info!("Creating Anthropic provider from environment");
+        Self::from_config(ProviderEnvConfig::load("ANTHROPIC")?)
+    }
 
-        let api_key = env::var("ANTHROPIC_API_KEY")
-            .context("ANTHROPIC_API_KEY environment variable not set")?;
-
-        let base_url = env::var("ANTHROPIC_BASE_URL").ok();
-
-        let model = env::var("ANTHROPIC_MODEL")
-            .context("ANTHROPIC_MODEL environment variable not set")?;
-
-        let max_tokens = env::var("ANTHROPIC_MAX_TOKENS")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(32000); // Must be > thinking.budget_tokens (16000)
+    /// Create a new Anthropic provider from already-loaded settings
+    pub fn from_config(config: ProviderEnvConfig) -> Result<Self> {
+        let max_tokens = config.max_tokens.unwrap_or(32000); // Must be > thinking.budget_tokens (16000)
 
-        tracing::info!("Using model: {}", model);
+        tracing::info!("Using model: {}", config.model);
         tracing::info!("Max tokens: {}", max_tokens);
-        if let Some(ref url) = base_url {
+        if let Some(ref url) = config.base_url {
             tracing::info!("Using custom base URL: {}", url);
         }
 
-        let client = Client::new();
-
         Ok(Self {
-            client,
+            client: build_client(config.timeout)?,
             auth: AuthSource::Static(AuthConfig {
-                api_key,
-                base_url,
+                api_key: config.api_key,
+                base_url: config.base_url,
             }),
-            model,
+            model: config.model,
             max_tokens,
+            headers: RequestHeaders::new(),
+            base_url: None,
+            betas: default_betas(),
+            max_retries: config.max_retries,
         })
     }
 
@@ -107,6 +132,10 @@ impl AnthropicProvider {
             auth: AuthSource::Static(AuthConfig::new(api_key)),
             model: "".to_string(),
             max_tokens: 32000,
+            headers: RequestHeaders::new(),
+            base_url: None,
+            betas: default_betas(),
+            max_retries: 0,
         })
     }
 
@@ -136,6 +165,10 @@ impl AnthropicProvider {
             auth: AuthSource::Dynamic(Arc::new(auth_provider(provider))),
             model: "".to_string(),
             max_tokens: 32000,
+            headers: RequestHeaders::new(),
+            base_url: None,
+            betas: default_betas(),
+            max_retries: 0,
         }
     }
 
@@ -148,6 +181,10 @@ impl AnthropicProvider {
             auth: AuthSource::Dynamic(provider),
             model: "".to_string(),
             max_tokens: 32000,
+            headers: RequestHeaders::new(),
+            base_url: None,
+            betas: default_betas(),
+            max_retries: 0,
         }
     }
 
@@ -163,6 +200,62 @@ impl AnthropicProvider {
         self
     }
 
+    /// Set the `User-Agent` sent with each request
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.headers.user_agent = user_agent.into();
+        self
+    }
+
+    /// Add a header sent with each request (e.g. `X-User-Id`)
+    ///
+    /// The session ID is sent automatically when passed to the send methods
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Send requests to a different endpoint, e.g. a gateway or proxy
+    ///
+    /// Takes the full Messages API URL (like `https://gateway.example.com/v1/messages`),
+    /// the same form as `ANTHROPIC_BASE_URL` and `AuthConfig::base_url`, and
+    /// overrides both.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Opt into a beta feature, sent in the `anthropic-beta` header
+    ///
+    /// Can be called multiple times. `interleaved-thinking-2025-05-14` is
+    /// always enabled.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let llm = AnthropicProvider::from_env()?.with_beta("context-1m-2025-08-07");
+    /// ```
+    pub fn with_beta(mut self, beta: &str) -> Self {
+        if !self.betas.iter().any(|b| b == beta) {
+            self.betas.push(beta.to_string());
+        }
+        self
+    }
+
+    /// Give up on a read after `timeout` without data (see `http::build_client`)
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        match build_client(Some(timeout)) {
+            Ok(client) => self.client = client,
+            Err(e) => tracing::warn!("Failed to apply timeout: {}", e),
+        }
+        self
+    }
+
+    /// Retry transient failures up to `max_retries` times (see `http::send_with_retries`)
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
     /// Get the current model
     pub fn model(&self) -> &str {
         &self.model
@@ -186,10 +279,14 @@ impl AnthropicProvider {
     /// ```
     pub fn with_model_override(&self, model: impl Into<String>) -> Self {
         Self {
-            client: Client::new(),
+            client: self.client.clone(),
             auth: self.auth.clone(),
             model: model.into(),
             max_tokens: self.max_tokens,
+            headers: self.headers.clone(),
+            base_url: self.base_url.clone(),
+            betas: self.betas.clone(),
+            max_retries: self.max_retries,
         }
     }
 
@@ -200,10 +297,14 @@ impl AnthropicProvider {
         max_tokens: u32,
     ) -> Self {
         Self {
-            client: Client::new(),
+            client: self.client.clone(),
             auth: self.auth.clone(),
             model: model.into(),
             max_tokens,
+            headers: self.headers.clone(),
+            base_url: self.base_url.clone(),
+            betas: self.betas.clone(),
+            max_retries: self.max_retries,
         }
     }
 
@@ -239,6 +340,7 @@ impl AnthropicProvider {
             thinking: None,
             temperature: None,
             stream: None,
+            metadata: None,
         };
 
         let response = self.send_request(&request, session_id).await?;
@@ -275,6 +377,7 @@ impl AnthropicProvider {
             thinking,
             temperature,
             stream: None,
+            metadata: None,
         };
 
         self.send_request(&request, None).await
@@ -292,6 +395,23 @@ impl AnthropicProvider {
         tool_choice: Option<ToolChoice>,
         thinking: Option<ThinkingConfig>,
         session_id: Option<&str>,
+    ) -> Result<MessageResponse> {
+        let options = RequestOptions::for_session(session_id);
+        self.send_with_options(messages, system, tools, tool_choice, thinking, &options)
+            .await
+    }
+
+    /// Like `send_with_tools_and_system`, with per-request options
+    ///
+    /// The options' user ID is sent as `metadata.user_id`.
+    pub async fn send_with_options(
+        &self,
+        messages: Vec<Message>,
+        system: Option<SystemPrompt>,
+        tools: Vec<ToolDefinition>,
+        tool_choice: Option<ToolChoice>,
+        thinking: Option<ThinkingConfig>,
+        options: &RequestOptions,
     ) -> Result<MessageResponse> {
         tracing::info!("Sending message with tools to Anthropic API");
         tracing::debug!("Messages count: {}", messages.len());
@@ -311,41 +431,48 @@ impl AnthropicProvider {
             thinking,
             temperature,
             stream: None,
+            metadata: RequestMetadata::for_user(options.user_id.as_deref()),
         };
 
-        self.send_request(&request, session_id).await
+        self.send_request(&request, options.session_id.as_deref()).await
+    }
+
+    /// The Messages API URL: the override, then the auth config's, then the default
+    fn api_url<'a>(&'a self, auth_config: &'a AuthConfig) -> &'a str {
+        self.base_url
+            .as_deref()
+            .or(auth_config.base_url.as_deref())
+            .unwrap_or(DEFAULT_API_URL)
+    }
+
+    /// Start a Messages API request with the URL, credentials and headers
+    fn messages_request(&self, auth_config: &AuthConfig, session_id: Option<&str>) -> reqwest::RequestBuilder {
+        let request_builder = self
+            .client
+            .post(self.api_url(auth_config))
+            .header("Content-Type", "application/json")
+            .header("x-api-key", &auth_config.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .header("anthropic-beta", self.betas.join(","));
+        self.headers.apply(request_builder, session_id)
     }
 
     /// Send a raw request to the Anthropic API
     async fn send_request(&self, request: &MessageRequest, session_id: Option<&str>) -> Result<MessageResponse> {
+        require_model(&request.model, "ANTHROPIC")?;
         tracing::debug!("Model: {}", request.model);
         tracing::debug!("Max tokens: {}", request.max_tokens);
 
         // Get auth credentials (static or from provider)
         let auth_config = self.auth.get_auth().await
             .context("Failed to get authentication credentials")?;
-        let api_url = auth_config.base_url.as_deref().unwrap_or(DEFAULT_API_URL);
-
         let request_json = serde_json::to_string(request)
             .context("Failed to serialize request")?;
         tracing::debug!("Request JSON: {}", request_json);
 
-        let mut request_builder = self
-            .client
-            .post(api_url)
-            .header("Content-Type", "application/json")
-            .header("x-api-key", &auth_config.api_key)
-            .header("anthropic-version", ANTHROPIC_VERSION)
-            .header("anthropic-beta", "interleaved-thinking-2025-05-14");
+        let request_builder = self.messages_request(&auth_config, session_id);
 
-        // Add agent-session-id header if session_id is provided
-        if let Some(sid) = session_id {
-            request_builder = request_builder.header("agent-session-id", sid);
-        }
-
-        let response = request_builder
-            .body(request_json)
-            .send()
+        let response = send_with_retries(request_builder.body(request_json), self.max_retries)
             .await
             .context("Failed to send request to Anthropic API")?;
 
@@ -420,6 +547,7 @@ impl AnthropicProvider {
             thinking: None,
             temperature: None,
             stream: Some(true),
+            metadata: None,
         };
 
         self.send_streaming_request(&request, None).await
@@ -454,6 +582,7 @@ impl AnthropicProvider {
             thinking,
             temperature,
             stream: Some(true),
+            metadata: None,
         };
 
         self.send_streaming_request(&request, None).await
@@ -471,6 +600,23 @@ impl AnthropicProvider {
         tool_choice: Option<ToolChoice>,
         thinking: Option<ThinkingConfig>,
         session_id: Option<&str>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamEvent>> + Send>>> {
+        let options = RequestOptions::for_session(session_id);
+        self.stream_with_options(messages, system, tools, tool_choice, thinking, &options)
+            .await
+    }
+
+    /// Like `stream_with_tools_and_system`, with per-request options
+    ///
+    /// The options' user ID is sent as `metadata.user_id`.
+    pub async fn stream_with_options(
+        &self,
+        messages: Vec<Message>,
+        system: Option<SystemPrompt>,
+        tools: Vec<ToolDefinition>,
+        tool_choice: Option<ToolChoice>,
+        thinking: Option<ThinkingConfig>,
+        options: &RequestOptions,
     ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamEvent>> + Send>>> {
         tracing::info!("Streaming message with tools from Anthropic API");
         tracing::debug!("Messages count: {}", messages.len());
@@ -490,9 +636,10 @@ impl AnthropicProvider {
             thinking,
             temperature,
             stream: Some(true),
+            metadata: RequestMetadata::for_user(options.user_id.as_deref()),
         };
 
-        self.send_streaming_request(&request, session_id).await
+        self.send_streaming_request(&request, options.session_id.as_deref()).await
     }
 
     /// Send a streaming request to the Anthropic API
@@ -501,34 +648,20 @@ impl AnthropicProvider {
         request: &MessageRequest,
         session_id: Option<&str>,
     ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamEvent>> + Send>>> {
+        require_model(&request.model, "ANTHROPIC")?;
         tracing::debug!("Model: {}", request.model);
         tracing::debug!("Max tokens: {}", request.max_tokens);
 
         // Get auth credentials (static or from provider)
         let auth_config = self.auth.get_auth().await
             .context("Failed to get authentication credentials")?;
-        let api_url = auth_config.base_url.as_deref().unwrap_or(DEFAULT_API_URL);
-
         let request_json =
             serde_json::to_string(request).context("Failed to serialize request")?;
         tracing::debug!("Request JSON: {}", request_json);
 
-        let mut request_builder = self
-            .client
-            .post(api_url)
-            .header("Content-Type", "application/json")
-            .header("x-api-key", &auth_config.api_key)
-            .header("anthropic-version", ANTHROPIC_VERSION)
-            .header("anthropic-beta", "interleaved-thinking-2025-05-14");
-
-        // Add agent-session-id header if session_id is provided
-        if let Some(sid) = session_id {
-            request_builder = request_builder.header("X-Agent-Session-Id", sid);
-        }
+        let request_builder = self.messages_request(&auth_config, session_id);
 
-        let response = request_builder
-            .body(request_json)
-            .send()
+        let response = send_with_retries(request_builder.body(request_json), self.max_retries)
             .await
             .context("Failed to send streaming request to Anthropic API")?;
 
@@ -632,6 +765,19 @@ impl LlmProvider for AnthropicProvider {
             .await
     }
 
+    async fn send_with_options(
+        &self,
+        messages: Vec<Message>,
+        system: Option<SystemPrompt>,
+        tools: Vec<ToolDefinition>,
+        tool_choice: Option<ToolChoice>,
+        thinking: Option<ThinkingConfig>,
+        options: &RequestOptions,
+    ) -> Result<MessageResponse> {
+        self.send_with_options(messages, system, tools, tool_choice, thinking, options)
+            .await
+    }
+
     async fn stream_with_tools_and_system(
         &self,
         messages: Vec<Message>,
@@ -645,6 +791,32 @@ impl LlmProvider for AnthropicProvider {
             .await
     }
 
+    async fn stream_with_options(
+        &self,
+        messages: Vec<Message>,
+        system: Option<SystemPrompt>,
+        tools: Vec<ToolDefinition>,
+        tool_choice: Option<ToolChoice>,
+        thinking: Option<ThinkingConfig>,
+        options: &RequestOptions,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamEvent>> + Send>>> {
+        self.stream_with_options(messages, system, tools, tool_choice, thinking, options)
+            .await
+    }
+
+    /// Fetches the model from the Models API next to the Messages URL
+    async fn validate_credentials(&self) -> Result<()> {
+        require_model(&self.model, "ANTHROPIC")?;
+        let auth_config = self.auth.get_auth().await
+            .context("Failed to get authentication credentials")?;
+        let request = self
+            .client
+            .get(model_url(self.api_url(&auth_config), &self.model))
+            .header("x-api-key", &auth_config.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION);
+        check_credentials(self.headers.apply(request, None), self.provider_name()).await
+    }
+
     fn model(&self) -> String {
         self.model.clone()
     }
@@ -658,6 +830,171 @@ impl LlmProvider for AnthropicProvider {
     }
 }
 
+// ============================================================================
+// Message Batches API
+// ============================================================================
+
+/// Batch object returned by the Message Batches API
+#[derive(Debug, Deserialize)]
+struct AnthropicBatch {
+    id: String,
+    processing_status: String,
+    request_counts: AnthropicBatchCounts,
+    results_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicBatchCounts {
+    processing: u32,
+    succeeded: u32,
+    errored: u32,
+    canceled: u32,
+    expired: u32,
+}
+
+/// One line of a batch results file
+#[derive(Debug, Deserialize)]
+struct AnthropicBatchResultLine {
+    custom_id: String,
+    result: AnthropicBatchOutcome,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AnthropicBatchOutcome {
+    Succeeded { message: MessageResponse },
+    Errored { error: serde_json::Value },
+    Canceled,
+    Expired,
+}
+
+impl AnthropicProvider {
+    /// Call the Message Batches API and return the response body
+    ///
+    /// `target` is either a path under the batches endpoint (e.g. `/msgbatch_123`)
+    /// or an absolute URL such as a batch's `results_url`.
+    async fn batch_call(&self, method: reqwest::Method, target: &str, body: Option<String>) -> Result<String> {
+        let auth_config = self.auth.get_auth().await
+            .context("Failed to get authentication credentials")?;
+        let url = if target.starts_with("http://") || target.starts_with("https://") {
+            target.to_string()
+        } else {
+            let api_url = self.api_url(&auth_config);
+            format!("{}/batches{}", api_url.trim_end_matches('/'), target)
+        };
+
+        let mut builder = self
+            .client
+            .request(method, &url)
+            .header("x-api-key", &auth_config.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .header("anthropic-beta", self.betas.join(","));
+        builder = self.headers.apply(builder, None);
+        if let Some(body) = body {
+            builder = builder.header("Content-Type", "application/json").body(body);
+        }
+
+        let response = builder
+            .send()
+            .await
+            .context("Failed to send request to Anthropic Batches API")?;
+
+        let status = response.status();
+        let response_text = response
+            .text()
+            .await
+            .context("Failed to read response body")?;
+
+        if !status.is_success() {
+            tracing::error!("Batches API error: {} - {}", status, response_text);
+            anyhow::bail!("Anthropic Batches API error ({}): {}", status, response_text);
+        }
+
+        Ok(response_text)
+    }
+
+    /// Retrieve a batch object
+    async fn get_batch(&self, batch_id: &str) -> Result<AnthropicBatch> {
+        let body = self
+            .batch_call(reqwest::Method::GET, &format!("/{}", batch_id), None)
+            .await?;
+        serde_json::from_str(&body).context("Failed to parse batch response")
+    }
+}
+
+#[async_trait::async_trait]
+impl BatchProvider for AnthropicProvider {
+    async fn submit_batch(&self, requests: Vec<BatchRequest>) -> Result<String> {
+        tracing::info!("Submitting batch of {} requests to Anthropic API", requests.len());
+
+        let requests: Vec<serde_json::Value> = requests
+            .into_iter()
+            .map(|item| {
+                serde_json::json!({
+                    "custom_id": item.custom_id,
+                    "params": with_defaults(item.request, &self.model, self.max_tokens),
+                })
+            })
+            .collect();
+        let body = serde_json::to_string(&serde_json::json!({ "requests": requests }))
+            .context("Failed to serialize batch")?;
+
+        let response = self.batch_call(reqwest::Method::POST, "", Some(body)).await?;
+        let batch: AnthropicBatch =
+            serde_json::from_str(&response).context("Failed to parse batch response")?;
+
+        tracing::info!("Created batch {}", batch.id);
+        Ok(batch.id)
+    }
+
+    async fn poll_batch(&self, batch_id: &str) -> Result<BatchStatus> {
+        let batch = self.get_batch(batch_id).await?;
+        let counts = &batch.request_counts;
+
+        Ok(BatchStatus {
+            state: match batch.processing_status.as_str() {
+                "ended" => BatchState::Ended,
+                _ => BatchState::InProgress,
+            },
+            succeeded: counts.succeeded,
+            errored: counts.errored + counts.canceled + counts.expired,
+            processing: counts.processing,
+            id: batch.id,
+        })
+    }
+
+    async fn fetch_results(&self, batch_id: &str) -> Result<Vec<BatchResult>> {
+        let batch = self.get_batch(batch_id).await?;
+        let Some(results_url) = batch.results_url else {
+            anyhow::bail!("Batch {} has no results yet (status: {})", batch_id, batch.processing_status);
+        };
+
+        let body = self.batch_call(reqwest::Method::GET, &results_url, None).await?;
+
+        body.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let line: AnthropicBatchResultLine =
+                    serde_json::from_str(line).context("Failed to parse batch result")?;
+                let result = match line.result {
+                    AnthropicBatchOutcome::Succeeded { message } => Ok(message),
+                    AnthropicBatchOutcome::Errored { error } => Err(error.to_string()),
+                    AnthropicBatchOutcome::Canceled => Err("Request was canceled".to_string()),
+                    AnthropicBatchOutcome::Expired => Err("Request expired".to_string()),
+                };
+                Ok(BatchResult {
+                    custom_id: line.custom_id,
+                    result,
+                })
+            })
+            .collect()
+    }
+
+    fn batch_request(&self, messages: Vec<Message>) -> MessageRequest {
+        plain_request(messages, &self.model, self.max_tokens)
+    }
+}
+
 /// Helper function to build a simple tool definition
 pub fn define_tool(
     name: impl Into<String>,
@@ -679,3 +1016,33 @@ pub fn define_tool(
         cache_control: None,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base_url_and_betas() {
+        let auth = AuthConfig::with_base_url("key", "https://proxy.example.com/v1/messages");
+
+        // Without an override the auth config's URL is used
+        let provider = AnthropicProvider::new("key").unwrap();
+        let request = provider.messages_request(&auth, None).build().unwrap();
+        assert_eq!(request.url().as_str(), "https://proxy.example.com/v1/messages");
+        assert_eq!(request.headers().get("anthropic-beta").unwrap(), DEFAULT_BETAS.join(",").as_str());
+
+        // The override wins, and extra betas are added once after the defaults
+        let provider = AnthropicProvider::new("key")
+            .unwrap()
+            .with_base_url("https://gateway.example.com/v1/messages")
+            .with_beta("context-1m-2025-08-07")
+            .with_beta("context-1m-2025-08-07")
+            .with_beta(DEFAULT_BETAS[0]);
+        let request = provider.messages_request(&auth, None).build().unwrap();
+        assert_eq!(request.url().as_str(), "https://gateway.example.com/v1/messages");
+        assert_eq!(
+            request.headers().get("anthropic-beta").unwrap(),
+            format!("{},context-1m-2025-08-07", DEFAULT_BETAS.join(",")).as_str()
+        );
+    }
+}
@@ -0,0 +1,169 @@
+//! Batch APIs for offline bulk requests
+//!
+//! Anthropic and OpenAI both accept large sets of independent requests as a
+//! batch, processed asynchronously (usually within minutes, at most 24 hours)
+//! at a discount. `BatchProvider` exposes this with the framework's own
+//! request and response types, so the same messages, tools, and system
+//! prompts used interactively can be submitted in bulk.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use picrust::llm::{AnthropicProvider, BatchProvider, BatchRequest, Message, MessageRequest};
+//!
+//! let llm = AnthropicProvider::from_env()?;
+//!
+//! let requests = prompts
+//!     .iter()
+//!     .enumerate()
+//!     .map(|(i, prompt)| BatchRequest::new(format!("eval-{}", i), llm.batch_request(vec![Message::user(prompt)])))
+//!     .collect();
+//!
+//! let batch_id = llm.submit_batch(requests).await?;
+//! while !llm.poll_batch(&batch_id).await?.is_finished() {
+//!     tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+//! }
+//!
+//! for item in llm.fetch_results(&batch_id).await? {
+//!     match item.result {
+//!         Ok(response) => println!("{}: {:?}", item.custom_id, response.content),
+//!         Err(e) => eprintln!("{} failed: {}", item.custom_id, e),
+//!     }
+//! }
+//! ```
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use super::types::{Message, MessageRequest, MessageResponse};
+
+/// A request in a batch, identified by a caller-chosen ID
+#[derive(Debug, Clone)]
+pub struct BatchRequest {
+    /// ID used to match the result to the request (unique within the batch)
+    pub custom_id: String,
+    /// The request to run
+    pub request: MessageRequest,
+}
+
+impl BatchRequest {
+    /// Create a batch request
+    pub fn new(custom_id: impl Into<String>, request: MessageRequest) -> Self {
+        Self {
+            custom_id: custom_id.into(),
+            request,
+        }
+    }
+}
+
+/// Processing state of a batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchState {
+    /// Requests are still being processed
+    InProgress,
+    /// Processing has ended; results are available for every finished request
+    Ended,
+    /// The batch failed as a whole (e.g. invalid input) and has no results
+    Failed,
+}
+
+/// Status of a submitted batch
+#[derive(Debug, Clone)]
+pub struct BatchStatus {
+    /// Provider batch ID
+    pub id: String,
+    /// Processing state
+    pub state: BatchState,
+    /// Requests that completed successfully
+    pub succeeded: u32,
+    /// Requests that failed, were canceled, or expired
+    pub errored: u32,
+    /// Requests still being processed
+    pub processing: u32,
+}
+
+impl BatchStatus {
+    /// Whether the batch is done processing (successfully or not)
+    pub fn is_finished(&self) -> bool {
+        self.state != BatchState::InProgress
+    }
+}
+
+/// Result of one request in a batch
+#[derive(Debug, Clone)]
+pub struct BatchResult {
+    /// The `custom_id` of the request
+    pub custom_id: String,
+    /// The response, or a description of why the request failed
+    pub result: std::result::Result<MessageResponse, String>,
+}
+
+/// Provider that supports asynchronous batch processing
+///
+/// Requests use the same `MessageRequest` type as interactive calls. A
+/// request with an empty `model` or a `max_tokens` of 0 uses the provider's
+/// configured value; `stream` is ignored.
+#[async_trait]
+pub trait BatchProvider: Send + Sync {
+    /// Submit requests as a batch, returning the provider's batch ID
+    async fn submit_batch(&self, requests: Vec<BatchRequest>) -> Result<String>;
+
+    /// Get the current status of a batch
+    async fn poll_batch(&self, batch_id: &str) -> Result<BatchStatus>;
+
+    /// Get the results of a finished batch, one per request
+    ///
+    /// Results are not guaranteed to be in submission order; match them by `custom_id`.
+    async fn fetch_results(&self, batch_id: &str) -> Result<Vec<BatchResult>>;
+
+    /// Build a request for the provider's model and token limit
+    fn batch_request(&self, messages: Vec<Message>) -> MessageRequest;
+}
+
+/// Fill in the provider defaults for a batched request
+pub(crate) fn with_defaults(mut request: MessageRequest, model: &str, max_tokens: u32) -> MessageRequest {
+    if request.model.is_empty() {
+        request.model = model.to_string();
+    }
+    if request.max_tokens == 0 {
+        request.max_tokens = max_tokens;
+    }
+    request.stream = None;
+    request
+}
+
+/// Build a plain request for `messages` with no system prompt or tools
+pub(crate) fn plain_request(messages: Vec<Message>, model: &str, max_tokens: u32) -> MessageRequest {
+    MessageRequest {
+        model: model.to_string(),
+        max_tokens,
+        messages,
+        system: None,
+        tools: None,
+        tool_choice: None,
+        thinking: None,
+        temperature: None,
+        stream: None,
+        metadata: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_defaults() {
+        let mut request = plain_request(vec![Message::user("Hi")], "", 0);
+        request.stream = Some(true);
+
+        let request = with_defaults(request, "claude-test", 1024);
+        assert_eq!(request.model, "claude-test");
+        assert_eq!(request.max_tokens, 1024);
+        assert!(request.stream.is_none());
+
+        let request = with_defaults(plain_request(vec![], "custom", 10), "claude-test", 1024);
+        assert_eq!(request.model, "custom");
+        assert_eq!(request.max_tokens, 10);
+    }
+}
@@ -0,0 +1,257 @@
+//! Reassembling a streamed response
+//!
+//! `collect_stream` consumes the events of `stream_with_tools_and_system`
+//! and returns the `MessageResponse` the non-streaming call would have
+//! given: text, thinking (with its signature) and tool calls with their
+//! parsed input, plus the stop reason and final usage.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use picrust::llm::collect_stream;
+//!
+//! let stream = llm.stream_with_tools_and_system(messages, system, tools, None, None, None).await?;
+//! let response = collect_stream(stream).await?;
+//! ```
+
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, bail, Result};
+use futures::stream::{Stream, StreamExt};
+use serde_json::Value;
+
+use super::tool_input::{ToolInputAccumulator, ToolInputError};
+use super::types::{ContentBlock, ContentBlockStart, ContentDelta, MessageResponse, StreamEvent, Usage};
+
+/// A content block that is still streaming
+///
+/// Also used by `StandardAgent`'s streaming loop, which adds its own output
+/// and hooks around each step.
+pub(crate) enum PartialBlock {
+    Text(String),
+    Thinking {
+        thinking: String,
+        signature: String,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        signature: Option<String>,
+        /// Input given in full at block start (used when no deltas follow)
+        initial_input: Value,
+        input: ToolInputAccumulator,
+    },
+}
+
+impl PartialBlock {
+    /// Start a block from its `content_block_start` event
+    pub(crate) fn start(start: ContentBlockStart) -> Self {
+        match start {
+            ContentBlockStart::Text { text } => PartialBlock::Text(text),
+            ContentBlockStart::Thinking { thinking } => PartialBlock::Thinking {
+                thinking,
+                signature: String::new(),
+            },
+            ContentBlockStart::ToolUse {
+                id,
+                name,
+                input,
+                signature,
+            } => PartialBlock::ToolUse {
+                id,
+                name,
+                signature,
+                initial_input: input,
+                input: ToolInputAccumulator::new(),
+            },
+        }
+    }
+
+    /// Add a delta to the block
+    ///
+    /// Deltas for another block type are ignored. Malformed tool input is
+    /// returned as soon as it is seen; `finish` reports it again.
+    pub(crate) fn push(&mut self, delta: &ContentDelta) -> Result<(), ToolInputError> {
+        match (self, delta) {
+            (PartialBlock::Text(text), ContentDelta::TextDelta { text: more }) => text.push_str(more),
+            (PartialBlock::Thinking { thinking, .. }, ContentDelta::ThinkingDelta { thinking: more }) => {
+                thinking.push_str(more)
+            }
+            (PartialBlock::Thinking { signature, .. }, ContentDelta::SignatureDelta { signature: more }) => {
+                signature.push_str(more)
+            }
+            (PartialBlock::ToolUse { input, .. }, ContentDelta::InputJsonDelta { partial_json }) => {
+                return input.push(partial_json);
+            }
+            (_, other) => tracing::warn!("[PartialBlock] Ignoring {:?} for a content block of another type", other),
+        }
+        Ok(())
+    }
+
+    /// Complete the block
+    ///
+    /// A tool call with malformed input comes back with empty input, along
+    /// with the error.
+    pub(crate) fn finish(self) -> (ContentBlock, Option<ToolInputError>) {
+        match self {
+            PartialBlock::Text(text) => (ContentBlock::text(text), None),
+            PartialBlock::Thinking { thinking, signature } => (ContentBlock::Thinking { thinking, signature }, None),
+            PartialBlock::ToolUse {
+                id,
+                name,
+                signature,
+                initial_input,
+                input,
+            } => {
+                let (input, error) = if input.is_empty() {
+                    (initial_input, None)
+                } else {
+                    match input.finish() {
+                        Ok(input) => (input, None),
+                        Err(error) => (Value::Object(Default::default()), Some(error)),
+                    }
+                };
+                let block = ContentBlock::ToolUse {
+                    id,
+                    name,
+                    input,
+                    signature,
+                };
+                (block, error)
+            }
+        }
+    }
+}
+
+/// Consume a response stream and return the equivalent non-streaming response
+///
+/// Blocks are ordered by their stream index. Fails on a stream error event,
+/// on malformed tool input, and when the stream ends before `message_stop`.
+pub async fn collect_stream<S>(stream: S) -> Result<MessageResponse>
+where
+    S: Stream<Item = Result<StreamEvent>>,
+{
+    let mut stream = std::pin::pin!(stream);
+    let mut response = MessageResponse {
+        id: String::new(),
+        response_type: "message".to_string(),
+        role: "assistant".to_string(),
+        content: Vec::new(),
+        model: String::new(),
+        stop_reason: None,
+        stop_sequence: None,
+        usage: Usage::default(),
+    };
+    let mut open: BTreeMap<usize, PartialBlock> = BTreeMap::new();
+    let mut done: BTreeMap<usize, ContentBlock> = BTreeMap::new();
+    let mut stopped = false;
+
+    while let Some(event) = stream.next().await {
+        match event? {
+            StreamEvent::MessageStart(start) => {
+                response.id = start.message.id;
+                response.model = start.message.model;
+                response.usage = start.message.usage;
+            }
+            StreamEvent::ContentBlockStart(start) => {
+                open.insert(start.index, PartialBlock::start(start.content_block));
+            }
+            StreamEvent::ContentBlockDelta(delta) => {
+                let block = open
+                    .get_mut(&delta.index)
+                    .ok_or_else(|| anyhow!("Delta for content block {} before its start", delta.index))?;
+                // Errors are kept by the accumulator and reported by `finish`
+                let _ = block.push(&delta.delta);
+            }
+            StreamEvent::ContentBlockStop(stop) => {
+                if let Some(block) = open.remove(&stop.index) {
+                    let (block, error) = block.finish();
+                    if let (Some(error), Some((id, name, _))) = (error, block.as_tool_use()) {
+                        bail!("Invalid input for tool call {} ({}): {}", id, name, error);
+                    }
+                    done.insert(stop.index, block);
+                }
+            }
+            StreamEvent::MessageDelta(delta) => {
+                response.stop_reason = delta.delta.stop_reason;
+                response.stop_sequence = delta.delta.stop_sequence;
+                // Input tokens arrive in message_start unless the provider reports them at the end
+                response.usage.output_tokens = delta.usage.output_tokens;
+                if let Some(input_tokens) = delta.usage.input_tokens {
+                    response.usage.input_tokens = input_tokens;
+                }
+                if delta.usage.thoughts_token_count.is_some() {
+                    response.usage.thoughts_token_count = delta.usage.thoughts_token_count;
+                }
+            }
+            StreamEvent::MessageStop => {
+                stopped = true;
+                break;
+            }
+            StreamEvent::Ping => {}
+            StreamEvent::Error(err) => {
+                bail!("Stream error: {}: {}", err.error.error_type, err.error.message)
+            }
+        }
+    }
+
+    if !stopped {
+        bail!("Stream ended before message_stop");
+    }
+    if let Some(index) = open.keys().next() {
+        bail!("Stream ended with content block {} still open", index);
+    }
+    response.content = done.into_values().collect();
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::{LlmProvider, Message, StopReason};
+    use crate::testing::MockLlmProvider;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_collect_matches_non_streaming() {
+        let content = vec![
+            ContentBlock::Thinking {
+                thinking: "Let me look".to_string(),
+                signature: "sig".to_string(),
+            },
+            ContentBlock::text("Reading the file."),
+            ContentBlock::tool_use("call_1", "Read", json!({"file_path": "src/main.rs"})),
+        ];
+        let llm = MockLlmProvider::new(vec![content.clone(), content]);
+        let messages = vec![Message::user("Hi")];
+
+        let expected = llm
+            .send_with_tools_and_system(messages.clone(), None, vec![], None, None, None)
+            .await
+            .unwrap();
+        let stream = llm
+            .stream_with_tools_and_system(messages, None, vec![], None, None, None)
+            .await
+            .unwrap();
+        let mut collected = collect_stream(stream).await.unwrap();
+
+        // The mock numbers its responses
+        collected.id = expected.id.clone();
+        assert_eq!(serde_json::to_value(&collected).unwrap(), serde_json::to_value(&expected).unwrap());
+        assert_eq!(collected.stop_reason, Some(StopReason::ToolUse));
+    }
+
+    #[tokio::test]
+    async fn test_collect_incomplete_stream() {
+        let llm = MockLlmProvider::new(vec![vec![ContentBlock::text("Hi")]]);
+        let stream = llm
+            .stream_with_tools_and_system(vec![Message::user("Hi")], None, vec![], None, None, None)
+            .await
+            .unwrap();
+        // Drop message_stop
+        let events: Vec<_> = stream.collect().await;
+        let truncated = futures::stream::iter(events.into_iter().take(4));
+        let error = collect_stream(truncated).await.unwrap_err();
+        assert!(error.to_string().contains("message_stop"));
+    }
+}
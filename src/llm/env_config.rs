@@ -0,0 +1,174 @@
+//! Provider configuration from environment variables
+//!
+//! Every provider's `from_env()` reads the same set of variables, prefixed
+//! with the provider name:
+//!
+//! | Variable | Required | Meaning |
+//! |----------|----------|---------|
+//! | `{PREFIX}_API_KEY` | yes | API key |
+//! | `{PREFIX}_MODEL` | yes | Model name |
+//! | `{PREFIX}_BASE_URL` | no | Endpoint override |
+//! | `{PREFIX}_MAX_TOKENS` | no | Max output tokens (provider default otherwise) |
+//! | `{PREFIX}_TIMEOUT_SECS` | no | Read timeout in seconds (none by default) |
+//! | `{PREFIX}_MAX_RETRIES` | no | Retries for transient failures (default 0) |
+//!
+//! Missing required variables are reported together, and a value that
+//! doesn't parse is an error rather than being silently ignored.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use picrust::llm::ProviderEnvConfig;
+//!
+//! let config = ProviderEnvConfig::load("ANTHROPIC")?;
+//! // Error if unset: "Missing environment variables: ANTHROPIC_API_KEY, ANTHROPIC_MODEL"
+//! ```
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+
+/// Provider settings read from `{PREFIX}_*` environment variables
+#[derive(Clone, PartialEq, Eq)]
+pub struct ProviderEnvConfig {
+    /// API key (`{PREFIX}_API_KEY`)
+    pub api_key: String,
+    /// Model name (`{PREFIX}_MODEL`)
+    pub model: String,
+    /// Endpoint override (`{PREFIX}_BASE_URL`)
+    pub base_url: Option<String>,
+    /// Max output tokens (`{PREFIX}_MAX_TOKENS`)
+    pub max_tokens: Option<u32>,
+    /// Read timeout (`{PREFIX}_TIMEOUT_SECS`)
+    pub timeout: Option<Duration>,
+    /// Retries for transient failures (`{PREFIX}_MAX_RETRIES`, default 0)
+    pub max_retries: u32,
+}
+
+impl ProviderEnvConfig {
+    /// Read the configuration for `prefix` (e.g. `"OPENAI"`) from the environment
+    pub fn load(prefix: &str) -> Result<Self> {
+        Self::from_vars(prefix, |key| std::env::var(key).ok())
+    }
+
+    /// Read the configuration using `lookup` instead of the process environment
+    ///
+    /// Empty values count as unset.
+    pub fn from_vars<F>(prefix: &str, lookup: F) -> Result<Self>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let var = |name: &str| {
+            let key = format!("{}_{}", prefix, name);
+            let value = lookup(&key).filter(|value| !value.trim().is_empty());
+            (key, value)
+        };
+
+        let (api_key_var, api_key) = var("API_KEY");
+        let (model_var, model) = var("MODEL");
+        let (api_key, model) = match (api_key, model) {
+            (Some(api_key), Some(model)) => (api_key, model),
+            (api_key, model) => {
+                let missing: Vec<String> = [(api_key_var, api_key), (model_var, model)]
+                    .into_iter()
+                    .filter(|(_, value)| value.is_none())
+                    .map(|(key, _)| key)
+                    .collect();
+                bail!("Missing environment variables: {}", missing.join(", "));
+            }
+        };
+
+        let timeout_secs: Option<u64> = parse(var("TIMEOUT_SECS"), "a number of seconds")?;
+
+        Ok(Self {
+            api_key,
+            model,
+            base_url: var("BASE_URL").1,
+            max_tokens: parse(var("MAX_TOKENS"), "a positive integer")?,
+            timeout: timeout_secs.map(Duration::from_secs),
+            max_retries: parse(var("MAX_RETRIES"), "a non-negative integer")?.unwrap_or(0),
+        })
+    }
+}
+
+impl std::fmt::Debug for ProviderEnvConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProviderEnvConfig")
+            .field("api_key", &"<redacted>")
+            .field("model", &self.model)
+            .field("base_url", &self.base_url)
+            .field("max_tokens", &self.max_tokens)
+            .field("timeout", &self.timeout)
+            .field("max_retries", &self.max_retries)
+            .finish()
+    }
+}
+
+/// Fail with a hint when no model is configured
+///
+/// Providers created with `new()` or an auth callback start without a model.
+pub(crate) fn require_model(model: &str, prefix: &str) -> Result<()> {
+    if model.trim().is_empty() {
+        bail!("No model set. Call with_model() or set {}_MODEL and use from_env()", prefix);
+    }
+    Ok(())
+}
+
+/// Parse an optional variable, naming it and the expected format on failure
+fn parse<T: FromStr>((key, value): (String, Option<String>), expected: &str) -> Result<Option<T>> {
+    match value {
+        None => Ok(None),
+        Some(value) => match value.trim().parse() {
+            Ok(parsed) => Ok(Some(parsed)),
+            Err(_) => bail!("{} must be {}, got '{}'", key, expected, value),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn load(vars: &[(&str, &str)]) -> Result<ProviderEnvConfig> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        ProviderEnvConfig::from_vars("TEST", |key| vars.get(key).cloned())
+    }
+
+    #[test]
+    fn test_load_provider_env_config() {
+        let config = load(&[
+            ("TEST_API_KEY", "key"),
+            ("TEST_MODEL", "model-1"),
+            ("TEST_MAX_TOKENS", "4096"),
+            ("TEST_TIMEOUT_SECS", "30"),
+            ("TEST_BASE_URL", ""),
+        ])
+        .unwrap();
+
+        assert_eq!(config.model, "model-1");
+        assert_eq!(config.max_tokens, Some(4096));
+        assert_eq!(config.timeout, Some(Duration::from_secs(30)));
+        assert_eq!(config.base_url, None);
+        assert_eq!(config.max_retries, 0);
+        assert!(!format!("{:?}", config).contains("key\""));
+    }
+
+    #[test]
+    fn test_provider_env_config_errors() {
+        let error = load(&[]).unwrap_err().to_string();
+        assert_eq!(error, "Missing environment variables: TEST_API_KEY, TEST_MODEL");
+
+        let error = load(&[("TEST_API_KEY", "key")]).unwrap_err().to_string();
+        assert_eq!(error, "Missing environment variables: TEST_MODEL");
+
+        let error = load(&[("TEST_API_KEY", "key"), ("TEST_MODEL", "m"), ("TEST_MAX_TOKENS", "lots")])
+            .unwrap_err()
+            .to_string();
+        assert_eq!(error, "TEST_MAX_TOKENS must be a positive integer, got 'lots'");
+    }
+}
@@ -0,0 +1,280 @@
+//! Choosing an LLM provider at runtime
+//!
+//! `ProviderFactory` builds an `Arc<dyn LlmProvider>` from a provider name,
+//! an optional model and an API key source, so one binary can switch between
+//! Anthropic, OpenAI and Gemini without code changes. Settings not given
+//! explicitly come from the provider's `{PREFIX}_*` variables (see
+//! [`ProviderEnvConfig`]).
+//!
+//! # Example
+//!
+//! ```ignore
+//! use picrust::llm::ProviderFactory;
+//!
+//! // my-agent --provider openai:gpt-5   or   PICRUST_PROVIDER=gemini my-agent
+//! let llm = ProviderFactory::from_args_or_env(std::env::args())?.build()?;
+//!
+//! // Or explicitly
+//! let llm = ProviderFactory::new(ProviderKind::Anthropic)
+//!     .with_model("claude-sonnet-4-5")
+//!     .build()?;
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Result};
+
+use super::env_config::ProviderEnvConfig;
+use super::{AnthropicProvider, GeminiProvider, LlmProvider, OpenAIProvider};
+
+/// Environment variable read by `from_args_or_env` when no `--provider` flag is given
+pub const PROVIDER_ENV_VAR: &str = "PICRUST_PROVIDER";
+
+/// The built-in providers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProviderKind {
+    Anthropic,
+    OpenAI,
+    Gemini,
+}
+
+impl ProviderKind {
+    /// All built-in providers
+    pub const ALL: [ProviderKind; 3] = [ProviderKind::Anthropic, ProviderKind::OpenAI, ProviderKind::Gemini];
+
+    /// Lowercase name, as reported by `LlmProvider::provider_name`
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProviderKind::Anthropic => "anthropic",
+            ProviderKind::OpenAI => "openai",
+            ProviderKind::Gemini => "gemini",
+        }
+    }
+
+    /// Prefix of the provider's environment variables (e.g. `OPENAI`)
+    pub fn env_prefix(&self) -> &'static str {
+        match self {
+            ProviderKind::Anthropic => "ANTHROPIC",
+            ProviderKind::OpenAI => "OPENAI",
+            ProviderKind::Gemini => "GEMINI",
+        }
+    }
+}
+
+impl FromStr for ProviderKind {
+    type Err = anyhow::Error;
+
+    /// Case-insensitive; also accepts `claude` and `google`
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "anthropic" | "claude" => Ok(ProviderKind::Anthropic),
+            "openai" => Ok(ProviderKind::OpenAI),
+            "gemini" | "google" => Ok(ProviderKind::Gemini),
+            other => bail!("Unknown provider '{}' (expected anthropic, openai or gemini)", other),
+        }
+    }
+}
+
+impl fmt::Display for ProviderKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Builds a provider chosen at runtime
+#[derive(Clone)]
+pub struct ProviderFactory {
+    kind: ProviderKind,
+    /// Model override (otherwise `{PREFIX}_MODEL`)
+    model: Option<String>,
+    /// API key override (otherwise `{PREFIX}_API_KEY`)
+    api_key: Option<String>,
+}
+
+impl ProviderFactory {
+    /// Build `kind`, reading everything else from its environment variables
+    pub fn new(kind: ProviderKind) -> Self {
+        Self {
+            kind,
+            model: None,
+            api_key: None,
+        }
+    }
+
+    /// Parse `provider` or `provider:model` (e.g. `openai:gpt-5`)
+    pub fn parse(spec: &str) -> Result<Self> {
+        match spec.split_once(':') {
+            Some((kind, model)) if !model.trim().is_empty() => {
+                Ok(Self::new(kind.parse()?).with_model(model.trim()))
+            }
+            Some((kind, _)) => Ok(Self::new(kind.parse()?)),
+            None => Ok(Self::new(spec.parse()?)),
+        }
+    }
+
+    /// Choose the provider from command-line flags, falling back to the environment
+    ///
+    /// Recognizes `--provider <spec>` and `--model <model>` (also in `--flag=value`
+    /// form) anywhere in `args`. Without `--provider`, the spec comes from
+    /// `PICRUST_PROVIDER`. `--model` overrides a model given in the spec.
+    pub fn from_args_or_env<I>(args: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        Self::from_args_with(args, |key| std::env::var(key).ok())
+    }
+
+    fn from_args_with<I, F>(args: I, lookup: F) -> Result<Self>
+    where
+        I: IntoIterator<Item = String>,
+        F: Fn(&str) -> Option<String>,
+    {
+        let mut provider = None;
+        let mut model = None;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+            let slot = match flag.as_str() {
+                "--provider" => &mut provider,
+                "--model" => &mut model,
+                _ => continue,
+            };
+            let value = inline
+                .or_else(|| args.next())
+                .ok_or_else(|| anyhow!("{} needs a value", flag))?;
+            *slot = Some(value);
+        }
+
+        let spec = provider
+            .or_else(|| lookup(PROVIDER_ENV_VAR).filter(|value| !value.trim().is_empty()))
+            .ok_or_else(|| {
+                anyhow!(
+                    "No provider chosen. Pass --provider <anthropic|openai|gemini>[:model] or set {}",
+                    PROVIDER_ENV_VAR
+                )
+            })?;
+        let factory = Self::parse(&spec)?;
+        Ok(match model {
+            Some(model) => factory.with_model(model),
+            None => factory,
+        })
+    }
+
+    /// Use `model` instead of `{PREFIX}_MODEL`
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Use `api_key` instead of `{PREFIX}_API_KEY`
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// The provider this factory builds
+    pub fn kind(&self) -> ProviderKind {
+        self.kind
+    }
+
+    /// Model override, if one was given
+    pub fn model(&self) -> Option<&str> {
+        self.model.as_deref()
+    }
+
+    /// Settings the provider will be built with
+    pub fn config(&self) -> Result<ProviderEnvConfig> {
+        self.config_with(|key| std::env::var(key).ok())
+    }
+
+    fn config_with<F>(&self, lookup: F) -> Result<ProviderEnvConfig>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let prefix = self.kind.env_prefix();
+        ProviderEnvConfig::from_vars(prefix, |key| {
+            let name = key.strip_prefix(prefix).unwrap_or(key);
+            match name {
+                "_MODEL" if self.model.is_some() => self.model.clone(),
+                "_API_KEY" if self.api_key.is_some() => self.api_key.clone(),
+                _ => lookup(key),
+            }
+        })
+    }
+
+    /// Build the provider
+    pub fn build(&self) -> Result<Arc<dyn LlmProvider>> {
+        let config = self.config()?;
+        tracing::info!("[ProviderFactory] Building {} provider with model {}", self.kind, config.model);
+        Ok(match self.kind {
+            ProviderKind::Anthropic => Arc::new(AnthropicProvider::from_config(config)?),
+            ProviderKind::OpenAI => Arc::new(OpenAIProvider::from_config(config)?),
+            ProviderKind::Gemini => Arc::new(GeminiProvider::from_config(config)?),
+        })
+    }
+}
+
+impl fmt::Debug for ProviderFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProviderFactory")
+            .field("kind", &self.kind)
+            .field("model", &self.model)
+            .field("api_key", &self.api_key.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_from_args_and_env() {
+        let env = HashMap::from([(PROVIDER_ENV_VAR, "gemini:gemini-2.5-flash")]);
+        let lookup = |key: &str| env.get(key).map(|value| value.to_string());
+
+        let factory = ProviderFactory::from_args_with(args(&["agent", "--provider", "OpenAI:gpt-5"]), lookup).unwrap();
+        assert_eq!((factory.kind(), factory.model()), (ProviderKind::OpenAI, Some("gpt-5")));
+
+        let factory = ProviderFactory::from_args_with(args(&["agent", "--model=flash-lite"]), lookup).unwrap();
+        assert_eq!((factory.kind(), factory.model()), (ProviderKind::Gemini, Some("flash-lite")));
+
+        assert!(ProviderFactory::from_args_with(args(&["agent"]), |_| None).is_err());
+        assert!(ProviderFactory::from_args_with(args(&["--provider", "mistral"]), lookup).is_err());
+        assert!(ProviderFactory::from_args_with(args(&["--provider"]), lookup).is_err());
+    }
+
+    #[test]
+    fn test_config_overrides_env() {
+        let env = HashMap::from([
+            ("ANTHROPIC_API_KEY", "env-key"),
+            ("ANTHROPIC_MODEL", "env-model"),
+            ("ANTHROPIC_MAX_RETRIES", "2"),
+        ]);
+        let lookup = |key: &str| env.get(key).map(|value| value.to_string());
+
+        let config = ProviderFactory::new(ProviderKind::Anthropic).config_with(lookup).unwrap();
+        assert_eq!((config.api_key.as_str(), config.model.as_str()), ("env-key", "env-model"));
+
+        let config = ProviderFactory::parse("claude:flag-model")
+            .unwrap()
+            .with_api_key("flag-key")
+            .config_with(lookup)
+            .unwrap();
+        assert_eq!((config.api_key.as_str(), config.model.as_str()), ("flag-key", "flag-model"));
+        assert_eq!(config.max_retries, 2);
+
+        let missing = ProviderFactory::new(ProviderKind::OpenAI).config_with(lookup).unwrap_err();
+        assert!(missing.to_string().contains("OPENAI_API_KEY"));
+    }
+}
@@ -22,14 +22,19 @@ use futures::StreamExt;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::env;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::io::AsyncBufReadExt;
 use tokio_util::io::StreamReader;
 
+use crate::helpers::truncate_bytes_safe;
+
 use super::auth::{auth_provider, AuthConfig, AuthProvider, AuthSource};
+use super::env_config::{require_model, ProviderEnvConfig};
+use super::headers::RequestHeaders;
+use super::http::{build_client, check_credentials, send_with_retries};
 use super::provider::LlmProvider;
 use super::types::{
     ContentBlock, ContentBlockDeltaEvent, ContentBlockStart, ContentBlockStartEvent,
@@ -235,38 +240,44 @@ pub struct GeminiProvider {
     model: String,
     max_tokens: u32,
     api_base: String,
+    headers: RequestHeaders,
+    max_retries: u32,
 }
 
 impl GeminiProvider {
     /// Create a new Gemini provider from environment variables
     ///
-    /// Reads from:
+    /// Reads the `GEMINI_*` variables described in
+    /// [`ProviderEnvConfig`](super::ProviderEnvConfig):
     /// - `GEMINI_API_KEY` (required)
     /// - `GEMINI_MODEL` (required)
+    /// - `GEMINI_BASE_URL` (optional, API base, defaults to `https://generativelanguage.googleapis.com/v1beta`)
     /// - `GEMINI_MAX_TOKENS` (optional, defaults to 8192)
+    /// - `GEMINI_TIMEOUT_SECS` (optional, no timeout by default)
+    /// - `GEMINI_MAX_RETRIES` (optional, defaults to 0)
     pub fn from_env() -> Result<Self> {
         tracing::info!("Creating Gemini provider from environment");
+        Self::from_config(ProviderEnvConfig::load("GEMINI")?)
+    }
 
-        let api_key = env::var("GEMINI_API_KEY")
-            .context("GEMINI_API_KEY environment variable not set")?;
-
-        let model = env::var("GEMINI_MODEL")
-            .context("GEMINI_MODEL environment variable not set")?;
-
-        let max_tokens = env::var("GEMINI_MAX_TOKENS")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(8192);
+    /// Create a new Gemini provider from already-loaded settings
+    pub fn from_config(config: ProviderEnvConfig) -> Result<Self> {
+        let max_tokens = config.max_tokens.unwrap_or(8192);
 
-        tracing::info!("Using model: {}", model);
+        tracing::info!("Using model: {}", config.model);
         tracing::info!("Max tokens: {}", max_tokens);
+        if let Some(ref url) = config.base_url {
+            tracing::info!("Using custom base URL: {}", url);
+        }
 
         Ok(Self {
-            client: Client::new(),
-            auth: AuthSource::Static(AuthConfig::new(api_key)),
-            model,
+            client: build_client(config.timeout)?,
+            auth: AuthSource::Static(AuthConfig::new(config.api_key)),
+            model: config.model,
             max_tokens,
-            api_base: DEFAULT_API_BASE.to_string(),
+            api_base: config.base_url.unwrap_or_else(|| DEFAULT_API_BASE.to_string()),
+            headers: RequestHeaders::new(),
+            max_retries: config.max_retries,
         })
     }
 
@@ -278,6 +289,8 @@ impl GeminiProvider {
             model: "".to_string(),
             max_tokens: 8192,
             api_base: DEFAULT_API_BASE.to_string(),
+            headers: RequestHeaders::new(),
+            max_retries: 0,
         })
     }
 
@@ -311,6 +324,8 @@ impl GeminiProvider {
             model: "".to_string(),
             max_tokens: 8192,
             api_base: DEFAULT_API_BASE.to_string(),
+            headers: RequestHeaders::new(),
+            max_retries: 0,
         }
     }
 
@@ -324,6 +339,8 @@ impl GeminiProvider {
             model: "".to_string(),
             max_tokens: 8192,
             api_base: DEFAULT_API_BASE.to_string(),
+            headers: RequestHeaders::new(),
+            max_retries: 0,
         }
     }
 
@@ -339,14 +356,45 @@ impl GeminiProvider {
         self
     }
 
+    /// Set the `User-Agent` sent with each request
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.headers.user_agent = user_agent.into();
+        self
+    }
+
+    /// Add a header sent with each request (e.g. `X-User-Id`)
+    ///
+    /// The session ID is sent automatically when passed to the send methods
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Give up on a read after `timeout` without data (see `http::build_client`)
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        match build_client(Some(timeout)) {
+            Ok(client) => self.client = client,
+            Err(e) => tracing::warn!("[Gemini] Failed to apply timeout: {}", e),
+        }
+        self
+    }
+
+    /// Retry transient failures up to `max_retries` times (see `http::send_with_retries`)
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
     /// Create a variant with different model/tokens, sharing the same auth config
     fn create_variant_impl(&self, model: &str, max_tokens: u32) -> Self {
         Self {
-            client: Client::new(),
+            client: self.client.clone(),
             auth: self.auth.clone(),
             model: model.to_string(),
             max_tokens,
             api_base: self.api_base.clone(),
+            headers: self.headers.clone(),
+            max_retries: self.max_retries,
         }
     }
 
@@ -515,7 +563,8 @@ impl GeminiProvider {
                                 });
 
                             let result_content = content
-                                .clone()
+                                .as_ref()
+                                .map(|c| c.text())
                                 .unwrap_or_else(|| "No output".to_string());
 
                             let response = if is_error.unwrap_or(false) {
@@ -535,6 +584,17 @@ impl GeminiProvider {
                                 }),
                                 ..Default::default()
                             });
+
+                            // Images from the tool follow its response as inline data
+                            for source in content.iter().flat_map(|c| c.images()) {
+                                parts.push(GeminiPart {
+                                    inline_data: Some(GeminiInlineData {
+                                        mime_type: source.media_type.clone(),
+                                        data: source.data.clone(),
+                                    }),
+                                    ..Default::default()
+                                });
+                            }
                         }
                         ContentBlock::Thinking { thinking, signature } => {
                             // Send thinking as a thought-flagged text part, preserving signature
@@ -891,6 +951,8 @@ impl GeminiProvider {
 
     /// Send a non-streaming request to the Gemini API
     async fn send_gemini_request(&self, request: &GeminiRequest, session_id: Option<&str>) -> Result<GeminiResponse> {
+        require_model(&self.model, "GEMINI")?;
+
         // Get auth credentials (static or from provider)
         let auth_config = self.auth.get_auth().await
             .context("Failed to get authentication credentials")?;
@@ -901,20 +963,14 @@ impl GeminiProvider {
             .context("Failed to serialize Gemini request")?;
         tracing::debug!("[Gemini] Request JSON: {}", request_json);
 
-        let mut request_builder = self
+        let request_builder = self
             .client
             .post(&url)
             .header("Content-Type", "application/json")
             .header("x-goog-api-key", &auth_config.api_key);
+        let request_builder = self.headers.apply(request_builder, session_id);
 
-        // Add agent-session-id header if session_id is provided
-        if let Some(sid) = session_id {
-            request_builder = request_builder.header("agent-session-id", sid);
-        }
-
-        let response = request_builder
-            .body(request_json)
-            .send()
+        let response = send_with_retries(request_builder.body(request_json), self.max_retries)
             .await
             .context("Failed to send request to Gemini API")?;
 
@@ -951,6 +1007,8 @@ impl GeminiProvider {
         request: &GeminiRequest,
         session_id: Option<&str>,
     ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamEvent>> + Send>>> {
+        require_model(&self.model, "GEMINI")?;
+
         // Get auth credentials (static or from provider)
         let auth_config = self.auth.get_auth().await
             .context("Failed to get authentication credentials")?;
@@ -961,20 +1019,14 @@ impl GeminiProvider {
             .context("Failed to serialize Gemini streaming request")?;
         tracing::debug!("[Gemini] Streaming request JSON: {}", request_json);
 
-        let mut request_builder = self
+        let request_builder = self
             .client
             .post(&url)
             .header("Content-Type", "application/json")
             .header("x-goog-api-key", &auth_config.api_key);
+        let request_builder = self.headers.apply(request_builder, session_id);
 
-        // Add agent-session-id header if session_id is provided
-        if let Some(sid) = session_id {
-            request_builder = request_builder.header("X-Agent-Session-Id", sid);
-        }
-
-        let response = request_builder
-            .body(request_json)
-            .send()
+        let response = send_with_retries(request_builder.body(request_json), self.max_retries)
             .await
             .context("Failed to send streaming request to Gemini API")?;
 
@@ -1014,7 +1066,7 @@ impl GeminiProvider {
                     tracing::debug!("[Gemini] Stream: ignoring line after finish");
                     continue;
                 }
-                tracing::debug!("[Gemini] Stream: got line ({} chars): {}", line.len(), &line[..line.len().min(100)]);
+                tracing::debug!("[Gemini] Stream: got line ({} chars): {}", line.len(), truncate_bytes_safe(&line, 100));
 
                 if !line.starts_with("data: ") {
                     continue;
@@ -1215,7 +1267,8 @@ impl GeminiProvider {
                                 stop_reason
                             };
 
-                            let output_tokens = gemini_resp.usage_metadata.as_ref()
+                            let usage_metadata = gemini_resp.usage_metadata.as_ref();
+                            let output_tokens = usage_metadata
                                 .map(|u| u.candidates_token_count)
                                 .unwrap_or(0);
 
@@ -1224,7 +1277,11 @@ impl GeminiProvider {
                                     stop_reason: Some(stop_reason),
                                     stop_sequence: None,
                                 },
-                                usage: DeltaUsage { output_tokens },
+                                usage: DeltaUsage {
+                                    output_tokens,
+                                    input_tokens: usage_metadata.map(|u| u.prompt_token_count),
+                                    thoughts_token_count: usage_metadata.and_then(|u| u.thoughts_token_count),
+                                },
                             });
 
                             // Mark as finished - don't wait for more data
@@ -1358,6 +1415,19 @@ impl LlmProvider for GeminiProvider {
         self.send_gemini_streaming_request(&request, session_id).await
     }
 
+    /// Fetches the model from the models endpoint
+    async fn validate_credentials(&self) -> Result<()> {
+        require_model(&self.model, "GEMINI")?;
+        let auth_config = self.auth.get_auth().await
+            .context("Failed to get authentication credentials")?;
+        let api_base = auth_config.base_url.as_deref().unwrap_or(&self.api_base);
+        let request = self
+            .client
+            .get(format!("{}/models/{}", api_base, self.model))
+            .header("x-goog-api-key", &auth_config.api_key);
+        check_credentials(self.headers.apply(request, None), self.provider_name()).await
+    }
+
     fn model(&self) -> String {
         self.model.clone()
     }
@@ -1366,6 +1436,10 @@ impl LlmProvider for GeminiProvider {
         "gemini"
     }
 
+    fn tool_definitions_json(&self, tools: &[ToolDefinition]) -> serde_json::Value {
+        serde_json::to_value(self.convert_tools(tools)).unwrap_or_default()
+    }
+
     fn create_variant(&self, model: &str, max_tokens: u32) -> Arc<dyn LlmProvider> {
         Arc::new(self.create_variant_impl(model, max_tokens))
     }
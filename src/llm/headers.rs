@@ -0,0 +1,91 @@
+//! Request attribution headers for LLM APIs
+//!
+//! Every provider request carries a `User-Agent`, any extra headers set by the
+//! application, and the session ID when one is passed to the send methods.
+//! This lets proxies and gateways attribute traffic per app, user, or session.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use picrust::llm::AnthropicProvider;
+//!
+//! let llm = AnthropicProvider::from_env()?
+//!     .with_user_agent("my-app/1.2.0")
+//!     .with_header("X-User-Id", user_id);
+//! ```
+
+use reqwest::RequestBuilder;
+
+/// Default `User-Agent` sent when none is configured
+pub const DEFAULT_USER_AGENT: &str = concat!("picrust/", env!("CARGO_PKG_VERSION"));
+
+/// Header carrying the agent session ID
+pub const SESSION_ID_HEADER: &str = "agent-session-id";
+
+/// Identifying headers added to every provider request
+#[derive(Debug, Clone)]
+pub struct RequestHeaders {
+    /// Value of the `User-Agent` header
+    pub user_agent: String,
+    /// Additional headers, sent in insertion order
+    pub extra: Vec<(String, String)>,
+}
+
+impl RequestHeaders {
+    /// Create headers with the default `User-Agent` and no extras
+    pub fn new() -> Self {
+        Self {
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            extra: Vec::new(),
+        }
+    }
+
+    /// Add an extra header, replacing any earlier value with the same name
+    pub fn insert(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        let name = name.into();
+        self.extra.retain(|(n, _)| !n.eq_ignore_ascii_case(&name));
+        self.extra.push((name, value.into()));
+    }
+
+    /// Apply the headers (and the session ID, if any) to a request
+    pub fn apply(&self, mut builder: RequestBuilder, session_id: Option<&str>) -> RequestBuilder {
+        builder = builder.header(reqwest::header::USER_AGENT, &self.user_agent);
+        for (name, value) in &self.extra {
+            builder = builder.header(name, value);
+        }
+        if let Some(sid) = session_id {
+            builder = builder.header(SESSION_ID_HEADER, sid);
+        }
+        builder
+    }
+}
+
+impl Default for RequestHeaders {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_headers() {
+        let mut headers = RequestHeaders::new();
+        headers.user_agent = "my-app/1.0".into();
+        headers.insert("X-User-Id", "user-1");
+        headers.insert("x-user-id", "user-2");
+
+        let request = headers
+            .apply(reqwest::Client::new().get("http://localhost"), Some("session-1"))
+            .build()
+            .unwrap();
+
+        let h = request.headers();
+        assert_eq!(h.get("user-agent").unwrap(), "my-app/1.0");
+        assert_eq!(h.get("x-user-id").unwrap(), "user-2");
+        assert_eq!(h.get_all("x-user-id").iter().count(), 1);
+        assert_eq!(h.get(SESSION_ID_HEADER).unwrap(), "session-1");
+    }
+}
@@ -0,0 +1,207 @@
+//! HTTP helpers shared by the providers
+//!
+//! Builds the `reqwest` client with an optional read timeout and retries
+//! requests that fail with a connection error, a timeout, `429 Too Many
+//! Requests` or a `5xx` status.
+//!
+//! Retried requests are delivered at least once: a request that timed out
+//! may still have been processed by the server. When retries are enabled,
+//! each call to `send_with_retries` carries an `Idempotency-Key` header that
+//! stays the same across its attempts, so servers and gateways that support
+//! it can drop the duplicates.
+//!
+//! The key only covers these HTTP-level retries. Retries made above this
+//! layer send a new request with a new key: `StandardAgent`'s turn retry
+//! re-runs the whole turn, and the OpenAI provider's retry without an
+//! unsupported parameter sends a different body.
+
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use uuid::Uuid;
+
+/// Delay before the first retry; doubled for each further attempt
+const INITIAL_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Longest delay between two attempts
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Header carrying the key shared by all attempts of one request
+pub(crate) const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// Build an HTTP client
+///
+/// The timeout limits how long a single read may wait for data, so long
+/// streaming responses are not cut off as long as data keeps arriving. To
+/// bound a whole request instead, wrap the provider in `TimeoutLlmProvider`.
+pub(crate) fn build_client(timeout: Option<Duration>) -> Result<Client> {
+    let mut builder = Client::builder();
+    if let Some(timeout) = timeout {
+        builder = builder.read_timeout(timeout);
+    }
+    builder.build().context("Failed to build HTTP client")
+}
+
+/// Send a request, retrying transient failures up to `max_retries` times
+///
+/// Connection errors, timeouts, `429` and `5xx` responses are retried after
+/// a delay that starts at 500ms and doubles for each attempt, up to 30s.
+/// The final response is returned whatever its status, so callers report
+/// API errors as before. When retries are enabled, a fresh idempotency key
+/// is attached and reused for every attempt.
+pub(crate) async fn send_with_retries(
+    request: RequestBuilder,
+    max_retries: u32,
+) -> reqwest::Result<Response> {
+    let request = if max_retries > 0 {
+        request.header(IDEMPOTENCY_KEY_HEADER, Uuid::new_v4().to_string())
+    } else {
+        request
+    };
+    let mut delay = INITIAL_RETRY_DELAY;
+    for attempt in 1..=max_retries {
+        let Some(retry) = request.try_clone() else {
+            break;
+        };
+        match retry.send().await {
+            Ok(response) if !is_retryable(response.status()) => return Ok(response),
+            Err(e) if !(e.is_connect() || e.is_timeout()) => return Err(e),
+            Ok(response) => {
+                tracing::warn!(
+                    "[Http] Request failed with {} (attempt {} of {}), retrying in {:?}",
+                    response.status(),
+                    attempt,
+                    max_retries + 1,
+                    delay
+                );
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "[Http] Request failed: {} (attempt {} of {}), retrying in {:?}",
+                    e,
+                    attempt,
+                    max_retries + 1,
+                    delay
+                );
+            }
+        }
+        tokio::time::sleep(delay).await;
+        delay = next_retry_delay(delay);
+    }
+    request.send().await
+}
+
+/// URL of `model` in the models list next to `api_url`
+///
+/// `https://api.example.com/v1/messages` becomes
+/// `https://api.example.com/v1/models/{model}`.
+pub(crate) fn model_url(api_url: &str, model: &str) -> String {
+    let api_url = api_url.trim_end_matches('/');
+    let parent = api_url.rsplit_once('/').map_or(api_url, |(parent, _)| parent);
+    format!("{}/models/{}", parent, model)
+}
+
+/// Check credentials by fetching a model's metadata, which costs no tokens
+///
+/// Fails with the provider's error body when the key or model is rejected.
+pub(crate) async fn check_credentials(request: RequestBuilder, provider: &str) -> Result<()> {
+    let response = request
+        .send()
+        .await
+        .with_context(|| format!("{} credentials check failed", provider))?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let body = response.text().await.unwrap_or_default();
+    bail!("{} credentials check failed ({}): {}", provider, status, body)
+}
+
+fn next_retry_delay(delay: Duration) -> Duration {
+    delay.saturating_mul(2).min(MAX_RETRY_DELAY)
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Answer each connection with the next status and record its idempotency key
+    async fn serve(listener: TcpListener, statuses: Vec<u16>) -> Vec<Option<String>> {
+        let mut keys = Vec::new();
+        for status in statuses {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let request = String::from_utf8_lossy(&request).to_lowercase();
+            keys.push(
+                request
+                    .lines()
+                    .find_map(|line| line.strip_prefix("idempotency-key: "))
+                    .map(str::to_string),
+            );
+            let response = format!(
+                "HTTP/1.1 {} Status\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                status
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+        }
+        keys
+    }
+
+    #[test]
+    fn test_model_url() {
+        assert_eq!(
+            model_url("https://api.anthropic.com/v1/messages", "claude-x"),
+            "https://api.anthropic.com/v1/models/claude-x"
+        );
+        assert_eq!(
+            model_url("https://proxy.example.com/openai/v1/responses/", "gpt-5"),
+            "https://proxy.example.com/openai/v1/models/gpt-5"
+        );
+    }
+
+    #[test]
+    fn test_retry_delay_is_capped() {
+        assert_eq!(next_retry_delay(INITIAL_RETRY_DELAY), Duration::from_secs(1));
+        assert_eq!(next_retry_delay(Duration::from_secs(20)), MAX_RETRY_DELAY);
+        assert_eq!(next_retry_delay(Duration::MAX), MAX_RETRY_DELAY);
+    }
+
+    #[tokio::test]
+    async fn test_retries_share_idempotency_key() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(serve(listener, vec![503, 200]));
+
+        let client = build_client(None).unwrap();
+        let response = send_with_retries(client.get(&url), 1).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let keys = server.await.unwrap();
+        assert!(keys[0].is_some());
+        assert_eq!(keys[0], keys[1]);
+    }
+
+    #[tokio::test]
+    async fn test_no_idempotency_key_without_retries() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(serve(listener, vec![200]));
+
+        let client = build_client(None).unwrap();
+        send_with_retries(client.get(&url), 0).await.unwrap();
+
+        assert_eq!(server.await.unwrap(), vec![None]);
+    }
+}
@@ -1,21 +1,41 @@
 pub mod anthropic;
 pub mod auth;
+pub mod batch;
+pub mod collect;
+pub mod env_config;
+pub mod factory;
 pub mod gemini;
+pub mod headers;
+mod http;
 pub mod openai;
 pub mod provider;
+pub mod stream_guard;
 pub mod swappable;
+pub mod timeout;
+pub mod tool_input;
+pub mod tool_schema;
 pub mod types;
 
 pub use anthropic::{define_tool, AnthropicProvider};
 pub use auth::{auth_provider, AuthConfig, AuthProvider};
+pub use batch::{BatchProvider, BatchRequest, BatchResult, BatchState, BatchStatus};
+pub use collect::collect_stream;
+pub use env_config::ProviderEnvConfig;
+pub use factory::{ProviderFactory, ProviderKind, PROVIDER_ENV_VAR};
 pub use gemini::GeminiProvider;
+pub use headers::RequestHeaders;
 pub use openai::OpenAIProvider;
-pub use provider::LlmProvider;
+pub use provider::{LlmProvider, RequestOptions};
+pub use stream_guard::GuardedStream;
 pub use swappable::{LlmProviderHandle, SwappableLlmProvider};
+pub use timeout::TimeoutLlmProvider;
+pub use tool_input::{ToolInputAccumulator, ToolInputError};
+pub use tool_schema::{compact_tool_definitions, estimate_tool_tokens};
 pub use types::{
     CacheControl, ContentBlock, ContentBlockDeltaEvent, ContentBlockStart, ContentBlockStartEvent,
     ContentBlockStopEvent, ContentDelta, DeltaUsage, Message, MessageContent,
     MessageDeltaData, MessageDeltaEvent, MessageRequest, MessageResponse, MessageStartData,
-    MessageStartEvent, RawStreamEvent, StopReason, StreamError, StreamErrorDetails, StreamEvent,
-    SystemBlock, SystemPrompt, ThinkingConfig, ToolChoice, ToolDefinition, ToolInputSchema, Usage,
+    MessageStartEvent, RawStreamEvent, ReasoningEffort, RequestMetadata, StopReason, StreamError, StreamErrorDetails, StreamEvent,
+    SystemBlock, SystemPrompt, ThinkingConfig, ToolChoice, ToolDefinition, ToolInputSchema,
+    ToolResultContent, Usage,
 };
@@ -24,19 +24,27 @@ use futures::StreamExt;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::env;
+use std::collections::{HashMap, HashSet};
 use std::future::Future;
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::io::AsyncBufReadExt;
 use tokio_util::io::StreamReader;
 
 use super::auth::{auth_provider, AuthConfig, AuthProvider, AuthSource};
-use super::provider::LlmProvider;
+use super::batch::{
+    plain_request, with_defaults, BatchProvider, BatchRequest, BatchResult, BatchState,
+    BatchStatus,
+};
+use super::env_config::{require_model, ProviderEnvConfig};
+use super::headers::RequestHeaders;
+use super::http::{build_client, check_credentials, model_url, send_with_retries};
+use super::provider::{LlmProvider, RequestOptions};
 use super::types::{
     ContentBlock, ContentBlockDeltaEvent, ContentBlockStart, ContentBlockStartEvent,
     ContentBlockStopEvent, ContentDelta, DeltaUsage, Message, MessageContent,
-    MessageDeltaData, MessageDeltaEvent, MessageResponse, MessageStartData, MessageStartEvent,
+    MessageDeltaData, MessageDeltaEvent, MessageRequest, MessageResponse, MessageStartData, MessageStartEvent,
     StopReason, StreamEvent, SystemPrompt, ThinkingConfig, ToolChoice, ToolDefinition, Usage,
 };
 
@@ -62,6 +70,8 @@ struct OpenAIRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     reasoning: Option<OpenAIReasoning>,
     stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user: Option<String>,
 }
 
 /// Reasoning configuration for o-series and reasoning-capable models
@@ -344,39 +354,48 @@ pub struct OpenAIProvider {
     auth: AuthSource,
     model: String,
     max_tokens: u32,
+    headers: RequestHeaders,
+    max_retries: u32,
+    /// Parameters each model rejected, left out of later requests
+    unsupported_params: Arc<Mutex<HashSet<(String, String)>>>,
 }
 
 impl OpenAIProvider {
     /// Create a provider from environment variables.
     ///
-    /// Reads:
+    /// Reads the `OPENAI_*` variables described in [`ProviderEnvConfig`](super::ProviderEnvConfig):
     /// - `OPENAI_API_KEY` (required)
     /// - `OPENAI_MODEL` (required)
     /// - `OPENAI_BASE_URL` (optional, defaults to `https://api.openai.com/v1/responses`)
     /// - `OPENAI_MAX_TOKENS` (optional, defaults to 32000)
+    /// - `OPENAI_TIMEOUT_SECS` (optional, no timeout by default)
+    /// - `OPENAI_MAX_RETRIES` (optional, defaults to 0)
     pub fn from_env() -> Result<Self> {
-        let api_key = env::var("OPENAI_API_KEY")
-            .context("OPENAI_API_KEY environment variable not set")?;
-        let model = env::var("OPENAI_MODEL")
-            .context("OPENAI_MODEL environment variable not set")?;
-        let base_url = env::var("OPENAI_BASE_URL").ok();
-        let max_tokens = env::var("OPENAI_MAX_TOKENS")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(32000);
-
         tracing::info!("Creating OpenAI provider from environment");
-        tracing::info!("Using model: {}", model);
+        Self::from_config(ProviderEnvConfig::load("OPENAI")?)
+    }
+
+    /// Create a provider from already-loaded settings.
+    pub fn from_config(config: ProviderEnvConfig) -> Result<Self> {
+        let max_tokens = config.max_tokens.unwrap_or(32000);
+
+        tracing::info!("Using model: {}", config.model);
         tracing::info!("Max tokens: {}", max_tokens);
-        if let Some(ref url) = base_url {
+        if let Some(ref url) = config.base_url {
             tracing::info!("Using custom base URL: {}", url);
         }
 
         Ok(Self {
-            client: Client::new(),
-            auth: AuthSource::Static(AuthConfig { api_key, base_url }),
-            model,
+            client: build_client(config.timeout)?,
+            auth: AuthSource::Static(AuthConfig {
+                api_key: config.api_key,
+                base_url: config.base_url,
+            }),
+            model: config.model,
             max_tokens,
+            headers: RequestHeaders::new(),
+            max_retries: config.max_retries,
+            unsupported_params: Arc::default(),
         })
     }
 
@@ -387,6 +406,9 @@ impl OpenAIProvider {
             auth: AuthSource::Static(AuthConfig::new(api_key)),
             model: String::new(),
             max_tokens: 32000,
+            headers: RequestHeaders::new(),
+            max_retries: 0,
+            unsupported_params: Arc::default(),
         })
     }
 
@@ -401,6 +423,9 @@ impl OpenAIProvider {
             auth: AuthSource::Dynamic(Arc::new(auth_provider(provider))),
             model: String::new(),
             max_tokens: 32000,
+            headers: RequestHeaders::new(),
+            max_retries: 0,
+            unsupported_params: Arc::default(),
         }
     }
 
@@ -411,6 +436,9 @@ impl OpenAIProvider {
             auth: AuthSource::Dynamic(provider),
             model: String::new(),
             max_tokens: 32000,
+            headers: RequestHeaders::new(),
+            max_retries: 0,
+            unsupported_params: Arc::default(),
         }
     }
 
@@ -426,6 +454,20 @@ impl OpenAIProvider {
         self
     }
 
+    /// Set the `User-Agent` sent with each request.
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.headers.user_agent = user_agent.into();
+        self
+    }
+
+    /// Add a header sent with each request (e.g. `X-User-Id`).
+    ///
+    /// The session ID is sent automatically when passed to the send methods.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
     /// Override the base URL (e.g. for Azure OpenAI or a local proxy).
     ///
     /// The URL should point directly to the responses endpoint, e.g.:
@@ -444,13 +486,31 @@ impl OpenAIProvider {
         self
     }
 
+    /// Give up on a read after `timeout` without data (see `http::build_client`).
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        match build_client(Some(timeout)) {
+            Ok(client) => self.client = client,
+            Err(e) => tracing::warn!("Failed to apply timeout: {}", e),
+        }
+        self
+    }
+
+    /// Retry transient failures up to `max_retries` times (see `http::send_with_retries`).
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
     /// Clone with a different model and max_tokens (shares auth).
     pub fn with_model_and_tokens_override(&self, model: impl Into<String>, max_tokens: u32) -> Self {
         Self {
-            client: Client::new(),
+            client: self.client.clone(),
             auth: self.auth.clone(),
             model: model.into(),
             max_tokens,
+            headers: self.headers.clone(),
+            max_retries: self.max_retries,
+            unsupported_params: self.unsupported_params.clone(),
         }
     }
 
@@ -458,6 +518,77 @@ impl OpenAIProvider {
     // Internal helpers
     // ------------------------------------------------------------------ //
 
+    /// POST a Responses API request and return the successful response
+    ///
+    /// If the model rejects a parameter or its value, the request is sent once
+    /// more without it. Only a parameter rejected as unsupported is left out of
+    /// later requests to the same model; a rejected value may be a one-off.
+    async fn post(
+        &self,
+        api_url: &str,
+        api_key: &str,
+        session_id: Option<&str>,
+        request: &OpenAIRequest,
+    ) -> Result<reqwest::Response> {
+        let mut body = serde_json::to_value(request).context("Failed to serialize OpenAI request")?;
+        let known_unsupported: Vec<String> = self
+            .unsupported_params
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(model, _)| *model == request.model)
+            .map(|(_, param)| param.clone())
+            .collect();
+        for param in &known_unsupported {
+            remove_param(&mut body, param);
+        }
+
+        let mut retried = false;
+        loop {
+            tracing::debug!("OpenAI request JSON: {}", body);
+            let builder = self.client
+                .post(api_url)
+                .header("Content-Type", "application/json")
+                .header("Authorization", format!("Bearer {}", api_key));
+            let builder = self.headers.apply(builder, session_id);
+
+            let response = send_with_retries(builder.body(body.to_string()), self.max_retries)
+                .await
+                .context("Failed to send request to OpenAI API")?;
+
+            let status = response.status();
+            if status.is_success() {
+                return Ok(response);
+            }
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Failed to read error body".to_string());
+
+            if !retried {
+                if let Some(rejected) = rejected_param(status, &error_text) {
+                    let param = rejected.param();
+                    if remove_param(&mut body, param) {
+                        tracing::warn!(
+                            "[OpenAI] Model '{}' rejected parameter '{}', retrying without it",
+                            request.model,
+                            param
+                        );
+                        if let RejectedParam::Unsupported(param) = rejected {
+                            self.unsupported_params
+                                .lock()
+                                .unwrap()
+                                .insert((request.model.clone(), param));
+                        }
+                        retried = true;
+                        continue;
+                    }
+                }
+            }
+            anyhow::bail!("OpenAI API error ({}): {}", status, error_text);
+        }
+    }
+
     async fn send_request_internal(
         &self,
         messages: Vec<Message>,
@@ -465,13 +596,14 @@ impl OpenAIProvider {
         tools: Vec<ToolDefinition>,
         tool_choice: Option<ToolChoice>,
         thinking: Option<ThinkingConfig>,
-        session_id: Option<&str>,
+        options: &RequestOptions,
     ) -> Result<MessageResponse> {
         let auth_config = self.auth.get_auth().await
             .context("Failed to get authentication credentials")?;
         let api_url = auth_config.base_url.as_deref().unwrap_or(DEFAULT_API_URL);
 
-        let openai_req = build_request(
+        require_model(&self.model, "OPENAI")?;
+        let mut openai_req = build_request(
             &self.model,
             self.max_tokens,
             messages,
@@ -481,25 +613,11 @@ impl OpenAIProvider {
             thinking,
             false,
         );
+        openai_req.user = options.user_id.clone();
 
-        let req_json = serde_json::to_string(&openai_req)
-            .context("Failed to serialize OpenAI request")?;
-        tracing::debug!("OpenAI request JSON: {}", req_json);
-
-        let mut builder = self.client
-            .post(api_url)
-            .header("Content-Type", "application/json")
-            .header("Authorization", format!("Bearer {}", auth_config.api_key));
-
-        if let Some(sid) = session_id {
-            builder = builder.header("agent-session-id", sid);
-        }
-
-        let response = builder
-            .body(req_json)
-            .send()
-            .await
-            .context("Failed to send request to OpenAI API")?;
+        let response = self
+            .post(api_url, &auth_config.api_key, options.session_id.as_deref(), &openai_req)
+            .await?;
 
         let status = response.status();
         let body = response.text().await.context("Failed to read OpenAI response body")?;
@@ -507,10 +625,6 @@ impl OpenAIProvider {
         tracing::debug!("OpenAI response status: {}", status);
         tracing::debug!("OpenAI response body: {}", body);
 
-        if !status.is_success() {
-            anyhow::bail!("OpenAI API error ({}): {}", status, body);
-        }
-
         let openai_resp: OpenAIResponse = serde_json::from_str(&body)
             .context("Failed to parse OpenAI response")?;
 
@@ -524,13 +638,14 @@ impl OpenAIProvider {
         tools: Vec<ToolDefinition>,
         tool_choice: Option<ToolChoice>,
         thinking: Option<ThinkingConfig>,
-        session_id: Option<&str>,
+        options: &RequestOptions,
     ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamEvent>> + Send>>> {
         let auth_config = self.auth.get_auth().await
             .context("Failed to get authentication credentials")?;
         let api_url = auth_config.base_url.as_deref().unwrap_or(DEFAULT_API_URL);
 
-        let openai_req = build_request(
+        require_model(&self.model, "OPENAI")?;
+        let mut openai_req = build_request(
             &self.model,
             self.max_tokens,
             messages,
@@ -540,34 +655,11 @@ impl OpenAIProvider {
             thinking,
             true,
         );
+        openai_req.user = options.user_id.clone();
 
-        let req_json = serde_json::to_string(&openai_req)
-            .context("Failed to serialize OpenAI request")?;
-        tracing::debug!("OpenAI streaming request JSON: {}", req_json);
-
-        let mut builder = self.client
-            .post(api_url)
-            .header("Content-Type", "application/json")
-            .header("Authorization", format!("Bearer {}", auth_config.api_key));
-
-        if let Some(sid) = session_id {
-            builder = builder.header("agent-session-id", sid);
-        }
-
-        let response = builder
-            .body(req_json)
-            .send()
-            .await
-            .context("Failed to send streaming request to OpenAI API")?;
-
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Failed to read error body".to_string());
-            anyhow::bail!("OpenAI API error ({}): {}", status, error_text);
-        }
+        let response = self
+            .post(api_url, &auth_config.api_key, options.session_id.as_deref(), &openai_req)
+            .await?;
 
         let model = self.model.clone();
         let byte_stream = response.bytes_stream();
@@ -645,9 +737,217 @@ fn build_request(
         temperature: None,
         reasoning,
         stream,
+        user: None,
     }
 }
 
+/// The Responses API body this provider would send for `request`
+///
+/// Uses the same translation as the provider, so the result can be pasted
+/// into a support ticket or replayed with `curl`.
+pub fn to_request_json(request: &MessageRequest) -> Value {
+    let mut openai_req = build_request(
+        &request.model,
+        request.max_tokens,
+        request.messages.clone(),
+        request.system.clone(),
+        request.tools.clone().unwrap_or_default(),
+        request.tool_choice.clone(),
+        request.thinking.clone(),
+        request.stream.unwrap_or(false),
+    );
+    openai_req.user = request.metadata.as_ref().and_then(|m| m.user_id.clone());
+    serde_json::to_value(&openai_req).unwrap_or_default()
+}
+
+/// Convert a Chat Completions conversation (`{role, content}` messages) to the internal format
+///
+/// For importing chat logs recorded by Chat Completions clients; the provider
+/// itself speaks the Responses API. Returns the system prompt (the `system`
+/// and `developer` messages, joined) and the history. Assistant `tool_calls`
+/// become `ToolUse` blocks and `tool` messages become `ToolResult` blocks in a
+/// user message; consecutive messages on the same side are merged so turns
+/// alternate. Images given as data URLs are kept, remote image URLs become a
+/// text note.
+///
+/// Fails if a tool call has no `tool` message answering it before the next
+/// assistant message, since the APIs reject a call without a result.
+pub fn from_chat_messages(messages: &[Value]) -> Result<(Option<String>, Vec<Message>)> {
+    let mut system = Vec::new();
+    let mut history: Vec<Message> = Vec::new();
+    // Tool calls still waiting for their `tool` message
+    let mut unanswered: Vec<String> = Vec::new();
+    for (index, message) in messages.iter().enumerate() {
+        let role = message
+            .get("role")
+            .and_then(Value::as_str)
+            .with_context(|| format!("Message {} has no role", index))?;
+        let content = message.get("content").unwrap_or(&Value::Null);
+        let (role, blocks) = match role {
+            "system" | "developer" => {
+                system.push(chat_content_text(content));
+                continue;
+            }
+            "user" => ("user", chat_content_blocks(content)),
+            "assistant" => {
+                if let Some(id) = unanswered.first() {
+                    anyhow::bail!("Tool call {} has no tool message before message {}", id, index);
+                }
+                let mut blocks = chat_content_blocks(content);
+                for call in message.get("tool_calls").and_then(Value::as_array).into_iter().flatten() {
+                    let call = chat_tool_call(call).with_context(|| format!("Message {} has an invalid tool call", index))?;
+                    if let Some((id, _, _)) = call.as_tool_use() {
+                        unanswered.push(id.to_string());
+                    }
+                    blocks.push(call);
+                }
+                ("assistant", blocks)
+            }
+            "tool" => {
+                let id = message
+                    .get("tool_call_id")
+                    .and_then(Value::as_str)
+                    .with_context(|| format!("Tool message {} has no tool_call_id", index))?;
+                unanswered.retain(|call| call != id);
+                ("user", vec![ContentBlock::tool_result(id, chat_content_text(content), false)])
+            }
+            other => anyhow::bail!("Message {} has unsupported role '{}'", index, other),
+        };
+        if blocks.is_empty() {
+            continue;
+        }
+        match history.last_mut() {
+            Some(Message { role: last_role, content: MessageContent::Blocks(existing), .. }) if last_role == role => {
+                existing.extend(blocks)
+            }
+            _ => history.push(Message::new(role, MessageContent::Blocks(blocks))),
+        }
+    }
+
+    if let Some(id) = unanswered.first() {
+        anyhow::bail!("Tool call {} has no tool message", id);
+    }
+
+    // Plain-text messages are stored as text, like `Message::user`
+    for message in &mut history {
+        if let MessageContent::Blocks(blocks) = &message.content {
+            if let [ContentBlock::Text { text, .. }] = blocks.as_slice() {
+                message.content = MessageContent::Text(text.clone());
+            }
+        }
+    }
+
+    let system = system.join("\n\n");
+    Ok(((!system.is_empty()).then_some(system), history))
+}
+
+/// Blocks for a Chat Completions `content` (a string, an array of parts, or null)
+fn chat_content_blocks(content: &Value) -> Vec<ContentBlock> {
+    match content {
+        Value::String(text) if !text.is_empty() => vec![ContentBlock::text(text)],
+        Value::Array(parts) => parts
+            .iter()
+            .filter_map(|part| match part.get("type").and_then(Value::as_str)? {
+                "text" => Some(ContentBlock::text(part.get("text")?.as_str()?)),
+                "refusal" => Some(ContentBlock::text(part.get("refusal")?.as_str()?)),
+                "image_url" => {
+                    let url = part.get("image_url")?.get("url")?.as_str()?;
+                    let data_url = url
+                        .strip_prefix("data:")
+                        .and_then(|rest| rest.split_once(";base64,"));
+                    Some(match data_url {
+                        Some((media_type, data)) => ContentBlock::image(data.to_string(), media_type.to_string()),
+                        None => ContentBlock::text(format!("[Image: {}]", url)),
+                    })
+                }
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Text of a Chat Completions `content`, with array parts joined by newlines
+fn chat_content_text(content: &Value) -> String {
+    chat_content_blocks(content)
+        .iter()
+        .filter_map(|block| block.as_text())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// `ToolUse` block for a Chat Completions tool call
+fn chat_tool_call(call: &Value) -> Result<ContentBlock> {
+    let id = call.get("id").and_then(Value::as_str).context("Tool call has no id")?;
+    let function = call.get("function").context("Tool call has no function")?;
+    let name = function.get("name").and_then(Value::as_str).context("Tool call has no name")?;
+    let arguments = function.get("arguments").and_then(Value::as_str).unwrap_or_default();
+    let input = match arguments.trim() {
+        "" => Value::Object(Default::default()),
+        arguments => serde_json::from_str(arguments)
+            .with_context(|| format!("Arguments of tool call {} are not valid JSON", id))?,
+    };
+    Ok(ContentBlock::tool_use(id, name, input))
+}
+
+/// Request fields that are never dropped, even if the API names them
+const REQUIRED_PARAMS: &[&str] = &["model", "input", "stream"];
+
+/// A request parameter the API rejected
+#[derive(Debug, PartialEq)]
+enum RejectedParam {
+    /// The model does not support the parameter at all
+    Unsupported(String),
+    /// The model does not accept the value given for it
+    BadValue(String),
+}
+
+impl RejectedParam {
+    fn param(&self) -> &str {
+        match self {
+            RejectedParam::Unsupported(param) | RejectedParam::BadValue(param) => param,
+        }
+    }
+}
+
+/// The parameter named by an "unsupported parameter" or "unsupported value" error
+fn rejected_param(status: reqwest::StatusCode, body: &str) -> Option<RejectedParam> {
+    if status != reqwest::StatusCode::BAD_REQUEST {
+        return None;
+    }
+    let error: Value = serde_json::from_str(body).ok()?;
+    let error = error.get("error")?;
+    let param = error.get("param")?.as_str()?.to_string();
+    match error.get("code")?.as_str()? {
+        "unsupported_parameter" => Some(RejectedParam::Unsupported(param)),
+        "unsupported_value" => Some(RejectedParam::BadValue(param)),
+        _ => None,
+    }
+}
+
+/// Remove a parameter such as `temperature` or `reasoning.effort`
+///
+/// Returns false if the parameter is required or not in the request.
+fn remove_param(body: &mut Value, param: &str) -> bool {
+    if REQUIRED_PARAMS.contains(&param) {
+        return false;
+    }
+    let mut path: Vec<&str> = param.split('.').collect();
+    let Some(last) = path.pop() else {
+        return false;
+    };
+    let mut target = body;
+    for key in path {
+        match target.get_mut(key) {
+            Some(next) => target = next,
+            None => return false,
+        }
+    }
+    target
+        .as_object_mut()
+        .is_some_and(|object| object.remove(last).is_some())
+}
+
 /// Convert internal ThinkingConfig to OpenAI reasoning format
 fn thinking_to_reasoning(thinking: Option<ThinkingConfig>) -> Option<OpenAIReasoning> {
     thinking.map(|config| {
@@ -746,7 +1046,24 @@ fn messages_to_input_items(messages: Vec<Message>) -> Vec<InputItem> {
                             });
                         }
                         ContentBlock::ToolResult { tool_use_id, content, .. } => {
-                            let output = content.unwrap_or_default();
+                            // Function outputs are text-only, so images go in a user
+                            // message after the outputs, labelled with the call ID
+                            let images = content.as_ref().map(|c| c.images()).unwrap_or_default();
+                            let mut output = content.as_ref().map(|c| c.text()).unwrap_or_default();
+                            if !images.is_empty() {
+                                if !output.is_empty() {
+                                    output.push('\n');
+                                }
+                                output.push_str("[Image output follows in the next message]");
+                                text_parts.push(InputContentPart::Text {
+                                    text: format!("Image output of tool call {}:", tool_use_id),
+                                });
+                                for source in images {
+                                    text_parts.push(InputContentPart::Image {
+                                        image_url: format!("data:{};base64,{}", source.media_type, source.data),
+                                    });
+                                }
+                            }
                             items.push(InputItem::FunctionCallOutput {
                                 call_id: tool_use_id,
                                 output,
@@ -778,7 +1095,53 @@ fn messages_to_input_items(messages: Vec<Message>) -> Vec<InputItem> {
         }
     }
 
-    items
+    order_function_outputs(items)
+}
+
+/// Move each function output directly after the assistant turn that called it
+///
+/// OpenAI rejects a request unless function outputs follow their calls, but
+/// our history can put user text between an assistant's tool calls and the
+/// results, or return results in a different order than the calls. Outputs
+/// are emitted after the run of assistant items (calls and text) that holds
+/// their call, in call order. Outputs without a matching call are dropped.
+fn order_function_outputs(items: Vec<InputItem>) -> Vec<InputItem> {
+    let mut outputs: HashMap<String, InputItem> = HashMap::new();
+    let mut rest = Vec::with_capacity(items.len());
+    for item in items {
+        match item {
+            InputItem::FunctionCallOutput { ref call_id, .. } => {
+                outputs.entry(call_id.clone()).or_insert(item);
+            }
+            other => rest.push(other),
+        }
+    }
+    if outputs.is_empty() {
+        return rest;
+    }
+
+    let mut ordered = Vec::with_capacity(rest.len() + outputs.len());
+    let mut pending_calls: Vec<String> = Vec::new();
+    for item in rest {
+        let in_assistant_turn = match item {
+            InputItem::FunctionCall { .. } => true,
+            InputItem::Message { ref role, .. } => role == "assistant",
+            InputItem::FunctionCallOutput { .. } => false,
+        };
+        if !in_assistant_turn {
+            ordered.extend(pending_calls.drain(..).filter_map(|id| outputs.remove(&id)));
+        }
+        if let InputItem::FunctionCall { ref call_id, .. } = item {
+            pending_calls.push(call_id.clone());
+        }
+        ordered.push(item);
+    }
+    ordered.extend(pending_calls.drain(..).filter_map(|id| outputs.remove(&id)));
+
+    for call_id in outputs.keys() {
+        tracing::warn!("[OpenAI] Dropping output for unknown function call {}", call_id);
+    }
+    ordered
 }
 
 fn tool_def_to_openai(tool: ToolDefinition) -> Option<OpenAITool> {
@@ -1023,6 +1386,12 @@ fn translate_stream_event(
                     },
                     usage: DeltaUsage {
                         output_tokens: usage.output_tokens,
+                        input_tokens: Some(usage.input_tokens),
+                        thoughts_token_count: usage
+                            .output_tokens_details
+                            .as_ref()
+                            .map(|d| d.reasoning_tokens)
+                            .filter(|&t| t > 0),
                     },
                 }),
                 StreamEvent::MessageStop,
@@ -1057,7 +1426,7 @@ impl LlmProvider for OpenAIProvider {
 
         let system = system_prompt.map(|s| SystemPrompt::Text(s.to_string()));
         let resp = self
-            .send_request_internal(messages, system, vec![], None, None, session_id)
+            .send_request_internal(messages, system, vec![], None, None, &RequestOptions::for_session(session_id))
             .await?;
         Ok(resp.text())
     }
@@ -1071,7 +1440,22 @@ impl LlmProvider for OpenAIProvider {
         thinking: Option<ThinkingConfig>,
         session_id: Option<&str>,
     ) -> Result<MessageResponse> {
-        self.send_request_internal(messages, system, tools, tool_choice, thinking, session_id)
+        let options = RequestOptions::for_session(session_id);
+        self.send_request_internal(messages, system, tools, tool_choice, thinking, &options)
+            .await
+    }
+
+    /// Sends the options' user ID as `user`
+    async fn send_with_options(
+        &self,
+        messages: Vec<Message>,
+        system: Option<SystemPrompt>,
+        tools: Vec<ToolDefinition>,
+        tool_choice: Option<ToolChoice>,
+        thinking: Option<ThinkingConfig>,
+        options: &RequestOptions,
+    ) -> Result<MessageResponse> {
+        self.send_request_internal(messages, system, tools, tool_choice, thinking, options)
             .await
     }
 
@@ -1084,10 +1468,38 @@ impl LlmProvider for OpenAIProvider {
         thinking: Option<ThinkingConfig>,
         session_id: Option<&str>,
     ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamEvent>> + Send>>> {
-        self.stream_request_internal(messages, system, tools, tool_choice, thinking, session_id)
+        let options = RequestOptions::for_session(session_id);
+        self.stream_request_internal(messages, system, tools, tool_choice, thinking, &options)
+            .await
+    }
+
+    /// Sends the options' user ID as `user`
+    async fn stream_with_options(
+        &self,
+        messages: Vec<Message>,
+        system: Option<SystemPrompt>,
+        tools: Vec<ToolDefinition>,
+        tool_choice: Option<ToolChoice>,
+        thinking: Option<ThinkingConfig>,
+        options: &RequestOptions,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamEvent>> + Send>>> {
+        self.stream_request_internal(messages, system, tools, tool_choice, thinking, options)
             .await
     }
 
+    /// Fetches the model from the models endpoint next to the Responses URL
+    async fn validate_credentials(&self) -> Result<()> {
+        require_model(&self.model, "OPENAI")?;
+        let auth_config = self.auth.get_auth().await
+            .context("Failed to get authentication credentials")?;
+        let api_url = auth_config.base_url.as_deref().unwrap_or(DEFAULT_API_URL);
+        let request = self
+            .client
+            .get(model_url(api_url, &self.model))
+            .header("Authorization", format!("Bearer {}", auth_config.api_key));
+        check_credentials(self.headers.apply(request, None), self.provider_name()).await
+    }
+
     fn model(&self) -> String {
         self.model.clone()
     }
@@ -1096,7 +1508,532 @@ impl LlmProvider for OpenAIProvider {
         "openai"
     }
 
+    fn tool_definitions_json(&self, tools: &[ToolDefinition]) -> Value {
+        let openai_tools: Vec<OpenAITool> =
+            tools.iter().cloned().filter_map(tool_def_to_openai).collect();
+        serde_json::to_value(openai_tools).unwrap_or_default()
+    }
+
     fn create_variant(&self, model: &str, max_tokens: u32) -> Arc<dyn LlmProvider> {
         Arc::new(self.with_model_and_tokens_override(model, max_tokens))
     }
 }
+
+// ============================================================================
+// Batch API
+// ============================================================================
+
+/// Batch object returned by the OpenAI Batch API
+#[derive(Debug, Deserialize)]
+struct OpenAIBatch {
+    id: String,
+    status: String,
+    #[serde(default)]
+    request_counts: Option<OpenAIBatchCounts>,
+    #[serde(default)]
+    output_file_id: Option<String>,
+    #[serde(default)]
+    error_file_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIBatchCounts {
+    total: u32,
+    completed: u32,
+    failed: u32,
+}
+
+/// Uploaded file object
+#[derive(Debug, Deserialize)]
+struct OpenAIFile {
+    id: String,
+}
+
+/// One line of a batch output or error file
+#[derive(Debug, Deserialize)]
+struct OpenAIBatchResultLine {
+    custom_id: String,
+    #[serde(default)]
+    response: Option<OpenAIBatchResponse>,
+    #[serde(default)]
+    error: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIBatchResponse {
+    status_code: u16,
+    body: Value,
+}
+
+/// Endpoint that batched requests are sent to
+const BATCH_ENDPOINT: &str = "/v1/responses";
+
+impl OpenAIProvider {
+    /// Call an API endpoint relative to the configured base URL
+    ///
+    /// The base is the configured Responses URL without its `/responses`
+    /// suffix, so `path` is e.g. `/batches` or `/files/file_123/content`.
+    async fn api_call(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<reqwest::Body>,
+        content_type: Option<String>,
+    ) -> Result<String> {
+        let auth_config = self.auth.get_auth().await
+            .context("Failed to get authentication credentials")?;
+        let api_url = auth_config.base_url.as_deref().unwrap_or(DEFAULT_API_URL);
+        let base = api_url.trim_end_matches('/').trim_end_matches("/responses");
+
+        let mut builder = self.client
+            .request(method, format!("{}{}", base, path))
+            .header("Authorization", format!("Bearer {}", auth_config.api_key));
+        builder = self.headers.apply(builder, None);
+        if let Some(content_type) = content_type {
+            builder = builder.header("Content-Type", content_type);
+        }
+        if let Some(body) = body {
+            builder = builder.body(body);
+        }
+
+        let response = builder
+            .send()
+            .await
+            .context("Failed to send request to OpenAI API")?;
+
+        let status = response.status();
+        let body = response.text().await.context("Failed to read OpenAI response body")?;
+
+        if !status.is_success() {
+            anyhow::bail!("OpenAI API error ({}): {}", status, body);
+        }
+
+        Ok(body)
+    }
+
+    /// Retrieve a batch object
+    async fn get_batch(&self, batch_id: &str) -> Result<OpenAIBatch> {
+        let body = self
+            .api_call(reqwest::Method::GET, &format!("/batches/{}", batch_id), None, None)
+            .await?;
+        serde_json::from_str(&body).context("Failed to parse OpenAI batch")
+    }
+
+    /// Convert a framework request into a Responses API request body
+    fn batch_body(&self, request: MessageRequest) -> OpenAIRequest {
+        let request = with_defaults(request, &self.model, self.max_tokens);
+        let mut openai_req = build_request(
+            &request.model,
+            request.max_tokens,
+            request.messages,
+            request.system,
+            request.tools.unwrap_or_default(),
+            request.tool_choice,
+            request.thinking,
+            false,
+        );
+        openai_req.temperature = request.temperature;
+        openai_req.user = request.metadata.and_then(|m| m.user_id);
+        openai_req
+    }
+}
+
+#[async_trait::async_trait]
+impl BatchProvider for OpenAIProvider {
+    async fn submit_batch(&self, requests: Vec<BatchRequest>) -> Result<String> {
+        tracing::info!("Submitting batch of {} requests to OpenAI API", requests.len());
+
+        // The Batch API takes its input as an uploaded JSONL file
+        let mut jsonl = String::new();
+        for item in requests {
+            let line = serde_json::json!({
+                "custom_id": item.custom_id,
+                "method": "POST",
+                "url": BATCH_ENDPOINT,
+                "body": self.batch_body(item.request),
+            });
+            jsonl.push_str(&serde_json::to_string(&line).context("Failed to serialize batch")?);
+            jsonl.push('\n');
+        }
+
+        let form = reqwest::multipart::Form::new()
+            .text("purpose", "batch")
+            .part(
+                "file",
+                reqwest::multipart::Part::text(jsonl)
+                    .file_name("batch.jsonl")
+                    .mime_str("application/jsonl")?,
+            );
+        let content_type = format!("multipart/form-data; boundary={}", form.boundary());
+        let body = reqwest::Body::wrap_stream(form.into_stream());
+        let file: OpenAIFile = serde_json::from_str(
+            &self
+                .api_call(reqwest::Method::POST, "/files", Some(body), Some(content_type))
+                .await?,
+        )
+        .context("Failed to parse OpenAI file upload response")?;
+
+        let create = serde_json::json!({
+            "input_file_id": file.id,
+            "endpoint": BATCH_ENDPOINT,
+            "completion_window": "24h",
+        });
+        let batch: OpenAIBatch = serde_json::from_str(
+            &self
+                .api_call(
+                    reqwest::Method::POST,
+                    "/batches",
+                    Some(create.to_string().into()),
+                    Some("application/json".to_string()),
+                )
+                .await?,
+        )
+        .context("Failed to parse OpenAI batch")?;
+
+        tracing::info!("Created batch {}", batch.id);
+        Ok(batch.id)
+    }
+
+    async fn poll_batch(&self, batch_id: &str) -> Result<BatchStatus> {
+        let batch = self.get_batch(batch_id).await?;
+        let (total, completed, failed) = batch
+            .request_counts
+            .map(|c| (c.total, c.completed, c.failed))
+            .unwrap_or_default();
+
+        Ok(BatchStatus {
+            state: match batch.status.as_str() {
+                "completed" | "expired" | "cancelled" => BatchState::Ended,
+                "failed" => BatchState::Failed,
+                _ => BatchState::InProgress,
+            },
+            succeeded: completed,
+            errored: failed,
+            processing: total.saturating_sub(completed + failed),
+            id: batch.id,
+        })
+    }
+
+    async fn fetch_results(&self, batch_id: &str) -> Result<Vec<BatchResult>> {
+        let batch = self.get_batch(batch_id).await?;
+        if batch.output_file_id.is_none() && batch.error_file_id.is_none() {
+            anyhow::bail!("Batch {} has no results yet (status: {})", batch_id, batch.status);
+        }
+
+        let mut results = Vec::new();
+        for file_id in batch.output_file_id.iter().chain(batch.error_file_id.iter()) {
+            let body = self
+                .api_call(reqwest::Method::GET, &format!("/files/{}/content", file_id), None, None)
+                .await?;
+
+            for line in body.lines().filter(|line| !line.trim().is_empty()) {
+                let line: OpenAIBatchResultLine =
+                    serde_json::from_str(line).context("Failed to parse batch result")?;
+                let result = match (line.response, line.error) {
+                    (Some(response), _) if response.status_code == 200 => {
+                        serde_json::from_value::<OpenAIResponse>(response.body)
+                            .map(openai_response_to_anthropic)
+                            .map_err(|e| format!("Failed to parse response: {}", e))
+                    }
+                    (Some(response), _) => Err(format!("HTTP {}: {}", response.status_code, response.body)),
+                    (None, Some(error)) => Err(error.to_string()),
+                    (None, None) => Err("No response".to_string()),
+                };
+                results.push(BatchResult {
+                    custom_id: line.custom_id,
+                    result,
+                });
+            }
+        }
+
+        Ok(results)
+    }
+
+    fn batch_request(&self, messages: Vec<Message>) -> MessageRequest {
+        plain_request(messages, &self.model, self.max_tokens)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// (type, role or call_id) for each input item
+    fn item_summary(items: &[InputItem]) -> Vec<(String, String)> {
+        serde_json::to_value(items)
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| {
+                let kind = item["type"].as_str().unwrap().to_string();
+                let key = item.get("role").or(item.get("call_id")).unwrap();
+                (kind, key.as_str().unwrap().to_string())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_function_outputs_follow_their_calls() {
+        let messages = vec![
+            Message::user("Compare Cargo.toml and Cargo.lock"),
+            Message::assistant_with_blocks(vec![
+                ContentBlock::text("I'll read both files."),
+                ContentBlock::tool_use("call_a", "Read", json!({"path": "Cargo.toml"})),
+                ContentBlock::tool_use("call_b", "Read", json!({"path": "Cargo.lock"})),
+            ]),
+            // The user typed while the tools ran, and the results came back out of order
+            Message::user("Only the dependency versions matter"),
+            Message::user_with_blocks(vec![
+                ContentBlock::text("Also check the edition."),
+                ContentBlock::tool_result("call_b", "lock contents", false),
+                ContentBlock::tool_result("call_a", "toml contents", false),
+                ContentBlock::tool_result("call_stale", "from an earlier session", false),
+            ]),
+            Message::assistant("Both use serde 1.0."),
+        ];
+
+        let items = messages_to_input_items(messages);
+        let pair = |kind: &str, key: &str| (kind.to_string(), key.to_string());
+        assert_eq!(
+            item_summary(&items),
+            vec![
+                pair("message", "user"),
+                pair("message", "assistant"),
+                pair("function_call", "call_a"),
+                pair("function_call", "call_b"),
+                pair("function_call_output", "call_a"),
+                pair("function_call_output", "call_b"),
+                pair("message", "user"),
+                pair("message", "user"),
+                pair("message", "assistant"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stream_completion_reports_final_usage() {
+        let event: OpenAIStreamEvent = serde_json::from_value(json!({
+            "type": "response.completed",
+            "response": {
+                "id": "resp_1",
+                "status": "completed",
+                "usage": {
+                    "input_tokens": 1200,
+                    "output_tokens": 80,
+                    "output_tokens_details": {"reasoning_tokens": 32}
+                },
+                "output": []
+            }
+        }))
+        .unwrap();
+
+        let events = translate_stream_event(event, "gpt-test", &mut 0);
+        let StreamEvent::MessageDelta(delta) = &events[0] else {
+            panic!("expected a message delta, got {:?}", events[0]);
+        };
+        assert_eq!(delta.usage.output_tokens, 80);
+        assert_eq!(delta.usage.input_tokens, Some(1200));
+        assert_eq!(delta.usage.thoughts_token_count, Some(32));
+        assert!(matches!(events[1], StreamEvent::MessageStop));
+    }
+
+    #[test]
+    fn test_from_chat_messages() {
+        let messages = vec![
+            json!({"role": "system", "content": "Be brief."}),
+            json!({"role": "user", "content": [
+                {"type": "text", "text": "What is in this image?"},
+                {"type": "image_url", "image_url": {"url": "data:image/png;base64,iVBOR"}}
+            ]}),
+            json!({"role": "assistant", "content": null, "tool_calls": [
+                {"id": "call_1", "type": "function", "function": {"name": "Read", "arguments": "{\"file_path\":\"a.rs\"}"}},
+                {"id": "call_2", "type": "function", "function": {"name": "LS", "arguments": ""}}
+            ]}),
+            json!({"role": "tool", "tool_call_id": "call_1", "content": "fn main() {}"}),
+            json!({"role": "tool", "tool_call_id": "call_2", "content": "a.rs"}),
+            json!({"role": "assistant", "content": "A cat."}),
+        ];
+
+        let (system, history) = from_chat_messages(&messages).unwrap();
+        assert_eq!(system.as_deref(), Some("Be brief."));
+        assert_eq!(history.len(), 4);
+        assert!(matches!(history[0].blocks().unwrap()[1], ContentBlock::Image { .. }));
+        assert_eq!(
+            history[1].tool_uses(),
+            vec![("call_1", "Read", &json!({"file_path": "a.rs"})), ("call_2", "LS", &json!({}))]
+        );
+        assert_eq!(history[2].role, "user");
+        assert_eq!(history[2].tool_results().len(), 2);
+        assert_eq!(history[3].text(), Some("A cat."));
+
+        assert!(from_chat_messages(&[json!({"role": "narrator", "content": "Hi"})]).is_err());
+
+        // A tool call must be answered before the assistant speaks again
+        let unanswered = &messages[1..3];
+        let error = from_chat_messages(unanswered).unwrap_err();
+        assert_eq!(error.to_string(), "Tool call call_1 has no tool message");
+        let mut skipped = messages[1..4].to_vec();
+        skipped.push(json!({"role": "assistant", "content": "Done."}));
+        let error = from_chat_messages(&skipped).unwrap_err();
+        assert_eq!(error.to_string(), "Tool call call_2 has no tool message before message 3");
+    }
+
+    #[test]
+    fn test_unsupported_param_is_removed() {
+        let error = json!({
+            "error": {
+                "message": "Unsupported parameter: 'temperature' is not supported with this model.",
+                "type": "invalid_request_error",
+                "param": "temperature",
+                "code": "unsupported_parameter"
+            }
+        })
+        .to_string();
+        let bad_request = reqwest::StatusCode::BAD_REQUEST;
+        assert_eq!(
+            rejected_param(bad_request, &error),
+            Some(RejectedParam::Unsupported("temperature".to_string()))
+        );
+        assert_eq!(rejected_param(reqwest::StatusCode::INTERNAL_SERVER_ERROR, &error), None);
+        assert_eq!(rejected_param(bad_request, "not json"), None);
+        let bad_value = json!({
+            "error": {"param": "reasoning.effort", "code": "unsupported_value"}
+        })
+        .to_string();
+        assert_eq!(
+            rejected_param(bad_request, &bad_value),
+            Some(RejectedParam::BadValue("reasoning.effort".to_string()))
+        );
+
+        let mut body = json!({
+            "model": "gpt-test",
+            "temperature": 0.2,
+            "reasoning": {"effort": "minimal", "summary": "auto"}
+        });
+        assert!(remove_param(&mut body, "temperature"));
+        assert!(remove_param(&mut body, "reasoning.effort"));
+        assert!(!remove_param(&mut body, "model"));
+        assert!(!remove_param(&mut body, "top_p"));
+        assert_eq!(body, json!({"model": "gpt-test", "reasoning": {"summary": "auto"}}));
+    }
+
+    #[tokio::test]
+    async fn test_only_unsupported_params_are_remembered() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::net::TcpListener;
+
+        // Rejects any request that sets reasoning.effort with `code`
+        async fn serve(code: &'static str, requests: usize) -> (String, tokio::task::JoinHandle<Vec<bool>>) {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}/v1/responses", listener.local_addr().unwrap());
+            let server = tokio::spawn(async move {
+                let mut sent_effort = Vec::new();
+                for _ in 0..requests {
+                    let (mut stream, _) = listener.accept().await.unwrap();
+                    let mut buf = vec![0u8; 65536];
+                    let n = stream.read(&mut buf).await.unwrap();
+                    let has_effort = String::from_utf8_lossy(&buf[..n]).contains("\"effort\"");
+                    sent_effort.push(has_effort);
+                    let (status, body) = if has_effort {
+                        let error = json!({"error": {"param": "reasoning.effort", "code": code}});
+                        ("400 Bad Request", error.to_string())
+                    } else {
+                        ("200 OK", "{}".to_string())
+                    };
+                    let response = format!(
+                        "HTTP/1.1 {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                        status,
+                        body.len(),
+                        body
+                    );
+                    stream.write_all(response.as_bytes()).await.unwrap();
+                }
+                sent_effort
+            });
+            (url, server)
+        }
+
+        let request = build_request(
+            "gpt-test",
+            64,
+            vec![Message::user("Hi")],
+            None,
+            Vec::new(),
+            None,
+            Some(ThinkingConfig::enabled(4096)),
+            false,
+        );
+        let provider = OpenAIProvider::new("key").unwrap().with_model("gpt-test");
+
+        // A rejected value is retried without the parameter every time
+        let (url, server) = serve("unsupported_value", 4).await;
+        for _ in 0..2 {
+            provider.post(&url, "key", None, &request).await.unwrap();
+        }
+        assert_eq!(server.await.unwrap(), [true, false, true, false]);
+
+        // An unsupported parameter is left out from then on
+        let (url, server) = serve("unsupported_parameter", 3).await;
+        for _ in 0..2 {
+            provider.post(&url, "key", None, &request).await.unwrap();
+        }
+        assert_eq!(server.await.unwrap(), [true, false, false]);
+    }
+
+    #[tokio::test]
+    async fn test_validate_credentials_fetches_model() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/v1/responses", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 4096];
+            let n = stream.read(&mut buf).await.unwrap();
+            let body = r#"{"id":"gpt-test","object":"model"}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&buf[..n]).to_string()
+        });
+
+        let provider = OpenAIProvider::new("good-key").unwrap().with_base_url(url).with_model("gpt-test");
+        provider.validate_credentials().await.unwrap();
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("GET /v1/models/gpt-test "), "{}", request);
+        assert!(request.to_lowercase().contains("authorization: bearer good-key"), "{}", request);
+    }
+
+    #[tokio::test]
+    async fn test_validate_credentials_reports_rejected_key() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/v1/responses", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 4096];
+            let _ = stream.read(&mut buf).await.unwrap();
+            let body = r#"{"error":{"message":"Incorrect API key provided"}}"#;
+            let response = format!(
+                "HTTP/1.1 401 Unauthorized\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        let provider = OpenAIProvider::new("bad-key").unwrap().with_base_url(url).with_model("gpt-test");
+        let error = provider.validate_credentials().await.unwrap_err().to_string();
+        assert!(error.starts_with("openai credentials check failed"), "{}", error);
+        assert!(error.contains("Incorrect API key"), "{}", error);
+    }
+}
@@ -13,6 +13,48 @@ use super::types::{
     ToolDefinition,
 };
 
+/// Per-request settings that are not part of the conversation
+///
+/// Passed to `send_with_options` and `stream_with_options`. More settings may
+/// be added, so build it with `new()` and the `with_*` methods.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RequestOptions {
+    /// Session the request belongs to
+    pub session_id: Option<String>,
+    /// End-user ID for attribution (Anthropic `metadata.user_id`, OpenAI `user`)
+    pub user_id: Option<String>,
+}
+
+impl RequestOptions {
+    /// Options with nothing set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the session the request belongs to
+    pub fn with_session_id(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
+
+    /// Set the end-user ID
+    ///
+    /// Use an opaque value such as a UUID or hash, never personal data.
+    pub fn with_user_id(mut self, user_id: impl Into<String>) -> Self {
+        self.user_id = Some(user_id.into());
+        self
+    }
+
+    /// Options carrying only an optional session ID
+    pub(crate) fn for_session(session_id: Option<&str>) -> Self {
+        Self {
+            session_id: session_id.map(String::from),
+            ..Self::default()
+        }
+    }
+}
+
 /// Trait for LLM providers that can be used with StandardAgent.
 ///
 /// This trait abstracts the interface needed by the agent loop, allowing
@@ -51,6 +93,10 @@ pub trait LlmProvider: Send + Sync {
     ///
     /// Returns an async stream of StreamEvent that yields events as they arrive.
     /// This is the primary method used by the agent loop for streaming requests.
+    ///
+    /// A response is only complete once `MessageStop` arrives; the stream may end
+    /// earlier if the connection drops. Wrap it in [`GuardedStream`](super::GuardedStream)
+    /// to turn an early end into an error rather than a silently truncated message.
     async fn stream_with_tools_and_system(
         &self,
         messages: Vec<Message>,
@@ -61,12 +107,84 @@ pub trait LlmProvider: Send + Sync {
         session_id: Option<&str>,
     ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamEvent>> + Send>>>;
 
+    /// Like `send_with_tools_and_system`, with per-request options.
+    ///
+    /// The agent loop calls this. The default passes on the session ID and
+    /// ignores the other options; providers that support them override it.
+    async fn send_with_options(
+        &self,
+        messages: Vec<Message>,
+        system: Option<SystemPrompt>,
+        tools: Vec<ToolDefinition>,
+        tool_choice: Option<ToolChoice>,
+        thinking: Option<ThinkingConfig>,
+        options: &RequestOptions,
+    ) -> Result<MessageResponse> {
+        self.send_with_tools_and_system(
+            messages,
+            system,
+            tools,
+            tool_choice,
+            thinking,
+            options.session_id.as_deref(),
+        )
+        .await
+    }
+
+    /// Like `stream_with_tools_and_system`, with per-request options.
+    ///
+    /// The agent loop calls this. The default passes on the session ID and
+    /// ignores the other options; providers that support them override it.
+    async fn stream_with_options(
+        &self,
+        messages: Vec<Message>,
+        system: Option<SystemPrompt>,
+        tools: Vec<ToolDefinition>,
+        tool_choice: Option<ToolChoice>,
+        thinking: Option<ThinkingConfig>,
+        options: &RequestOptions,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamEvent>> + Send>>> {
+        self.stream_with_tools_and_system(
+            messages,
+            system,
+            tools,
+            tool_choice,
+            thinking,
+            options.session_id.as_deref(),
+        )
+        .await
+    }
+
     /// Get the current model name.
     fn model(&self) -> String;
 
     /// Get the provider name (e.g., "anthropic", "gemini").
     fn provider_name(&self) -> &str;
 
+    /// Serialize tool definitions exactly as this provider sends them on the wire.
+    ///
+    /// Defaults to the internal (Anthropic) format. Providers that translate
+    /// tools into their own schema override this so callers can inspect what
+    /// the model actually receives.
+    fn tool_definitions_json(&self, tools: &[ToolDefinition]) -> serde_json::Value {
+        serde_json::to_value(tools).unwrap_or_default()
+    }
+
+    /// Check that the provider's credentials and model are accepted.
+    ///
+    /// Call at startup to surface a bad API key or model name before the
+    /// first user message. The built-in providers fetch the model from their
+    /// models endpoint, which generates nothing. The default sends a short
+    /// message without thinking through `create_variant`; 16 tokens is the
+    /// smallest output limit every built-in API accepts.
+    async fn validate_credentials(&self) -> Result<()> {
+        self.create_variant(&self.model(), 16)
+            .send_message("ping", &[], None, None)
+            .await
+            .map(|_| ())
+            .map_err(|e| anyhow::anyhow!("{} credentials check failed: {}", self.provider_name(), e))
+    }
+
     /// Create a lightweight variant of this provider with a different model and max tokens.
     ///
     /// Used by ConversationNamer to create a Haiku-based namer that shares
@@ -0,0 +1,159 @@
+//! Completion guard for streaming responses
+//!
+//! A provider stream can end before `message_stop` - the connection drops,
+//! the server gives up, or a proxy cuts the response short. A consumer that
+//! simply stops at the end of the stream would then treat a half-built
+//! assistant message as finished, leaving partial tool calls or unsigned
+//! thinking blocks in the session history.
+//!
+//! `GuardedStream` makes the end of a response explicit:
+//!
+//! - A stream that reaches `message_stop` ends normally.
+//! - A stream that ends early yields one final `Err` naming the blocks that
+//!   were still open, so the consumer discards the partial message instead of
+//!   persisting it.
+//! - After an `Err` from the provider the stream is fused and yields nothing more.
+//!
+//! Dropping the stream mid-way (e.g. on user interrupt) closes the underlying
+//! connection. Nothing is emitted on drop; whatever the consumer accumulated
+//! is its own to finalize or discard. `StandardAgent` keeps the partial text
+//! and drops incomplete thinking and tool calls.
+
+use std::collections::BTreeSet;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use anyhow::Result;
+use futures::stream::Stream;
+
+use super::types::StreamEvent;
+
+/// Boxed provider stream, as returned by `LlmProvider::stream_with_tools_and_system`
+type EventStream = Pin<Box<dyn Stream<Item = Result<StreamEvent>> + Send>>;
+
+/// Stream adapter that reports responses which end before `message_stop`
+pub struct GuardedStream {
+    inner: EventStream,
+    /// Indices of content blocks that have started but not stopped
+    open_blocks: BTreeSet<usize>,
+    /// Whether `message_stop` was received
+    stopped: bool,
+    /// Whether the stream has finished (no more items will be yielded)
+    done: bool,
+}
+
+impl GuardedStream {
+    /// Wrap a provider stream
+    pub fn new(inner: EventStream) -> Self {
+        Self {
+            inner,
+            open_blocks: BTreeSet::new(),
+            stopped: false,
+            done: false,
+        }
+    }
+
+    /// Whether the response has been fully received
+    pub fn is_complete(&self) -> bool {
+        self.stopped
+    }
+
+    /// Update block tracking for an event
+    fn track(&mut self, event: &StreamEvent) {
+        match event {
+            StreamEvent::ContentBlockStart(start) => {
+                self.open_blocks.insert(start.index);
+            }
+            StreamEvent::ContentBlockStop(stop) => {
+                self.open_blocks.remove(&stop.index);
+            }
+            StreamEvent::MessageStop => {
+                self.stopped = true;
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Stream for GuardedStream {
+    type Item = Result<StreamEvent>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.done {
+            return Poll::Ready(None);
+        }
+
+        match this.inner.as_mut().poll_next(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(Some(Ok(event))) => {
+                this.track(&event);
+                Poll::Ready(Some(Ok(event)))
+            }
+            Poll::Ready(Some(Err(e))) => {
+                this.done = true;
+                Poll::Ready(Some(Err(e)))
+            }
+            Poll::Ready(None) => {
+                this.done = true;
+                if this.stopped {
+                    return Poll::Ready(None);
+                }
+                tracing::warn!(
+                    "[GuardedStream] Stream ended before message_stop ({} open blocks)",
+                    this.open_blocks.len()
+                );
+                Poll::Ready(Some(Err(anyhow::anyhow!(
+                    "Stream ended before the response was complete (open content blocks: {:?})",
+                    this.open_blocks
+                ))))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::types::{ContentBlockStart, ContentBlockStartEvent, ContentBlockStopEvent};
+    use futures::StreamExt;
+
+    fn block_start(index: usize) -> Result<StreamEvent> {
+        Ok(StreamEvent::ContentBlockStart(ContentBlockStartEvent {
+            index,
+            content_block: ContentBlockStart::Text {
+                text: String::new(),
+            },
+        }))
+    }
+
+    fn block_stop(index: usize) -> Result<StreamEvent> {
+        Ok(StreamEvent::ContentBlockStop(ContentBlockStopEvent { index }))
+    }
+
+    #[tokio::test]
+    async fn test_complete_stream_passes_through() {
+        let events = vec![block_start(0), block_stop(0), Ok(StreamEvent::MessageStop)];
+        let mut stream = GuardedStream::new(Box::pin(futures::stream::iter(events)));
+
+        let mut count = 0;
+        while let Some(event) = stream.next().await {
+            assert!(event.is_ok());
+            count += 1;
+        }
+        assert_eq!(count, 3);
+        assert!(stream.is_complete());
+    }
+
+    #[tokio::test]
+    async fn test_truncated_stream_ends_with_error() {
+        let events = vec![block_start(0), block_stop(0), block_start(1)];
+        let mut stream = GuardedStream::new(Box::pin(futures::stream::iter(events)));
+
+        let results: Vec<_> = (&mut stream).collect().await;
+        assert_eq!(results.len(), 4);
+        let err = results.last().unwrap().as_ref().unwrap_err().to_string();
+        assert!(err.contains("{1}"));
+        assert!(!stream.is_complete());
+    }
+}
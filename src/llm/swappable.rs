@@ -31,7 +31,7 @@ use std::pin::Pin;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
-use super::provider::LlmProvider;
+use super::provider::{LlmProvider, RequestOptions};
 use super::types::{
     Message, MessageResponse, StreamEvent, SystemPrompt, ThinkingConfig, ToolChoice,
     ToolDefinition,
@@ -139,6 +139,41 @@ impl LlmProvider for SwappableLlmProvider {
             .await
     }
 
+    async fn send_with_options(
+        &self,
+        messages: Vec<Message>,
+        system: Option<SystemPrompt>,
+        tools: Vec<ToolDefinition>,
+        tool_choice: Option<ToolChoice>,
+        thinking: Option<ThinkingConfig>,
+        options: &RequestOptions,
+    ) -> Result<MessageResponse> {
+        let provider = self.inner.read().await.clone();
+        provider
+            .send_with_options(messages, system, tools, tool_choice, thinking, options)
+            .await
+    }
+
+    async fn stream_with_options(
+        &self,
+        messages: Vec<Message>,
+        system: Option<SystemPrompt>,
+        tools: Vec<ToolDefinition>,
+        tool_choice: Option<ToolChoice>,
+        thinking: Option<ThinkingConfig>,
+        options: &RequestOptions,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamEvent>> + Send>>> {
+        let provider = self.inner.read().await.clone();
+        provider
+            .stream_with_options(messages, system, tools, tool_choice, thinking, options)
+            .await
+    }
+
+    async fn validate_credentials(&self) -> Result<()> {
+        let provider = self.inner.read().await.clone();
+        provider.validate_credentials().await
+    }
+
     fn model(&self) -> String {
         match self.inner.try_read() {
             Ok(guard) => guard.model(),
@@ -161,6 +196,13 @@ impl LlmProvider for SwappableLlmProvider {
         }
     }
 
+    fn tool_definitions_json(&self, tools: &[ToolDefinition]) -> serde_json::Value {
+        match self.inner.try_read() {
+            Ok(guard) => guard.tool_definitions_json(tools),
+            Err(_) => serde_json::to_value(tools).unwrap_or_default(),
+        }
+    }
+
     fn create_variant(&self, model: &str, max_tokens: u32) -> Arc<dyn LlmProvider> {
         // For variants (e.g., conversation naming), we create from the current
         // inner provider. The variant is NOT swappable - it's a lightweight
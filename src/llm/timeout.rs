@@ -0,0 +1,282 @@
+//! Per-request deadlines for any LLM provider
+//!
+//! A provider's `with_timeout` limits how long each read may wait for data,
+//! so a response that keeps trickling in is never cut off. `TimeoutLlmProvider`
+//! instead bounds the whole request: a non-streaming call must return, and a
+//! stream must reach its end, within the deadline.
+//!
+//! Wrapping is cheap, so a deadline can be chosen per call - a short one for
+//! a quick classification, minutes for a reasoning model.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use picrust::llm::TimeoutLlmProvider;
+//!
+//! let bounded = TimeoutLlmProvider::new(llm.clone(), Duration::from_secs(300));
+//! let stream = bounded.stream_with_tools_and_system(messages, system, tools, None, None, None).await?;
+//! ```
+
+use anyhow::{anyhow, Result};
+use futures::stream::{Stream, StreamExt};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use super::provider::{LlmProvider, RequestOptions};
+use super::types::{
+    Message, MessageResponse, StreamEvent, SystemPrompt, ThinkingConfig, ToolChoice,
+    ToolDefinition,
+};
+
+/// Stands in for a deadline too far away to represent, about 30 years
+const FAR_FUTURE: Duration = Duration::from_secs(86400 * 365 * 30);
+
+/// An LLM provider whose requests fail once they run longer than a deadline
+pub struct TimeoutLlmProvider {
+    inner: Arc<dyn LlmProvider>,
+    timeout: Duration,
+}
+
+impl TimeoutLlmProvider {
+    /// Bound every request made through `provider` to `timeout`
+    pub fn new(provider: Arc<dyn LlmProvider>, timeout: Duration) -> Self {
+        Self {
+            inner: provider,
+            timeout,
+        }
+    }
+
+    /// The deadline applied to each request
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn timed_out(&self) -> anyhow::Error {
+        anyhow!("{} request timed out after {:?}", self.inner.provider_name(), self.timeout)
+    }
+}
+
+#[async_trait::async_trait]
+impl LlmProvider for TimeoutLlmProvider {
+    async fn send_message(
+        &self,
+        user_message: &str,
+        conversation_history: &[Message],
+        system_prompt: Option<&str>,
+        session_id: Option<&str>,
+    ) -> Result<String> {
+        let request = self
+            .inner
+            .send_message(user_message, conversation_history, system_prompt, session_id);
+        tokio::time::timeout(self.timeout, request)
+            .await
+            .map_err(|_| self.timed_out())?
+    }
+
+    async fn send_with_tools_and_system(
+        &self,
+        messages: Vec<Message>,
+        system: Option<SystemPrompt>,
+        tools: Vec<ToolDefinition>,
+        tool_choice: Option<ToolChoice>,
+        thinking: Option<ThinkingConfig>,
+        session_id: Option<&str>,
+    ) -> Result<MessageResponse> {
+        let options = RequestOptions::for_session(session_id);
+        self.send_with_options(messages, system, tools, tool_choice, thinking, &options)
+            .await
+    }
+
+    async fn stream_with_tools_and_system(
+        &self,
+        messages: Vec<Message>,
+        system: Option<SystemPrompt>,
+        tools: Vec<ToolDefinition>,
+        tool_choice: Option<ToolChoice>,
+        thinking: Option<ThinkingConfig>,
+        session_id: Option<&str>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamEvent>> + Send>>> {
+        let options = RequestOptions::for_session(session_id);
+        self.stream_with_options(messages, system, tools, tool_choice, thinking, &options)
+            .await
+    }
+
+    async fn send_with_options(
+        &self,
+        messages: Vec<Message>,
+        system: Option<SystemPrompt>,
+        tools: Vec<ToolDefinition>,
+        tool_choice: Option<ToolChoice>,
+        thinking: Option<ThinkingConfig>,
+        options: &RequestOptions,
+    ) -> Result<MessageResponse> {
+        let request = self
+            .inner
+            .send_with_options(messages, system, tools, tool_choice, thinking, options);
+        tokio::time::timeout(self.timeout, request)
+            .await
+            .map_err(|_| self.timed_out())?
+    }
+
+    /// The deadline covers opening the stream and reading it to the end
+    ///
+    /// When it passes mid-stream, one final `Err` is yielded and the
+    /// underlying stream is dropped, closing the connection.
+    async fn stream_with_options(
+        &self,
+        messages: Vec<Message>,
+        system: Option<SystemPrompt>,
+        tools: Vec<ToolDefinition>,
+        tool_choice: Option<ToolChoice>,
+        thinking: Option<ThinkingConfig>,
+        options: &RequestOptions,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamEvent>> + Send>>> {
+        // Saturate like `tokio::time::timeout` does, so `Duration::MAX` means no deadline
+        let deadline = Instant::now()
+            .checked_add(self.timeout)
+            .unwrap_or_else(|| Instant::now() + FAR_FUTURE);
+        let request = self
+            .inner
+            .stream_with_options(messages, system, tools, tool_choice, thinking, options);
+        let mut inner = tokio::time::timeout_at(deadline, request)
+            .await
+            .map_err(|_| self.timed_out())??;
+
+        let timed_out = self.timed_out();
+        Ok(Box::pin(async_stream::stream! {
+            loop {
+                match tokio::time::timeout_at(deadline, inner.next()).await {
+                    Ok(Some(event)) => yield event,
+                    Ok(None) => break,
+                    Err(_) => {
+                        tracing::warn!("[TimeoutLlmProvider] {}", timed_out);
+                        yield Err(timed_out);
+                        break;
+                    }
+                }
+            }
+        }))
+    }
+
+    async fn validate_credentials(&self) -> Result<()> {
+        tokio::time::timeout(self.timeout, self.inner.validate_credentials())
+            .await
+            .map_err(|_| self.timed_out())?
+    }
+
+    fn model(&self) -> String {
+        self.inner.model()
+    }
+
+    fn provider_name(&self) -> &str {
+        self.inner.provider_name()
+    }
+
+    fn tool_definitions_json(&self, tools: &[ToolDefinition]) -> serde_json::Value {
+        self.inner.tool_definitions_json(tools)
+    }
+
+    fn create_variant(&self, model: &str, max_tokens: u32) -> Arc<dyn LlmProvider> {
+        Arc::new(Self::new(self.inner.create_variant(model, max_tokens), self.timeout))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::ContentBlock;
+    use crate::testing::MockLlmProvider;
+
+    /// Streams the mock's events, then stalls instead of ending
+    struct StallingProvider(MockLlmProvider);
+
+    #[async_trait::async_trait]
+    impl LlmProvider for StallingProvider {
+        async fn send_message(&self, _: &str, _: &[Message], _: Option<&str>, _: Option<&str>) -> Result<String> {
+            futures::future::pending().await
+        }
+
+        async fn send_with_tools_and_system(
+            &self,
+            messages: Vec<Message>,
+            system: Option<SystemPrompt>,
+            tools: Vec<ToolDefinition>,
+            tool_choice: Option<ToolChoice>,
+            thinking: Option<ThinkingConfig>,
+            session_id: Option<&str>,
+        ) -> Result<MessageResponse> {
+            self.0
+                .send_with_tools_and_system(messages, system, tools, tool_choice, thinking, session_id)
+                .await
+        }
+
+        async fn stream_with_tools_and_system(
+            &self,
+            messages: Vec<Message>,
+            system: Option<SystemPrompt>,
+            tools: Vec<ToolDefinition>,
+            tool_choice: Option<ToolChoice>,
+            thinking: Option<ThinkingConfig>,
+            session_id: Option<&str>,
+        ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamEvent>> + Send>>> {
+            let events = self
+                .0
+                .stream_with_tools_and_system(messages, system, tools, tool_choice, thinking, session_id)
+                .await?;
+            Ok(Box::pin(events.chain(futures::stream::pending())))
+        }
+
+        fn model(&self) -> String {
+            "stalling".to_string()
+        }
+
+        fn provider_name(&self) -> &str {
+            "mock"
+        }
+
+        fn create_variant(&self, _model: &str, _max_tokens: u32) -> Arc<dyn LlmProvider> {
+            Arc::new(StallingProvider(self.0.clone()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_deadline() {
+        let inner = StallingProvider(MockLlmProvider::new(vec![
+            vec![ContentBlock::text("Quick")],
+            vec![ContentBlock::text("Slow")],
+        ]));
+        let llm = TimeoutLlmProvider::new(Arc::new(inner), Duration::from_millis(50));
+
+        let response = llm
+            .send_with_tools_and_system(vec![Message::user("Hi")], None, vec![], None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(response.content[0].as_text(), Some("Quick"));
+
+        let error = llm.send_message("Hi", &[], None, None).await.unwrap_err();
+        assert_eq!(error.to_string(), "mock request timed out after 50ms");
+
+        let stream = llm
+            .stream_with_tools_and_system(vec![Message::user("Hi")], None, vec![], None, None, None)
+            .await
+            .unwrap();
+        let events: Vec<_> = stream.collect().await;
+        assert!(events[..events.len() - 1].iter().all(|event| event.is_ok()));
+        assert!(events.last().unwrap().as_ref().is_err_and(|e| e.to_string().contains("timed out")));
+    }
+
+    #[tokio::test]
+    async fn test_unbounded_deadline() {
+        let inner = MockLlmProvider::new(vec![vec![ContentBlock::text("Done")]]);
+        let llm = TimeoutLlmProvider::new(Arc::new(inner), Duration::MAX);
+
+        let stream = llm
+            .stream_with_tools_and_system(vec![Message::user("Hi")], None, vec![], None, None, None)
+            .await
+            .unwrap();
+        let events: Vec<_> = stream.collect().await;
+        assert!(events.iter().all(|event| event.is_ok()));
+    }
+}
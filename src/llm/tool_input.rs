@@ -0,0 +1,174 @@
+//! Incremental accumulation of streamed tool input
+//!
+//! Streaming providers send a tool call's arguments as `input_json_delta`
+//! fragments. `ToolInputAccumulator` collects them and checks the structure
+//! as it goes: brackets must match, the input must be a single object, and
+//! nothing may follow it. A malformed input is reported at the fragment
+//! where it goes wrong, not only once the block ends, and the rest of it is
+//! not buffered.
+//!
+//! The check is structural only. Values inside the object (numbers,
+//! literals) are validated by the final parse in `finish()`.
+
+use serde_json::Value;
+
+/// Why streamed tool input could not be used
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{message} (after {received} bytes)")]
+pub struct ToolInputError {
+    /// What was wrong with the input
+    pub message: String,
+    /// Bytes of input received when the problem was found
+    pub received: usize,
+}
+
+/// Accumulates `input_json_delta` fragments for one tool call
+#[derive(Debug, Default)]
+pub struct ToolInputAccumulator {
+    buffer: String,
+    /// Open brackets, innermost last
+    stack: Vec<char>,
+    in_string: bool,
+    escaped: bool,
+    /// Whether the top-level object has been closed
+    closed: bool,
+    error: Option<ToolInputError>,
+}
+
+impl ToolInputAccumulator {
+    /// Create an empty accumulator
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a fragment, returning an error as soon as the input is malformed
+    ///
+    /// After the first error, further fragments are ignored and the same
+    /// error is returned.
+    pub fn push(&mut self, fragment: &str) -> Result<(), ToolInputError> {
+        if let Some(ref error) = self.error {
+            return Err(error.clone());
+        }
+
+        for c in fragment.chars() {
+            if let Err(message) = self.scan(c) {
+                let error = ToolInputError {
+                    message,
+                    received: self.buffer.len() + c.len_utf8(),
+                };
+                self.error = Some(error.clone());
+                self.buffer.clear();
+                return Err(error);
+            }
+            self.buffer.push(c);
+        }
+        Ok(())
+    }
+
+    /// Parse the accumulated input
+    ///
+    /// An empty input is a call with no arguments and yields `{}`.
+    pub fn finish(&self) -> Result<Value, ToolInputError> {
+        if let Some(ref error) = self.error {
+            return Err(error.clone());
+        }
+        if self.buffer.trim().is_empty() {
+            return Ok(Value::Object(Default::default()));
+        }
+
+        let error = |message: String| ToolInputError {
+            message,
+            received: self.buffer.len(),
+        };
+        if !self.closed {
+            return Err(error(format!("input ended with {} unclosed bracket(s)", self.stack.len())));
+        }
+        serde_json::from_str(&self.buffer).map_err(|e| error(format!("invalid JSON: {}", e)))
+    }
+
+    /// Whether any input has been received
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty() && self.error.is_none()
+    }
+
+    /// Reset for the next tool call
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Advance the structural state by one character
+    fn scan(&mut self, c: char) -> Result<(), String> {
+        if self.in_string {
+            if self.escaped {
+                self.escaped = false;
+            } else if c == '\\' {
+                self.escaped = true;
+            } else if c == '"' {
+                self.in_string = false;
+            }
+            return Ok(());
+        }
+        if c.is_whitespace() {
+            return Ok(());
+        }
+        if self.closed {
+            return Err(format!("unexpected '{}' after the end of the input object", c));
+        }
+        if self.stack.is_empty() && c != '{' {
+            return Err(format!("input must be a JSON object, found '{}'", c));
+        }
+
+        match c {
+            '"' => self.in_string = true,
+            '{' | '[' => self.stack.push(c),
+            '}' | ']' => {
+                let expected = if c == '}' { '{' } else { '[' };
+                if self.stack.pop() != Some(expected) {
+                    return Err(format!("mismatched '{}'", c));
+                }
+                self.closed = self.stack.is_empty();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accumulates_fragments() {
+        let mut acc = ToolInputAccumulator::new();
+        for fragment in [r#"{"path": "a}"#, r#"[b]\"", "lines": [1, "#, "2]}"] {
+            acc.push(fragment).unwrap();
+        }
+        let value = acc.finish().unwrap();
+        assert_eq!(value["path"], "a}[b]\"");
+        assert_eq!(value["lines"][1], 2);
+
+        acc.clear();
+        assert_eq!(acc.finish().unwrap(), serde_json::json!({}));
+    }
+
+    #[test]
+    fn test_detects_malformed_input_early() {
+        let mut acc = ToolInputAccumulator::new();
+        acc.push(r#"{"items": [1, 2}"#).unwrap_err();
+        let error = acc.push(r#", "more": "ignored"}"#).unwrap_err();
+        assert!(error.message.contains("mismatched"));
+        assert!(acc.finish().is_err());
+
+        let mut acc = ToolInputAccumulator::new();
+        assert!(acc.push("[1]").is_err());
+
+        let mut acc = ToolInputAccumulator::new();
+        acc.push(r#"{"a": 1"#).unwrap();
+        assert!(acc.finish().unwrap_err().message.contains("unclosed"));
+
+        let mut acc = ToolInputAccumulator::new();
+        acc.push(r#"{"a": tru}"#).unwrap();
+        assert!(acc.finish().unwrap_err().message.contains("invalid JSON"));
+    }
+}
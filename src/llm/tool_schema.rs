@@ -0,0 +1,174 @@
+//! Compact tool definitions
+//!
+//! Tool definitions are sent with every request, so verbose descriptions and
+//! schemas are a recurring input-token cost. `compact_tool_definitions`
+//! rewrites custom tool definitions to a smaller but equivalent form:
+//!
+//! - `examples`, `example` and `$comment` keywords are removed from schemas
+//! - runs of whitespace in descriptions are collapsed to a single space
+//! - optionally, descriptions longer than [`LONG_DESCRIPTION_CHARS`] are cut
+//!   to their first sentence
+//!
+//! Built-in tools (bash, text editor) have no schema and are left unchanged.
+//! Use [`estimate_tool_tokens`] to compare the size before and after.
+
+use serde_json::Value;
+
+use super::types::ToolDefinition;
+
+/// Descriptions longer than this are cut to their first sentence when trimming
+pub const LONG_DESCRIPTION_CHARS: usize = 200;
+
+/// Schema keywords that only document the schema
+const DOC_KEYWORDS: [&str; 3] = ["examples", "example", "$comment"];
+
+/// Compact every custom tool definition in `tools`
+///
+/// With `trim_descriptions`, long descriptions are also cut to their first
+/// sentence. That is best done once the model has already seen the full
+/// descriptions earlier in the conversation.
+pub fn compact_tool_definitions(
+    tools: &[ToolDefinition],
+    trim_descriptions: bool,
+) -> Vec<ToolDefinition> {
+    tools
+        .iter()
+        .cloned()
+        .map(|tool| match tool {
+            ToolDefinition::Custom(mut tool) => {
+                tool.description = tool
+                    .description
+                    .map(|description| compact_description(&description, trim_descriptions));
+                if let Some(ref mut properties) = tool.input_schema.properties {
+                    compact_properties(properties, trim_descriptions);
+                }
+                ToolDefinition::Custom(tool)
+            }
+            other => other,
+        })
+        .collect()
+}
+
+/// Rough input-token count of `tools` as sent on the wire
+///
+/// Uses the common estimate of four bytes of JSON per token; good enough to
+/// compare definitions, not to bill them.
+pub fn estimate_tool_tokens(tools: &[ToolDefinition]) -> usize {
+    serde_json::to_string(tools).map(|json| json.len().div_ceil(4)).unwrap_or(0)
+}
+
+/// Compact each schema in a `properties` object
+fn compact_properties(properties: &mut Value, trim_descriptions: bool) {
+    if let Value::Object(properties) = properties {
+        for schema in properties.values_mut() {
+            compact_schema(schema, trim_descriptions);
+        }
+    }
+}
+
+/// Compact a single schema and everything nested in it
+fn compact_schema(schema: &mut Value, trim_descriptions: bool) {
+    let Value::Object(map) = schema else {
+        return;
+    };
+
+    for keyword in DOC_KEYWORDS {
+        map.remove(keyword);
+    }
+    if let Some(Value::String(description)) = map.get_mut("description") {
+        *description = compact_description(description, trim_descriptions);
+    }
+
+    for key in ["properties", "$defs", "definitions"] {
+        if let Some(properties) = map.get_mut(key) {
+            compact_properties(properties, trim_descriptions);
+        }
+    }
+    for key in ["items", "additionalProperties", "not"] {
+        if let Some(nested) = map.get_mut(key) {
+            compact_schema(nested, trim_descriptions);
+        }
+    }
+    for key in ["anyOf", "oneOf", "allOf"] {
+        if let Some(Value::Array(variants)) = map.get_mut(key) {
+            for variant in variants {
+                compact_schema(variant, trim_descriptions);
+            }
+        }
+    }
+}
+
+/// Collapse whitespace and optionally cut a long description
+fn compact_description(description: &str, trim: bool) -> String {
+    let collapsed = description.split_whitespace().collect::<Vec<_>>().join(" ");
+    if !trim || collapsed.chars().count() <= LONG_DESCRIPTION_CHARS {
+        return collapsed;
+    }
+    match collapsed.find(". ") {
+        Some(end) => collapsed[..=end].to_string(),
+        None => collapsed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::define_tool;
+    use serde_json::json;
+
+    fn verbose_tool() -> ToolDefinition {
+        let long = format!("Path to the file to read.   {}", "More detail. ".repeat(20));
+        define_tool(
+            "Read",
+            "Read a file.\n\n    Returns the contents   with line numbers.",
+            json!({
+                "path": { "type": "string", "description": long, "examples": ["/tmp/a.txt"] },
+                "options": {
+                    "type": "object",
+                    "properties": {
+                        "examples": { "type": "boolean", "$comment": "a property, not a keyword" }
+                    }
+                }
+            }),
+            vec!["path".to_string()],
+        )
+    }
+
+    #[test]
+    fn test_compact_tool_definitions() {
+        let tools = vec![verbose_tool()];
+        let compact = compact_tool_definitions(&tools, false);
+        let ToolDefinition::Custom(ref tool) = compact[0] else {
+            panic!("expected a custom tool");
+        };
+        let properties = tool.input_schema.properties.as_ref().unwrap();
+
+        assert_eq!(
+            tool.description.as_deref(),
+            Some("Read a file. Returns the contents with line numbers.")
+        );
+        assert!(properties["path"].get("examples").is_none());
+        assert!(properties["path"]["description"].as_str().unwrap().starts_with("Path to the file to read. More"));
+        // A property that happens to be named like a keyword is kept
+        let nested = &properties["options"]["properties"]["examples"];
+        assert_eq!(nested, &json!({ "type": "boolean" }));
+
+        assert!(estimate_tool_tokens(&compact) < estimate_tool_tokens(&tools));
+    }
+
+    #[test]
+    fn test_trim_long_descriptions() {
+        let compact = compact_tool_definitions(&[verbose_tool()], true);
+        let ToolDefinition::Custom(ref tool) = compact[0] else {
+            panic!("expected a custom tool");
+        };
+        let properties = tool.input_schema.properties.as_ref().unwrap();
+
+        assert_eq!(properties["path"]["description"], "Path to the file to read.");
+        // Short descriptions are kept whole
+        assert_eq!(
+            tool.description.as_deref(),
+            Some("Read a file. Returns the contents with line numbers.")
+        );
+    }
+}
@@ -2,6 +2,7 @@
 //!
 //! These types are designed to serialize/deserialize correctly with the Anthropic Messages API.
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -126,6 +127,27 @@ pub struct MessageRequest {
     /// Whether to stream the response (optional)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stream: Option<bool>,
+
+    /// Request metadata such as the end-user ID (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<RequestMetadata>,
+}
+
+/// Metadata about the request, used by Anthropic for abuse monitoring
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestMetadata {
+    /// Opaque identifier for the end user (a UUID or hash, never PII)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+}
+
+impl RequestMetadata {
+    /// Build metadata for an optional user ID (`None` if there is nothing to send)
+    pub fn for_user(user_id: Option<&str>) -> Option<Self> {
+        user_id.map(|id| Self {
+            user_id: Some(id.to_string()),
+        })
+    }
 }
 
 /// Extended thinking configuration
@@ -147,6 +169,37 @@ impl ThinkingConfig {
             budget_tokens,
         }
     }
+
+    /// Create a thinking config from a provider-neutral effort level
+    pub fn with_effort(effort: ReasoningEffort) -> Self {
+        Self::enabled(effort.budget_tokens())
+    }
+}
+
+/// Provider-neutral reasoning effort
+///
+/// Each level is a thinking budget that providers translate to their own
+/// setting: Anthropic uses the budget as is, OpenAI maps it to
+/// `reasoning.effort`, and Gemini 3 to a thinking level of the same name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReasoningEffort {
+    /// 2,048 thinking tokens
+    Low,
+    /// 8,192 thinking tokens
+    Medium,
+    /// 16,000 thinking tokens
+    High,
+}
+
+impl ReasoningEffort {
+    /// Thinking budget for this level
+    pub fn budget_tokens(self) -> u32 {
+        match self {
+            ReasoningEffort::Low => 2048,
+            ReasoningEffort::Medium => 8192,
+            ReasoningEffort::High => 16000,
+        }
+    }
 }
 
 /// A message in the conversation
@@ -157,6 +210,15 @@ pub struct Message {
 
     /// Content of the message - can be a string or array of content blocks
     pub content: MessageContent,
+
+    /// When the message was added to the session
+    ///
+    /// Set by `AgentSession::add_message` and stored in the history file, but
+    /// never sent to a provider. `None` for messages that were never stored
+    /// and for history written before timestamps were recorded. Private so
+    /// that new fields don't break callers; read it with `timestamp()`.
+    #[serde(skip)]
+    pub(crate) timestamp: Option<DateTime<Utc>>,
 }
 
 /// Message content - either a simple string or array of content blocks
@@ -169,40 +231,95 @@ pub enum MessageContent {
     Blocks(Vec<ContentBlock>),
 }
 
+impl MessageContent {
+    /// The content blocks (empty for simple text content)
+    pub fn blocks(&self) -> &[ContentBlock] {
+        match self {
+            MessageContent::Text(_) => &[],
+            MessageContent::Blocks(blocks) => blocks,
+        }
+    }
+
+    /// All text, with text blocks joined by newlines
+    ///
+    /// Tool results and thinking are not included.
+    pub fn text(&self) -> String {
+        match self {
+            MessageContent::Text(text) => text.clone(),
+            MessageContent::Blocks(blocks) => blocks
+                .iter()
+                .filter_map(|block| block.as_text())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+
+    /// The tool use blocks as `(id, name, input)`
+    pub fn tool_uses(&self) -> Vec<(&str, &str, &Value)> {
+        self.blocks().iter().filter_map(|block| block.as_tool_use()).collect()
+    }
+
+    /// The tool result blocks as `(tool_use_id, content, is_error)`
+    pub fn tool_results(&self) -> Vec<(&str, Option<&ToolResultContent>, bool)> {
+        self.blocks().iter().filter_map(|block| block.as_tool_result()).collect()
+    }
+
+    /// Whether there is at least one tool use block
+    pub fn has_tool_use(&self) -> bool {
+        self.blocks().iter().any(|block| block.as_tool_use().is_some())
+    }
+
+    /// Thinking text, with thinking blocks joined by newlines (None without thinking)
+    pub fn thinking(&self) -> Option<String> {
+        let thinking: Vec<&str> = self.blocks().iter().filter_map(|block| block.as_thinking()).collect();
+        (!thinking.is_empty()).then(|| thinking.join("\n"))
+    }
+}
+
 impl Message {
-    /// Create a simple user message with text content
-    pub fn user(text: impl Into<String>) -> Self {
+    /// Create a message with any role and content
+    pub fn new(role: impl Into<String>, content: MessageContent) -> Self {
         Self {
-            role: "user".to_string(),
-            content: MessageContent::Text(text.into()),
+            role: role.into(),
+            content,
+            timestamp: None,
         }
     }
 
+    /// Create a simple user message with text content
+    pub fn user(text: impl Into<String>) -> Self {
+        Self::new("user", MessageContent::Text(text.into()))
+    }
+
     /// Create a simple assistant message with text content
     pub fn assistant(text: impl Into<String>) -> Self {
-        Self {
-            role: "assistant".to_string(),
-            content: MessageContent::Text(text.into()),
-        }
+        Self::new("assistant", MessageContent::Text(text.into()))
     }
 
     /// Create a user message with content blocks (for tool results)
     pub fn user_with_blocks(blocks: Vec<ContentBlock>) -> Self {
-        Self {
-            role: "user".to_string(),
-            content: MessageContent::Blocks(blocks),
-        }
+        Self::new("user", MessageContent::Blocks(blocks))
     }
 
     /// Create an assistant message with content blocks (for tool use)
     pub fn assistant_with_blocks(blocks: Vec<ContentBlock>) -> Self {
-        Self {
-            role: "assistant".to_string(),
-            content: MessageContent::Blocks(blocks),
-        }
+        Self::new("assistant", MessageContent::Blocks(blocks))
+    }
+
+    /// Set when the message was created
+    pub fn with_timestamp(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// When the message was added to the session, if known
+    pub fn timestamp(&self) -> Option<DateTime<Utc>> {
+        self.timestamp
     }
 
     /// Get text content if this is a simple text message
+    ///
+    /// Use `content.text()` for the text of block messages as well.
     pub fn text(&self) -> Option<&str> {
         match &self.content {
             MessageContent::Text(s) => Some(s.as_str()),
@@ -218,6 +335,26 @@ impl Message {
         }
     }
 
+    /// The tool use blocks as `(id, name, input)`
+    pub fn tool_uses(&self) -> Vec<(&str, &str, &Value)> {
+        self.content.tool_uses()
+    }
+
+    /// The tool result blocks as `(tool_use_id, content, is_error)`
+    pub fn tool_results(&self) -> Vec<(&str, Option<&ToolResultContent>, bool)> {
+        self.content.tool_results()
+    }
+
+    /// Whether the message requests at least one tool call
+    pub fn has_tool_use(&self) -> bool {
+        self.content.has_tool_use()
+    }
+
+    /// Thinking text of the message, if any
+    pub fn thinking(&self) -> Option<String> {
+        self.content.thinking()
+    }
+
     /// Append text to this message
     ///
     /// - For Text messages: appends to the string
@@ -285,6 +422,63 @@ impl ImageSource {
     }
 }
 
+/// Content of a tool result: plain text, or a list of blocks
+///
+/// The block form lets a tool return images as its result. How the images
+/// reach the model depends on the provider:
+/// - Anthropic: sent as-is, as image blocks inside the `tool_result`
+/// - OpenAI: function call outputs are text-only, so the images are sent in a
+///   user message after the outputs, labelled with the call ID
+/// - Gemini: sent as inline data parts after the function response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ToolResultContent {
+    /// Plain text result
+    Text(String),
+    /// Text and image blocks
+    Blocks(Vec<ContentBlock>),
+}
+
+impl ToolResultContent {
+    /// The text of the result, with text blocks joined by newlines
+    pub fn text(&self) -> String {
+        match self {
+            ToolResultContent::Text(text) => text.clone(),
+            ToolResultContent::Blocks(blocks) => blocks
+                .iter()
+                .filter_map(|block| block.as_text())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+
+    /// The images in the result
+    pub fn images(&self) -> Vec<&ImageSource> {
+        match self {
+            ToolResultContent::Text(_) => Vec::new(),
+            ToolResultContent::Blocks(blocks) => blocks
+                .iter()
+                .filter_map(|block| match block {
+                    ContentBlock::Image { source, .. } => Some(source),
+                    _ => None,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<String> for ToolResultContent {
+    fn from(text: String) -> Self {
+        ToolResultContent::Text(text)
+    }
+}
+
+impl From<&str> for ToolResultContent {
+    fn from(text: &str) -> Self {
+        ToolResultContent::Text(text.to_string())
+    }
+}
+
 /// Document source for document content blocks (PDFs)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DocumentSource {
@@ -338,7 +532,7 @@ pub enum ContentBlock {
     ToolResult {
         tool_use_id: String,
         #[serde(skip_serializing_if = "Option::is_none")]
-        content: Option<String>,
+        content: Option<ToolResultContent>,
         #[serde(skip_serializing_if = "Option::is_none")]
         is_error: Option<bool>,
         #[serde(skip_serializing_if = "Option::is_none")]
@@ -421,7 +615,22 @@ impl ContentBlock {
     pub fn tool_result(tool_use_id: impl Into<String>, content: impl Into<String>, is_error: bool) -> Self {
         ContentBlock::ToolResult {
             tool_use_id: tool_use_id.into(),
-            content: Some(content.into()),
+            content: Some(ToolResultContent::Text(content.into())),
+            is_error: if is_error { Some(true) } else { None },
+            cache_control: None,
+        }
+    }
+
+    /// Create a tool result whose content is an image (base64 data)
+    pub fn tool_result_image(
+        tool_use_id: impl Into<String>,
+        data: String,
+        media_type: String,
+        is_error: bool,
+    ) -> Self {
+        ContentBlock::ToolResult {
+            tool_use_id: tool_use_id.into(),
+            content: Some(ToolResultContent::Blocks(vec![ContentBlock::image(data, media_type)])),
             is_error: if is_error { Some(true) } else { None },
             cache_control: None,
         }
@@ -436,7 +645,7 @@ impl ContentBlock {
     ) -> Self {
         ContentBlock::ToolResult {
             tool_use_id: tool_use_id.into(),
-            content: Some(content.into()),
+            content: Some(ToolResultContent::Text(content.into())),
             is_error: if is_error { Some(true) } else { None },
             cache_control: Some(cache_control),
         }
@@ -495,6 +704,27 @@ impl ContentBlock {
             _ => None,
         }
     }
+
+    /// Get the tool use ID, content and error flag if this is a tool result block
+    pub fn as_tool_result(&self) -> Option<(&str, Option<&ToolResultContent>, bool)> {
+        match self {
+            ContentBlock::ToolResult {
+                tool_use_id,
+                content,
+                is_error,
+                ..
+            } => Some((tool_use_id.as_str(), content.as_ref(), is_error.unwrap_or(false))),
+            _ => None,
+        }
+    }
+
+    /// Get the thinking text if this is a thinking block
+    pub fn as_thinking(&self) -> Option<&str> {
+        match self {
+            ContentBlock::Thinking { thinking, .. } => Some(thinking.as_str()),
+            _ => None,
+        }
+    }
 }
 
 // ============================================================================
@@ -514,6 +744,15 @@ pub enum ToolDefinition {
 }
 
 impl ToolDefinition {
+    /// Get the tool name
+    pub fn name(&self) -> &str {
+        match self {
+            ToolDefinition::Custom(tool) => &tool.name,
+            ToolDefinition::Bash(tool) => &tool.name,
+            ToolDefinition::TextEditor(tool) => &tool.name,
+        }
+    }
+
     /// Add cache control to this tool definition
     pub fn with_cache_control(mut self, cache_control: CacheControl) -> Self {
         match &mut self {
@@ -793,7 +1032,7 @@ pub enum StopReason {
 }
 
 /// Token usage information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Usage {
     /// Input tokens used
     pub input_tokens: u32,
@@ -815,6 +1054,23 @@ pub struct Usage {
     pub thoughts_token_count: Option<u32>,
 }
 
+impl Usage {
+    /// Add another call's usage to this one
+    pub fn accumulate(&mut self, other: &Usage) {
+        fn add(total: &mut Option<u32>, value: Option<u32>) {
+            if let Some(value) = value {
+                *total = Some(total.unwrap_or(0) + value);
+            }
+        }
+
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        add(&mut self.cache_creation_input_tokens, other.cache_creation_input_tokens);
+        add(&mut self.cache_read_input_tokens, other.cache_read_input_tokens);
+        add(&mut self.thoughts_token_count, other.thoughts_token_count);
+    }
+}
+
 // ============================================================================
 // Error Types
 // ============================================================================
@@ -979,10 +1235,20 @@ pub struct MessageDeltaData {
 }
 
 /// Usage in delta events (may only have output_tokens)
-#[derive(Debug, Clone, Deserialize)]
+///
+/// Providers that only know the input tokens once the response is complete
+/// (OpenAI streams, for example, report zero in `MessageStart`) set
+/// `input_tokens` here; it then replaces the `MessageStart` value.
+#[derive(Debug, Clone, Default, Deserialize)]
 pub struct DeltaUsage {
     /// Output tokens (cumulative)
     pub output_tokens: u32,
+    /// Final input tokens, if the provider reports them at the end
+    #[serde(default)]
+    pub input_tokens: Option<u32>,
+    /// Final reasoning tokens, if the provider reports them at the end
+    #[serde(default)]
+    pub thoughts_token_count: Option<u32>,
 }
 
 /// Error in stream
@@ -1070,6 +1336,34 @@ mod tests {
         assert!(json.contains("\"content\":\"Hello\""));
     }
 
+    #[test]
+    fn test_message_content_helpers() {
+        let msg = Message::assistant_with_blocks(vec![
+            ContentBlock::Thinking {
+                thinking: "Check the file".into(),
+                signature: String::new(),
+            },
+            ContentBlock::text("Reading"),
+            ContentBlock::tool_use("tool_1", "Read", serde_json::json!({"file_path": "a.rs"})),
+            ContentBlock::text("now"),
+        ]);
+        assert_eq!(msg.content.text(), "Reading\nnow");
+        assert_eq!(msg.thinking().as_deref(), Some("Check the file"));
+        assert!(msg.has_tool_use());
+        assert_eq!(msg.tool_uses()[0].1, "Read");
+        assert!(msg.tool_results().is_empty());
+
+        let results = Message::user_with_blocks(vec![ContentBlock::tool_result("tool_1", "oops", true)]);
+        let (id, content, is_error) = results.tool_results()[0];
+        assert_eq!((id, is_error), ("tool_1", true));
+        assert_eq!(content.map(|c| c.text()).as_deref(), Some("oops"));
+
+        let plain = Message::user("Hi");
+        assert_eq!(plain.content.text(), "Hi");
+        assert!(!plain.has_tool_use());
+        assert_eq!(plain.thinking(), None);
+    }
+
     #[test]
     fn test_content_block_serialization() {
         let block = ContentBlock::text("Hello");
@@ -1108,4 +1402,47 @@ mod tests {
             _ => panic!("Expected ContentBlockDelta"),
         }
     }
+
+    #[test]
+    fn test_usage_accumulate() {
+        let mut total = Usage::default();
+        total.accumulate(&Usage {
+            input_tokens: 100,
+            output_tokens: 20,
+            cache_read_input_tokens: Some(80),
+            ..Default::default()
+        });
+        total.accumulate(&Usage {
+            input_tokens: 150,
+            output_tokens: 30,
+            ..Default::default()
+        });
+
+        assert_eq!(total.input_tokens, 250);
+        assert_eq!(total.output_tokens, 50);
+        assert_eq!(total.cache_read_input_tokens, Some(80));
+        assert_eq!(total.cache_creation_input_tokens, None);
+    }
+
+    #[test]
+    fn test_tool_result_image_serialization() {
+        let block = ContentBlock::tool_result_image("tool_1", "aGk=".to_string(), "image/png".to_string(), false);
+        let json = serde_json::to_value(&block).unwrap();
+        assert_eq!(json["type"], "tool_result");
+        assert_eq!(json["content"][0]["type"], "image");
+        assert_eq!(json["content"][0]["source"]["media_type"], "image/png");
+
+        // Text results keep the plain string form
+        let json = serde_json::to_value(ContentBlock::tool_result("tool_2", "ok", false)).unwrap();
+        assert_eq!(json["content"], "ok");
+
+        let parsed: ContentBlock = serde_json::from_value(json).unwrap();
+        match parsed {
+            ContentBlock::ToolResult { content: Some(content), .. } => {
+                assert_eq!(content.text(), "ok");
+                assert!(content.images().is_empty());
+            }
+            other => panic!("unexpected block: {:?}", other),
+        }
+    }
 }
@@ -22,8 +22,20 @@ pub struct MCPServerConfig {
     #[serde(default = "default_reconnect_attempts")]
     pub reconnect_attempts: u32,
 
+    /// Delay before the first reconnection attempt in milliseconds, doubled for each further attempt
+    #[serde(default = "default_reconnect_backoff_ms")]
+    pub reconnect_backoff_ms: u64,
+
     /// Optional health check interval in seconds
     pub health_check_interval_secs: Option<u64>,
+
+    /// Tools to expose, as names or glob patterns (`None` exposes all tools)
+    #[serde(default)]
+    pub enabled_tools: Option<Vec<String>>,
+
+    /// Tools to hide, as names or glob patterns; applied after `enabled_tools`
+    #[serde(default)]
+    pub disabled_tools: Vec<String>,
 }
 
 fn default_enabled() -> bool {
@@ -34,6 +46,10 @@ fn default_reconnect_attempts() -> u32 {
     3
 }
 
+fn default_reconnect_backoff_ms() -> u64 {
+    500
+}
+
 impl MCPServerConfig {
     /// Create a new MCP server configuration
     pub fn new(id: impl Into<String>, uri: impl Into<String>) -> Self {
@@ -42,7 +58,10 @@ impl MCPServerConfig {
             uri: uri.into(),
             enabled: true,
             reconnect_attempts: 3,
+            reconnect_backoff_ms: 500,
             health_check_interval_secs: None,
+            enabled_tools: None,
+            disabled_tools: Vec::new(),
         }
     }
 
@@ -58,18 +77,68 @@ impl MCPServerConfig {
         self
     }
 
+    /// Set the delay before the first reconnection attempt (doubled for each further attempt)
+    pub fn with_reconnect_backoff(mut self, backoff: Duration) -> Self {
+        self.reconnect_backoff_ms = backoff.as_millis() as u64;
+        self
+    }
+
+    /// Get the reconnection backoff as Duration
+    pub fn reconnect_backoff(&self) -> Duration {
+        Duration::from_millis(self.reconnect_backoff_ms)
+    }
+
     /// Set health check interval
     pub fn with_health_check_interval(mut self, interval_secs: u64) -> Self {
         self.health_check_interval_secs = Some(interval_secs);
         self
     }
 
+    /// Only expose the tools matching these names or glob patterns (e.g. `"read_*"`)
+    pub fn with_enabled_tools<I, S>(mut self, tools: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.enabled_tools = Some(tools.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Hide the tools matching these names or glob patterns
+    pub fn with_disabled_tools<I, S>(mut self, tools: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.disabled_tools = tools.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Whether a tool (by its name on the server) passes the enabled/disabled lists
+    pub fn is_tool_enabled(&self, name: &str) -> bool {
+        let enabled = self
+            .enabled_tools
+            .as_ref()
+            .is_none_or(|patterns| matches_any(patterns, name));
+        enabled && !matches_any(&self.disabled_tools, name)
+    }
+
     /// Get health check interval as Duration
     pub fn health_check_interval(&self) -> Option<Duration> {
         self.health_check_interval_secs.map(Duration::from_secs)
     }
 }
 
+/// Whether `name` matches any of `patterns`
+///
+/// Patterns use glob syntax; one that isn't a valid glob only matches itself.
+fn matches_any(patterns: &[String], name: &str) -> bool {
+    patterns.iter().any(|pattern| match glob::Pattern::new(pattern) {
+        Ok(glob) => glob.matches(name),
+        Err(_) => pattern == name,
+    })
+}
+
 /// Global MCP configuration
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct MCPConfig {
@@ -103,3 +172,31 @@ impl MCPConfig {
         self.global_timeout_ms.map(Duration::from_millis)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_filter() {
+        let config = MCPServerConfig::new("fs", "http://localhost:8005/mcp");
+        assert!(config.is_tool_enabled("delete_file"));
+
+        let config = config
+            .with_enabled_tools(["read_*", "list_directory"])
+            .with_disabled_tools(["read_secret*"]);
+        assert!(config.is_tool_enabled("read_file"));
+        assert!(config.is_tool_enabled("list_directory"));
+        assert!(!config.is_tool_enabled("delete_file"));
+        assert!(!config.is_tool_enabled("read_secrets"));
+    }
+
+    #[test]
+    fn test_tool_filter_defaults_when_deserialized() {
+        let config: MCPServerConfig =
+            serde_json::from_str(r#"{"id": "fs", "uri": "http://localhost:8005/mcp"}"#).unwrap();
+        assert_eq!(config.enabled_tools, None);
+        assert!(config.disabled_tools.is_empty());
+        assert_eq!(config.reconnect_backoff_ms, 500);
+    }
+}
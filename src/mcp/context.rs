@@ -0,0 +1,158 @@
+//! MCP resources and prompts as agent context
+//!
+//! Bridges the non-tool parts of MCP into the agent:
+//! - `MCPResourceInjection` - injects a fetched resource into the conversation
+//! - `prompt_to_text` - flattens an MCP prompt so it can be used as a system prompt
+//!
+//! # Example
+//!
+//! ```ignore
+//! // Inject a resource as context before each LLM call
+//! let schema = MCPResourceInjection::fetch(&mcp_manager, "db", "db://schema").await?;
+//! let config = AgentConfig::new().with_injection(schema);
+//!
+//! // Use an MCP prompt as the session's system prompt
+//! let prompt = mcp_manager.get_prompt("reviewer", "code_review", None).await?;
+//! session.update_system_prompt(prompt_to_text(&prompt))?;
+//! ```
+
+use anyhow::Result;
+use rmcp::model::{GetPromptResult, PromptMessageContent, ReadResourceResult, ResourceContents};
+
+use crate::helpers::{prepend_to_first_user_message, ContextInjection};
+use crate::llm::Message;
+use crate::runtime::AgentInternals;
+
+use super::manager::MCPServerManager;
+
+/// Context injection that adds an MCP resource to the first user message
+///
+/// The resource is fetched once up front (injections run synchronously) and
+/// prepended to the first user message on every LLM call, wrapped in an
+/// `<mcp-resource>` tag with its URI.
+#[derive(Debug, Clone)]
+pub struct MCPResourceInjection {
+    name: String,
+    uri: String,
+    text: String,
+}
+
+impl MCPResourceInjection {
+    /// Create an injection from already-fetched resource text
+    pub fn new(uri: impl Into<String>, text: impl Into<String>) -> Self {
+        let uri = uri.into();
+        Self {
+            name: format!("mcp_resource:{}", uri),
+            uri,
+            text: text.into(),
+        }
+    }
+
+    /// Fetch a resource from a server and create an injection for it
+    pub async fn fetch(manager: &MCPServerManager, server_id: &str, uri: &str) -> Result<Self> {
+        let result = manager.read_resource(server_id, uri).await?;
+        Ok(Self::new(uri, resource_to_text(&result)))
+    }
+
+    /// Get the resource URI
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    /// Get the injected resource text
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+impl ContextInjection for MCPResourceInjection {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn inject(&self, _internals: &AgentInternals, mut messages: Vec<Message>) -> Vec<Message> {
+        let block = format!(
+            "<mcp-resource uri=\"{}\">\n{}\n</mcp-resource>\n\n",
+            self.uri, self.text
+        );
+        prepend_to_first_user_message(&mut messages, &block);
+        messages
+    }
+}
+
+/// Flatten the contents of a resource read into text
+///
+/// Binary (blob) contents are replaced by a placeholder with their MIME type.
+pub fn resource_to_text(result: &ReadResourceResult) -> String {
+    result
+        .contents
+        .iter()
+        .map(|contents| match contents {
+            ResourceContents::TextResourceContents { text, .. } => text.clone(),
+            ResourceContents::BlobResourceContents { uri, mime_type, .. } => format!(
+                "[binary resource {} ({})]",
+                uri,
+                mime_type.as_deref().unwrap_or("unknown type")
+            ),
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Flatten an MCP prompt into text suitable for a system prompt
+///
+/// Text and embedded text resources are kept in order; images and resource
+/// links are skipped.
+pub fn prompt_to_text(prompt: &GetPromptResult) -> String {
+    prompt
+        .messages
+        .iter()
+        .filter_map(|message| match &message.content {
+            PromptMessageContent::Text { text } => Some(text.clone()),
+            PromptMessageContent::Resource { resource } => match &resource.resource {
+                ResourceContents::TextResourceContents { text, .. } => Some(text.clone()),
+                ResourceContents::BlobResourceContents { .. } => None,
+            },
+            PromptMessageContent::Image { .. } | PromptMessageContent::ResourceLink { .. } => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::{PromptMessage, PromptMessageRole};
+
+    #[test]
+    fn test_resource_to_text() {
+        let result = ReadResourceResult {
+            contents: vec![
+                ResourceContents::text("CREATE TABLE users;", "db://schema"),
+                ResourceContents::BlobResourceContents {
+                    uri: "db://dump".into(),
+                    mime_type: Some("application/octet-stream".into()),
+                    blob: String::new(),
+                    meta: None,
+                },
+            ],
+        };
+
+        let text = resource_to_text(&result);
+        assert!(text.starts_with("CREATE TABLE users;"));
+        assert!(text.contains("[binary resource db://dump (application/octet-stream)]"));
+    }
+
+    #[test]
+    fn test_prompt_to_text() {
+        let prompt = GetPromptResult {
+            description: None,
+            messages: vec![
+                PromptMessage::new_text(PromptMessageRole::User, "You review code."),
+                PromptMessage::new_text(PromptMessageRole::User, "Be concise."),
+            ],
+        };
+
+        assert_eq!(prompt_to_text(&prompt), "You review code.\n\nBe concise.");
+    }
+}
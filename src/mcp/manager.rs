@@ -3,6 +3,7 @@
 //! Manages multiple MCP server connections
 
 use anyhow::{anyhow, Result};
+use futures::future::join_all;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -14,6 +15,12 @@ use rmcp::{RoleClient, ServiceExt};
 use super::config::MCPServerConfig;
 use super::server::MCPServer;
 
+/// Default per-server timeout for tool discovery
+const DEFAULT_TOOL_DISCOVERY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Longest wait between two connection attempts
+const MAX_RECONNECT_BACKOFF: Duration = Duration::from_secs(30);
+
 /// Information about an MCP tool from a specific server
 #[derive(Debug, Clone)]
 pub struct MCPToolInfo {
@@ -27,10 +34,49 @@ pub struct MCPToolInfo {
     pub tool_def: rmcp::model::Tool,
 }
 
+/// Metadata of an MCP tool, for inspection before enabling it
+#[derive(Debug, Clone, PartialEq)]
+pub struct MCPToolDescription {
+    /// Tool name on the MCP server
+    pub name: String,
+    /// Name the tool is exposed under to agents (`server_id__name`)
+    pub exposed_name: String,
+    /// Tool description, if the server provides one
+    pub description: Option<String>,
+    /// JSON schema of the tool's input
+    pub input_schema: serde_json::Value,
+}
+
+/// Information about an MCP resource from a specific server
+#[derive(Debug, Clone)]
+pub struct MCPResourceInfo {
+    /// ID of the server this resource belongs to
+    pub server_id: String,
+
+    /// The resource description from rmcp
+    pub resource: rmcp::model::Resource,
+}
+
+/// Information about an MCP prompt from a specific server
+#[derive(Debug, Clone)]
+pub struct MCPPromptInfo {
+    /// ID of the server this prompt belongs to
+    pub server_id: String,
+
+    /// The prompt description from rmcp
+    pub prompt: rmcp::model::Prompt,
+}
+
 /// Manages connections to multiple MCP servers
 pub struct MCPServerManager {
     /// Map of server ID to server instance
     servers: Arc<RwLock<HashMap<String, Arc<MCPServer>>>>,
+
+    /// Maximum time to wait for a single server's tool list
+    tool_discovery_timeout: Duration,
+
+    /// Configs of servers added with `add_server()`, used to filter their tools
+    configs: Arc<RwLock<HashMap<String, MCPServerConfig>>>,
 }
 
 impl MCPServerManager {
@@ -38,9 +84,21 @@ impl MCPServerManager {
     pub fn new() -> Self {
         Self {
             servers: Arc::new(RwLock::new(HashMap::new())),
+            tool_discovery_timeout: DEFAULT_TOOL_DISCOVERY_TIMEOUT,
+            configs: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
+    /// Set the per-server timeout used by `get_all_tools()` (default: 30 seconds)
+    ///
+    /// A server that does not return its tool list within this time is skipped,
+    /// so one unresponsive server cannot stall tool discovery for the rest.
+    /// `list_resources()` and `list_prompts()` use the same timeout.
+    pub fn with_tool_discovery_timeout(mut self, timeout: Duration) -> Self {
+        self.tool_discovery_timeout = timeout;
+        self
+    }
+
     /// Add an MCP server with a service refresher callback
     ///
     /// The refresher is REQUIRED and is called:
@@ -140,6 +198,9 @@ impl MCPServerManager {
     /// Add and connect to a new MCP server from config
     ///
     /// Creates a simple refresher that reconnects to the URI on every refresh.
+    /// A failed connection is retried `reconnect_attempts` times, waiting
+    /// `reconnect_backoff` before the first retry and doubling it each time,
+    /// up to 30 seconds.
     /// For more control (JWT refresh, custom caching), use `add_server_with_refresher()`.
     pub async fn add_server(&self, config: MCPServerConfig) -> Result<()> {
         if !config.enabled {
@@ -152,6 +213,8 @@ impl MCPServerManager {
 
         let id = config.id.clone();
         let uri = config.uri.clone();
+        let retries = config.reconnect_attempts;
+        let backoff = config.reconnect_backoff();
 
         // Check if server already exists
         if self.servers.read().await.contains_key(&id) {
@@ -165,11 +228,13 @@ impl MCPServerManager {
         let last_refresh = Arc::new(RwLock::new(Instant::now() - Duration::from_secs(999)));
 
         let refresher = {
+            let id = id.clone();
             let uri = uri.clone();
             let cached = cached_service.clone();
             let last_refresh = last_refresh.clone();
 
             move || {
+                let id = id.clone();
                 let uri = uri.clone();
                 let cached = cached.clone();
                 let last_refresh = last_refresh.clone();
@@ -188,8 +253,7 @@ impl MCPServerManager {
                     }
 
                     // Create new service
-                    let transport = StreamableHttpClientTransport::from_uri(uri.as_str());
-                    let service = ().serve(transport).await?;
+                    let service = connect_with_retries(&id, &uri, retries, backoff).await?;
 
                     // Cache it (just for tracking the timestamp)
                     // We don't actually return from cache since RunningService doesn't impl Clone
@@ -206,6 +270,7 @@ impl MCPServerManager {
 
         // Add to map
         self.servers.write().await.insert(id.clone(), server);
+        self.configs.write().await.insert(id.clone(), config);
 
         tracing::debug!("[MCPServerManager] Added MCP server '{}'", id);
 
@@ -223,18 +288,40 @@ impl MCPServerManager {
     }
 
     /// Get all tools from all connected servers
+    ///
+    /// Servers are queried concurrently, each bounded by the tool discovery
+    /// timeout. Servers that fail or time out are logged and skipped. For
+    /// servers added with `add_server()`, tools outside the config's
+    /// `enabled_tools` or inside its `disabled_tools` are left out.
     pub async fn get_all_tools(&self) -> Result<Vec<MCPToolInfo>> {
-        let mut all_tools = Vec::new();
+        let servers = self.snapshot_servers().await;
 
-        let servers = self.servers.read().await;
+        let timeout = self.tool_discovery_timeout;
+        let results = join_all(servers.into_iter().map(|(server_id, server)| async move {
+            let result = tokio::time::timeout(timeout, server.list_tools()).await;
+            (server_id, server, result)
+        }))
+        .await;
 
-        for (server_id, server) in servers.iter() {
-            match server.list_tools().await {
-                Ok(tools) => {
+        let mut all_tools = Vec::new();
+        let configs = self.configs.read().await;
+
+        for (server_id, server, result) in results {
+            match result {
+                Ok(Ok(tools)) => {
+                    let offered = tools.len();
+                    let tools: Vec<_> = match configs.get(&server_id) {
+                        Some(config) => tools
+                            .into_iter()
+                            .filter(|tool| config.is_tool_enabled(&tool.name))
+                            .collect(),
+                        None => tools,
+                    };
                     tracing::info!(
-                        "[MCPServerManager] Got {} tools from server '{}'",
-                        tools.len(),
-                        server_id
+                        "[MCPServerManager] Got {} tools from server '{}' ({} enabled)",
+                        offered,
+                        server_id,
+                        tools.len()
                     );
 
                     for tool_def in tools {
@@ -245,7 +332,7 @@ impl MCPServerManager {
                         });
                     }
                 }
-                Err(e) => {
+                Ok(Err(e)) => {
                     tracing::warn!(
                         "[MCPServerManager] Failed to get tools from server '{}': {}",
                         server_id,
@@ -253,12 +340,177 @@ impl MCPServerManager {
                     );
                     // Continue with other servers instead of failing completely
                 }
+                Err(_) => {
+                    tracing::warn!(
+                        "[MCPServerManager] Timed out after {:?} getting tools from server '{}'",
+                        timeout,
+                        server_id
+                    );
+                }
             }
         }
 
         Ok(all_tools)
     }
 
+    /// Snapshot the servers so the lock isn't held across network calls
+    async fn snapshot_servers(&self) -> Vec<(String, Arc<MCPServer>)> {
+        self.servers
+            .read()
+            .await
+            .iter()
+            .map(|(id, server)| (id.clone(), server.clone()))
+            .collect()
+    }
+
+    /// List the tools a server offers without registering them anywhere
+    ///
+    /// Connects to the server if needed and returns each tool's name,
+    /// description and input schema, e.g. to let an operator choose which
+    /// tools to enable. All tools are listed, whatever the server's
+    /// `enabled_tools`/`disabled_tools`. Bounded by the tool discovery timeout.
+    pub async fn describe_server(&self, id: &str) -> Result<Vec<MCPToolDescription>> {
+        let server = self
+            .get_server(id)
+            .await
+            .ok_or_else(|| anyhow!("Server '{}' not found", id))?;
+
+        let tools = tokio::time::timeout(self.tool_discovery_timeout, server.list_tools())
+            .await
+            .map_err(|_| {
+                anyhow!(
+                    "Timed out after {:?} listing tools of server '{}'",
+                    self.tool_discovery_timeout,
+                    id
+                )
+            })??;
+
+        Ok(tools
+            .into_iter()
+            .map(|tool| MCPToolDescription {
+                exposed_name: format!("{}__{}", id, tool.name),
+                name: tool.name.to_string(),
+                description: tool.description.as_ref().map(|d| d.to_string()),
+                input_schema: serde_json::Value::Object(tool.input_schema.as_ref().clone()),
+            })
+            .collect())
+    }
+
+    /// Get all resources from all connected servers
+    ///
+    /// Servers are queried concurrently, each bounded by the tool discovery
+    /// timeout. Servers that fail, time out or don't support resources are
+    /// logged and skipped.
+    pub async fn list_resources(&self) -> Result<Vec<MCPResourceInfo>> {
+        let servers = self.snapshot_servers().await;
+
+        let timeout = self.tool_discovery_timeout;
+        let results = join_all(servers.into_iter().map(|(server_id, server)| async move {
+            let result = tokio::time::timeout(timeout, server.list_resources()).await;
+            (server_id, result)
+        }))
+        .await;
+
+        let mut all_resources = Vec::new();
+        for (server_id, result) in results {
+            match result {
+                Ok(Ok(resources)) => {
+                    all_resources.extend(resources.into_iter().map(|resource| MCPResourceInfo {
+                        server_id: server_id.clone(),
+                        resource,
+                    }));
+                }
+                Ok(Err(e)) => {
+                    tracing::warn!(
+                        "[MCPServerManager] Failed to get resources from server '{}': {}",
+                        server_id,
+                        e
+                    );
+                }
+                Err(_) => {
+                    tracing::warn!(
+                        "[MCPServerManager] Timed out after {:?} getting resources from server '{}'",
+                        timeout,
+                        server_id
+                    );
+                }
+            }
+        }
+
+        Ok(all_resources)
+    }
+
+    /// Read a resource from a specific server
+    pub async fn read_resource(
+        &self,
+        server_id: &str,
+        uri: &str,
+    ) -> Result<rmcp::model::ReadResourceResult> {
+        let server = self
+            .get_server(server_id)
+            .await
+            .ok_or_else(|| anyhow!("Server '{}' not found", server_id))?;
+        server.read_resource(uri).await
+    }
+
+    /// Get all prompts from all connected servers
+    ///
+    /// Servers are queried concurrently, each bounded by the tool discovery
+    /// timeout. Servers that fail, time out or don't support prompts are
+    /// logged and skipped.
+    pub async fn list_prompts(&self) -> Result<Vec<MCPPromptInfo>> {
+        let servers = self.snapshot_servers().await;
+
+        let timeout = self.tool_discovery_timeout;
+        let results = join_all(servers.into_iter().map(|(server_id, server)| async move {
+            let result = tokio::time::timeout(timeout, server.list_prompts()).await;
+            (server_id, result)
+        }))
+        .await;
+
+        let mut all_prompts = Vec::new();
+        for (server_id, result) in results {
+            match result {
+                Ok(Ok(prompts)) => {
+                    all_prompts.extend(prompts.into_iter().map(|prompt| MCPPromptInfo {
+                        server_id: server_id.clone(),
+                        prompt,
+                    }));
+                }
+                Ok(Err(e)) => {
+                    tracing::warn!(
+                        "[MCPServerManager] Failed to get prompts from server '{}': {}",
+                        server_id,
+                        e
+                    );
+                }
+                Err(_) => {
+                    tracing::warn!(
+                        "[MCPServerManager] Timed out after {:?} getting prompts from server '{}'",
+                        timeout,
+                        server_id
+                    );
+                }
+            }
+        }
+
+        Ok(all_prompts)
+    }
+
+    /// Get a prompt from a specific server
+    pub async fn get_prompt(
+        &self,
+        server_id: &str,
+        name: &str,
+        arguments: Option<serde_json::Map<String, serde_json::Value>>,
+    ) -> Result<rmcp::model::GetPromptResult> {
+        let server = self
+            .get_server(server_id)
+            .await
+            .ok_or_else(|| anyhow!("Server '{}' not found", server_id))?;
+        server.get_prompt(name, arguments).await
+    }
+
     /// Run health checks on all servers
     pub async fn health_check_all(&self) -> HashMap<String, Result<()>> {
         let mut results = HashMap::new();
@@ -272,6 +524,32 @@ impl MCPServerManager {
         results
     }
 
+    /// Disconnect from a server and forget it, along with its config
+    ///
+    /// Returns `false` if no server has this ID.
+    pub async fn remove_server(&self, id: &str) -> bool {
+        let server = self.servers.write().await.remove(id);
+        self.configs.write().await.remove(id);
+        match server {
+            Some(server) => {
+                server.disconnect().await;
+                tracing::debug!("[MCPServerManager] Removed MCP server '{}'", id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Close the connections to all servers
+    ///
+    /// Servers stay registered and reconnect on their next operation.
+    pub async fn disconnect_all(&self) {
+        let servers: Vec<Arc<MCPServer>> = self.servers.read().await.values().cloned().collect();
+        for server in servers {
+            server.disconnect().await;
+        }
+    }
+
     /// Force reconnect a specific server by clearing its cache
     ///
     /// This will cause the next operation to trigger the refresher.
@@ -306,6 +584,48 @@ impl MCPServerManager {
     }
 }
 
+/// Connect to an MCP server over streamable HTTP, retrying failed attempts
+///
+/// Waits `backoff` before the first retry and doubles it for each further
+/// one, up to `MAX_RECONNECT_BACKOFF`.
+async fn connect_with_retries(
+    id: &str,
+    uri: &str,
+    retries: u32,
+    backoff: Duration,
+) -> Result<RunningService<RoleClient, ()>> {
+    let mut delay = backoff;
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let transport = StreamableHttpClientTransport::from_uri(uri);
+        match ().serve(transport).await {
+            Ok(service) => return Ok(service),
+            Err(e) if attempt > retries => {
+                return Err(anyhow!(
+                    "Failed to connect to MCP server '{}' at {} after {} attempt(s): {}",
+                    id,
+                    uri,
+                    attempt,
+                    e
+                ));
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "[MCPServerManager] Connection to '{}' failed (attempt {} of {}): {}, retrying in {:?}",
+                    id,
+                    attempt,
+                    retries + 1,
+                    e,
+                    delay
+                );
+                tokio::time::sleep(delay).await;
+                delay = delay.saturating_mul(2).min(MAX_RECONNECT_BACKOFF);
+            }
+        }
+    }
+}
+
 impl Default for MCPServerManager {
     fn default() -> Self {
         Self::new()
@@ -328,4 +648,69 @@ mod tests {
         let manager = MCPServerManager::new();
         assert!(manager.server_ids().await.is_empty());
     }
+
+    #[tokio::test]
+    async fn test_connect_retries_then_reports_error() {
+        // Nothing listens on port 9 locally, so every attempt is refused
+        let error = tokio::time::timeout(
+            Duration::from_secs(10),
+            connect_with_retries("local", "http://127.0.0.1:9/mcp", 2, Duration::from_millis(1)),
+        )
+        .await
+        .expect("retries should finish")
+        .unwrap_err()
+        .to_string();
+
+        assert!(error.starts_with("Failed to connect to MCP server 'local' at http://127.0.0.1:9/mcp after 3 attempt(s)"));
+    }
+
+    #[tokio::test]
+    async fn test_describe_server_errors() {
+        let manager = MCPServerManager::new();
+        manager
+            .add_server_with_refresher("down", || async {
+                Err::<Option<RunningService<RoleClient, ()>>, _>(anyhow!("connection refused"))
+            })
+            .await
+            .unwrap();
+
+        let error = manager.describe_server("missing").await.unwrap_err();
+        assert_eq!(error.to_string(), "Server 'missing' not found");
+        let error = manager.describe_server("down").await.unwrap_err();
+        assert!(format!("{:#}", error).contains("connection refused"));
+    }
+
+    #[tokio::test]
+    async fn test_remove_server() {
+        let manager = MCPServerManager::new();
+        manager
+            .add_server(MCPServerConfig::new("local", "http://127.0.0.1:9/mcp"))
+            .await
+            .unwrap();
+        assert!(manager.configs.read().await.contains_key("local"));
+
+        assert!(manager.remove_server("local").await);
+        assert!(manager.is_empty().await);
+        assert!(manager.configs.read().await.is_empty());
+        assert!(!manager.remove_server("local").await);
+    }
+
+    #[tokio::test]
+    async fn test_get_all_tools_skips_unresponsive_server() {
+        let manager = MCPServerManager::new().with_tool_discovery_timeout(Duration::from_millis(50));
+
+        // Refresher never completes, simulating a hung server
+        manager
+            .add_server_with_refresher("hung", || async {
+                std::future::pending::<Result<Option<RunningService<RoleClient, ()>>>>().await
+            })
+            .await
+            .unwrap();
+
+        let tools = tokio::time::timeout(Duration::from_secs(5), manager.get_all_tools())
+            .await
+            .expect("get_all_tools should not hang")
+            .unwrap();
+        assert!(tools.is_empty());
+    }
 }
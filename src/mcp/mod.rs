@@ -9,6 +9,7 @@
 //! - `MCPServerManager`: Manages multiple MCP servers
 //! - `MCPToolAdapter`: Adapts MCP tools to implement the Tool trait
 //! - `MCPToolProvider`: Implements ToolProvider to expose MCP tools to the registry
+//! - `MCPResourceInjection`: Injects an MCP resource into the conversation as context
 //!
 //! # Usage
 //!
@@ -121,6 +122,7 @@
 //! - Exposed name: `filesystem__read_file`
 
 mod config;
+mod context;
 mod manager;
 mod provider;
 mod server;
@@ -128,7 +130,10 @@ mod tool_adapter;
 
 // Public exports
 pub use config::{MCPConfig, MCPServerConfig};
-pub use manager::{MCPServerManager, MCPToolInfo};
+pub use context::{prompt_to_text, resource_to_text, MCPResourceInjection};
+pub use manager::{
+    MCPPromptInfo, MCPResourceInfo, MCPServerManager, MCPToolDescription, MCPToolInfo,
+};
 pub use provider::MCPToolProvider;
 pub use server::{service_refresher, MCPServer, ServiceRefreshFuture, ServiceRefresher};
 pub use tool_adapter::MCPToolAdapter;
@@ -3,7 +3,10 @@
 //! Wraps rmcp service to manage connections to individual MCP servers
 
 use anyhow::{anyhow, Result};
-use rmcp::model::{CallToolRequestParams, CallToolResult, Tool};
+use rmcp::model::{
+    CallToolRequestParams, CallToolResult, GetPromptRequestParams, GetPromptResult, Prompt,
+    ReadResourceRequestParams, ReadResourceResult, Resource, Tool,
+};
 use rmcp::service::RunningService;
 use rmcp::RoleClient;
 use serde_json::{Map, Value};
@@ -12,6 +15,9 @@ use std::pin::Pin;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+/// Timeout for resource and prompt requests
+const RESOURCE_REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
 /// Type alias for service refresher callback future
 pub type ServiceRefreshFuture =
     Pin<Box<dyn Future<Output = Result<Option<RunningService<RoleClient, ()>>>> + Send>>;
@@ -517,6 +523,93 @@ impl MCPServer {
         Ok(result)
     }
 
+    /// List all resources exposed by this server
+    pub async fn list_resources(&self) -> Result<Vec<Resource>> {
+        self.ensure_service_valid().await?;
+        let service_guard = self.service.read().await;
+        let service = service_guard
+            .as_ref()
+            .ok_or_else(|| anyhow!("MCP server '{}' is not connected", self.id))?;
+
+        tracing::info!("[MCPServer] Listing resources on '{}'", self.id);
+        tokio::time::timeout(RESOURCE_REQUEST_TIMEOUT, service.list_all_resources())
+            .await
+            .map_err(|_| anyhow!("Timeout listing resources from '{}'", self.id))?
+            .map_err(Into::into)
+    }
+
+    /// Read a resource by URI
+    pub async fn read_resource(&self, uri: &str) -> Result<ReadResourceResult> {
+        self.ensure_service_valid().await?;
+        let service_guard = self.service.read().await;
+        let service = service_guard
+            .as_ref()
+            .ok_or_else(|| anyhow!("MCP server '{}' is not connected", self.id))?;
+
+        tracing::info!("[MCPServer] Reading resource '{}' from '{}'", uri, self.id);
+        let read_future = service.read_resource(ReadResourceRequestParams {
+            meta: None,
+            uri: uri.to_string(),
+        });
+        tokio::time::timeout(RESOURCE_REQUEST_TIMEOUT, read_future)
+            .await
+            .map_err(|_| anyhow!("Timeout reading resource '{}' from '{}'", uri, self.id))?
+            .map_err(Into::into)
+    }
+
+    /// List all prompts exposed by this server
+    pub async fn list_prompts(&self) -> Result<Vec<Prompt>> {
+        self.ensure_service_valid().await?;
+        let service_guard = self.service.read().await;
+        let service = service_guard
+            .as_ref()
+            .ok_or_else(|| anyhow!("MCP server '{}' is not connected", self.id))?;
+
+        tracing::info!("[MCPServer] Listing prompts on '{}'", self.id);
+        tokio::time::timeout(RESOURCE_REQUEST_TIMEOUT, service.list_all_prompts())
+            .await
+            .map_err(|_| anyhow!("Timeout listing prompts from '{}'", self.id))?
+            .map_err(Into::into)
+    }
+
+    /// Get a prompt by name, filling in its arguments
+    pub async fn get_prompt(
+        &self,
+        name: &str,
+        arguments: Option<Map<String, Value>>,
+    ) -> Result<GetPromptResult> {
+        self.ensure_service_valid().await?;
+        let service_guard = self.service.read().await;
+        let service = service_guard
+            .as_ref()
+            .ok_or_else(|| anyhow!("MCP server '{}' is not connected", self.id))?;
+
+        tracing::info!("[MCPServer] Getting prompt '{}' from '{}'", name, self.id);
+        let prompt_future = service.get_prompt(GetPromptRequestParams {
+            meta: None,
+            name: name.to_string(),
+            arguments,
+        });
+        tokio::time::timeout(RESOURCE_REQUEST_TIMEOUT, prompt_future)
+            .await
+            .map_err(|_| anyhow!("Timeout getting prompt '{}' from '{}'", name, self.id))?
+            .map_err(Into::into)
+    }
+
+    /// Close the connection, if any
+    ///
+    /// The next operation calls the refresher to connect again, so the
+    /// refresher must return a new service when it has none cached.
+    pub async fn disconnect(&self) {
+        let service = self.service.write().await.take();
+        if let Some(service) = service {
+            if let Err(e) = service.cancel().await {
+                tracing::warn!("[MCPServer] Error closing connection to '{}': {}", self.id, e);
+            }
+            tracing::info!("[MCPServer] Disconnected from '{}'", self.id);
+        }
+    }
+
     /// Health check - try to list tools to verify connection
     pub async fn health_check(&self) -> Result<()> {
         self.list_tools().await?;
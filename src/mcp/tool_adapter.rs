@@ -29,6 +29,9 @@ pub struct MCPToolAdapter {
 
     /// Tool definition converted to framework format
     tool_definition: ToolDefinition,
+
+    /// Whether the server marks the tool as read-only
+    read_only: bool,
 }
 
 impl MCPToolAdapter {
@@ -50,6 +53,11 @@ impl MCPToolAdapter {
             tool_name: rmcp_tool.name.to_string(),
             exposed_name,
             tool_definition,
+            read_only: rmcp_tool
+                .annotations
+                .as_ref()
+                .and_then(|annotations| annotations.read_only_hint)
+                .unwrap_or(false),
         }
     }
 
@@ -192,6 +200,10 @@ impl Tool for MCPToolAdapter {
         // All MCP tools require permission by default
         true
     }
+
+    fn is_read_only(&self) -> bool {
+        self.read_only
+    }
 }
 
 #[cfg(test)]
@@ -4,9 +4,13 @@
 //! - Global: Shared across all agents (Arc<RwLock<>>)
 //! - Local: Agent-type specific rules
 //! - Session: Rules added during current session
+//!
+//! Session rules can also be time-limited ("allow Bash for 5 minutes"); they
+//! stop matching once they expire.
 
 use serde::{Deserialize, Serialize};
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
 
 /// Type of permission rule
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -209,6 +213,8 @@ pub struct PermissionManager {
     local: Vec<PermissionRule>,
     /// Rules added during this session
     session: Vec<PermissionRule>,
+    /// Session rules with the time they expire; pruned on check
+    timed: Mutex<Vec<(PermissionRule, Instant)>>,
     /// Whether we can prompt the user (false for background agents)
     interactive: bool,
     /// Agent type (for loading/saving local rules)
@@ -222,6 +228,7 @@ impl PermissionManager {
             global,
             local: Vec::new(),
             session: Vec::new(),
+            timed: Mutex::new(Vec::new()),
             interactive: true,
             agent_type: agent_type.into(),
         }
@@ -237,6 +244,7 @@ impl PermissionManager {
             global,
             local: local_rules,
             session: Vec::new(),
+            timed: Mutex::new(Vec::new()),
             interactive: true,
             agent_type: agent_type.into(),
         }
@@ -257,6 +265,16 @@ impl PermissionManager {
             return CheckResult::Allowed;
         }
 
+        // Then time-limited session rules, dropping the expired ones
+        {
+            let mut timed = self.timed.lock().unwrap();
+            let now = Instant::now();
+            timed.retain(|(_, expires_at)| *expires_at > now);
+            if timed.iter().any(|(r, _)| r.matches(tool_name, input)) {
+                return CheckResult::Allowed;
+            }
+        }
+
         // Check local (agent-type) rules
         if self.local.iter().any(|r| r.matches(tool_name, input)) {
             return CheckResult::Allowed;
@@ -301,6 +319,30 @@ impl PermissionManager {
         }
     }
 
+    /// Add a session rule that stops matching after `duration`
+    ///
+    /// Once it expires, matching actions go back to asking the user. A
+    /// duration too long to represent (e.g. `Duration::MAX`) never expires,
+    /// like a rule added with `PermissionScope::Session`.
+    pub fn add_rule_for(&mut self, rule: PermissionRule, duration: Duration) {
+        let Some(expires_at) = Instant::now().checked_add(duration) else {
+            self.add_rule(rule, PermissionScope::Session);
+            return;
+        };
+        tracing::info!(
+            "Adding session permission rule: {:?} for {} (expires in {:?})",
+            rule.rule_type,
+            rule.tool_name,
+            duration
+        );
+        self.timed.get_mut().unwrap().push((rule, expires_at));
+    }
+
+    /// Allow a whole tool for `duration` (e.g. "allow Bash for 5 minutes")
+    pub fn always_allow_tool_for(&mut self, tool_name: impl Into<String>, duration: Duration) {
+        self.add_rule_for(PermissionRule::allow_tool(tool_name), duration);
+    }
+
     /// Process a permission decision
     ///
     /// If the decision is AlwaysAllow, creates and stores a rule.
@@ -353,9 +395,17 @@ impl PermissionManager {
         &self.agent_type
     }
 
-    /// Clear session rules
+    /// Get the time-limited session rules that have not expired, with their expiry
+    pub fn timed_rules(&self) -> Vec<(PermissionRule, Instant)> {
+        let now = Instant::now();
+        let timed = self.timed.lock().unwrap();
+        timed.iter().filter(|(_, expires_at)| *expires_at > now).cloned().collect()
+    }
+
+    /// Clear session rules, including time-limited ones
     pub fn clear_session_rules(&mut self) {
         self.session.clear();
+        self.timed.get_mut().unwrap().clear();
     }
 
     /// Check if running in interactive mode
@@ -461,6 +511,28 @@ mod tests {
         assert_eq!(manager2.check("Bash", "echo hi"), CheckResult::Allowed);
     }
 
+    #[test]
+    fn test_rule_expires() {
+        let global = Arc::new(GlobalPermissions::new());
+        let mut manager = PermissionManager::new(global, "test-agent");
+
+        manager.always_allow_tool_for("Bash", Duration::from_secs(300));
+        manager.add_rule_for(PermissionRule::allow_prefix("Write", "notes"), Duration::ZERO);
+
+        assert_eq!(manager.check("Bash", "cargo test"), CheckResult::Allowed);
+        assert_eq!(manager.check("Write", "notes.md"), CheckResult::AskUser);
+        assert_eq!(manager.timed_rules().len(), 1);
+        assert_eq!(manager.timed.lock().unwrap().len(), 1);
+
+        // "Forever" doesn't overflow; it just never expires
+        manager.add_rule_for(PermissionRule::allow_tool("Read"), Duration::MAX);
+        assert_eq!(manager.check("Read", "src/main.rs"), CheckResult::Allowed);
+        assert_eq!(manager.timed_rules().len(), 1);
+
+        manager.clear_session_rules();
+        assert_eq!(manager.check("Bash", "cargo test"), CheckResult::AskUser);
+    }
+
     #[test]
     fn test_global_shared_across_managers() {
         let global = Arc::new(GlobalPermissions::new());
@@ -3,6 +3,22 @@
 //! Agents communicate via two channel types:
 //! - **Input channel** (mpsc): Single-producer, single-consumer for sending commands to the agent
 //! - **Output channel** (broadcast): Multi-consumer for streaming output to multiple subscribers
+//!
+//! # Backpressure
+//!
+//! The output channel is bounded, but by default it never makes the agent
+//! wait: streaming from the provider continues at full speed. When a subscriber
+//! falls more than the channel capacity behind, the oldest chunks are dropped
+//! for that subscriber and its next `recv()` returns `RecvError::Lagged(n)`
+//! with the number of chunks it missed. Subscribers should treat `Lagged` as a
+//! gap and keep receiving; only `RecvError::Closed` means the agent is gone.
+//!
+//! Raise the capacity with `AgentRuntime::with_output_buffer` when consumers
+//! are slow (e.g. rendering to a remote client) and losing deltas matters.
+//!
+//! Alternatively, `OutputBackpressure::Wait` makes the agent stop reading the
+//! provider stream while the buffer is full, so nothing is dropped and the
+//! provider is slowed down to the pace of the slowest subscriber instead.
 
 use tokio::sync::{broadcast, mpsc};
 
@@ -14,6 +30,18 @@ pub const INPUT_CHANNEL_SIZE: usize = 32;
 /// Default buffer size for output broadcast channel
 pub const OUTPUT_CHANNEL_SIZE: usize = 256;
 
+/// What happens when a subscriber falls a full output buffer behind
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputBackpressure {
+    /// Keep streaming; the subscriber loses the oldest chunks (default)
+    #[default]
+    DropOldest,
+    /// Stop reading the provider stream until every subscriber has caught up
+    ///
+    /// Every subscriber must keep receiving, or the agent stalls.
+    Wait,
+}
+
 // ============================================================================
 // Channel Type Aliases
 // ============================================================================
@@ -47,7 +75,18 @@ pub fn create_input_channel() -> (InputSender, InputReceiver) {
 /// Returns the sender. Receivers are created by calling `sender.subscribe()`.
 /// Multiple subscribers can receive the same output chunks.
 pub fn create_output_channel() -> OutputSender {
-    let (tx, _) = broadcast::channel(OUTPUT_CHANNEL_SIZE);
+    create_output_channel_with_capacity(OUTPUT_CHANNEL_SIZE)
+}
+
+/// Create a new output broadcast channel holding up to `capacity` chunks
+///
+/// Subscribers more than `capacity` chunks behind lose the oldest ones.
+///
+/// # Panics
+///
+/// Panics if `capacity` is 0.
+pub fn create_output_channel_with_capacity(capacity: usize) -> OutputSender {
+    let (tx, _) = broadcast::channel(capacity);
     tx
 }
 
@@ -60,6 +99,15 @@ pub fn create_agent_channels() -> (InputSender, InputReceiver, OutputSender) {
     (input_tx, input_rx, output_tx)
 }
 
+/// Create both input and output channels, with an output buffer of `output_capacity` chunks
+pub fn create_agent_channels_with_capacity(
+    output_capacity: usize,
+) -> (InputSender, InputReceiver, OutputSender) {
+    let (input_tx, input_rx) = create_input_channel();
+    let output_tx = create_output_channel_with_capacity(output_capacity);
+    (input_tx, input_rx, output_tx)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -178,6 +226,25 @@ mod tests {
         assert!(matches!(chunk, OutputChunk::TextDelta(s) if s == "Late"));
     }
 
+    #[tokio::test]
+    async fn test_slow_subscriber_lags() {
+        let tx = create_output_channel_with_capacity(2);
+        let mut rx = tx.subscribe();
+
+        // The sender never waits for the subscriber
+        for text in ["One", "Two", "Three"] {
+            tx.send(OutputChunk::TextDelta(text.into())).unwrap();
+        }
+
+        // The oldest chunk was dropped and reported as a gap
+        assert!(matches!(
+            rx.recv().await,
+            Err(broadcast::error::RecvError::Lagged(1))
+        ));
+        let chunk = rx.recv().await.unwrap();
+        assert!(matches!(chunk, OutputChunk::TextDelta(s) if s == "Two"));
+    }
+
     #[tokio::test]
     async fn test_send_without_subscribers() {
         let tx = create_output_channel();
@@ -8,9 +8,9 @@
 //! - Request interrupt or shutdown
 
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
 
-use crate::core::{AgentState, FrameworkError, FrameworkResult, InputMessage};
+use crate::core::{AgentState, FrameworkError, FrameworkResult, InputMessage, OutputChunk};
 use crate::session::AgentSession;
 use crate::tools::ToolResult;
 
@@ -163,6 +163,45 @@ impl AgentHandle {
         self.output_tx.subscribe()
     }
 
+    /// Forward this agent's output to `parent`'s subscribers
+    ///
+    /// Each chunk reaches the parent wrapped in `OutputChunk::SubAgentOutput`,
+    /// so a subagent that forwards its own children yields nested wrappers.
+    /// Text and thinking deltas are not forwarded; their `*Complete` chunks
+    /// are, so a chatty subagent can't flood the parent's channel and make
+    /// its subscribers lag. The task ends when either agent's output channel
+    /// closes.
+    ///
+    /// Only chunks sent after this call are forwarded.
+    pub fn forward_output_to(&self, parent: &AgentHandle) -> tokio::task::JoinHandle<()> {
+        let mut rx = self.subscribe();
+        // Weak, so forwarding doesn't keep the parent's channel open
+        let parent_tx = parent.output_tx.downgrade();
+        let session_id = self.session_id.clone();
+
+        tokio::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(OutputChunk::TextDelta(_) | OutputChunk::ThinkingDelta(_)) => {}
+                    Ok(chunk) => {
+                        let Some(parent_tx) = parent_tx.upgrade() else {
+                            break;
+                        };
+                        // No subscribers on the parent is not an error
+                        let _ = parent_tx.send(OutputChunk::SubAgentOutput {
+                            session_id: session_id.clone(),
+                            chunk: Box::new(chunk),
+                        });
+                    }
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        tracing::warn!("[AgentHandle] Skipped {} output chunks from subagent {}", missed, session_id);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
     /// Get the number of current subscribers
     pub fn subscriber_count(&self) -> usize {
         self.output_tx.receiver_count()
@@ -241,6 +280,11 @@ impl AgentHandle {
         Ok(())
     }
 
+    /// Write the session to disk
+    pub async fn save_session(&self) -> FrameworkResult<()> {
+        self.session.write().await.save()
+    }
+
     /// Get custom metadata from the session
     pub async fn get_custom_metadata(&self, key: &str) -> Option<serde_json::Value> {
         let session = self.session.read().await;
@@ -299,6 +343,35 @@ impl AgentHandle {
         session.set_conversation_name(name)?;
         Ok(())
     }
+
+    /// Get the current system prompt
+    pub async fn system_prompt(&self) -> String {
+        let session = self.session.read().await;
+        session.system_prompt().to_string()
+    }
+
+    /// Replace the system prompt
+    ///
+    /// Takes effect from the next LLM call and is written to `system_prompt.md`,
+    /// so a session resumed later with `AgentSession::load()` keeps using it.
+    ///
+    /// For an agent configured with `with_system_blocks()` or
+    /// `with_system_template()`, the prompt set here is kept until the blocks
+    /// or the rendered template change; from then on the agent uses those again.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// handle.set_system_prompt("You are now reviewing code. Be terse.").await?;
+    /// ```
+    pub async fn set_system_prompt(&self, prompt: impl Into<String>) -> FrameworkResult<()> {
+        let mut session = self.session.write().await;
+        session.update_system_prompt(prompt)?;
+        session.set_custom("system_prompt_overridden", true);
+        session.save()?;
+        tracing::info!("[AgentHandle] System prompt updated for '{}'", self.session_id);
+        Ok(())
+    }
 }
 
 impl std::fmt::Debug for AgentHandle {
@@ -393,6 +466,34 @@ mod tests {
         assert!(matches!(chunk2, OutputChunk::TextDelta(s) if s == "Hi"));
     }
 
+    #[tokio::test]
+    async fn test_forward_output_to() {
+        let (parent, _parent_rx, _parent_temp) = create_test_handle();
+        let (child, _child_rx, _child_temp) = create_test_handle();
+        let mut sub = parent.subscribe();
+
+        let forwarding = child.forward_output_to(&parent);
+        child.output_tx.send(OutputChunk::TextDelta("Hi".into())).unwrap();
+        child.output_tx.send(OutputChunk::TextComplete("Hi".into())).unwrap();
+
+        // Deltas are skipped; the complete text arrives
+        match sub.recv().await.unwrap() {
+            OutputChunk::SubAgentOutput { session_id, chunk } => {
+                assert_eq!(session_id, "test-session");
+                assert!(matches!(*chunk, OutputChunk::TextComplete(s) if s == "Hi"));
+            }
+            other => panic!("Expected SubAgentOutput, got {:?}", other),
+        }
+
+        // Forwarding stops once the parent is gone
+        drop(parent);
+        child.output_tx.send(OutputChunk::Done).unwrap();
+        tokio::time::timeout(std::time::Duration::from_secs(1), forwarding)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
     #[tokio::test]
     async fn test_state() {
         let temp_dir = TempDir::new().unwrap();
@@ -450,6 +551,19 @@ mod tests {
         ));
     }
 
+    #[tokio::test]
+    async fn test_set_system_prompt() {
+        let (handle, _rx, temp) = create_test_handle();
+
+        handle.set_system_prompt("You are terse.").await.unwrap();
+        assert_eq!(handle.system_prompt().await, "You are terse.");
+
+        // Persisted for resumed sessions
+        let storage = SessionStorage::with_dir(temp.path());
+        let resumed = AgentSession::load_with_storage("test-session", storage).unwrap();
+        assert_eq!(resumed.system_prompt(), "You are terse.");
+    }
+
     #[tokio::test]
     async fn test_clone() {
         let (handle1, mut rx, _temp) = create_test_handle();
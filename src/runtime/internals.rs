@@ -8,16 +8,20 @@
 //! - Check and manage permissions
 
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 
 use std::collections::HashMap;
 
-use crate::core::{AgentContext, AgentState, FrameworkError, FrameworkResult, InputMessage, OutputChunk};
+use crate::core::{AgentContext, AgentState, FrameworkError, FrameworkResult, InputMessage, OutputChunk, TurnOutcome};
 use crate::core::output::UserQuestion;
 use crate::permissions::{CheckResult, PermissionManager, PermissionRule, PermissionScope};
 use crate::session::AgentSession;
 
-use super::channels::{InputReceiver, OutputSender};
+use super::channels::{InputReceiver, OutputBackpressure, OutputSender, OUTPUT_CHANNEL_SIZE};
+
+/// How often `wait_for_output_capacity` checks whether subscribers caught up
+const OUTPUT_CAPACITY_POLL_INTERVAL: Duration = Duration::from_millis(5);
 
 /// Internal state and channels for an agent
 ///
@@ -42,6 +46,12 @@ pub struct AgentInternals {
 
     /// Current agent state (shared with AgentHandle)
     state: Arc<RwLock<AgentState>>,
+
+    /// What to do when subscribers fall behind
+    output_backpressure: OutputBackpressure,
+
+    /// Capacity of the output channel
+    output_capacity: usize,
 }
 
 impl AgentInternals {
@@ -63,9 +73,18 @@ impl AgentInternals {
             input_rx,
             output_tx,
             state,
+            output_backpressure: OutputBackpressure::default(),
+            output_capacity: OUTPUT_CHANNEL_SIZE,
         }
     }
 
+    /// Set what happens when subscribers fall behind an output channel of `capacity` chunks
+    pub fn with_output_backpressure(mut self, backpressure: OutputBackpressure, capacity: usize) -> Self {
+        self.output_backpressure = backpressure;
+        self.output_capacity = capacity;
+        self
+    }
+
     // =========================================================================
     // Input Methods
     // =========================================================================
@@ -105,6 +124,28 @@ impl AgentInternals {
         self.output_tx.send(chunk).unwrap_or(0)
     }
 
+    /// Wait until every subscriber has room for more output
+    ///
+    /// Returns at once unless the backpressure mode is
+    /// `OutputBackpressure::Wait`. Agents call this before reading more of
+    /// the provider stream.
+    ///
+    /// Subscribers read the broadcast channel directly, so nothing signals
+    /// when they catch up; the buffer is polled every
+    /// `OUTPUT_CAPACITY_POLL_INTERVAL` instead. This only costs anything while
+    /// the buffer is full: a timer wake-up per interval, and up to one interval
+    /// of extra delay once space frees up.
+    pub async fn wait_for_output_capacity(&self) {
+        if self.output_backpressure != OutputBackpressure::Wait {
+            return;
+        }
+        // Chunks not yet seen by every subscriber; sending into a full
+        // buffer would overwrite the oldest of them
+        while self.output_tx.len() >= self.output_capacity && self.output_tx.receiver_count() > 0 {
+            tokio::time::sleep(OUTPUT_CAPACITY_POLL_INTERVAL).await;
+        }
+    }
+
     /// Send a text delta
     pub fn send_text(&self, text: impl Into<String>) -> usize {
         self.send(OutputChunk::TextDelta(text.into()))
@@ -135,6 +176,11 @@ impl AgentInternals {
         self.send(OutputChunk::Error(error.into()))
     }
 
+    /// Send the outcome of the turn that just finished
+    pub fn send_turn_complete(&self, outcome: TurnOutcome) -> usize {
+        self.send(OutputChunk::TurnComplete(outcome))
+    }
+
     /// Send done signal
     pub fn send_done(&self) -> usize {
         self.send(OutputChunk::Done)
@@ -273,6 +319,24 @@ impl AgentInternals {
         &self.context.agent_type
     }
 
+    /// Get the project directory recorded for this session
+    pub fn working_dir(&self) -> Option<&str> {
+        self.context.working_dir.as_deref()
+    }
+
+    /// Get the directory tools should resolve relative paths against
+    ///
+    /// Uses the session's recorded working directory, falling back to the
+    /// process's current directory.
+    pub fn default_base_dir(&self) -> String {
+        match self.working_dir() {
+            Some(dir) => dir.to_string(),
+            None => std::env::current_dir()
+                .map(|dir| dir.to_string_lossy().to_string())
+                .unwrap_or_else(|_| ".".to_string()),
+        }
+    }
+
     /// Increment the turn counter
     pub fn next_turn(&mut self) {
         self.context.next_turn();
@@ -577,6 +641,39 @@ impl std::fmt::Debug for AgentInternals {
     }
 }
 
+/// Internals for a session stored in `dir`, with its output receiver and input sender
+///
+/// An existing session in `dir` is loaded, so a test can restart an agent on
+/// the same history. Shared by the tests of modules that need internals.
+#[cfg(test)]
+pub(crate) fn test_internals(
+    dir: &std::path::Path,
+) -> (AgentInternals, super::OutputReceiver, super::InputSender) {
+    use crate::permissions::GlobalPermissions;
+    use crate::session::SessionStorage;
+
+    let (input_tx, input_rx, output_tx) = super::channels::create_agent_channels();
+    let output_rx = output_tx.subscribe();
+    let storage = SessionStorage::with_dir(dir);
+    let session = if AgentSession::exists_with_storage("test-session", &storage) {
+        AgentSession::load_with_storage("test-session", storage)
+    } else {
+        AgentSession::new_with_storage("test-session", "test-agent", "Test Agent", "A test agent", "", storage)
+    }
+    .unwrap();
+    let context = AgentContext::new("test-session", "test-agent", "Test Agent", "A test agent");
+    let permissions = PermissionManager::new(Arc::new(GlobalPermissions::new()), "test-agent");
+    let internals = AgentInternals::new(
+        Arc::new(RwLock::new(session)),
+        context,
+        permissions,
+        input_rx,
+        output_tx,
+        Arc::new(RwLock::new(AgentState::Idle)),
+    );
+    (internals, output_rx, input_tx)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -618,6 +715,23 @@ mod tests {
         (internals, input_tx, output_rx)
     }
 
+    #[tokio::test]
+    async fn test_wait_for_output_capacity() {
+        let (internals, _input_tx, mut output_rx) = create_test_internals();
+        let wait = || tokio::time::timeout(std::time::Duration::from_millis(50), internals.wait_for_output_capacity());
+
+        internals.send_status("one");
+        internals.send_status("two");
+        // Dropping the oldest (the default) never waits
+        assert!(wait().await.is_ok());
+
+        let internals = internals.with_output_backpressure(OutputBackpressure::Wait, 2);
+        let wait = || tokio::time::timeout(std::time::Duration::from_millis(50), internals.wait_for_output_capacity());
+        assert!(wait().await.is_err());
+        output_rx.recv().await.unwrap();
+        assert!(wait().await.is_ok());
+    }
+
     #[tokio::test]
     async fn test_receive() {
         let (mut internals, input_tx, _output_rx) = create_test_internals();
@@ -631,6 +745,19 @@ mod tests {
         assert!(matches!(msg, InputMessage::UserInput(s) if s == "Hello"));
     }
 
+    #[test]
+    fn test_default_base_dir() {
+        let (mut internals, _input_tx, _output_rx) = create_test_internals();
+        assert_eq!(
+            internals.default_base_dir(),
+            std::env::current_dir().unwrap().to_string_lossy()
+        );
+
+        internals.context.working_dir = Some("/home/user/project".into());
+        assert_eq!(internals.working_dir(), Some("/home/user/project"));
+        assert_eq!(internals.default_base_dir(), "/home/user/project");
+    }
+
     #[tokio::test]
     async fn test_send() {
         let (internals, _input_tx, mut output_rx) = create_test_internals();
@@ -15,8 +15,8 @@ pub mod internals;
 pub mod runtime;
 pub mod subagent_manager;
 
-pub use channels::{InputReceiver, InputSender, OutputReceiver, OutputSender};
+pub use channels::{InputReceiver, InputSender, OutputBackpressure, OutputReceiver, OutputSender};
 pub use handle::AgentHandle;
 pub use internals::AgentInternals;
-pub use runtime::AgentRuntime;
+pub use runtime::{AgentRuntime, DEFAULT_MAX_SUBAGENT_DEPTH};
 pub use subagent_manager::{CompletedSubAgent, SubAgentManager};
@@ -16,11 +16,14 @@ use crate::core::{AgentContext, AgentState, FrameworkError, FrameworkResult};
 use crate::permissions::{GlobalPermissions, PermissionManager, PermissionRule};
 use crate::session::AgentSession;
 
-use super::channels::create_agent_channels;
+use super::channels::{create_agent_channels_with_capacity, OutputBackpressure, OUTPUT_CHANNEL_SIZE};
 use super::handle::AgentHandle;
 use super::internals::AgentInternals;
 use super::subagent_manager::SubAgentManager;
 
+/// Default for `AgentRuntime::with_max_subagent_depth`
+pub const DEFAULT_MAX_SUBAGENT_DEPTH: u32 = 5;
+
 /// Runtime for spawning and managing agents
 ///
 /// The runtime maintains a registry of running agents and provides
@@ -34,6 +37,14 @@ pub struct AgentRuntime {
     agents: Arc<RwLock<HashMap<String, AgentHandle>>>,
     /// Shared global permissions for all agents
     global_permissions: Arc<GlobalPermissions>,
+    /// Output chunks buffered per agent before slow subscribers start lagging
+    output_buffer: usize,
+    /// Deepest subagent `spawn_subagent` will create
+    max_subagent_depth: u32,
+    /// Whether `spawn_subagent` forwards a subagent's output to its parent
+    forward_subagent_output: bool,
+    /// What agents do when a subscriber falls a full buffer behind
+    output_backpressure: OutputBackpressure,
 }
 
 impl AgentRuntime {
@@ -42,6 +53,10 @@ impl AgentRuntime {
         Self {
             agents: Arc::new(RwLock::new(HashMap::new())),
             global_permissions: Arc::new(GlobalPermissions::new()),
+            output_buffer: OUTPUT_CHANNEL_SIZE,
+            max_subagent_depth: DEFAULT_MAX_SUBAGENT_DEPTH,
+            forward_subagent_output: true,
+            output_backpressure: OutputBackpressure::default(),
         }
     }
 
@@ -50,9 +65,73 @@ impl AgentRuntime {
         Self {
             agents: Arc::new(RwLock::new(HashMap::new())),
             global_permissions: Arc::new(GlobalPermissions::with_rules(rules)),
+            output_buffer: OUTPUT_CHANNEL_SIZE,
+            max_subagent_depth: DEFAULT_MAX_SUBAGENT_DEPTH,
+            forward_subagent_output: true,
+            output_backpressure: OutputBackpressure::default(),
         }
     }
 
+    /// Set how many output chunks each spawned agent buffers (default 256)
+    ///
+    /// With the default `OutputBackpressure::DropOldest`, the agent never waits
+    /// for subscribers: one that falls more than this many chunks behind loses
+    /// the oldest ones and receives `RecvError::Lagged`. Raise it for slow
+    /// consumers such as remote clients. With `OutputBackpressure::Wait` the
+    /// agent waits for buffer space instead; see
+    /// [`with_output_backpressure`](Self::with_output_backpressure).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is 0.
+    pub fn with_output_buffer(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "output buffer capacity must be greater than 0");
+        self.output_buffer = capacity;
+        self
+    }
+
+    /// Output chunks buffered per spawned agent
+    pub fn output_buffer(&self) -> usize {
+        self.output_buffer
+    }
+
+    /// Set what spawned agents do when a subscriber falls a full buffer behind
+    ///
+    /// With the default `OutputBackpressure::DropOldest`, the subscriber loses
+    /// the oldest chunks. With `OutputBackpressure::Wait`, the agent stops
+    /// reading the provider stream until every subscriber has caught up, so
+    /// nothing is lost but a subscriber that stops receiving stalls the agent.
+    /// While waiting, the agent checks the buffer every few milliseconds.
+    pub fn with_output_backpressure(mut self, backpressure: OutputBackpressure) -> Self {
+        self.output_backpressure = backpressure;
+        self
+    }
+
+    /// Refuse to spawn subagents nested deeper than `depth` (default 5)
+    ///
+    /// A root session's subagents are at depth 1, theirs at depth 2, and so on.
+    /// Guards against agents that keep delegating to new subagents.
+    pub fn with_max_subagent_depth(mut self, depth: u32) -> Self {
+        self.max_subagent_depth = depth;
+        self
+    }
+
+    /// Deepest subagent `spawn_subagent` will create
+    pub fn max_subagent_depth(&self) -> u32 {
+        self.max_subagent_depth
+    }
+
+    /// Set whether subagents' output reaches their parent's subscribers (default: true)
+    ///
+    /// When on, `spawn_subagent` forwards a subagent's output to its running
+    /// parent as `OutputChunk::SubAgentOutput`, so a UI subscribed to the
+    /// parent can show the subagent's progress. Text and thinking deltas are
+    /// left out (see `AgentHandle::forward_output_to`).
+    pub fn with_forward_subagent_output(mut self, forward: bool) -> Self {
+        self.forward_subagent_output = forward;
+        self
+    }
+
     /// Get a reference to the global permissions
     ///
     /// This can be used to add rules that apply to all agents.
@@ -102,6 +181,24 @@ impl AgentRuntime {
         local_rules: Vec<PermissionRule>,
         agent_fn: F,
     ) -> AgentHandle
+    where
+        F: FnOnce(AgentInternals) -> Fut + Send + 'static,
+        Fut: Future<Output = FrameworkResult<()>> + Send + 'static,
+    {
+        self.spawn_inner(session, local_rules, None, agent_fn).await
+    }
+
+    /// Spawn an agent, forwarding its output to `forward_to` if given
+    ///
+    /// Forwarding is attached before the agent task starts, so none of its
+    /// output is missed.
+    async fn spawn_inner<F, Fut>(
+        &self,
+        session: AgentSession,
+        local_rules: Vec<PermissionRule>,
+        forward_to: Option<AgentHandle>,
+        agent_fn: F,
+    ) -> AgentHandle
     where
         F: FnOnce(AgentInternals) -> Fut + Send + 'static,
         Fut: Future<Output = FrameworkResult<()>> + Send + 'static,
@@ -113,20 +210,30 @@ impl AgentRuntime {
         let session = Arc::new(RwLock::new(session));
 
         // Create channels
-        let (input_tx, input_rx, output_tx) = create_agent_channels();
+        let (input_tx, input_rx, output_tx) = create_agent_channels_with_capacity(self.output_buffer);
 
         // Create shared state
         let state = Arc::new(RwLock::new(AgentState::Idle));
 
+        // Record the working directory on first run so resumed sessions keep using it
+        let mut session_write = session.write().await;
+        if session_write.working_dir().is_none() {
+            if let Ok(cwd) = std::env::current_dir() {
+                if let Err(e) = session_write.set_working_dir(cwd.to_string_lossy()) {
+                    tracing::warn!(session_id = %session_id, error = %e, "Failed to record working directory");
+                }
+            }
+        }
+
         // Create context from session
-        let session_read = session.read().await;
         let mut context = AgentContext::new(
-            session_read.session_id(),
-            session_read.agent_type(),
-            session_read.name(),
-            session_read.description(),
+            session_write.session_id(),
+            session_write.agent_type(),
+            session_write.name(),
+            session_write.description(),
         );
-        drop(session_read); // Release the lock
+        context.working_dir = session_write.working_dir().map(String::from);
+        drop(session_write); // Release the lock
 
         // Add SubAgentManager to context for tracking spawned subagents
         context.insert_resource(SubAgentManager::new());
@@ -149,7 +256,8 @@ impl AgentRuntime {
             input_rx,
             output_tx.clone(),
             state.clone(),
-        );
+        )
+        .with_output_backpressure(self.output_backpressure, self.output_buffer);
 
         // Create handle for external use
         let handle = AgentHandle::new(
@@ -166,6 +274,10 @@ impl AgentRuntime {
             agents.insert(session_id.clone(), handle.clone());
         }
 
+        if let Some(parent) = forward_to {
+            handle.forward_output_to(&parent);
+        }
+
         // Spawn the agent task
         let agents_ref = self.agents.clone();
         let session_id_clone = session_id.clone();
@@ -192,6 +304,12 @@ impl AgentRuntime {
     /// Spawn a subagent
     ///
     /// Similar to `spawn`, but creates a subagent session linked to a parent.
+    /// Fails with `FrameworkError::SubagentNotAllowed`, before anything is
+    /// saved, if the subagent would be deeper than `max_subagent_depth` or
+    /// `session_id` is one of the parent's ancestors.
+    ///
+    /// If the parent is running in this runtime, the subagent's output is
+    /// forwarded to it (see `with_forward_subagent_output`).
     pub async fn spawn_subagent<F, Fut>(
         &self,
         session_id: impl Into<String>,
@@ -207,17 +325,38 @@ impl AgentRuntime {
         F: FnOnce(AgentInternals) -> Fut + Send + 'static,
         Fut: Future<Output = FrameworkResult<()>> + Send + 'static,
     {
+        let session_id = session_id.into();
+        let parent_session_id = parent_session_id.into();
+        let depth = AgentSession::subagent_depth(&session_id, &parent_session_id)?;
+        if depth > self.max_subagent_depth {
+            tracing::warn!(
+                "[AgentRuntime] Refusing to spawn subagent {} at depth {} (max {})",
+                session_id,
+                depth,
+                self.max_subagent_depth
+            );
+            return Err(FrameworkError::SubagentNotAllowed(format!(
+                "{} would be at depth {}, deeper than the maximum of {}",
+                session_id, depth, self.max_subagent_depth
+            )));
+        }
+
         let session = AgentSession::new_subagent(
             session_id,
             agent_type,
             name,
             description,
             system_prompt,
-            parent_session_id,
+            &parent_session_id,
             parent_tool_use_id,
         )?;
 
-        Ok(self.spawn(session, agent_fn).await)
+        let parent = if self.forward_subagent_output {
+            self.get(&parent_session_id).await
+        } else {
+            None
+        };
+        Ok(self.spawn_inner(session, Vec::new(), parent, agent_fn).await)
     }
 
     /// Get a handle to a running agent
@@ -544,6 +683,64 @@ mod tests {
         assert!(handle.is_done().await);
     }
 
+    #[tokio::test]
+    async fn test_max_subagent_depth() {
+        let runtime = AgentRuntime::new().with_max_subagent_depth(0);
+        assert_eq!(AgentRuntime::new().max_subagent_depth(), DEFAULT_MAX_SUBAGENT_DEPTH);
+
+        let result = runtime
+            .spawn_subagent("depth-child", "worker", "Worker", "", "", "depth-parent", "tool_1", |_| async {
+                Ok(())
+            })
+            .await;
+        assert!(matches!(result, Err(FrameworkError::SubagentNotAllowed(_))));
+        assert_eq!(runtime.count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_forward_subagent_output() {
+        let runtime = AgentRuntime::new();
+        let (parent_session, _parent_temp) = create_test_session("forward-parent");
+        let (child_session, _child_temp) = create_test_session("forward-child");
+
+        let parent = runtime
+            .spawn(parent_session, |mut internals| async move {
+                while !matches!(internals.receive().await, Some(InputMessage::Shutdown) | None) {}
+                Ok(())
+            })
+            .await;
+        let mut rx = parent.subscribe();
+
+        // A child that finishes at once still has all its output forwarded
+        let child = runtime
+            .spawn_inner(child_session, Vec::new(), Some(parent.clone()), |internals| async move {
+                internals.send_text("partial");
+                internals.send(OutputChunk::TextComplete("Finished".into()));
+                internals.send_done();
+                Ok(())
+            })
+            .await;
+
+        let mut forwarded = Vec::new();
+        while forwarded.len() < 2 {
+            let chunk = tokio::time::timeout(std::time::Duration::from_secs(1), rx.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            match chunk {
+                OutputChunk::SubAgentOutput { session_id, chunk } => {
+                    assert_eq!(session_id, child.session_id());
+                    forwarded.push(*chunk);
+                }
+                other => panic!("Expected SubAgentOutput, got {:?}", other),
+            }
+        }
+        assert!(matches!(&forwarded[0], OutputChunk::TextComplete(text) if text == "Finished"));
+        assert!(matches!(forwarded[1], OutputChunk::Done));
+
+        runtime.shutdown_all().await;
+    }
+
     #[tokio::test]
     async fn test_clone_runtime() {
         let runtime1 = AgentRuntime::new();
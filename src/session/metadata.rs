@@ -28,6 +28,16 @@ pub struct SessionMetadata {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub conversation_name: Option<String>,
 
+    /// Short summary of what happened in the session
+    /// Typically set when the session is finished, e.g. for handoff or browsing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+
+    /// Project directory the session works in
+    /// Recorded when the session is first run so resumed sessions use the same directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<String>,
+
     // --- Lineage ---
     /// Parent session ID (if this is a subagent)
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -41,6 +51,10 @@ pub struct SessionMetadata {
     #[serde(default)]
     pub child_session_ids: Vec<String>,
 
+    /// Number of ancestors (0 for a root session, 1 for its subagents, ...)
+    #[serde(default)]
+    pub depth: u32,
+
     // --- LLM Configuration ---
     /// Model being used
     pub model: String,
@@ -76,9 +90,12 @@ impl SessionMetadata {
             name: name.into(),
             description: description.into(),
             conversation_name: None,
+            summary: None,
+            working_dir: None,
             parent_session_id: None,
             parent_tool_use_id: None,
             child_session_ids: Vec::new(),
+            depth: 0,
             model: String::new(),
             provider: String::new(),
             created_at: now,
@@ -103,9 +120,12 @@ impl SessionMetadata {
             name: name.into(),
             description: description.into(),
             conversation_name: None,
+            summary: None,
+            working_dir: None,
             parent_session_id: Some(parent_session_id.into()),
             parent_tool_use_id: Some(parent_tool_use_id.into()),
             child_session_ids: Vec::new(),
+            depth: 1,
             model: String::new(),
             provider: String::new(),
             created_at: now,
@@ -161,6 +181,28 @@ impl SessionMetadata {
         self.conversation_name.is_some()
     }
 
+    /// Set the session summary
+    pub fn set_summary(&mut self, summary: impl Into<String>) {
+        self.summary = Some(summary.into());
+        self.touch();
+    }
+
+    /// Get the session summary
+    pub fn summary(&self) -> Option<&str> {
+        self.summary.as_deref()
+    }
+
+    /// Set the working directory
+    pub fn set_working_dir(&mut self, dir: impl Into<String>) {
+        self.working_dir = Some(dir.into());
+        self.touch();
+    }
+
+    /// Get the working directory
+    pub fn working_dir(&self) -> Option<&str> {
+        self.working_dir.as_deref()
+    }
+
     /// Set custom metadata
     pub fn set_custom(&mut self, key: impl Into<String>, value: impl Into<Value>) {
         self.custom.insert(key.into(), value.into());
@@ -267,6 +309,19 @@ mod tests {
         assert_eq!(loaded.conversation_name(), Some("Debugging session"));
     }
 
+    #[test]
+    fn test_working_dir_serialization() {
+        let meta = SessionMetadata::new("session", "test", "Test", "Testing");
+        let json = serde_json::to_string(&meta).unwrap();
+        assert!(!json.contains("working_dir"));
+
+        let mut meta = meta;
+        meta.set_working_dir("/home/user/project");
+        let json = serde_json::to_string(&meta).unwrap();
+        let loaded: SessionMetadata = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.working_dir(), Some("/home/user/project"));
+    }
+
     #[test]
     fn test_conversation_name_skipped_when_none() {
         let meta = SessionMetadata::new("session", "test", "Test", "Testing");
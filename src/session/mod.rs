@@ -9,7 +9,10 @@
 pub mod metadata;
 pub mod session;
 pub mod storage;
+mod tool_calls;
+mod transcript;
 
 pub use metadata::SessionMetadata;
 pub use session::AgentSession;
-pub use storage::SessionStorage;
+pub use storage::{FlushPolicy, SessionStorage};
+pub use tool_calls::ToolCallRecord;
@@ -3,11 +3,16 @@
 //! The `AgentSession` struct combines metadata and message history,
 //! providing a complete view of an agent's conversation state.
 
-use crate::core::FrameworkResult;
-use crate::llm::Message;
+use chrono::Utc;
+
+use crate::core::{FrameworkError, FrameworkResult};
+use crate::llm::{ContentBlock, Message, MessageContent, MessageRequest, SystemPrompt, ToolDefinition};
 
 use super::metadata::SessionMetadata;
-use super::storage::SessionStorage;
+use super::storage::{SessionStorage, MAX_LINEAGE_DEPTH};
+
+/// `max_tokens` used in exported requests (the providers' default)
+const EXPORT_MAX_TOKENS: u32 = 32000;
 
 /// An agent session that tracks conversation history and metadata
 ///
@@ -95,7 +100,7 @@ impl AgentSession {
         let parent_id = parent_session_id.into();
         let system_prompt = system_prompt.into();
 
-        let metadata = SessionMetadata::new_subagent(
+        let mut metadata = SessionMetadata::new_subagent(
             session_id,
             agent_type,
             name,
@@ -103,16 +108,18 @@ impl AgentSession {
             &parent_id,
             parent_tool_use_id,
         );
+        metadata.depth = Self::subagent_depth_with_storage(&metadata.session_id, &parent_id, &storage)?;
 
-        storage.save_metadata(&metadata)?;
-        storage.save_system_prompt(&metadata.session_id, &system_prompt)?;
-
-        // Update parent to track this child
+        // Update parent to track this child; the child works in the parent's directory
         if let Ok(mut parent_meta) = storage.load_metadata(&parent_id) {
+            metadata.working_dir = parent_meta.working_dir.clone();
             parent_meta.add_child(&metadata.session_id);
             storage.save_metadata(&parent_meta)?;
         }
 
+        storage.save_metadata(&metadata)?;
+        storage.save_system_prompt(&metadata.session_id, &system_prompt)?;
+
         Ok(Self {
             metadata,
             messages: Vec::new(),
@@ -135,7 +142,7 @@ impl AgentSession {
         let parent_id = parent_session_id.into();
         let system_prompt = system_prompt.into();
 
-        let metadata = SessionMetadata::new_subagent(
+        let mut metadata = SessionMetadata::new_subagent(
             session_id,
             agent_type,
             name,
@@ -143,16 +150,18 @@ impl AgentSession {
             &parent_id,
             parent_tool_use_id,
         );
+        metadata.depth = Self::subagent_depth_with_storage(&metadata.session_id, &parent_id, &storage)?;
 
-        storage.save_metadata(&metadata)?;
-        storage.save_system_prompt(&metadata.session_id, &system_prompt)?;
-
-        // Update parent to track this child
+        // Update parent to track this child; the child works in the parent's directory
         if let Ok(mut parent_meta) = storage.load_metadata(&parent_id) {
+            metadata.working_dir = parent_meta.working_dir.clone();
             parent_meta.add_child(&metadata.session_id);
             storage.save_metadata(&parent_meta)?;
         }
 
+        storage.save_metadata(&metadata)?;
+        storage.save_system_prompt(&metadata.session_id, &system_prompt)?;
+
         Ok(Self {
             metadata,
             messages: Vec::new(),
@@ -161,6 +170,59 @@ impl AgentSession {
         })
     }
 
+    /// Depth a new subagent `session_id` of `parent_session_id` would have
+    ///
+    /// One more than the parent's depth in the default storage. Fails if
+    /// `session_id` is already among the ancestors, which would make the
+    /// session its own ancestor.
+    pub fn subagent_depth(session_id: &str, parent_session_id: &str) -> FrameworkResult<u32> {
+        Self::subagent_depth_with_storage(session_id, parent_session_id, &SessionStorage::new())
+    }
+
+    /// Depth a new subagent would have, using the parent's metadata in `storage`
+    ///
+    /// The lineage is only walked when `session_id` is already saved, since
+    /// a new session cannot be an ancestor of its parent.
+    pub fn subagent_depth_with_storage(
+        session_id: &str,
+        parent_session_id: &str,
+        storage: &SessionStorage,
+    ) -> FrameworkResult<u32> {
+        if session_id == parent_session_id || storage.session_exists(session_id) {
+            Self::check_not_ancestor(session_id, parent_session_id, storage)?;
+        }
+        // Parents that were never saved are treated as root sessions
+        Ok(storage
+            .load_metadata(parent_session_id)
+            .map_or(1, |parent| parent.depth + 1))
+    }
+
+    /// Fail if `session_id` is `parent_session_id` or one of its ancestors
+    fn check_not_ancestor(session_id: &str, parent_session_id: &str, storage: &SessionStorage) -> FrameworkResult<()> {
+        let mut ancestor = parent_session_id.to_string();
+        for _ in 0..MAX_LINEAGE_DEPTH {
+            if ancestor == session_id {
+                return Err(FrameworkError::SubagentNotAllowed(format!(
+                    "session {} would be its own ancestor",
+                    session_id
+                )));
+            }
+            // Sessions that were never saved end the lineage
+            let Some(parent) = storage
+                .load_metadata(&ancestor)
+                .ok()
+                .and_then(|metadata| metadata.parent_session_id)
+            else {
+                return Ok(());
+            };
+            ancestor = parent;
+        }
+        Err(FrameworkError::SubagentNotAllowed(format!(
+            "lineage of {} is longer than {} sessions; it may contain a cycle",
+            parent_session_id, MAX_LINEAGE_DEPTH
+        )))
+    }
+
     /// Load an existing session from storage
     pub fn load(session_id: &str) -> FrameworkResult<Self> {
         let storage = SessionStorage::new();
@@ -218,6 +280,11 @@ impl AgentSession {
         self.metadata.is_subagent()
     }
 
+    /// Number of ancestors (0 for a root session)
+    pub fn depth(&self) -> u32 {
+        self.metadata.depth
+    }
+
     /// Get the parent session ID (if this is a subagent)
     pub fn parent_session_id(&self) -> Option<&str> {
         self.metadata.parent_session_id.as_deref()
@@ -230,8 +297,10 @@ impl AgentSession {
 
     /// Add a message to the conversation history
     ///
-    /// The message is immediately persisted to disk.
-    pub fn add_message(&mut self, message: Message) -> FrameworkResult<()> {
+    /// The message is immediately persisted to disk, stamped with the
+    /// current time unless it already has a timestamp.
+    pub fn add_message(&mut self, mut message: Message) -> FrameworkResult<()> {
+        message.timestamp.get_or_insert_with(Utc::now);
         self.storage
             .append_message(&self.metadata.session_id, &message)?;
         self.messages.push(message);
@@ -253,6 +322,47 @@ impl AgentSession {
         &mut self.messages
     }
 
+    /// Get the assistant response that was being streamed when the process stopped
+    ///
+    /// Only present if streaming checkpoints were enabled and the turn never
+    /// completed. Use [`recover_interrupted_response`](Self::recover_interrupted_response)
+    /// or [`discard_interrupted_response`](Self::discard_interrupted_response) to resolve it.
+    pub fn interrupted_response(&self) -> FrameworkResult<Option<Message>> {
+        self.storage.load_partial_response(&self.metadata.session_id)
+    }
+
+    /// Add the text of an interrupted response to history and remove the checkpoint
+    ///
+    /// Only text blocks are kept: tool calls would have no results and thinking
+    /// blocks may lack a valid signature. Returns `true` if anything was recovered.
+    pub fn recover_interrupted_response(&mut self) -> FrameworkResult<bool> {
+        let Some(partial) = self.interrupted_response()? else {
+            return Ok(false);
+        };
+
+        let text_blocks: Vec<ContentBlock> = match partial.content {
+            MessageContent::Text(text) => vec![ContentBlock::text(text)],
+            MessageContent::Blocks(blocks) => blocks
+                .into_iter()
+                .filter(
+                    |block| matches!(block, ContentBlock::Text { text, .. } if !text.is_empty()),
+                )
+                .collect(),
+        };
+
+        let recovered = !text_blocks.is_empty();
+        if recovered {
+            self.add_message(Message::assistant_with_blocks(text_blocks))?;
+        }
+        self.discard_interrupted_response()?;
+        Ok(recovered)
+    }
+
+    /// Remove the checkpoint of an interrupted response without recovering it
+    pub fn discard_interrupted_response(&self) -> FrameworkResult<()> {
+        self.storage.clear_partial_response(&self.metadata.session_id)
+    }
+
     /// Save the entire session (metadata and messages)
     ///
     /// This overwrites the existing history file.
@@ -264,6 +374,78 @@ impl AgentSession {
         Ok(())
     }
 
+    /// Write history buffered by the storage to disk
+    ///
+    /// Only needed with `SessionStorage::with_buffered_writes`; `save()` also
+    /// writes everything.
+    pub fn flush(&self) -> FrameworkResult<()> {
+        self.storage.flush(&self.metadata.session_id)
+    }
+
+    /// The request a provider would receive for the current history
+    ///
+    /// Uses the recorded model, the system prompt and `tools`. Prompt caching
+    /// markers and context injections are not included.
+    pub fn to_message_request(&self, tools: &[ToolDefinition]) -> MessageRequest {
+        MessageRequest {
+            model: self.metadata.model.clone(),
+            max_tokens: EXPORT_MAX_TOKENS,
+            messages: self.messages.clone(),
+            system: (!self.system_prompt.is_empty()).then(|| SystemPrompt::Text(self.system_prompt.clone())),
+            tools: (!tools.is_empty()).then(|| tools.to_vec()),
+            tool_choice: None,
+            thinking: None,
+            temperature: None,
+            stream: None,
+            metadata: None,
+        }
+    }
+
+    /// The current history as an Anthropic Messages API request body
+    ///
+    /// For sharing a reproducible payload with provider support or pasting
+    /// into a playground.
+    pub fn to_anthropic_request_json(&self, tools: &[ToolDefinition]) -> serde_json::Value {
+        serde_json::to_value(self.to_message_request(tools)).unwrap_or_default()
+    }
+
+    /// The current history as an OpenAI Responses API request body
+    ///
+    /// Translated the same way `OpenAIProvider` translates its requests.
+    pub fn to_openai_request_json(&self, tools: &[ToolDefinition]) -> serde_json::Value {
+        crate::llm::openai::to_request_json(&self.to_message_request(tools))
+    }
+
+    /// Append a conversation in OpenAI Chat Completions format (`{role, content}`)
+    ///
+    /// Converted with `openai::from_chat_messages`. A system message in the
+    /// log replaces the session's system prompt. Returns the number of
+    /// messages added to the history.
+    ///
+    /// Fails without changing the session if the log has a tool call without
+    /// a result, or if its first message has the same role as the last
+    /// message already in the history, since turns must alternate.
+    pub fn import_openai_messages(&mut self, messages: Vec<serde_json::Value>) -> FrameworkResult<usize> {
+        let (system, history) = crate::llm::openai::from_chat_messages(&messages)
+            .map_err(|e| FrameworkError::other(format!("Failed to import OpenAI messages: {:#}", e)))?;
+        if let (Some(last), Some(first)) = (self.messages.last(), history.first()) {
+            if last.role == first.role {
+                return Err(FrameworkError::other(format!(
+                    "Failed to import OpenAI messages: the history already ends with a message from the {}",
+                    last.role
+                )));
+            }
+        }
+        if let Some(system) = system {
+            self.update_system_prompt(system)?;
+        }
+        let count = history.len();
+        for message in history {
+            self.add_message(message)?;
+        }
+        Ok(count)
+    }
+
     /// Reload the session from storage
     ///
     /// This discards any unsaved changes and reloads from disk.
@@ -329,6 +511,36 @@ impl AgentSession {
         self.metadata.has_conversation_name()
     }
 
+    /// Set the summary of this session
+    ///
+    /// See `StandardAgent::summarize_session` for generating one. The summary
+    /// is persisted to disk immediately.
+    pub fn set_summary(&mut self, summary: impl Into<String>) -> FrameworkResult<()> {
+        self.metadata.set_summary(summary);
+        self.storage.save_metadata(&self.metadata)?;
+        Ok(())
+    }
+
+    /// Get the session summary
+    pub fn summary(&self) -> Option<&str> {
+        self.metadata.summary()
+    }
+
+    /// Set the project directory this session works in
+    ///
+    /// The directory is persisted to disk immediately so resumed sessions
+    /// resolve relative paths against it.
+    pub fn set_working_dir(&mut self, dir: impl Into<String>) -> FrameworkResult<()> {
+        self.metadata.set_working_dir(dir);
+        self.storage.save_metadata(&self.metadata)?;
+        Ok(())
+    }
+
+    /// Get the recorded working directory
+    pub fn working_dir(&self) -> Option<&str> {
+        self.metadata.working_dir()
+    }
+
     /// Set custom metadata
     pub fn set_custom<T: Into<serde_json::Value>>(&mut self, key: impl Into<String>, value: T) {
         self.metadata.set_custom(key, value);
@@ -493,6 +705,51 @@ mod tests {
             .contains(&"sub_session".to_string()));
     }
 
+    #[test]
+    fn test_subagent_depth_and_cycles() {
+        let (storage, _temp) = create_test_storage();
+        let sub = |id: &str, parent: &str| {
+            AgentSession::new_subagent_with_storage(id, "worker", "Worker", "", "", parent, "tool_1", storage.clone())
+        };
+
+        let root = AgentSession::new_with_storage("root", "main", "Main", "", "", storage.clone()).unwrap();
+        assert_eq!(root.depth(), 0);
+        assert_eq!(sub("child", "root").unwrap().depth(), 1);
+        assert_eq!(sub("grandchild", "child").unwrap().depth(), 2);
+
+        let error = sub("root", "grandchild").unwrap_err();
+        assert!(matches!(error, FrameworkError::SubagentNotAllowed(_)));
+        assert!(sub("loop", "loop").is_err());
+        assert_eq!(AgentSession::get_metadata_with_storage("grandchild", &storage).unwrap().depth, 2);
+
+        // The depth comes from the parent's metadata, not the lineage
+        let mut child = storage.load_metadata("child").unwrap();
+        child.depth = 7;
+        storage.save_metadata(&child).unwrap();
+        assert_eq!(sub("sibling", "child").unwrap().depth(), 8);
+    }
+
+    #[test]
+    fn test_legacy_subagent_depth() {
+        let (storage, _temp) = create_test_storage();
+        AgentSession::new_with_storage("root", "main", "Main", "", "", storage.clone()).unwrap();
+        let mut metadata = SessionMetadata::new_subagent("child", "worker", "Worker", "", "root", "tool_1");
+        storage.save_metadata(&metadata).unwrap();
+        metadata = SessionMetadata::new_subagent("grandchild", "worker", "Worker", "", "child", "tool_2");
+        storage.save_metadata(&metadata).unwrap();
+
+        // Saved before depths were recorded: no depth field
+        for id in ["child", "grandchild"] {
+            let path = storage.metadata_path(id);
+            let mut json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+            json.as_object_mut().unwrap().remove("depth");
+            std::fs::write(&path, json.to_string()).unwrap();
+        }
+
+        assert_eq!(storage.load_metadata("child").unwrap().depth, 1);
+        assert_eq!(storage.load_metadata("grandchild").unwrap().depth, 2);
+    }
+
     #[test]
     fn test_add_and_get_messages() {
         let (storage, _temp) = create_test_storage();
@@ -512,6 +769,44 @@ mod tests {
         assert_eq!(reloaded.history().len(), 2);
     }
 
+    #[test]
+    fn test_recover_interrupted_response() {
+        let (storage, _temp) = create_test_storage();
+
+        let mut session = AgentSession::new_with_storage(
+            "partial_session",
+            "coder",
+            "Test",
+            "Testing",
+            "Test system prompt.",
+            storage.clone(),
+        )
+        .unwrap();
+        session
+            .add_message(Message::user("Write a long answer"))
+            .unwrap();
+
+        let partial = Message::assistant_with_blocks(vec![
+            ContentBlock::text("First half"),
+            ContentBlock::tool_use("tool_1", "Read", serde_json::json!({"file_path": "a.rs"})),
+        ]);
+        storage
+            .save_partial_response("partial_session", &partial)
+            .unwrap();
+
+        // Detected after reload
+        let mut session = AgentSession::load_with_storage("partial_session", storage).unwrap();
+        assert!(session.interrupted_response().unwrap().is_some());
+
+        // Recovery keeps only the text and removes the checkpoint
+        assert!(session.recover_interrupted_response().unwrap());
+        assert_eq!(session.history().len(), 2);
+        let recovered = &session.history()[1];
+        assert!(matches!(&recovered.content, MessageContent::Blocks(b) if b.len() == 1));
+        assert!(session.interrupted_response().unwrap().is_none());
+        assert!(!session.recover_interrupted_response().unwrap());
+    }
+
     #[test]
     fn test_save_and_reload() {
         let (storage, _temp) = create_test_storage();
@@ -598,6 +893,60 @@ mod tests {
         assert_eq!(session.provider(), "anthropic");
     }
 
+    #[test]
+    fn test_request_json_export() {
+        let (storage, _temp) = create_test_storage();
+
+        let mut session =
+            AgentSession::new_with_storage("export_test", "coder", "Test", "Testing", "Be brief.", storage).unwrap();
+        session.set_model("test-model");
+        session.add_message(Message::user("Hello")).unwrap();
+        let tools = vec![crate::llm::define_tool("Echo", "Echo the input", serde_json::json!({}), vec![])];
+
+        let anthropic = session.to_anthropic_request_json(&tools);
+        assert_eq!(anthropic["model"], "test-model");
+        assert_eq!(anthropic["system"], "Be brief.");
+        assert_eq!(anthropic["messages"][0]["content"], "Hello");
+        assert_eq!(anthropic["tools"][0]["name"], "Echo");
+        assert!(anthropic["messages"][0].get("timestamp").is_none());
+
+        let openai = session.to_openai_request_json(&tools);
+        assert_eq!(openai["model"], "test-model");
+        assert_eq!(openai["instructions"], "Be brief.");
+        assert_eq!(openai["input"].as_array().unwrap().len(), 1);
+        assert_eq!(openai["tools"][0]["name"], "Echo");
+    }
+
+    #[test]
+    fn test_import_openai_messages() {
+        let (storage, _temp) = create_test_storage();
+
+        let mut session =
+            AgentSession::new_with_storage("import_test", "coder", "Test", "Testing", "Old prompt.", storage.clone())
+                .unwrap();
+        let added = session
+            .import_openai_messages(vec![
+                serde_json::json!({"role": "system", "content": "Be brief."}),
+                serde_json::json!({"role": "user", "content": "Hello"}),
+                serde_json::json!({"role": "assistant", "content": "Hi!"}),
+            ])
+            .unwrap();
+        assert_eq!(added, 2);
+
+        let error = session
+            .import_openai_messages(vec![
+                serde_json::json!({"role": "system", "content": "Be verbose."}),
+                serde_json::json!({"role": "assistant", "content": "Anything else?"}),
+            ])
+            .unwrap_err();
+        assert!(error.to_string().contains("already ends with a message from the assistant"));
+
+        let loaded = AgentSession::load_with_storage("import_test", storage).unwrap();
+        assert_eq!(loaded.system_prompt(), "Be brief.");
+        assert_eq!(loaded.history().len(), 2);
+        assert_eq!(loaded.history()[1].text(), Some("Hi!"));
+    }
+
     #[test]
     fn test_conversation_name() {
         let (storage, _temp) = create_test_storage();
@@ -1,10 +1,21 @@
 //! Session storage helpers
 //!
 //! Handles reading and writing session data to disk.
+//!
+//! History records are appended to the file one at a time by default. With
+//! `with_buffered_writes`, they are held in memory and written in batches
+//! according to a [`FlushPolicy`]; `flush` writes them immediately, and any
+//! that remain are written when the last clone of the storage is dropped.
 
+use std::collections::HashMap;
 use std::fs::{self, File};
-use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::io::{BufReader, BufWriter, Write};
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 
 use crate::core::FrameworkResult;
 use crate::core::error::FrameworkError;
@@ -15,27 +26,174 @@ use super::metadata::SessionMetadata;
 /// Default directory for session storage
 const SESSIONS_DIR: &str = "sessions";
 
+/// Longest lineage walked through saved metadata, to stop on corrupt cycles
+pub(crate) const MAX_LINEAGE_DEPTH: u32 = 1000;
+
+/// A history record as written: the message plus when it was added
+///
+/// `Message` does not serialize its timestamp because the same type is sent
+/// to providers, so the history file carries it alongside.
+#[derive(Serialize)]
+struct HistoryRecord<'a> {
+    #[serde(flatten)]
+    message: &'a Message,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamp: Option<DateTime<Utc>>,
+}
+
+/// A history record as read; older records have no timestamp
+#[derive(Deserialize)]
+struct StoredRecord {
+    #[serde(flatten)]
+    message: Message,
+    #[serde(default)]
+    timestamp: Option<DateTime<Utc>>,
+}
+
+/// When buffered history records are written to disk
+///
+/// Records are written once `max_messages` are pending for a session, or on
+/// the first append after the oldest pending record is `max_interval` old.
+/// There is no background timer, so call `SessionStorage::flush` when a
+/// session goes quiet (e.g. at the end of a turn).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlushPolicy {
+    /// Pending records per session that trigger a write
+    pub max_messages: usize,
+    /// Age of the oldest pending record that triggers a write
+    pub max_interval: Duration,
+}
+
+impl Default for FlushPolicy {
+    fn default() -> Self {
+        Self {
+            max_messages: 32,
+            max_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Serialized records waiting to be appended to one history file
+#[derive(Debug)]
+struct PendingRecords {
+    data: Vec<u8>,
+    count: usize,
+    since: Instant,
+    /// Whether any storage that buffered these records wants an fsync
+    sync: bool,
+}
+
+impl PendingRecords {
+    /// Append the records to `path`, keeping them pending if the write fails
+    fn write(&mut self, path: &Path) -> std::io::Result<()> {
+        if self.data.is_empty() {
+            return Ok(());
+        }
+        append_to_file(path, &self.data, self.sync)?;
+        self.data.clear();
+        self.count = 0;
+        self.sync = false;
+        Ok(())
+    }
+}
+
+/// Pending records by history file, shared by clones of a storage
+///
+/// Each file has its own lock, held while its records are written, so
+/// concurrent writers to one file keep their records in order.
+#[derive(Debug, Default)]
+struct PendingHistory {
+    files: Mutex<HashMap<PathBuf, Arc<Mutex<PendingRecords>>>>,
+}
+
+impl PendingHistory {
+    /// The pending records for `path`, if any were ever buffered
+    fn get(&self, path: &Path) -> Option<Arc<Mutex<PendingRecords>>> {
+        let files = self.files.lock().unwrap_or_else(|e| e.into_inner());
+        files.get(path).cloned()
+    }
+}
+
+impl Drop for PendingHistory {
+    fn drop(&mut self) {
+        let files = self.files.get_mut().unwrap_or_else(|e| e.into_inner());
+        for (path, pending) in files.drain() {
+            let mut pending = pending.lock().unwrap_or_else(|e| e.into_inner());
+            let count = pending.count;
+            if let Err(e) = pending.write(&path) {
+                tracing::warn!(
+                    "[SessionStorage] Failed to flush {} records to {}: {}",
+                    count,
+                    path.display(),
+                    e
+                );
+            }
+        }
+    }
+}
+
 /// Session storage manager
 #[derive(Debug, Clone)]
 pub struct SessionStorage {
     base_dir: PathBuf,
+    /// Whether history records are written as pretty-printed JSON
+    pretty_history: bool,
+    /// Batch history writes by this policy instead of writing each record
+    flush_policy: Option<FlushPolicy>,
+    /// Whether writes are followed by an fsync
+    sync: bool,
+    pending: Arc<PendingHistory>,
 }
 
 impl SessionStorage {
     /// Create a new session storage with the default directory
     pub fn new() -> Self {
-        Self {
-            base_dir: PathBuf::from(SESSIONS_DIR),
-        }
+        Self::with_dir(SESSIONS_DIR)
     }
 
     /// Create a new session storage with a custom directory
     pub fn with_dir(dir: impl Into<PathBuf>) -> Self {
         Self {
             base_dir: dir.into(),
+            pretty_history: false,
+            flush_policy: None,
+            sync: false,
+            pending: Arc::new(PendingHistory::default()),
         }
     }
 
+    /// Buffer history records and write them in batches
+    ///
+    /// Appended messages are kept in memory until the policy triggers, until
+    /// `flush` is called, or until the last clone of this storage is dropped.
+    /// Loading a session's messages flushes it first. Records still pending
+    /// when the process is killed are lost, so flush at points where losing
+    /// them matters.
+    pub fn with_buffered_writes(mut self, policy: FlushPolicy) -> Self {
+        self.flush_policy = Some(policy);
+        self
+    }
+
+    /// Fsync history and metadata writes for durability
+    ///
+    /// Each write then returns only after the data has reached the disk,
+    /// which is slower but survives a power loss. Default: false.
+    pub fn with_sync(mut self, sync: bool) -> Self {
+        self.sync = sync;
+        self
+    }
+
+    /// Write history as pretty-printed JSON for reading by hand
+    ///
+    /// Each message becomes an indented, multi-line JSON object instead of a
+    /// single line. Loading reads either format, so existing sessions keep
+    /// working and the setting can be changed at any time. Default: compact
+    /// JSONL, one message per line.
+    pub fn with_pretty_history(mut self, pretty: bool) -> Self {
+        self.pretty_history = pretty;
+        self
+    }
+
     /// Get the directory path for a session
     pub fn session_dir(&self, session_id: &str) -> PathBuf {
         self.base_dir.join(session_id)
@@ -56,6 +214,11 @@ impl SessionStorage {
         self.session_dir(session_id).join("system_prompt.md")
     }
 
+    /// Get the in-progress response checkpoint path for a session
+    pub fn partial_response_path(&self, session_id: &str) -> PathBuf {
+        self.session_dir(session_id).join("partial_response.json")
+    }
+
     /// Save the system prompt to disk
     pub fn save_system_prompt(&self, session_id: &str, prompt: &str) -> FrameworkResult<()> {
         self.ensure_session_dir(session_id)?;
@@ -90,8 +253,12 @@ impl SessionStorage {
         let path = self.metadata_path(&metadata.session_id);
 
         let file = File::create(&path)?;
-        let writer = BufWriter::new(file);
-        serde_json::to_writer_pretty(writer, metadata)?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut writer, metadata)?;
+        writer.flush()?;
+        if self.sync {
+            writer.get_ref().sync_all()?;
+        }
 
         Ok(())
     }
@@ -106,29 +273,150 @@ impl SessionStorage {
 
         let file = File::open(&path)?;
         let reader = BufReader::new(file);
-        let metadata: SessionMetadata = serde_json::from_reader(reader)?;
+        let mut metadata: SessionMetadata = serde_json::from_reader(reader)?;
+
+        // Subagents saved before depths were recorded load with depth 0
+        if metadata.depth == 0 && metadata.is_subagent() {
+            metadata.depth = self.lineage_depth(&metadata);
+        }
 
         Ok(metadata)
     }
 
+    /// Count the ancestors of a session saved without a depth
+    ///
+    /// Stops at the first ancestor that has a depth, is a root session or
+    /// was never saved.
+    fn lineage_depth(&self, metadata: &SessionMetadata) -> u32 {
+        let mut depth = 0;
+        let mut parent = metadata.parent_session_id.clone();
+        while let Some(id) = parent {
+            depth += 1;
+            let Ok(file) = File::open(self.metadata_path(&id)) else {
+                break;
+            };
+            let Ok(ancestor) = serde_json::from_reader::<_, SessionMetadata>(BufReader::new(file)) else {
+                break;
+            };
+            if ancestor.depth > 0 || depth >= MAX_LINEAGE_DEPTH {
+                return depth + ancestor.depth;
+            }
+            parent = ancestor.parent_session_id;
+        }
+        depth
+    }
+
     /// Append a message to the history file
+    ///
+    /// With buffered writes, the message may only be written on a later
+    /// append or `flush`.
     pub fn append_message(&self, session_id: &str, message: &Message) -> FrameworkResult<()> {
         self.ensure_session_dir(session_id)?;
         let path = self.history_path(session_id);
 
-        let mut file = fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&path)?;
+        let mut data = Vec::new();
+        self.write_message(&mut data, message)?;
+
+        let Some(policy) = self.flush_policy else {
+            append_to_file(&path, &data, self.sync)?;
+            return Ok(());
+        };
+
+        let pending = {
+            let mut files = self.pending.files.lock().unwrap_or_else(|e| e.into_inner());
+            files
+                .entry(path.clone())
+                .or_insert_with(|| {
+                    Arc::new(Mutex::new(PendingRecords {
+                        data: Vec::new(),
+                        count: 0,
+                        since: Instant::now(),
+                        sync: false,
+                    }))
+                })
+                .clone()
+        };
+        let mut pending = pending.lock().unwrap_or_else(|e| e.into_inner());
+        if pending.count == 0 {
+            pending.since = Instant::now();
+        }
+        pending.data.extend_from_slice(&data);
+        pending.count += 1;
+        pending.sync |= self.sync;
+
+        if pending.count >= policy.max_messages || pending.since.elapsed() >= policy.max_interval {
+            pending.write(&path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Write a session's buffered history records to disk
+    ///
+    /// Does nothing when nothing is pending or writes are not buffered. If the
+    /// write fails, the records stay pending for the next attempt.
+    pub fn flush(&self, session_id: &str) -> FrameworkResult<()> {
+        let path = self.history_path(session_id);
+        if let Some(pending) = self.pending.get(&path) {
+            pending.lock().unwrap_or_else(|e| e.into_inner()).write(&path)?;
+        }
+        Ok(())
+    }
+
+    /// Write the buffered history records of every session to disk
+    ///
+    /// Every file is attempted even if an earlier one fails; the error lists
+    /// each failure, and the records of those files stay pending.
+    pub fn flush_all(&self) -> FrameworkResult<()> {
+        let pending: Vec<(PathBuf, Arc<Mutex<PendingRecords>>)> = {
+            let files = self.pending.files.lock().unwrap_or_else(|e| e.into_inner());
+            files.iter().map(|(path, records)| (path.clone(), records.clone())).collect()
+        };
+        let mut errors = Vec::new();
+        for (path, records) in pending {
+            if let Err(e) = records.lock().unwrap_or_else(|e| e.into_inner()).write(&path) {
+                errors.push(format!("{}: {}", path.display(), e));
+            }
+        }
+        if !errors.is_empty() {
+            return Err(FrameworkError::Other(format!(
+                "Failed to flush session history: {}",
+                errors.join("; ")
+            )));
+        }
+        Ok(())
+    }
 
-        let json = serde_json::to_string(message)?;
-        writeln!(file, "{}", json)?;
+    /// Forget a session's buffered records (its history is being replaced)
+    fn discard_pending(&self, session_id: &str) {
+        let path = self.history_path(session_id);
+        if let Some(pending) = self.pending.get(&path) {
+            let mut pending = pending.lock().unwrap_or_else(|e| e.into_inner());
+            pending.data.clear();
+            pending.count = 0;
+        }
+    }
 
+    /// Write one history record in the configured format
+    fn write_message(&self, writer: &mut impl Write, message: &Message) -> FrameworkResult<()> {
+        let record = HistoryRecord {
+            message,
+            timestamp: message.timestamp,
+        };
+        let json = if self.pretty_history {
+            serde_json::to_string_pretty(&record)?
+        } else {
+            serde_json::to_string(&record)?
+        };
+        writeln!(writer, "{}", json)?;
         Ok(())
     }
 
     /// Load all messages from the history file
+    ///
+    /// Reads compact and pretty-printed records alike.
     pub fn load_messages(&self, session_id: &str) -> FrameworkResult<Vec<Message>> {
+        self.flush(session_id)?;
         let path = self.history_path(session_id);
 
         if !path.exists() {
@@ -139,12 +427,11 @@ impl SessionStorage {
         let reader = BufReader::new(file);
         let mut messages = Vec::new();
 
-        for line in reader.lines() {
-            let line = line?;
-            if line.trim().is_empty() {
-                continue;
-            }
-            let message: Message = serde_json::from_str(&line)?;
+        // Records are whitespace-separated JSON values, whether on one line or many
+        for record in serde_json::Deserializer::from_reader(reader).into_iter::<StoredRecord>() {
+            let record = record?;
+            let mut message = record.message;
+            message.timestamp = record.timestamp;
             messages.push(message);
         }
 
@@ -153,6 +440,7 @@ impl SessionStorage {
 
     /// Save all messages (overwrites existing history)
     pub fn save_messages(&self, session_id: &str, messages: &[Message]) -> FrameworkResult<()> {
+        self.discard_pending(session_id);
         self.ensure_session_dir(session_id)?;
         let path = self.history_path(session_id);
 
@@ -160,11 +448,54 @@ impl SessionStorage {
         let mut writer = BufWriter::new(file);
 
         for message in messages {
-            let json = serde_json::to_string(message)?;
-            writeln!(writer, "{}", json)?;
+            self.write_message(&mut writer, message)?;
         }
 
         writer.flush()?;
+        if self.sync {
+            writer.get_ref().sync_all()?;
+        }
+        Ok(())
+    }
+
+    /// Checkpoint an assistant message that is still being streamed
+    ///
+    /// Written to a temporary file and renamed so a crash mid-write never
+    /// leaves a truncated checkpoint behind.
+    pub fn save_partial_response(
+        &self,
+        session_id: &str,
+        message: &Message,
+    ) -> FrameworkResult<()> {
+        self.ensure_session_dir(session_id)?;
+        let path = self.partial_response_path(session_id);
+        let tmp_path = path.with_extension("json.tmp");
+
+        fs::write(&tmp_path, serde_json::to_vec(message)?)?;
+        fs::rename(&tmp_path, &path)?;
+
+        Ok(())
+    }
+
+    /// Load the checkpoint of an interrupted streamed response, if any
+    pub fn load_partial_response(&self, session_id: &str) -> FrameworkResult<Option<Message>> {
+        let path = self.partial_response_path(session_id);
+
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let file = File::open(&path)?;
+        let reader = BufReader::new(file);
+        Ok(Some(serde_json::from_reader(reader)?))
+    }
+
+    /// Remove the streamed response checkpoint
+    pub fn clear_partial_response(&self, session_id: &str) -> FrameworkResult<()> {
+        let path = self.partial_response_path(session_id);
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
         Ok(())
     }
 
@@ -253,6 +584,7 @@ impl SessionStorage {
 
     /// Delete a session
     pub fn delete_session(&self, session_id: &str) -> FrameworkResult<()> {
+        self.discard_pending(session_id);
         let dir = self.session_dir(session_id);
         if dir.exists() {
             fs::remove_dir_all(&dir)?;
@@ -266,6 +598,16 @@ impl SessionStorage {
     }
 }
 
+/// Append `data` to the file at `path`, creating it if needed
+fn append_to_file(path: &Path, data: &[u8], sync: bool) -> std::io::Result<()> {
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(data)?;
+    if sync {
+        file.sync_all()?;
+    }
+    Ok(())
+}
+
 impl Default for SessionStorage {
     fn default() -> Self {
         Self::new()
@@ -314,6 +656,146 @@ mod tests {
         assert_eq!(messages.len(), 2);
     }
 
+    #[test]
+    fn test_buffered_writes() {
+        let (storage, _temp) = create_test_storage();
+        let storage = storage.with_buffered_writes(FlushPolicy {
+            max_messages: 3,
+            max_interval: Duration::from_secs(3600),
+        });
+        let on_disk = |storage: &SessionStorage| {
+            fs::read_to_string(storage.history_path("test_session"))
+                .map(|history| history.lines().count())
+                .unwrap_or(0)
+        };
+
+        storage.append_message("test_session", &Message::user("One")).unwrap();
+        storage.append_message("test_session", &Message::assistant("Two")).unwrap();
+        assert_eq!(on_disk(&storage), 0);
+
+        storage.append_message("test_session", &Message::user("Three")).unwrap();
+        assert_eq!(on_disk(&storage), 3);
+
+        storage.append_message("test_session", &Message::assistant("Four")).unwrap();
+        assert_eq!(on_disk(&storage), 3);
+        storage.flush("test_session").unwrap();
+        assert_eq!(on_disk(&storage), 4);
+
+        // Loading sees pending records, and dropping the last clone writes them
+        storage.append_message("test_session", &Message::user("Five")).unwrap();
+        assert_eq!(storage.load_messages("test_session").unwrap().len(), 5);
+        storage.append_message("test_session", &Message::user("Six")).unwrap();
+        let reader = SessionStorage::with_dir(storage.base_dir());
+        drop(storage);
+        assert_eq!(on_disk(&reader), 6);
+    }
+
+    #[test]
+    fn test_buffered_write_failure_keeps_records() {
+        let (storage, _temp) = create_test_storage();
+        let storage = storage.with_buffered_writes(FlushPolicy {
+            max_messages: 2,
+            max_interval: Duration::from_secs(3600),
+        });
+        let synced = storage.clone().with_sync(true);
+        assert!(!storage.sync);
+        assert!(synced.sync);
+
+        storage.append_message("broken", &Message::user("One")).unwrap();
+        storage.append_message("working", &Message::user("One")).unwrap();
+
+        // A directory in place of the history file makes its writes fail
+        let broken = storage.history_path("broken");
+        fs::create_dir(&broken).unwrap();
+        assert!(storage.append_message("broken", &Message::user("Two")).is_err());
+
+        // The other session is still written, and the failed records remain
+        let err = storage.flush_all().unwrap_err().to_string();
+        assert!(err.contains("broken"));
+        assert!(!err.contains("working"));
+        assert_eq!(storage.load_messages("working").unwrap().len(), 1);
+
+        fs::remove_dir(&broken).unwrap();
+        storage.flush("broken").unwrap();
+        let reader = SessionStorage::with_dir(storage.base_dir());
+        let messages = reader.load_messages("broken").unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].text(), Some("Two"));
+    }
+
+    #[test]
+    fn test_message_timestamps() {
+        let (storage, _temp) = create_test_storage();
+        let sent_at = Utc::now();
+
+        // Records written before timestamps existed still load
+        storage.ensure_session_dir("test_session").unwrap();
+        fs::write(
+            storage.history_path("test_session"),
+            "{\"role\":\"user\",\"content\":\"Hello\"}\n",
+        )
+        .unwrap();
+        storage
+            .append_message("test_session", &Message::assistant("Hi").with_timestamp(sent_at))
+            .unwrap();
+
+        let messages = storage.load_messages("test_session").unwrap();
+        assert_eq!(messages[0].timestamp(), None);
+        assert_eq!(messages[1].timestamp(), Some(sent_at));
+        assert_eq!(messages[1].text(), Some("Hi"));
+
+        // The timestamp is never part of what providers see
+        let json = serde_json::to_value(&messages[1]).unwrap();
+        assert_eq!(json, serde_json::json!({"role": "assistant", "content": "Hi"}));
+    }
+
+    #[test]
+    fn test_pretty_history() {
+        let (storage, temp) = create_test_storage();
+        let pretty = storage.clone().with_pretty_history(true);
+
+        storage.append_message("test_session", &Message::user("Hello")).unwrap();
+        pretty.append_message("test_session", &Message::assistant("Hi\nthere")).unwrap();
+
+        let raw = fs::read_to_string(storage.history_path("test_session")).unwrap();
+        assert!(raw.lines().count() > 2);
+
+        // Either storage reads the mixed file
+        let messages = SessionStorage::with_dir(temp.path()).load_messages("test_session").unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].text(), Some("Hi\nthere"));
+
+        pretty.save_messages("test_session", &messages).unwrap();
+        assert_eq!(storage.load_messages("test_session").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_partial_response_roundtrip() {
+        let (storage, _temp) = create_test_storage();
+
+        assert!(storage
+            .load_partial_response("test_session")
+            .unwrap()
+            .is_none());
+
+        storage
+            .save_partial_response("test_session", &Message::assistant("Half an ans"))
+            .unwrap();
+        let partial = storage
+            .load_partial_response("test_session")
+            .unwrap()
+            .unwrap();
+        assert_eq!(partial.role, "assistant");
+
+        storage.clear_partial_response("test_session").unwrap();
+        assert!(storage
+            .load_partial_response("test_session")
+            .unwrap()
+            .is_none());
+        // Clearing again is a no-op
+        storage.clear_partial_response("test_session").unwrap();
+    }
+
     #[test]
     fn test_session_exists() {
         let (storage, _temp) = create_test_storage();
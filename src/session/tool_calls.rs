@@ -0,0 +1,153 @@
+//! Tool call extraction
+//!
+//! Pairs each `tool_use` block in a session's history with its `tool_result`
+//! so tool usage can be analysed without walking raw content blocks.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::llm::{ContentBlock, MessageContent};
+
+use super::session::AgentSession;
+
+/// A tool call from a session's history together with its result
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCallRecord {
+    /// Tool use ID
+    pub id: String,
+    /// Tool name
+    pub name: String,
+    /// Tool input
+    pub input: Value,
+    /// Text of the tool result (None if no result was recorded)
+    pub result: Option<String>,
+    /// Whether the result was an error
+    pub is_error: bool,
+    /// Index in the history of the assistant message that made the call
+    pub message_index: usize,
+    /// When the assistant message that made the call was added
+    pub called_at: Option<DateTime<Utc>>,
+    /// When the message carrying the result was added
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl ToolCallRecord {
+    /// Whether a result was recorded for this call
+    ///
+    /// Calls without a result were typically cut off by an interrupt or a
+    /// crash before the tool finished.
+    pub fn has_result(&self) -> bool {
+        self.result.is_some()
+    }
+
+    /// Time between the call and its result, when both were timestamped
+    pub fn duration(&self) -> Option<chrono::Duration> {
+        Some(self.completed_at? - self.called_at?)
+    }
+}
+
+impl AgentSession {
+    /// The tool calls in the conversation history, in order
+    ///
+    /// Each `tool_use` block becomes one record, matched to its `tool_result`
+    /// by ID. Times come from the message timestamps and are `None` for
+    /// history recorded before timestamps were stored.
+    pub fn tool_calls(&self) -> Vec<ToolCallRecord> {
+        let mut records = Vec::new();
+        let mut pending: HashMap<String, usize> = HashMap::new();
+
+        for (message_index, message) in self.history().iter().enumerate() {
+            let MessageContent::Blocks(blocks) = &message.content else {
+                continue;
+            };
+            for block in blocks {
+                match block {
+                    ContentBlock::ToolUse { id, name, input, .. } => {
+                        pending.insert(id.clone(), records.len());
+                        records.push(ToolCallRecord {
+                            id: id.clone(),
+                            name: name.clone(),
+                            input: input.clone(),
+                            result: None,
+                            is_error: false,
+                            message_index,
+                            called_at: message.timestamp,
+                            completed_at: None,
+                        });
+                    }
+                    ContentBlock::ToolResult {
+                        tool_use_id,
+                        content,
+                        is_error,
+                        ..
+                    } => {
+                        if let Some(index) = pending.remove(tool_use_id) {
+                            let record = &mut records[index];
+                            record.result = Some(content.as_ref().map(|c| c.text()).unwrap_or_default());
+                            record.is_error = is_error.unwrap_or(false);
+                            record.completed_at = message.timestamp;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        records
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::Message;
+    use crate::session::SessionStorage;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_tool_calls() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = SessionStorage::with_dir(temp_dir.path());
+        let mut session =
+            AgentSession::new_with_storage("calls_session", "coder", "Coder", "Testing", "", storage)
+                .unwrap();
+
+        session.add_message(Message::user("Read two files")).unwrap();
+        session
+            .add_message(Message::assistant_with_blocks(vec![
+                ContentBlock::text("Reading them now"),
+                ContentBlock::tool_use("tool_1", "Read", json!({"file_path": "a.rs"})),
+                ContentBlock::tool_use("tool_2", "Read", json!({"file_path": "b.rs"})),
+            ]))
+            .unwrap();
+        session
+            .add_message(Message::user_with_blocks(vec![
+                ContentBlock::tool_result("tool_2", "No such file", true),
+                ContentBlock::tool_result("tool_1", "fn main() {}", false),
+            ]))
+            .unwrap();
+        session
+            .add_message(Message::assistant_with_blocks(vec![ContentBlock::tool_use(
+                "tool_3",
+                "Bash",
+                json!({"command": "ls"}),
+            )]))
+            .unwrap();
+
+        let calls = session.tool_calls();
+        assert_eq!(calls.len(), 3);
+        assert_eq!(calls[0].name, "Read");
+        assert_eq!(calls[0].input, json!({"file_path": "a.rs"}));
+        assert_eq!(calls[0].result.as_deref(), Some("fn main() {}"));
+        assert!(!calls[0].is_error);
+        assert!(calls[1].is_error);
+        assert_eq!(calls[2].message_index, 3);
+        assert!(!calls[2].has_result());
+        assert!(calls[0].duration().is_some_and(|d| d >= chrono::Duration::zero()));
+        assert_eq!(calls[2].duration(), None);
+    }
+}
@@ -0,0 +1,180 @@
+//! Markdown transcript export
+//!
+//! Renders a session's history as a human-readable Markdown document for
+//! sharing and review.
+
+use crate::helpers::truncate_chars;
+use crate::llm::{ContentBlock, Message, MessageContent};
+
+use super::session::AgentSession;
+
+/// Maximum number of characters of a tool result shown in a transcript
+const MAX_TOOL_RESULT_CHARS: usize = 2000;
+
+impl AgentSession {
+    /// Render the conversation history as a Markdown transcript
+    ///
+    /// - User and assistant turns become `## User` / `## Assistant` sections
+    /// - Tool calls are shown as fenced JSON blocks with their input
+    /// - Tool results are quoted and truncated to a readable length
+    /// - Thinking is collapsed inside a `<details>` element
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+
+        let title = self
+            .conversation_name()
+            .unwrap_or_else(|| self.name());
+        out.push_str(&format!("# {}\n\n", title));
+        out.push_str(&format!("- **Session:** `{}`\n", self.session_id()));
+        out.push_str(&format!("- **Agent:** {}\n", self.agent_type()));
+        if !self.model().is_empty() {
+            out.push_str(&format!("- **Model:** {}\n", self.model()));
+        }
+        out.push_str(&format!(
+            "- **Created:** {}\n",
+            self.metadata.created_at.format("%Y-%m-%d %H:%M:%S UTC")
+        ));
+
+        for message in self.history() {
+            out.push('\n');
+            render_message(&mut out, message);
+        }
+
+        out
+    }
+}
+
+/// Render a single message as a Markdown section
+fn render_message(out: &mut String, message: &Message) {
+    let heading = match message.role.as_str() {
+        "user" => "User",
+        "assistant" => "Assistant",
+        other => other,
+    };
+    out.push_str(&format!("## {}\n\n", heading));
+
+    match &message.content {
+        MessageContent::Text(text) => {
+            out.push_str(text.trim_end());
+            out.push_str("\n\n");
+        }
+        MessageContent::Blocks(blocks) => {
+            for block in blocks {
+                render_block(out, block);
+            }
+        }
+    }
+}
+
+/// Render a single content block
+fn render_block(out: &mut String, block: &ContentBlock) {
+    match block {
+        ContentBlock::Text { text, .. } => {
+            out.push_str(text.trim_end());
+            out.push_str("\n\n");
+        }
+        ContentBlock::ToolUse { id, name, input, .. } => {
+            let input = serde_json::to_string_pretty(input).unwrap_or_else(|_| input.to_string());
+            out.push_str(&format!("**Tool call:** `{}` (`{}`)\n\n", name, id));
+            out.push_str(&format!("```json\n{}\n```\n\n", input));
+        }
+        ContentBlock::ToolResult {
+            tool_use_id,
+            content,
+            is_error,
+            ..
+        } => {
+            let label = if is_error.unwrap_or(false) {
+                "Tool error"
+            } else {
+                "Tool result"
+            };
+            out.push_str(&format!("**{}** (`{}`)\n\n", label, tool_use_id));
+            let mut text = content.as_ref().map(|c| c.text()).unwrap_or_default();
+            for source in content.iter().flat_map(|c| c.images()) {
+                text.push_str(&format!("\n_[image: {}]_", source.media_type));
+            }
+            for line in truncate_with_note(&text, MAX_TOOL_RESULT_CHARS).lines() {
+                out.push_str("> ");
+                out.push_str(line);
+                out.push('\n');
+            }
+            out.push('\n');
+        }
+        ContentBlock::Thinking { thinking, .. } => {
+            out.push_str("<details>\n<summary>Thinking</summary>\n\n");
+            out.push_str(thinking.trim_end());
+            out.push_str("\n\n</details>\n\n");
+        }
+        ContentBlock::RedactedThinking { .. } => {
+            out.push_str("_[redacted thinking]_\n\n");
+        }
+        ContentBlock::Image { source, .. } => {
+            out.push_str(&format!("_[image: {}]_\n\n", source.media_type));
+        }
+        ContentBlock::Document { source, .. } => {
+            out.push_str(&format!("_[document: {}]_\n\n", source.media_type));
+        }
+    }
+}
+
+/// Truncate to at most `max` characters, noting how much was dropped
+fn truncate_with_note(s: &str, max: usize) -> String {
+    let total = s.chars().count();
+    if total <= max {
+        return s.to_string();
+    }
+    format!("{}\n... [truncated {} characters]", truncate_chars(s, max), total - max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::SessionStorage;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_to_markdown() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = SessionStorage::with_dir(temp_dir.path());
+        let mut session =
+            AgentSession::new_with_storage("md_session", "coder", "Coder", "Testing", "", storage)
+                .unwrap();
+
+        session.add_message(Message::user("List the files")).unwrap();
+        session
+            .add_message(Message::assistant_with_blocks(vec![
+                ContentBlock::Thinking {
+                    thinking: "I should use Glob".into(),
+                    signature: String::new(),
+                },
+                ContentBlock::tool_use("tool_1", "Glob", json!({"pattern": "*.rs"})),
+            ]))
+            .unwrap();
+        session
+            .add_message(Message::user_with_blocks(vec![ContentBlock::tool_result(
+                "tool_1",
+                "main.rs\nlib.rs",
+                false,
+            )]))
+            .unwrap();
+
+        let md = session.to_markdown();
+        assert!(md.starts_with("# Coder\n"));
+        assert!(md.contains("## User\n\nList the files"));
+        assert!(md.contains("<details>\n<summary>Thinking</summary>"));
+        assert!(md.contains("**Tool call:** `Glob` (`tool_1`)"));
+        assert!(md.contains("\"pattern\": \"*.rs\""));
+        assert!(md.contains("> main.rs\n> lib.rs\n"));
+    }
+
+    #[test]
+    fn test_truncate_with_note() {
+        assert_eq!(truncate_with_note("short", 10), "short");
+
+        let truncated = truncate_with_note("héllo wörld", 5);
+        assert!(truncated.starts_with("héllo\n"));
+        assert!(truncated.contains("truncated 6 characters"));
+    }
+}
@@ -0,0 +1,270 @@
+//! Scripted LLM provider for tests
+//!
+//! `MockLlmProvider` returns pre-recorded assistant messages in order, one per
+//! request, and records every request it receives. It supports both the
+//! streaming and non-streaming paths of `StandardAgent`.
+
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use futures::stream::Stream;
+
+use crate::llm::{
+    ContentBlock, ContentBlockDeltaEvent, ContentBlockStart, ContentBlockStartEvent,
+    ContentBlockStopEvent, ContentDelta, DeltaUsage, LlmProvider, Message, MessageContent,
+    MessageDeltaData, MessageDeltaEvent, MessageResponse, MessageStartData, MessageStartEvent,
+    RequestOptions, StopReason, StreamEvent, SystemPrompt, ThinkingConfig, ToolChoice, ToolDefinition, Usage,
+};
+
+/// Text returned by `send_message` (used by helpers such as the conversation namer)
+const SIMPLE_RESPONSE: &str = "Mock response";
+
+/// A request received by the mock provider
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    /// Messages sent with the request
+    pub messages: Vec<Message>,
+    /// Names of the tools offered
+    pub tool_names: Vec<String>,
+    /// System prompt sent with the request
+    pub system: Option<SystemPrompt>,
+    /// End-user ID from the request options
+    pub user_id: Option<String>,
+}
+
+/// LLM provider that replays scripted assistant messages
+#[derive(Clone)]
+pub struct MockLlmProvider {
+    script: Arc<Mutex<VecDeque<Vec<ContentBlock>>>>,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+    provider_name: String,
+}
+
+impl MockLlmProvider {
+    /// Create a provider that returns `responses` in order
+    ///
+    /// Each response is the content of one assistant message. The stop reason
+    /// is `ToolUse` when the message contains a tool call and `EndTurn` otherwise.
+    pub fn new(responses: Vec<Vec<ContentBlock>>) -> Self {
+        Self {
+            script: Arc::new(Mutex::new(responses.into())),
+            requests: Arc::new(Mutex::new(Vec::new())),
+            provider_name: "mock".to_string(),
+        }
+    }
+
+    /// Report `name` as the provider, to exercise provider-specific behavior
+    pub fn with_provider_name(mut self, name: impl Into<String>) -> Self {
+        self.provider_name = name.into();
+        self
+    }
+
+    /// Create a provider scripted from the assistant messages of a recorded history
+    pub fn from_history(history: &[Message]) -> Self {
+        let responses = history
+            .iter()
+            .filter(|message| message.role == "assistant")
+            .map(|message| match &message.content {
+                MessageContent::Text(text) => vec![ContentBlock::text(text)],
+                MessageContent::Blocks(blocks) => blocks.clone(),
+            })
+            .collect();
+        Self::new(responses)
+    }
+
+    /// Requests received so far
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Number of scripted responses not yet returned
+    pub fn remaining(&self) -> usize {
+        self.script.lock().unwrap().len()
+    }
+
+    /// Record a request and return the next scripted response
+    fn next_response(
+        &self,
+        messages: Vec<Message>,
+        system: Option<SystemPrompt>,
+        tools: &[ToolDefinition],
+        options: &RequestOptions,
+    ) -> Result<MessageResponse> {
+        self.requests.lock().unwrap().push(RecordedRequest {
+            messages,
+            tool_names: tools.iter().map(|t| t.name().to_string()).collect(),
+            system,
+            user_id: options.user_id.clone(),
+        });
+
+        let Some(content) = self.script.lock().unwrap().pop_front() else {
+            anyhow::bail!("MockLlmProvider: no scripted responses left");
+        };
+
+        let has_tool_use = content
+            .iter()
+            .any(|block| matches!(block, ContentBlock::ToolUse { .. }));
+
+        Ok(MessageResponse {
+            id: format!("mock_{}", self.requests.lock().unwrap().len()),
+            response_type: "message".to_string(),
+            role: "assistant".to_string(),
+            content,
+            model: "mock".to_string(),
+            stop_reason: Some(if has_tool_use {
+                StopReason::ToolUse
+            } else {
+                StopReason::EndTurn
+            }),
+            stop_sequence: None,
+            usage: Usage::default(),
+        })
+    }
+}
+
+/// Convert a full response into the events a streaming provider would send
+fn response_to_events(response: MessageResponse) -> Vec<StreamEvent> {
+    let mut events = vec![StreamEvent::MessageStart(MessageStartEvent {
+        message: MessageStartData {
+            id: response.id,
+            message_type: "message".to_string(),
+            role: "assistant".to_string(),
+            content: Vec::new(),
+            model: response.model,
+            stop_reason: None,
+            stop_sequence: None,
+            usage: response.usage.clone(),
+        },
+    })];
+
+    for (index, block) in response.content.into_iter().enumerate() {
+        let (start, deltas) = match block {
+            ContentBlock::Text { text, .. } => (
+                ContentBlockStart::Text { text: String::new() },
+                vec![ContentDelta::TextDelta { text }],
+            ),
+            ContentBlock::ToolUse { id, name, input, signature } => (
+                ContentBlockStart::ToolUse {
+                    id,
+                    name,
+                    input: serde_json::json!({}),
+                    signature,
+                },
+                vec![ContentDelta::InputJsonDelta {
+                    partial_json: input.to_string(),
+                }],
+            ),
+            ContentBlock::Thinking { thinking, signature } => (
+                ContentBlockStart::Thinking { thinking: String::new() },
+                vec![
+                    ContentDelta::ThinkingDelta { thinking },
+                    ContentDelta::SignatureDelta { signature },
+                ],
+            ),
+            // Other blocks never appear in model output
+            _ => continue,
+        };
+
+        events.push(StreamEvent::ContentBlockStart(ContentBlockStartEvent {
+            index,
+            content_block: start,
+        }));
+        for delta in deltas {
+            events.push(StreamEvent::ContentBlockDelta(ContentBlockDeltaEvent { index, delta }));
+        }
+        events.push(StreamEvent::ContentBlockStop(ContentBlockStopEvent { index }));
+    }
+
+    events.push(StreamEvent::MessageDelta(MessageDeltaEvent {
+        delta: MessageDeltaData {
+            stop_reason: response.stop_reason,
+            stop_sequence: None,
+        },
+        usage: DeltaUsage {
+            output_tokens: response.usage.output_tokens,
+            ..Default::default()
+        },
+    }));
+    events.push(StreamEvent::MessageStop);
+    events
+}
+
+#[async_trait::async_trait]
+impl LlmProvider for MockLlmProvider {
+    async fn send_message(
+        &self,
+        _user_message: &str,
+        _conversation_history: &[Message],
+        _system_prompt: Option<&str>,
+        _session_id: Option<&str>,
+    ) -> Result<String> {
+        // Simple calls don't consume the script so helpers can't shift replayed turns
+        Ok(SIMPLE_RESPONSE.to_string())
+    }
+
+    async fn send_with_tools_and_system(
+        &self,
+        messages: Vec<Message>,
+        system: Option<SystemPrompt>,
+        tools: Vec<ToolDefinition>,
+        _tool_choice: Option<ToolChoice>,
+        _thinking: Option<ThinkingConfig>,
+        session_id: Option<&str>,
+    ) -> Result<MessageResponse> {
+        self.next_response(messages, system, &tools, &RequestOptions::for_session(session_id))
+    }
+
+    async fn stream_with_tools_and_system(
+        &self,
+        messages: Vec<Message>,
+        system: Option<SystemPrompt>,
+        tools: Vec<ToolDefinition>,
+        tool_choice: Option<ToolChoice>,
+        thinking: Option<ThinkingConfig>,
+        session_id: Option<&str>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamEvent>> + Send>>> {
+        let options = RequestOptions::for_session(session_id);
+        self.stream_with_options(messages, system, tools, tool_choice, thinking, &options)
+            .await
+    }
+
+    async fn send_with_options(
+        &self,
+        messages: Vec<Message>,
+        system: Option<SystemPrompt>,
+        tools: Vec<ToolDefinition>,
+        _tool_choice: Option<ToolChoice>,
+        _thinking: Option<ThinkingConfig>,
+        options: &RequestOptions,
+    ) -> Result<MessageResponse> {
+        self.next_response(messages, system, &tools, options)
+    }
+
+    async fn stream_with_options(
+        &self,
+        messages: Vec<Message>,
+        system: Option<SystemPrompt>,
+        tools: Vec<ToolDefinition>,
+        _tool_choice: Option<ToolChoice>,
+        _thinking: Option<ThinkingConfig>,
+        options: &RequestOptions,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamEvent>> + Send>>> {
+        let response = self.next_response(messages, system, &tools, options)?;
+        let events: Vec<Result<StreamEvent>> = response_to_events(response).into_iter().map(Ok).collect();
+        Ok(Box::pin(futures::stream::iter(events)))
+    }
+
+    fn model(&self) -> String {
+        "mock".to_string()
+    }
+
+    fn provider_name(&self) -> &str {
+        &self.provider_name
+    }
+
+    fn create_variant(&self, _model: &str, _max_tokens: u32) -> Arc<dyn LlmProvider> {
+        Arc::new(self.clone())
+    }
+}
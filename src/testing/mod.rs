@@ -0,0 +1,15 @@
+//! Test support utilities
+//!
+//! Available in this crate's tests and, for downstream crates, with the
+//! `testing` feature:
+//! - `MockLlmProvider` - LLM provider that returns scripted responses
+//! - `ReplayHarness` - Replays a recorded session and checks the tool calls
+//! - `normalize_tool_ids` - Deterministic tool use IDs for golden-file comparisons
+
+pub mod mock_llm;
+pub mod normalize;
+pub mod replay;
+
+pub use mock_llm::{MockLlmProvider, RecordedRequest};
+pub use normalize::normalize_tool_ids;
+pub use replay::{ReplayHarness, ReplayReport, ReplayToolCall};
@@ -0,0 +1,84 @@
+//! Deterministic tool use IDs for comparing histories
+//!
+//! Providers assign a fresh ID to every tool use, so two runs of the same
+//! conversation never serialize identically. `normalize_tool_ids` renames the
+//! IDs to `tool_0`, `tool_1`, ... in order of appearance, keeping each tool
+//! result paired with its tool use, so histories can be compared as golden
+//! files.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use picrust::testing::normalize_tool_ids;
+//!
+//! let actual = serde_json::to_string_pretty(&normalize_tool_ids(session.history()))?;
+//! assert_eq!(actual, std::fs::read_to_string("tests/golden/refactor.json")?);
+//! ```
+
+use std::collections::HashMap;
+
+use crate::llm::{ContentBlock, Message, MessageContent};
+
+/// Copy of `messages` with tool use IDs renamed to `tool_0`, `tool_1`, ...
+///
+/// IDs are numbered in order of first appearance, whether in a tool use or a
+/// tool result, and every occurrence of an ID gets the same new name.
+pub fn normalize_tool_ids(messages: &[Message]) -> Vec<Message> {
+    let mut ids: HashMap<String, String> = HashMap::new();
+    let mut rename = |id: &mut String| {
+        let next = ids.len();
+        *id = ids
+            .entry(std::mem::take(id))
+            .or_insert_with(|| format!("tool_{}", next))
+            .clone();
+    };
+
+    let mut messages = messages.to_vec();
+    for message in &mut messages {
+        let MessageContent::Blocks(ref mut blocks) = message.content else {
+            continue;
+        };
+        for block in blocks {
+            match block {
+                ContentBlock::ToolUse { id, .. } => rename(id),
+                ContentBlock::ToolResult { tool_use_id, .. } => rename(tool_use_id),
+                _ => {}
+            }
+        }
+    }
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_normalize_tool_ids() {
+        let messages = vec![
+            Message::user("List the files"),
+            Message::assistant_with_blocks(vec![
+                ContentBlock::tool_use("toolu_01Xa", "Glob", json!({"pattern": "*"})),
+                ContentBlock::tool_use("toolu_01Yb", "LS", json!({})),
+            ]),
+            Message::user_with_blocks(vec![
+                ContentBlock::tool_result("toolu_01Yb", "src/", false),
+                ContentBlock::tool_result("toolu_01Xa", "lib.rs", false),
+            ]),
+        ];
+
+        let normalized = normalize_tool_ids(&messages);
+        let ids: Vec<&str> = normalized[1].tool_uses().into_iter().map(|(id, _, _)| id).collect();
+        assert_eq!(ids, vec!["tool_0", "tool_1"]);
+        let result_ids: Vec<&str> = normalized[2].tool_results().into_iter().map(|(id, _, _)| id).collect();
+        assert_eq!(result_ids, vec!["tool_1", "tool_0"]);
+
+        // The original is untouched and a second run gives the same result
+        assert_eq!(messages[1].tool_uses()[0].0, "toolu_01Xa");
+        assert_eq!(
+            serde_json::to_value(normalize_tool_ids(&normalized)).unwrap(),
+            serde_json::to_value(&normalized).unwrap()
+        );
+    }
+}
@@ -0,0 +1,318 @@
+//! Replay recorded sessions against a `StandardAgent`
+//!
+//! `ReplayHarness` turns a captured session into a regression test. The user
+//! turns of the recording are sent to a fresh agent, and the LLM is replaced by
+//! a [`MockLlmProvider`] that returns the recorded assistant messages. The real
+//! tools from your `AgentConfig` run against that script, and the report shows
+//! whether they were called with the recorded inputs.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use picrust::testing::ReplayHarness;
+//!
+//! #[tokio::test]
+//! async fn replay_refactor_session() {
+//!     let storage = SessionStorage::with_dir("tests/fixtures/sessions");
+//!     let harness = ReplayHarness::load("refactor-session", &storage).unwrap();
+//!
+//!     let config = AgentConfig::new().with_tools(tools).with_streaming(false);
+//!     let report = harness.run(config).await.unwrap();
+//!
+//!     report.assert_tool_calls_match();
+//! }
+//! ```
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+use crate::agent::{AgentConfig, StandardAgent};
+use crate::core::{FrameworkResult, OutputChunk};
+use crate::helpers::is_session_preamble;
+use crate::llm::{Message, MessageContent};
+use crate::runtime::AgentRuntime;
+use crate::session::{AgentSession, SessionStorage};
+
+use super::mock_llm::MockLlmProvider;
+
+/// Session ID used for the replayed session
+const REPLAY_SESSION_ID: &str = "replay";
+/// Default time to wait for a turn to finish
+const DEFAULT_TURN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A tool call by name and input
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayToolCall {
+    /// Tool name
+    pub name: String,
+    /// Tool input
+    pub input: Value,
+}
+
+/// Result of a replay
+#[derive(Debug, Clone)]
+pub struct ReplayReport {
+    /// Tool calls in the recording, in order
+    pub expected_tool_calls: Vec<ReplayToolCall>,
+    /// Tool calls the agent executed during the replay, in order
+    ///
+    /// Calls that were denied by permissions or hooks are not executed and
+    /// therefore not listed.
+    pub tool_calls: Vec<ReplayToolCall>,
+    /// Tools that asked for permission during the replay
+    pub permission_requests: Vec<String>,
+    /// Scripted assistant messages the agent never requested
+    pub unused_responses: usize,
+    /// History of the replayed session
+    pub history: Vec<Message>,
+}
+
+impl ReplayReport {
+    /// Whether the executed tool calls match the recording exactly
+    pub fn tool_calls_match(&self) -> bool {
+        self.tool_calls == self.expected_tool_calls
+    }
+
+    /// Panic with both call lists if the executed tool calls differ from the recording
+    pub fn assert_tool_calls_match(&self) {
+        assert!(
+            self.tool_calls_match(),
+            "replayed tool calls differ from the recording\nexpected: {:#?}\nactual: {:#?}",
+            self.expected_tool_calls,
+            self.tool_calls
+        );
+    }
+
+    /// History of the replayed session with deterministic tool use IDs
+    ///
+    /// See [`normalize_tool_ids`](super::normalize_tool_ids).
+    pub fn normalized_history(&self) -> Vec<Message> {
+        super::normalize::normalize_tool_ids(&self.history)
+    }
+}
+
+/// Replays a recorded conversation through a `StandardAgent`
+#[derive(Debug, Clone)]
+pub struct ReplayHarness {
+    history: Vec<Message>,
+    system_prompt: String,
+    allow_permissions: bool,
+    turn_timeout: Duration,
+}
+
+impl ReplayHarness {
+    /// Create a harness from a recorded history
+    pub fn from_history(history: Vec<Message>) -> Self {
+        Self {
+            history,
+            system_prompt: String::new(),
+            allow_permissions: true,
+            turn_timeout: DEFAULT_TURN_TIMEOUT,
+        }
+    }
+
+    /// Load a recorded session (history and system prompt) from storage
+    pub fn load(session_id: &str, storage: &SessionStorage) -> FrameworkResult<Self> {
+        let session = AgentSession::load_with_storage(session_id, storage.clone())?;
+        let mut harness = Self::from_history(session.history().to_vec());
+        harness.system_prompt = session.system_prompt().to_string();
+        Ok(harness)
+    }
+
+    /// Set how permission requests are answered (default: allow)
+    pub fn with_permissions(mut self, allow: bool) -> Self {
+        self.allow_permissions = allow;
+        self
+    }
+
+    /// Set how long to wait for each turn before failing (default: 30 seconds)
+    pub fn with_turn_timeout(mut self, timeout: Duration) -> Self {
+        self.turn_timeout = timeout;
+        self
+    }
+
+    /// The user inputs that start each recorded turn
+    ///
+    /// Tool result messages are part of a turn, not the start of one.
+    pub fn user_inputs(&self) -> Vec<String> {
+        self.history
+            .iter()
+            .filter(|message| message.role == "user")
+            .filter(|message| message.tool_results().is_empty())
+            .map(|message| match &message.content {
+                // Leave out the session preamble; the agent adds it again
+                MessageContent::Blocks(blocks) => blocks
+                    .iter()
+                    .filter(|block| !is_session_preamble(block))
+                    .filter_map(|block| block.as_text())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                content => content.text(),
+            })
+            .collect()
+    }
+
+    /// The tool calls made in the recording, in order
+    pub fn expected_tool_calls(&self) -> Vec<ReplayToolCall> {
+        self.history
+            .iter()
+            .filter(|message| message.role == "assistant")
+            .flat_map(|message| message.tool_uses())
+            .map(|(_, name, input)| ReplayToolCall {
+                name: name.to_string(),
+                input: input.clone(),
+            })
+            .collect()
+    }
+
+    /// Replay the recording with `config` and report what the agent did
+    ///
+    /// The replay runs in a temporary session; the recording is never modified.
+    pub async fn run(&self, config: AgentConfig) -> Result<ReplayReport> {
+        let temp_dir = tempfile::TempDir::new().context("Failed to create replay directory")?;
+        let storage = SessionStorage::with_dir(temp_dir.path());
+        let session = AgentSession::new_with_storage(
+            REPLAY_SESSION_ID,
+            "replay",
+            "Replay",
+            "Replayed session",
+            self.system_prompt.clone(),
+            storage.clone(),
+        )?;
+
+        let llm = MockLlmProvider::from_history(&self.history);
+        let agent = StandardAgent::new(config, Arc::new(llm.clone()));
+
+        let runtime = AgentRuntime::new();
+        let handle = runtime
+            .spawn(session, move |internals| agent.run(internals))
+            .await;
+        let mut rx = handle.subscribe();
+
+        let mut tool_calls = Vec::new();
+        let mut permission_requests = Vec::new();
+
+        for input in self.user_inputs() {
+            handle.send_input(input).await?;
+
+            loop {
+                let chunk = tokio::time::timeout(self.turn_timeout, rx.recv())
+                    .await
+                    .context("Timed out waiting for the replayed turn to finish")?
+                    .context("Agent output channel closed")?;
+
+                match chunk {
+                    OutputChunk::ToolStart { name, input, .. } => {
+                        tool_calls.push(ReplayToolCall { name, input });
+                    }
+                    OutputChunk::PermissionRequest { tool_name, .. } => {
+                        handle
+                            .send_permission_response(&tool_name, self.allow_permissions, false)
+                            .await?;
+                        permission_requests.push(tool_name);
+                    }
+                    OutputChunk::Done => break,
+                    _ => {}
+                }
+            }
+        }
+
+        handle.shutdown().await?;
+        let history = AgentSession::load_with_storage(REPLAY_SESSION_ID, storage)?
+            .history()
+            .to_vec();
+
+        Ok(ReplayReport {
+            expected_tool_calls: self.expected_tool_calls(),
+            tool_calls,
+            permission_requests,
+            unused_responses: llm.remaining(),
+            history,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::{define_tool, ContentBlock, ToolDefinition};
+    use crate::tools::{Tool, ToolInfo, ToolRegistry, ToolResult};
+    use crate::runtime::AgentInternals;
+    use serde_json::json;
+
+    struct EchoTool;
+
+    #[async_trait::async_trait]
+    impl Tool for EchoTool {
+        fn name(&self) -> &str {
+            "Echo"
+        }
+
+        fn description(&self) -> &str {
+            "Echo the input"
+        }
+
+        fn definition(&self) -> ToolDefinition {
+            define_tool("Echo", "Echo the input", json!({"text": {"type": "string"}}), vec![])
+        }
+
+        fn get_info(&self, _input: &Value) -> ToolInfo {
+            ToolInfo {
+                name: "Echo".to_string(),
+                action_description: "Echo".to_string(),
+                details: None,
+            }
+        }
+
+        async fn execute(&self, input: &Value, _internals: &mut AgentInternals) -> Result<ToolResult> {
+            Ok(ToolResult::success(input.to_string()))
+        }
+
+        fn requires_permission(&self) -> bool {
+            false
+        }
+    }
+
+    fn recorded_history() -> Vec<Message> {
+        vec![
+            Message::user("Say hello"),
+            Message::assistant_with_blocks(vec![ContentBlock::tool_use(
+                "tool_1",
+                "Echo",
+                json!({"text": "hello"}),
+            )]),
+            Message::user_with_blocks(vec![ContentBlock::tool_result("tool_1", "hello", false)]),
+            Message::assistant("Done."),
+        ]
+    }
+
+    #[test]
+    fn test_user_inputs_skip_tool_results() {
+        let harness = ReplayHarness::from_history(recorded_history());
+        assert_eq!(harness.user_inputs(), vec!["Say hello".to_string()]);
+        assert_eq!(harness.expected_tool_calls().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_replay_matches_recording() {
+        let mut tools = ToolRegistry::new();
+        tools.register(EchoTool);
+        let config = AgentConfig::new()
+            .with_tools(Arc::new(tools))
+            .with_streaming(true)
+            .with_auto_name(false);
+
+        let report = ReplayHarness::from_history(recorded_history())
+            .run(config)
+            .await
+            .unwrap();
+
+        report.assert_tool_calls_match();
+        assert_eq!(report.unused_responses, 0);
+        assert_eq!(report.history.len(), 4);
+    }
+
+}
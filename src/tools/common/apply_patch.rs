@@ -0,0 +1,334 @@
+//! ApplyPatch tool for editing files with unified diffs
+//!
+//! Applies a unified diff that may touch several files, including creating
+//! and deleting them. Every hunk is checked before anything is written: if
+//! any hunk fails, no file is changed and the model gets a per-hunk report.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+
+use super::super::tool::{Tool, ToolInfo, ToolResult};
+use super::patch::{apply_hunks, parse_patch, FilePatch, MatchKind, PatchedFile, Placement};
+use crate::llm::{ToolDefinition, ToolInputSchema};
+use crate::runtime::AgentInternals;
+
+/// ApplyPatch tool for multi-file edits from a unified diff
+#[derive(Default)]
+pub struct ApplyPatchTool {
+    /// Base directory for file operations (None = the session's working directory)
+    base_dir: Option<String>,
+}
+
+/// Input for the apply patch tool
+#[derive(Debug, Deserialize)]
+struct ApplyPatchInput {
+    /// The unified diff to apply (required)
+    patch: String,
+    /// Only check the patch and report what would change (default false)
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// A file change that has been validated but not yet written
+enum Change {
+    Write { path: String, content: String },
+    Delete { path: String },
+}
+
+impl ApplyPatchTool {
+    /// Create a new ApplyPatch tool that resolves paths against the session's working directory
+    ///
+    /// Falls back to the current directory when the session has none recorded.
+    pub fn new() -> Result<Self> {
+        Ok(Self { base_dir: None })
+    }
+
+    /// Create a new ApplyPatch tool with a specific base directory
+    pub fn with_base_dir(base_dir: impl Into<String>) -> Self {
+        Self {
+            base_dir: Some(base_dir.into()),
+        }
+    }
+
+    /// Get the base directory, defaulting to the session's working directory
+    fn base_dir(&self, internals: &AgentInternals) -> String {
+        self.base_dir
+            .clone()
+            .unwrap_or_else(|| internals.default_base_dir())
+    }
+
+    /// Resolve a path (handle both absolute and relative)
+    fn resolve_path(&self, base_dir: &str, path: &str) -> String {
+        let path = Path::new(path);
+        if path.is_absolute() {
+            path.to_string_lossy().to_string()
+        } else {
+            Path::new(base_dir)
+                .join(path)
+                .to_string_lossy()
+                .to_string()
+        }
+    }
+
+    /// Validate and (unless `dry_run`) apply a patch
+    ///
+    /// Returns the per-file report on success, and the same report as the
+    /// error when any hunk fails.
+    fn apply_patch(&self, base_dir: &str, patch: &str, dry_run: bool) -> Result<String> {
+        let files = parse_patch(patch).map_err(|e| anyhow::anyhow!("Invalid patch: {}", e))?;
+
+        let mut changes = Vec::new();
+        let mut report = Vec::new();
+        let mut total_hunks = 0;
+        let mut failed_hunks = 0;
+
+        for file in &files {
+            total_hunks += file.hunks.len();
+            match self.plan_file(base_dir, file) {
+                Ok((file_changes, patched)) => {
+                    failed_hunks += patched.failed();
+                    report.push(format!("{}\n{}", file.path(), describe_hunks(&patched)));
+                    changes.extend(file_changes);
+                }
+                Err(e) => {
+                    failed_hunks += file.hunks.len();
+                    report.push(format!("{}\n  {}", file.path(), e));
+                }
+            }
+        }
+        let report = report.join("\n");
+
+        if failed_hunks > 0 {
+            anyhow::bail!(
+                "Patch not applied: {} of {} hunks failed. No files were changed.\n\n{}",
+                failed_hunks,
+                total_hunks,
+                report
+            );
+        }
+        if dry_run {
+            return Ok(format!(
+                "Dry run: patch applies cleanly to {} file(s). No files were changed.\n\n{}",
+                files.len(),
+                report
+            ));
+        }
+
+        for change in changes {
+            match change {
+                Change::Write { path, content } => {
+                    tracing::info!("Patching file: {}", path);
+                    if let Some(parent) = Path::new(&path).parent() {
+                        fs::create_dir_all(parent)
+                            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+                    }
+                    fs::write(&path, content).with_context(|| format!("Failed to write file: {}", path))?;
+                }
+                Change::Delete { path } => {
+                    tracing::info!("Deleting file: {}", path);
+                    fs::remove_file(&path).with_context(|| format!("Failed to delete file: {}", path))?;
+                }
+            }
+        }
+
+        Ok(format!(
+            "Successfully applied patch to {} file(s).\n\n{}",
+            files.len(),
+            report
+        ))
+    }
+
+    /// Work out the changes for one file
+    ///
+    /// No changes are returned when a hunk failed.
+    fn plan_file(&self, base_dir: &str, file: &FilePatch) -> Result<(Vec<Change>, PatchedFile)> {
+        let source = file.old_path.as_ref().map(|path| self.resolve_path(base_dir, path));
+        let target = file.new_path.as_ref().map(|path| self.resolve_path(base_dir, path));
+
+        let content = match source {
+            Some(ref path) => {
+                fs::read_to_string(path).with_context(|| format!("Failed to read file: {}", path))?
+            }
+            None => {
+                if target.as_ref().is_some_and(|path| Path::new(path).exists()) {
+                    anyhow::bail!("File already exists; the patch creates it from /dev/null");
+                }
+                String::new()
+            }
+        };
+
+        let mut patched = apply_hunks(&content, &file.hunks);
+        if patched.failed() > 0 {
+            return Ok((Vec::new(), patched));
+        }
+
+        let mut changes = Vec::new();
+        match (source, target) {
+            (Some(source), None) => changes.push(Change::Delete { path: source }),
+            (source, Some(target)) => {
+                if let Some(source) = source.filter(|source| *source != target) {
+                    changes.push(Change::Delete { path: source });
+                }
+                changes.push(Change::Write {
+                    path: target,
+                    content: std::mem::take(&mut patched.content),
+                });
+            }
+            (None, None) => anyhow::bail!("Both sides of the diff are /dev/null"),
+        }
+        Ok((changes, patched))
+    }
+}
+
+/// One line per hunk saying where it applied or why it failed
+fn describe_hunks(patched: &PatchedFile) -> String {
+    patched
+        .hunks
+        .iter()
+        .enumerate()
+        .map(|(i, result)| match result {
+            Ok(placement) => format!("  hunk {}: {}", i + 1, describe_placement(placement)),
+            Err(e) => format!("  hunk {}: failed, {}", i + 1, e),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Describe where a hunk applied, e.g. "applied at line 12 (offset +3)"
+fn describe_placement(placement: &Placement) -> String {
+    let mut description = format!("applied at line {}", placement.line);
+    if placement.offset != 0 {
+        description.push_str(&format!(" (offset {:+})", placement.offset));
+    }
+    match placement.kind {
+        MatchKind::Exact => {}
+        MatchKind::TrailingWhitespace => description.push_str(", ignoring trailing whitespace"),
+        MatchKind::Whitespace => description.push_str(", ignoring indentation"),
+    }
+    description
+}
+
+#[async_trait]
+impl Tool for ApplyPatchTool {
+    fn name(&self) -> &str {
+        "ApplyPatch"
+    }
+
+    fn description(&self) -> &str {
+        "Apply a unified diff to one or more files."
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        use crate::llm::types::CustomTool;
+
+        ToolDefinition::Custom(CustomTool {
+            name: "ApplyPatch".to_string(),
+            description: Some(
+                "Applies a unified diff (as produced by `diff -u` or `git diff`) to one or more files. \
+                Each file starts with '--- old/path' and '+++ new/path' lines; use /dev/null to create or delete a file. \
+                Include a few lines of unchanged context around each change; hunk line numbers may be approximate. \
+                If any hunk does not apply, no files are changed and each hunk's status is reported."
+                    .to_string(),
+            ),
+            input_schema: ToolInputSchema {
+                schema_type: "object".to_string(),
+                properties: Some(json!({
+                    "patch": {
+                        "type": "string",
+                        "description": "The unified diff to apply"
+                    },
+                    "dry_run": {
+                        "type": "boolean",
+                        "default": false,
+                        "description": "Check the patch and report where each hunk applies without changing any files (default false)"
+                    }
+                })),
+                required: Some(vec!["patch".to_string()]),
+            },
+            tool_type: None,
+            cache_control: None,
+        })
+    }
+
+    fn get_info(&self, input: &Value) -> ToolInfo {
+        let files: Vec<String> = input
+            .get("patch")
+            .and_then(|v| v.as_str())
+            .and_then(|patch| parse_patch(patch).ok())
+            .map(|files| files.iter().map(|file| file.path().to_string()).collect())
+            .unwrap_or_default();
+        let dry_run = input.get("dry_run").and_then(|v| v.as_bool()).unwrap_or(false);
+
+        ToolInfo {
+            name: "ApplyPatch".to_string(),
+            action_description: format!(
+                "{} patch to {} file(s)",
+                if dry_run { "Check" } else { "Apply" },
+                files.len()
+            ),
+            details: (!files.is_empty()).then(|| files.join("\n")),
+        }
+    }
+
+    async fn execute(&self, input: &Value, internals: &mut AgentInternals) -> Result<ToolResult> {
+        let patch_input: ApplyPatchInput = serde_json::from_value(input.clone())
+            .map_err(|e| anyhow::anyhow!("Invalid patch input: {}", e))?;
+
+        let base_dir = self.base_dir(internals);
+        match self.apply_patch(&base_dir, &patch_input.patch, patch_input.dry_run) {
+            Ok(output) => Ok(ToolResult::success(output)),
+            Err(e) => Ok(ToolResult::error(format!("{}", e))),
+        }
+    }
+
+    fn requires_permission(&self) -> bool {
+        true // Modifies files
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATCH: &str = "--- a/main.rs
++++ b/main.rs
+@@ -1,3 +1,3 @@
+ fn main() {
+-    println!(\"hello\");
++    println!(\"hello, world\");
+ }
+--- a/old.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-remove me
+";
+
+    #[test]
+    fn test_apply_patch_validates_before_writing() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let base_dir = dir.path().to_string_lossy().to_string();
+        let main_rs = dir.path().join("main.rs");
+        fs::write(&main_rs, "fn main() {\n    println!(\"hello\");\n}\n").unwrap();
+        let tool = ApplyPatchTool::with_base_dir(&base_dir);
+
+        // old.txt is missing, so nothing is written
+        let error = tool.apply_patch(&base_dir, PATCH, false).unwrap_err().to_string();
+        assert!(error.starts_with("Patch not applied: 1 of 2 hunks failed"));
+        assert!(error.contains("main.rs\n  hunk 1: applied at line 1"));
+        assert!(fs::read_to_string(&main_rs).unwrap().contains("\"hello\""));
+
+        let old_txt = dir.path().join("old.txt");
+        fs::write(&old_txt, "remove me\n").unwrap();
+        let preview = tool.apply_patch(&base_dir, PATCH, true).unwrap();
+        assert!(preview.starts_with("Dry run"));
+        assert!(old_txt.exists());
+
+        tool.apply_patch(&base_dir, PATCH, false).unwrap();
+        assert!(fs::read_to_string(&main_rs).unwrap().contains("hello, world"));
+        assert!(!old_txt.exists());
+    }
+}
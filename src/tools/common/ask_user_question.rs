@@ -3,11 +3,16 @@
 //! This tool allows the agent to ask the user questions and wait for responses.
 //! Questions are sent via `OutputChunk::AskUserQuestion`, and the tool waits
 //! for the user's answers via `InputMessage::UserQuestionResponse`.
+//!
+//! In non-interactive mode (`AgentInternals::set_interactive(false)`) nobody
+//! can answer, so the tool returns its configured default answer without
+//! waiting, or an error telling the model to proceed on its own.
 
 use anyhow::Result;
 use async_trait::async_trait;
 use serde::Deserialize;
 use serde_json::{json, Value};
+use std::collections::HashMap;
 
 use super::super::tool::{Tool, ToolInfo, ToolResult};
 use crate::core::output::{QuestionOption, UserQuestion};
@@ -37,25 +42,59 @@ struct AskUserQuestionInput {
     questions: Vec<QuestionInput>,
     /// Pre-filled answers (optional, not typically used)
     #[serde(default)]
-    _answers: Option<std::collections::HashMap<String, String>>,
+    _answers: Option<HashMap<String, String>>,
 }
 
 /// AskUserQuestion tool for interacting with users
 ///
 /// This tool allows the agent to ask the user questions with multiple-choice
 /// options and receive their responses.
-pub struct AskUserQuestionTool;
+#[derive(Default)]
+pub struct AskUserQuestionTool {
+    /// Answer given to every question in non-interactive mode
+    default_answer: Option<String>,
+}
 
 impl AskUserQuestionTool {
     /// Create a new AskUserQuestion tool
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    /// Answer every question with `answer` when running non-interactively
+    ///
+    /// Without a default answer, the tool returns an error in non-interactive
+    /// mode asking the model to continue with its best judgement.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let tool = AskUserQuestionTool::new()
+    ///     .with_default_answer("No preference, use the recommended option");
+    /// ```
+    pub fn with_default_answer(mut self, answer: impl Into<String>) -> Self {
+        self.default_answer = Some(answer.into());
+        self
     }
-}
 
-impl Default for AskUserQuestionTool {
-    fn default() -> Self {
-        Self::new()
+    /// Result returned instead of asking when nobody can answer
+    fn non_interactive_result(&self, questions: &[UserQuestion]) -> ToolResult {
+        let Some(ref answer) = self.default_answer else {
+            return ToolResult::error(
+                "The user can't be asked questions in non-interactive mode. \
+                Continue with your best judgement and state the assumptions you made.",
+            );
+        };
+
+        let answers: HashMap<&str, &str> = questions
+            .iter()
+            .map(|q| (q.header.as_str(), answer.as_str()))
+            .collect();
+        let answers_json = serde_json::to_string_pretty(&answers).unwrap_or_else(|_| format!("{:?}", answers));
+        ToolResult::success(format!(
+            "The user is not available (non-interactive mode). Default answers:\n{}",
+            answers_json
+        ))
     }
 }
 
@@ -213,6 +252,10 @@ impl Tool for AskUserQuestionTool {
             })
             .collect();
 
+        if !internals.is_interactive() {
+            return Ok(self.non_interactive_result(&questions));
+        }
+
         // Generate a unique request ID
         let request_id = format!("ask_{}", uuid::Uuid::new_v4());
 
@@ -243,4 +286,38 @@ impl Tool for AskUserQuestionTool {
     fn requires_permission(&self) -> bool {
         false // Questions ARE the user interaction, no additional permission needed
     }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::ToolResultData;
+
+    fn question(header: &str) -> UserQuestion {
+        UserQuestion {
+            question: "Which one?".to_string(),
+            header: header.to_string(),
+            options: Vec::new(),
+            multi_select: false,
+        }
+    }
+
+    #[test]
+    fn test_non_interactive_result() {
+        let result = AskUserQuestionTool::new().non_interactive_result(&[question("Library")]);
+        assert!(result.is_error);
+
+        let result = AskUserQuestionTool::new()
+            .with_default_answer("Your choice")
+            .non_interactive_result(&[question("Library")]);
+        assert!(!result.is_error);
+        let ToolResultData::Text(text) = result.content else {
+            panic!("expected text");
+        };
+        assert!(text.contains("\"Library\": \"Your choice\""));
+    }
 }
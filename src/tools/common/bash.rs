@@ -1,6 +1,8 @@
 //! Bash tool for executing shell commands
 //!
 //! This tool executes bash commands with optional timeout and description.
+//! How stdout and stderr are presented to the model is set with
+//! `BashTool::with_output_mode`.
 
 use anyhow::Result;
 use async_trait::async_trait;
@@ -12,6 +14,7 @@ use tokio::process::Command;
 use tokio::time::timeout;
 
 use super::super::tool::{Tool, ToolInfo, ToolResult};
+use crate::helpers::truncate_bytes_safe;
 use crate::llm::{ToolDefinition, ToolInputSchema};
 use crate::runtime::AgentInternals;
 
@@ -22,10 +25,37 @@ const MAX_TIMEOUT_MS: u64 = 600000;
 /// Maximum output length in characters
 const MAX_OUTPUT_LENGTH: usize = 30000;
 
+/// How the Bash tool reports stdout and stderr
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BashOutputMode {
+    /// Stdout, then stderr under a `STDERR:` marker (default)
+    #[default]
+    Combined,
+    /// Stdout and stderr in separate labeled sections, each always present
+    Separate,
+    /// Both streams through one pipe, in the order they were written
+    Interleaved,
+}
+
 /// Bash tool for executing shell commands
+#[derive(Default)]
 pub struct BashTool {
-    /// Working directory for command execution
-    working_dir: String,
+    /// Working directory for command execution (None = the session's working directory)
+    working_dir: Option<String>,
+    /// How stdout and stderr are reported
+    output_mode: BashOutputMode,
+}
+
+/// Output of a finished (or timed out) command
+struct CommandOutput {
+    /// Text shown to the model
+    output: String,
+    /// Exit code, or None if the command timed out or was killed by a signal
+    exit_code: Option<i32>,
+    /// Signal that killed the command, if any
+    signal: Option<i32>,
+    /// Length of the output before truncation, if it was truncated
+    original_len: Option<usize>,
 }
 
 /// Input for the bash tool
@@ -40,34 +70,49 @@ struct BashInput {
 }
 
 impl BashTool {
-    /// Create a new Bash tool with the current directory as working directory
+    /// Create a new Bash tool that runs commands in the session's working directory
+    ///
+    /// Falls back to the current directory when the session has none recorded.
     pub fn new() -> Result<Self> {
-        let working_dir = std::env::current_dir()?
-            .to_string_lossy()
-            .to_string();
-
-        Ok(Self { working_dir })
+        Ok(Self::default())
     }
 
     /// Create a new Bash tool with a specific working directory
     pub fn with_working_dir(working_dir: impl Into<String>) -> Self {
         Self {
-            working_dir: working_dir.into(),
+            working_dir: Some(working_dir.into()),
+            ..Self::default()
         }
     }
 
+    /// Set how stdout and stderr are reported (default: `Combined`)
+    ///
+    /// `Interleaved` keeps the order of output from commands that write to
+    /// both streams, but the model can no longer tell which stream a line
+    /// came from.
+    pub fn with_output_mode(mut self, mode: BashOutputMode) -> Self {
+        self.output_mode = mode;
+        self
+    }
+
     /// Execute a bash command with optional timeout
-    async fn run_command(&self, command: &str, timeout_ms: u64) -> Result<(String, i32)> {
+    async fn run_command(&self, working_dir: &str, command: &str, timeout_ms: u64) -> Result<CommandOutput> {
         tracing::info!("Executing bash command: {}", command);
-        tracing::debug!("Working directory: {}", self.working_dir);
+        tracing::debug!("Working directory: {}", working_dir);
         tracing::debug!("Timeout: {}ms", timeout_ms);
 
         let duration = Duration::from_millis(timeout_ms.min(MAX_TIMEOUT_MS));
 
+        // `exec 2>&1` points the shell's stderr at the stdout pipe for the whole script
+        let script = match self.output_mode {
+            BashOutputMode::Interleaved => format!("exec 2>&1\n{}", command),
+            _ => command.to_string(),
+        };
+
         let output_future = Command::new("bash")
             .arg("-c")
-            .arg(command)
-            .current_dir(&self.working_dir)
+            .arg(script)
+            .current_dir(working_dir)
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .output();
@@ -75,46 +120,73 @@ impl BashTool {
         let output = match timeout(duration, output_future).await {
             Ok(result) => result?,
             Err(_) => {
-                return Ok((
-                    format!("Command timed out after {}ms", timeout_ms),
-                    -1,
-                ));
+                return Ok(CommandOutput {
+                    output: format!("Command timed out after {}ms", timeout_ms),
+                    exit_code: None,
+                    signal: None,
+                    original_len: None,
+                });
             }
         };
 
-        let exit_code = output.status.code().unwrap_or(-1);
+        let exit_code = output.status.code();
+        let signal = exit_signal(&output.status);
         let stdout = String::from_utf8_lossy(&output.stdout);
         let stderr = String::from_utf8_lossy(&output.stderr);
+        let mut result = format_output(self.output_mode, &stdout, &stderr);
 
-        // Combine stdout and stderr
-        let mut result = String::new();
-        if !stdout.is_empty() {
-            result.push_str(&stdout);
-        }
-        if !stderr.is_empty() {
-            if !result.is_empty() {
-                result.push('\n');
-            }
-            result.push_str("STDERR:\n");
-            result.push_str(&stderr);
-        }
-
-        // Truncate if too long
+        // Truncate if too long (on a char boundary), remembering the full length
+        let mut original_len = None;
         if result.len() > MAX_OUTPUT_LENGTH {
-            result.truncate(MAX_OUTPUT_LENGTH);
-            result.push_str("\n... (output truncated)");
+            original_len = Some(result.len());
+            let end = truncate_bytes_safe(&result, MAX_OUTPUT_LENGTH).len();
+            result.truncate(end);
         }
 
-        tracing::debug!("Command exit code: {}", exit_code);
+        tracing::debug!("Command exit code: {:?}", exit_code);
         tracing::debug!("Output length: {} chars", result.len());
 
-        Ok((result, exit_code))
+        Ok(CommandOutput {
+            output: result,
+            exit_code,
+            signal,
+            original_len,
+        })
     }
 }
 
-impl Default for BashTool {
-    fn default() -> Self {
-        Self::with_working_dir(".")
+/// The signal that terminated a process
+#[cfg(unix)]
+fn exit_signal(status: &std::process::ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal()
+}
+
+#[cfg(not(unix))]
+fn exit_signal(_status: &std::process::ExitStatus) -> Option<i32> {
+    None
+}
+
+/// Present a command's stdout and stderr according to `mode`
+fn format_output(mode: BashOutputMode, stdout: &str, stderr: &str) -> String {
+    match mode {
+        // Stderr is already merged into stdout by the shell
+        BashOutputMode::Interleaved => stdout.to_string(),
+        BashOutputMode::Separate => {
+            let section = |text: &str| if text.is_empty() { "(empty)\n".to_string() } else { text.to_string() };
+            format!("STDOUT:\n{}\nSTDERR:\n{}", section(stdout), section(stderr))
+        }
+        BashOutputMode::Combined => {
+            let mut result = stdout.to_string();
+            if !stderr.is_empty() {
+                if !result.is_empty() {
+                    result.push('\n');
+                }
+                result.push_str("STDERR:\n");
+                result.push_str(stderr);
+            }
+            result
+        }
     }
 }
 
@@ -182,7 +254,7 @@ impl Tool for BashTool {
         }
     }
 
-    async fn execute(&self, input: &Value, _internals: &mut AgentInternals) -> Result<ToolResult> {
+    async fn execute(&self, input: &Value, internals: &mut AgentInternals) -> Result<ToolResult> {
         let bash_input: BashInput = serde_json::from_value(input.clone())
             .map_err(|e| anyhow::anyhow!("Invalid bash input: {}", e))?;
 
@@ -192,20 +264,38 @@ impl Tool for BashTool {
             tracing::info!("Command description: {}", desc);
         }
 
-        match self.run_command(&bash_input.command, timeout_ms).await {
-            Ok((output, exit_code)) => {
-                if exit_code == 0 {
-                    if output.is_empty() {
-                        Ok(ToolResult::success("Command completed successfully (no output)"))
-                    } else {
-                        Ok(ToolResult::success(output))
+        let working_dir = self
+            .working_dir
+            .clone()
+            .unwrap_or_else(|| internals.default_base_dir());
+        match self.run_command(&working_dir, &bash_input.command, timeout_ms).await {
+            Ok(CommandOutput { output, exit_code, signal, original_len }) => {
+                let result = match exit_code {
+                    Some(0) if output.is_empty() => {
+                        ToolResult::success("Command completed successfully (no output)")
                     }
-                } else {
-                    Ok(ToolResult::error(format!(
+                    Some(0) => ToolResult::success(output),
+                    Some(code) => ToolResult::error(format!(
                         "Command failed with exit code {}\n{}",
-                        exit_code, output
-                    )))
-                }
+                        code, output
+                    )),
+                    None => match signal {
+                        Some(signal) => ToolResult::error(format!(
+                            "Command terminated by signal {}\n{}",
+                            signal, output
+                        )),
+                        None => ToolResult::error(output),
+                    },
+                };
+                let result = match exit_code {
+                    Some(code) => result.with_exit_code(code),
+                    None => result,
+                };
+
+                Ok(match original_len {
+                    Some(len) => result.with_truncation(len),
+                    None => result,
+                })
             }
             Err(e) => Ok(ToolResult::error(format!("Failed to execute command: {}", e))),
         }
@@ -216,5 +306,55 @@ impl Tool for BashTool {
     }
 }
 
-// Tests temporarily disabled - require AgentInternals test helper
-// TODO: Create test infrastructure for tools that need AgentInternals
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::ToolResultData;
+
+    const SCRIPT: &str = "echo out1; echo err1 >&2; echo out2";
+
+    async fn run(mode: BashOutputMode) -> CommandOutput {
+        let dir = std::env::temp_dir().to_string_lossy().to_string();
+        let tool = BashTool::new().unwrap().with_output_mode(mode);
+        tool.run_command(&dir, SCRIPT, 10_000).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_output_modes() {
+        let combined = run(BashOutputMode::Combined).await;
+        assert_eq!(combined.output, "out1\nout2\n\nSTDERR:\nerr1\n");
+        assert_eq!(combined.exit_code, Some(0));
+
+        let separate = run(BashOutputMode::Separate).await;
+        assert_eq!(separate.output, "STDOUT:\nout1\nout2\n\nSTDERR:\nerr1\n");
+
+        let interleaved = run(BashOutputMode::Interleaved).await;
+        assert_eq!(interleaved.output, "out1\nerr1\nout2\n");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_reports_terminating_signal() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (mut internals, _, _) = crate::runtime::internals::test_internals(temp_dir.path());
+        let tool = BashTool::with_working_dir(temp_dir.path().to_string_lossy());
+
+        let result = tool
+            .execute(&json!({"command": "echo partial; kill -9 $$"}), &mut internals)
+            .await
+            .unwrap();
+        assert!(result.is_error);
+        let ToolResultData::Text(text) = result.content else {
+            panic!("Expected a text result");
+        };
+        assert_eq!(text, "Command terminated by signal 9\npartial\n");
+    }
+
+    #[test]
+    fn test_separate_marks_empty_streams() {
+        assert_eq!(
+            format_output(BashOutputMode::Separate, "", ""),
+            "STDOUT:\n(empty)\n\nSTDERR:\n(empty)\n"
+        );
+    }
+}
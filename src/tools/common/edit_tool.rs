@@ -14,9 +14,10 @@ use crate::llm::{ToolDefinition, ToolInputSchema};
 use crate::runtime::AgentInternals;
 
 /// Edit tool for string replacement in files
+#[derive(Default)]
 pub struct EditTool {
-    /// Base directory for file operations
-    base_dir: String,
+    /// Base directory for file operations (None = the session's working directory)
+    base_dir: Option<String>,
 }
 
 /// Input for the edit tool
@@ -34,29 +35,34 @@ struct EditInput {
 }
 
 impl EditTool {
-    /// Create a new Edit tool with the current directory as base
+    /// Create a new Edit tool that resolves paths against the session's working directory
+    ///
+    /// Falls back to the current directory when the session has none recorded.
     pub fn new() -> Result<Self> {
-        let base_dir = std::env::current_dir()?
-            .to_string_lossy()
-            .to_string();
-
-        Ok(Self { base_dir })
+        Ok(Self { base_dir: None })
     }
 
     /// Create a new Edit tool with a specific base directory
     pub fn with_base_dir(base_dir: impl Into<String>) -> Self {
         Self {
-            base_dir: base_dir.into(),
+            base_dir: Some(base_dir.into()),
         }
     }
 
+    /// Get the base directory, defaulting to the session's working directory
+    fn base_dir(&self, internals: &AgentInternals) -> String {
+        self.base_dir
+            .clone()
+            .unwrap_or_else(|| internals.default_base_dir())
+    }
+
     /// Resolve a path (handle both absolute and relative)
-    fn resolve_path(&self, path: &str) -> String {
+    fn resolve_path(&self, base_dir: &str, path: &str) -> String {
         let path = Path::new(path);
         if path.is_absolute() {
             path.to_string_lossy().to_string()
         } else {
-            Path::new(&self.base_dir)
+            Path::new(base_dir)
                 .join(path)
                 .to_string_lossy()
                 .to_string()
@@ -66,12 +72,13 @@ impl EditTool {
     /// Perform string replacement in a file
     fn str_replace(
         &self,
+        base_dir: &str,
         file_path: &str,
         old_str: &str,
         new_str: &str,
         replace_all: bool,
     ) -> Result<String> {
-        let resolved_path = self.resolve_path(file_path);
+        let resolved_path = self.resolve_path(base_dir, file_path);
         tracing::info!("Editing file: {}", resolved_path);
 
         if old_str == new_str {
@@ -117,12 +124,6 @@ impl EditTool {
     }
 }
 
-impl Default for EditTool {
-    fn default() -> Self {
-        Self::with_base_dir(".")
-    }
-}
-
 #[async_trait]
 impl Tool for EditTool {
     fn name(&self) -> &str {
@@ -189,11 +190,13 @@ impl Tool for EditTool {
         }
     }
 
-    async fn execute(&self, input: &Value, _internals: &mut AgentInternals) -> Result<ToolResult> {
+    async fn execute(&self, input: &Value, internals: &mut AgentInternals) -> Result<ToolResult> {
         let edit_input: EditInput = serde_json::from_value(input.clone())
             .map_err(|e| anyhow::anyhow!("Invalid edit input: {}", e))?;
 
+        let base_dir = self.base_dir(internals);
         match self.str_replace(
+            &base_dir,
             &edit_input.file_path,
             &edit_input.old_string,
             &edit_input.new_string,
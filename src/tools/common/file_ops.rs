@@ -0,0 +1,358 @@
+//! FileOps tool for deleting and renaming files
+//!
+//! Gives the agent `delete` and `rename` commands, so removing or moving
+//! files goes through a tool that asks for permission instead of Bash
+//! `rm`/`mv`. With `with_trash_dir`, deleted files are moved to a trash
+//! directory rather than removed.
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::super::tool::{Tool, ToolInfo, ToolResult};
+use crate::llm::{ToolDefinition, ToolInputSchema};
+use crate::runtime::AgentInternals;
+
+/// FileOps tool for deleting and renaming files
+#[derive(Default)]
+pub struct FileOpsTool {
+    /// Base directory for relative paths (None = the session's working directory)
+    base_dir: Option<String>,
+    /// Directory deleted entries are moved to (None = delete for good)
+    trash_dir: Option<PathBuf>,
+}
+
+/// A FileOps command
+#[derive(Debug, Deserialize)]
+#[serde(tag = "command", rename_all = "lowercase")]
+enum FileCommand {
+    /// Delete a file, or a directory with `recursive`
+    Delete {
+        path: String,
+        #[serde(default)]
+        recursive: bool,
+    },
+    /// Move `path` to `new_path`, creating parent directories
+    Rename { path: String, new_path: String },
+}
+
+impl FileOpsTool {
+    /// Create a new FileOps tool that resolves paths against the session's working directory
+    ///
+    /// Falls back to the current directory when the session has none recorded.
+    pub fn new() -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    /// Create a new FileOps tool with a specific base directory
+    pub fn with_base_dir(base_dir: impl Into<String>) -> Self {
+        Self {
+            base_dir: Some(base_dir.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Move deleted entries to `trash_dir` instead of removing them
+    ///
+    /// A relative path resolves against the base directory. Each entry gets a
+    /// timestamp prefix so repeated deletes of the same name don't collide.
+    pub fn with_trash_dir(mut self, trash_dir: impl Into<PathBuf>) -> Self {
+        self.trash_dir = Some(trash_dir.into());
+        self
+    }
+
+    /// Get the base directory, defaulting to the session's working directory
+    fn base_dir(&self, internals: &AgentInternals) -> String {
+        self.base_dir
+            .clone()
+            .unwrap_or_else(|| internals.default_base_dir())
+    }
+
+    /// Resolve a path (handle both absolute and relative)
+    fn resolve_path(&self, base_dir: &str, path: impl AsRef<Path>) -> PathBuf {
+        Path::new(base_dir).join(path)
+    }
+
+    /// Run a command, returning the message for the model
+    fn run(&self, base_dir: &str, command: &FileCommand) -> Result<String> {
+        match command {
+            FileCommand::Delete { path, recursive } => self.delete(base_dir, path, *recursive),
+            FileCommand::Rename { path, new_path } => self.rename(base_dir, path, new_path),
+        }
+    }
+
+    fn delete(&self, base_dir: &str, path: &str, recursive: bool) -> Result<String> {
+        let resolved = self.resolve_path(base_dir, path);
+        let metadata = fs::symlink_metadata(&resolved)
+            .with_context(|| format!("Cannot delete {}", resolved.display()))?;
+        if let Ok(canonical) = resolved.canonicalize() {
+            if Path::new(base_dir).canonicalize()?.starts_with(&canonical) {
+                bail!("Refusing to delete {}: it is or contains the working directory", resolved.display());
+            }
+            let trash_dir = self.trash_dir.as_ref().map(|dir| self.resolve_path(base_dir, dir));
+            if let Some(trash_dir) = trash_dir.and_then(|dir| dir.canonicalize().ok()) {
+                if trash_dir.starts_with(&canonical) {
+                    bail!("Refusing to delete {}: it is or contains the trash directory", resolved.display());
+                }
+            }
+        }
+        if metadata.is_dir() && !recursive && fs::read_dir(&resolved)?.next().is_some() {
+            bail!("{} is a non-empty directory; pass recursive: true to delete it", resolved.display());
+        }
+
+        if let Some(ref trash_dir) = self.trash_dir {
+            let trash_dir = self.resolve_path(base_dir, trash_dir);
+            fs::create_dir_all(&trash_dir)
+                .with_context(|| format!("Failed to create trash directory: {}", trash_dir.display()))?;
+            let name = resolved.file_name().unwrap_or_default().to_string_lossy();
+            let target = trash_dir.join(format!("{}-{}", chrono::Utc::now().format("%Y%m%d%H%M%S%3f"), name));
+            tracing::info!("Moving {} to trash: {}", resolved.display(), target.display());
+            move_entry(&resolved, &target)
+                .with_context(|| format!("Failed to move {} to trash", resolved.display()))?;
+            return Ok(format!("Moved to trash: {} (now {})", path, target.display()));
+        }
+
+        tracing::info!("Deleting: {}", resolved.display());
+        if metadata.is_dir() {
+            fs::remove_dir_all(&resolved)
+        } else {
+            fs::remove_file(&resolved)
+        }
+        .with_context(|| format!("Failed to delete {}", resolved.display()))?;
+        Ok(format!("Deleted: {}", path))
+    }
+
+    fn rename(&self, base_dir: &str, path: &str, new_path: &str) -> Result<String> {
+        let from = self.resolve_path(base_dir, path);
+        let to = self.resolve_path(base_dir, new_path);
+        if fs::symlink_metadata(&from).is_err() {
+            bail!("Cannot rename {}: no such file or directory", from.display());
+        }
+        if to.exists() {
+            bail!("Cannot rename to {}: it already exists", to.display());
+        }
+
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+        tracing::info!("Renaming {} to {}", from.display(), to.display());
+        fs::rename(&from, &to)
+            .with_context(|| format!("Failed to rename {} to {}", from.display(), to.display()))?;
+        Ok(format!("Renamed {} to {}", path, new_path))
+    }
+}
+
+/// Move `from` to `to`, copying and removing it when they are on different filesystems
+fn move_entry(from: &Path, to: &Path) -> std::io::Result<()> {
+    match fs::rename(from, to) {
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            copy_entry(from, to)?;
+            if fs::symlink_metadata(from)?.is_dir() {
+                fs::remove_dir_all(from)
+            } else {
+                fs::remove_file(from)
+            }
+        }
+        result => result,
+    }
+}
+
+/// Copy a file, symlink or directory tree from `from` to `to`
+fn copy_entry(from: &Path, to: &Path) -> std::io::Result<()> {
+    let metadata = fs::symlink_metadata(from)?;
+    if metadata.is_dir() {
+        fs::create_dir(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_entry(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else if metadata.is_symlink() {
+        copy_symlink(from, to)
+    } else {
+        fs::copy(from, to).map(|_| ())
+    }
+}
+
+#[cfg(unix)]
+fn copy_symlink(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(from)?, to)
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::copy(from, to).map(|_| ())
+}
+
+#[async_trait]
+impl Tool for FileOpsTool {
+    fn name(&self) -> &str {
+        "FileOps"
+    }
+
+    fn description(&self) -> &str {
+        "Delete or rename files and directories."
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        use crate::llm::types::CustomTool;
+
+        ToolDefinition::Custom(CustomTool {
+            name: "FileOps".to_string(),
+            description: Some(
+                "Deletes or renames files and directories. Use this instead of rm or mv in Bash. \
+                \"delete\" removes `path` (set `recursive` for a non-empty directory). \
+                \"rename\" moves `path` to `new_path`, creating parent directories; \
+                it fails if `new_path` already exists."
+                    .to_string(),
+            ),
+            input_schema: ToolInputSchema {
+                schema_type: "object".to_string(),
+                properties: Some(json!({
+                    "command": {
+                        "type": "string",
+                        "enum": ["delete", "rename"],
+                        "description": "The operation to perform"
+                    },
+                    "path": {
+                        "type": "string",
+                        "description": "The file or directory to delete or rename"
+                    },
+                    "new_path": {
+                        "type": "string",
+                        "description": "Where to move `path` (rename only)"
+                    },
+                    "recursive": {
+                        "type": "boolean",
+                        "description": "Delete a non-empty directory and its contents (delete only, default: false)"
+                    }
+                })),
+                required: Some(vec!["command".to_string(), "path".to_string()]),
+            },
+            tool_type: None,
+            cache_control: None,
+        })
+    }
+
+    fn get_info(&self, input: &Value) -> ToolInfo {
+        let field = |key: &str| input.get(key).and_then(|v| v.as_str()).unwrap_or("?");
+        let recursive = input.get("recursive").and_then(|v| v.as_bool()).unwrap_or(false);
+
+        let (action_description, details) = match field("command") {
+            "delete" => (
+                format!("Delete: {}", field("path")),
+                match (&self.trash_dir, recursive) {
+                    (Some(trash_dir), _) => Some(format!("Will be moved to {}", trash_dir.display())),
+                    (None, true) => Some("Including everything inside it".to_string()),
+                    (None, false) => None,
+                },
+            ),
+            "rename" => (format!("Rename: {} → {}", field("path"), field("new_path")), None),
+            other => (format!("FileOps {}: {}", other, field("path")), None),
+        };
+
+        ToolInfo {
+            name: "FileOps".to_string(),
+            action_description,
+            details,
+        }
+    }
+
+    async fn execute(&self, input: &Value, internals: &mut AgentInternals) -> Result<ToolResult> {
+        let command: FileCommand = serde_json::from_value(input.clone())
+            .map_err(|e| anyhow::anyhow!("Invalid FileOps input: {}", e))?;
+
+        let base_dir = self.base_dir(internals);
+        match self.run(&base_dir, &command) {
+            Ok(output) => Ok(ToolResult::success(output)),
+            Err(e) => Ok(ToolResult::error(format!("{:#}", e))),
+        }
+    }
+
+    fn requires_permission(&self) -> bool {
+        true // Deletes and moves files
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn command(input: Value) -> FileCommand {
+        serde_json::from_value(input).unwrap()
+    }
+
+    #[test]
+    fn test_delete_and_rename() {
+        let dir = TempDir::new().unwrap();
+        let base = dir.path().to_string_lossy().to_string();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/old.rs"), "fn main() {}").unwrap();
+        let tool = FileOpsTool::with_base_dir(&base);
+
+        tool.run(&base, &command(json!({"command": "rename", "path": "src/old.rs", "new_path": "bin/new.rs"})))
+            .unwrap();
+        assert_eq!(fs::read_to_string(dir.path().join("bin/new.rs")).unwrap(), "fn main() {}");
+        assert!(tool
+            .run(&base, &command(json!({"command": "rename", "path": "src/old.rs", "new_path": "x.rs"})))
+            .is_err());
+
+        let delete_bin = command(json!({"command": "delete", "path": "bin"}));
+        assert!(tool.run(&base, &delete_bin).is_err());
+        tool.run(&base, &command(json!({"command": "delete", "path": "bin", "recursive": true})))
+            .unwrap();
+        assert!(!dir.path().join("bin").exists());
+        assert!(tool.run(&base, &command(json!({"command": "delete", "path": "."}))).is_err());
+    }
+
+    #[test]
+    fn test_refuses_to_delete_ancestors() {
+        let dir = TempDir::new().unwrap();
+        let base = dir.path().join("project");
+        fs::create_dir_all(base.join(".trash")).unwrap();
+        let base = base.to_string_lossy().to_string();
+        let tool = FileOpsTool::with_base_dir(&base).with_trash_dir(".trash");
+
+        for path in [".", "..", "../project", "/", ".trash"] {
+            let delete = command(json!({"command": "delete", "path": path, "recursive": true}));
+            assert!(tool.run(&base, &delete).is_err(), "deleted {}", path);
+        }
+        assert!(Path::new(&base).join(".trash").exists());
+    }
+
+    #[test]
+    fn test_move_entry_copies_tree() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("from/nested")).unwrap();
+        fs::write(dir.path().join("from/nested/file.txt"), "data").unwrap();
+
+        copy_entry(&dir.path().join("from"), &dir.path().join("to")).unwrap();
+        assert_eq!(fs::read_to_string(dir.path().join("to/nested/file.txt")).unwrap(), "data");
+
+        move_entry(&dir.path().join("to"), &dir.path().join("moved")).unwrap();
+        assert!(!dir.path().join("to").exists());
+        assert!(dir.path().join("moved/nested/file.txt").exists());
+    }
+
+    #[test]
+    fn test_delete_to_trash() {
+        let dir = TempDir::new().unwrap();
+        let base = dir.path().to_string_lossy().to_string();
+        fs::write(dir.path().join("notes.txt"), "keep me").unwrap();
+        let tool = FileOpsTool::with_base_dir(&base).with_trash_dir(".trash");
+
+        tool.run(&base, &command(json!({"command": "delete", "path": "notes.txt"})))
+            .unwrap();
+        assert!(!dir.path().join("notes.txt").exists());
+        let trashed: Vec<_> = fs::read_dir(dir.path().join(".trash")).unwrap().collect();
+        assert_eq!(trashed.len(), 1);
+        let trashed = trashed[0].as_ref().unwrap().path();
+        assert!(trashed.to_string_lossy().ends_with("-notes.txt"));
+        assert_eq!(fs::read_to_string(trashed).unwrap(), "keep me");
+    }
+}
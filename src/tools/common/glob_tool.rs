@@ -1,22 +1,28 @@
 //! Glob tool for file pattern matching
 //!
 //! Fast file pattern matching tool that works with any codebase size.
+//! With `with_respect_gitignore(true)`, files excluded by `.gitignore`
+//! (and `.ignore`) files are left out of the results.
 
 use anyhow::Result;
 use async_trait::async_trait;
-use glob::glob;
+use glob::{glob, MatchOptions, Pattern};
+use ignore::WalkBuilder;
 use serde::Deserialize;
 use serde_json::{json, Value};
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
 
 use super::super::tool::{Tool, ToolInfo, ToolResult};
 use crate::llm::{ToolDefinition, ToolInputSchema};
 use crate::runtime::AgentInternals;
 
 /// Glob tool for file pattern matching
+#[derive(Default)]
 pub struct GlobTool {
-    /// Base directory for searches
-    base_dir: String,
+    /// Base directory for searches (None = the session's working directory)
+    base_dir: Option<String>,
+    /// Leave out files ignored by `.gitignore`
+    respect_gitignore: bool,
 }
 
 /// Input for the glob tool
@@ -29,25 +35,40 @@ struct GlobInput {
 }
 
 impl GlobTool {
-    /// Create a new Glob tool with the current directory as base
+    /// Create a new Glob tool that searches the session's working directory
+    ///
+    /// Falls back to the current directory when the session has none recorded.
     pub fn new() -> Result<Self> {
-        let base_dir = std::env::current_dir()?
-            .to_string_lossy()
-            .to_string();
-
-        Ok(Self { base_dir })
+        Ok(Self::default())
     }
 
     /// Create a new Glob tool with a specific base directory
     pub fn with_base_dir(base_dir: impl Into<String>) -> Self {
         Self {
-            base_dir: base_dir.into(),
+            base_dir: Some(base_dir.into()),
+            ..Self::default()
         }
     }
 
+    /// Leave out files ignored by `.gitignore`, `.ignore` and git's exclude file (default: false)
+    ///
+    /// Keeps `target/`, `node_modules/` and similar out of `**/*` results.
+    /// The `.git` directory is skipped too.
+    pub fn with_respect_gitignore(mut self, respect_gitignore: bool) -> Self {
+        self.respect_gitignore = respect_gitignore;
+        self
+    }
+
+    /// Get the base directory, defaulting to the session's working directory
+    fn base_dir(&self, internals: &AgentInternals) -> String {
+        self.base_dir
+            .clone()
+            .unwrap_or_else(|| internals.default_base_dir())
+    }
+
     /// Search for files matching the glob pattern
-    fn search(&self, pattern: &str, search_dir: Option<&str>) -> Result<Vec<String>> {
-        let base = search_dir.unwrap_or(&self.base_dir);
+    fn search(&self, base_dir: &str, pattern: &str, search_dir: Option<&str>) -> Result<Vec<String>> {
+        let base = search_dir.unwrap_or(base_dir);
 
         let full_pattern = if Path::new(pattern).is_absolute() {
             pattern.to_string()
@@ -57,12 +78,18 @@ impl GlobTool {
 
         tracing::info!("Searching with glob pattern: {}", full_pattern);
 
-        let mut entries: Vec<(String, std::time::SystemTime)> = glob(&full_pattern)?
-            .filter_map(|entry| entry.ok())
+        let paths: Vec<PathBuf> = if self.respect_gitignore {
+            gitignore_glob(&full_pattern)?
+        } else {
+            glob(&full_pattern)?.filter_map(|entry| entry.ok()).collect()
+        };
+
+        let mut entries: Vec<(String, std::time::SystemTime)> = paths
+            .into_iter()
             .filter_map(|path| {
                 let mtime = path.metadata().ok()?.modified().ok()?;
                 let display_path = path
-                    .strip_prefix(&self.base_dir)
+                    .strip_prefix(base_dir)
                     .map(|p| p.to_string_lossy().to_string())
                     .unwrap_or_else(|_| path.to_string_lossy().to_string());
                 Some((display_path, mtime))
@@ -76,10 +103,63 @@ impl GlobTool {
     }
 }
 
-impl Default for GlobTool {
-    fn default() -> Self {
-        Self::with_base_dir(".")
+/// Paths matching `full_pattern` that no ignore file excludes
+///
+/// Walks from the deepest directory of the pattern without glob characters
+/// and matches the rest of the pattern against paths relative to it, so
+/// `./` or doubled slashes in the pattern don't matter. `*` does not match
+/// `/`, as with `glob()`.
+fn gitignore_glob(full_pattern: &str) -> Result<Vec<PathBuf>> {
+    let is_literal = |component: &Component| {
+        !component
+            .as_os_str()
+            .to_string_lossy()
+            .contains(['*', '?', '['])
+    };
+    let components: Vec<Component> = Path::new(full_pattern).components().collect();
+    let split = components
+        .iter()
+        .position(|component| !is_literal(component))
+        .unwrap_or(components.len());
+
+    let mut root: PathBuf = components[..split].iter().collect();
+    if root.as_os_str().is_empty() {
+        root = PathBuf::from(".");
     }
+    let rest = &components[split..];
+    let pattern = Pattern::new(
+        &rest
+            .iter()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"),
+    )?;
+    let options = MatchOptions {
+        require_literal_separator: true,
+        ..MatchOptions::new()
+    };
+
+    // Without `**` a match can't be deeper than the pattern's own components
+    let max_depth = if rest.iter().any(|component| component.as_os_str() == "**") {
+        None
+    } else {
+        Some(rest.len())
+    };
+
+    let walker = WalkBuilder::new(&root)
+        .hidden(false)
+        .require_git(false)
+        .max_depth(max_depth)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build();
+    Ok(walker
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.into_path())
+        .filter(|path| {
+            path.strip_prefix(&root)
+                .is_ok_and(|relative| pattern.matches_path_with(relative, options))
+        })
+        .collect())
 }
 
 #[async_trait]
@@ -135,11 +215,12 @@ impl Tool for GlobTool {
         }
     }
 
-    async fn execute(&self, input: &Value, _internals: &mut AgentInternals) -> Result<ToolResult> {
+    async fn execute(&self, input: &Value, internals: &mut AgentInternals) -> Result<ToolResult> {
         let glob_input: GlobInput = serde_json::from_value(input.clone())
             .map_err(|e| anyhow::anyhow!("Invalid glob input: {}", e))?;
 
-        match self.search(&glob_input.pattern, glob_input.path.as_deref()) {
+        let base_dir = self.base_dir(internals);
+        match self.search(&base_dir, &glob_input.pattern, glob_input.path.as_deref()) {
             Ok(entries) => {
                 if entries.is_empty() {
                     Ok(ToolResult::success(format!(
@@ -168,7 +249,58 @@ impl Tool for GlobTool {
     fn requires_permission(&self) -> bool {
         false // Read-only operation
     }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
+
+    fn is_cacheable(&self) -> bool {
+        true
+    }
 }
 
-// Tests temporarily disabled - require AgentInternals test helper
-// TODO: Create test infrastructure for tools that need AgentInternals
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_respect_gitignore() {
+        let dir = TempDir::new().unwrap();
+        let base = dir.path().to_string_lossy().to_string();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::create_dir_all(dir.path().join("target/debug")).unwrap();
+        fs::write(dir.path().join(".gitignore"), "target/\n").unwrap();
+        fs::write(dir.path().join("src/lib.rs"), "").unwrap();
+        fs::write(dir.path().join("target/debug/build.rs"), "").unwrap();
+
+        let mut all = GlobTool::with_base_dir(&base).search(&base, "**/*.rs", None).unwrap();
+        all.sort();
+        assert_eq!(all, vec!["src/lib.rs", "target/debug/build.rs"]);
+
+        let tool = GlobTool::with_base_dir(&base).with_respect_gitignore(true);
+        assert_eq!(tool.search(&base, "**/*.rs", None).unwrap(), vec!["src/lib.rs"]);
+        assert_eq!(tool.search(&base, "*.rs", Some(&format!("{}/src", base))).unwrap(), vec!["src/lib.rs"]);
+        assert!(tool.search(&base, "*.rs", None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_respect_gitignore_normalizes_pattern() {
+        let dir = TempDir::new().unwrap();
+        let base = dir.path().to_string_lossy().to_string();
+        fs::create_dir_all(dir.path().join("src/nested")).unwrap();
+        fs::write(dir.path().join("src/lib.rs"), "").unwrap();
+        fs::write(dir.path().join("src/nested/mod.rs"), "").unwrap();
+
+        let tool = GlobTool::with_base_dir(&base).with_respect_gitignore(true);
+        assert_eq!(tool.search(&base, "./src/*.rs", None).unwrap(), vec!["src/lib.rs"]);
+        let trailing = format!("{}/", base);
+        assert_eq!(tool.search(&trailing, "src/*.rs", None).unwrap(), vec!["src/lib.rs"]);
+        assert_eq!(tool.search(&base, "src/lib.rs", None).unwrap(), vec!["src/lib.rs"]);
+
+        let mut all = tool.search(&base, "src/**/*.rs", None).unwrap();
+        all.sort();
+        assert_eq!(all, vec!["src/lib.rs", "src/nested/mod.rs"]);
+    }
+}
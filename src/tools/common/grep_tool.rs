@@ -14,9 +14,10 @@ use crate::llm::{ToolDefinition, ToolInputSchema};
 use crate::runtime::AgentInternals;
 
 /// Grep tool for content search
+#[derive(Default)]
 pub struct GrepTool {
-    /// Base directory for searches
-    base_dir: String,
+    /// Base directory for searches (None = the session's working directory)
+    base_dir: Option<String>,
 }
 
 /// Output mode for grep results
@@ -72,28 +73,34 @@ struct GrepInput {
 }
 
 impl GrepTool {
-    /// Create a new Grep tool with the current directory as base
+    /// Create a new Grep tool that searches the session's working directory
+    ///
+    /// Falls back to the current directory when the session has none recorded.
     pub fn new() -> Result<Self> {
-        let base_dir = std::env::current_dir()?
-            .to_string_lossy()
-            .to_string();
-
-        Ok(Self { base_dir })
+        Ok(Self { base_dir: None })
     }
 
     /// Create a new Grep tool with a specific base directory
     pub fn with_base_dir(base_dir: impl Into<String>) -> Self {
         Self {
-            base_dir: base_dir.into(),
+            base_dir: Some(base_dir.into()),
         }
     }
 
+    /// Get the base directory, defaulting to the session's working directory
+    fn base_dir(&self, internals: &AgentInternals) -> String {
+        self.base_dir
+            .clone()
+            .unwrap_or_else(|| internals.default_base_dir())
+    }
+
     /// Execute ripgrep with the given options
-    async fn search(&self, input: &GrepInput) -> Result<String> {
-        let search_path = input.path.as_deref().unwrap_or(&self.base_dir);
+    async fn search(&self, base_dir: &str, input: &GrepInput) -> Result<String> {
+        let search_path = input.path.as_deref().unwrap_or(base_dir);
         let output_mode = input.output_mode.unwrap_or_default();
 
         let mut cmd = Command::new("rg");
+        cmd.current_dir(base_dir);
 
         // Add pattern
         cmd.arg(&input.pattern);
@@ -195,12 +202,6 @@ impl GrepTool {
     }
 }
 
-impl Default for GrepTool {
-    fn default() -> Self {
-        Self::with_base_dir(".")
-    }
-}
-
 #[async_trait]
 impl Tool for GrepTool {
     fn name(&self) -> &str {
@@ -299,11 +300,12 @@ impl Tool for GrepTool {
         }
     }
 
-    async fn execute(&self, input: &Value, _internals: &mut AgentInternals) -> Result<ToolResult> {
+    async fn execute(&self, input: &Value, internals: &mut AgentInternals) -> Result<ToolResult> {
         let grep_input: GrepInput = serde_json::from_value(input.clone())
             .map_err(|e| anyhow::anyhow!("Invalid grep input: {}", e))?;
 
-        match self.search(&grep_input).await {
+        let base_dir = self.base_dir(internals);
+        match self.search(&base_dir, &grep_input).await {
             Ok(output) => {
                 if output.is_empty() {
                     Ok(ToolResult::success(format!(
@@ -321,6 +323,14 @@ impl Tool for GrepTool {
     fn requires_permission(&self) -> bool {
         false // Read-only operation
     }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
+
+    fn is_cacheable(&self) -> bool {
+        true
+    }
 }
 
 // Tests temporarily disabled - require AgentInternals test helper
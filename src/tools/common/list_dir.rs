@@ -0,0 +1,348 @@
+//! LS tool for listing a directory tree
+//!
+//! Lists a directory and its subdirectories up to a depth, with file sizes,
+//! so the agent can explore the project structure on demand.
+//! With `with_respect_gitignore(true)`, entries excluded by `.gitignore`
+//! (and `.ignore`) files are left out.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use glob::Pattern;
+use ignore::WalkBuilder;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use super::super::tool::{Tool, ToolInfo, ToolResult};
+use crate::helpers::{directory_entries, format_size};
+use crate::llm::{ToolDefinition, ToolInputSchema};
+use crate::runtime::AgentInternals;
+
+/// Depth listed when the input does not give one
+const DEFAULT_DEPTH: usize = 2;
+
+/// Deepest listing allowed
+const MAX_DEPTH: usize = 10;
+
+/// Entries listed before the output is cut off
+const MAX_ENTRIES: usize = 500;
+
+/// Directories skipped unless listed explicitly
+const SKIPPED_DIRS: &[&str] = &[".git"];
+
+/// LS tool for listing directory trees
+#[derive(Default)]
+pub struct ListDirTool {
+    /// Base directory for relative paths (None = the session's working directory)
+    base_dir: Option<String>,
+    /// Leave out entries ignored by `.gitignore`
+    respect_gitignore: bool,
+}
+
+/// Input for the LS tool
+#[derive(Debug, Deserialize)]
+struct ListDirInput {
+    /// Directory to list (optional)
+    path: Option<String>,
+    /// How many levels to descend (optional)
+    depth: Option<usize>,
+    /// Glob patterns of names to leave out (optional)
+    #[serde(default)]
+    ignore: Vec<String>,
+}
+
+impl ListDirTool {
+    /// Create a new LS tool that lists relative to the session's working directory
+    ///
+    /// Falls back to the current directory when the session has none recorded.
+    pub fn new() -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    /// Create a new LS tool confined to `base_dir`
+    ///
+    /// Relative paths resolve against it, and paths outside it are refused.
+    pub fn with_base_dir(base_dir: impl Into<String>) -> Self {
+        Self {
+            base_dir: Some(base_dir.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Leave out entries ignored by `.gitignore`, `.ignore` and git's exclude file (default: false)
+    pub fn with_respect_gitignore(mut self, respect_gitignore: bool) -> Self {
+        self.respect_gitignore = respect_gitignore;
+        self
+    }
+
+    /// Get the base directory, defaulting to the session's working directory
+    fn base_dir(&self, internals: &AgentInternals) -> String {
+        self.base_dir
+            .clone()
+            .unwrap_or_else(|| internals.default_base_dir())
+    }
+
+    /// Resolve `path` against `base_dir`, refusing paths outside a configured base
+    fn resolve(&self, base_dir: &str, path: Option<&str>) -> Result<PathBuf> {
+        let resolved = match path {
+            Some(path) => Path::new(base_dir).join(path),
+            None => PathBuf::from(base_dir),
+        };
+        let resolved = resolved
+            .canonicalize()
+            .map_err(|e| anyhow::anyhow!("Cannot access {}: {}", resolved.display(), e))?;
+
+        if self.base_dir.is_some() {
+            let base = Path::new(base_dir).canonicalize()?;
+            if !resolved.starts_with(&base) {
+                anyhow::bail!("{} is outside the allowed directory {}", resolved.display(), base.display());
+            }
+        }
+        if !resolved.is_dir() {
+            anyhow::bail!("{} is not a directory", resolved.display());
+        }
+        Ok(resolved)
+    }
+
+    /// List the tree under `base_dir`/`path`
+    fn list(&self, base_dir: &str, path: Option<&str>, depth: usize, ignore: &[String]) -> Result<String> {
+        let root = self.resolve(base_dir, path)?;
+        let ignore = ignore
+            .iter()
+            .map(|pattern| Pattern::new(pattern))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| anyhow::anyhow!("Invalid ignore pattern: {}", e))?;
+
+        let depth = depth.clamp(1, MAX_DEPTH);
+        let listed = self.respect_gitignore.then(|| not_ignored(&root, depth));
+
+        let mut output = format!("{}/\n", root.display());
+        let mut count = 0;
+        let filter = Filter {
+            patterns: &ignore,
+            listed: listed.as_ref(),
+        };
+        let complete = list_level(&root, 1, depth, &filter, &mut output, &mut count)?;
+        if !complete {
+            output.push_str(&format!(
+                "... stopped after {} entries; list a subdirectory or lower the depth\n",
+                MAX_ENTRIES
+            ));
+        } else if count == 0 {
+            output.push_str("  (empty directory)\n");
+        }
+        Ok(output)
+    }
+}
+
+/// Which entries are left out of a listing
+struct Filter<'a> {
+    /// Name patterns from the input
+    patterns: &'a [Pattern],
+    /// Entries not excluded by ignore files, when respecting them
+    listed: Option<&'a HashSet<PathBuf>>,
+}
+
+impl Filter<'_> {
+    fn skips(&self, dir: &Path, name: &str) -> bool {
+        if self.patterns.iter().any(|pattern| pattern.matches(name)) {
+            return true;
+        }
+        // Skipped directories are still shown, so they aren't in `listed`
+        self.listed
+            .is_some_and(|listed| !SKIPPED_DIRS.contains(&name) && !listed.contains(&dir.join(name)))
+    }
+}
+
+/// Paths under `root`, down to `depth` levels, that no ignore file excludes
+fn not_ignored(root: &Path, depth: usize) -> HashSet<PathBuf> {
+    WalkBuilder::new(root)
+        .max_depth(Some(depth))
+        .hidden(false)
+        .require_git(false)
+        .filter_entry(|entry| !SKIPPED_DIRS.contains(&entry.file_name().to_string_lossy().as_ref()))
+        .build()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+/// Append the entries of `dir` at `level` and below; false if the entry limit was hit
+fn list_level(
+    dir: &Path,
+    level: usize,
+    depth: usize,
+    filter: &Filter,
+    output: &mut String,
+    count: &mut usize,
+) -> Result<bool> {
+    let indent = "  ".repeat(level);
+    for (name, is_dir, size) in directory_entries(dir)? {
+        if filter.skips(dir, &name) {
+            continue;
+        }
+        if *count == MAX_ENTRIES {
+            return Ok(false);
+        }
+        *count += 1;
+
+        if !is_dir {
+            output.push_str(&format!("{}{} ({})\n", indent, name, format_size(size)));
+        } else if SKIPPED_DIRS.contains(&name.as_str()) {
+            output.push_str(&format!("{}{}/ (skipped)\n", indent, name));
+        } else {
+            output.push_str(&format!("{}{}/\n", indent, name));
+            // Unreadable subdirectories are listed without their contents
+            if level < depth && !list_level(&dir.join(&name), level + 1, depth, filter, output, count).unwrap_or(true) {
+                return Ok(false);
+            }
+        }
+    }
+    Ok(true)
+}
+
+#[async_trait]
+impl Tool for ListDirTool {
+    fn name(&self) -> &str {
+        "LS"
+    }
+
+    fn description(&self) -> &str {
+        "List a directory tree with file sizes."
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        use crate::llm::types::CustomTool;
+
+        ToolDefinition::Custom(CustomTool {
+            name: "LS".to_string(),
+            description: Some(format!(
+                "Lists a directory and its subdirectories as an indented tree, with file sizes. \
+                Use it to explore the project structure; use Glob to find files by name. \
+                Descends {} levels by default (at most {}) and stops after {} entries.",
+                DEFAULT_DEPTH, MAX_DEPTH, MAX_ENTRIES
+            )),
+            input_schema: ToolInputSchema {
+                schema_type: "object".to_string(),
+                properties: Some(json!({
+                    "path": {
+                        "type": "string",
+                        "description": "The directory to list. If not specified, the current working directory will be used."
+                    },
+                    "depth": {
+                        "type": "integer",
+                        "description": "How many levels to descend (1 lists only the directory itself)"
+                    },
+                    "ignore": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Glob patterns of file and directory names to leave out, e.g. [\"target\", \"*.log\"]"
+                    }
+                })),
+                required: None,
+            },
+            tool_type: None,
+            cache_control: None,
+        })
+    }
+
+    fn get_info(&self, input: &Value) -> ToolInfo {
+        let path = input.get("path").and_then(|v| v.as_str()).unwrap_or(".");
+
+        ToolInfo {
+            name: "LS".to_string(),
+            action_description: format!("List directory: {}", path),
+            details: None,
+        }
+    }
+
+    async fn execute(&self, input: &Value, internals: &mut AgentInternals) -> Result<ToolResult> {
+        let ls_input: ListDirInput = serde_json::from_value(input.clone())
+            .map_err(|e| anyhow::anyhow!("Invalid LS input: {}", e))?;
+
+        let base_dir = self.base_dir(internals);
+        let depth = ls_input.depth.unwrap_or(DEFAULT_DEPTH);
+        match self.list(&base_dir, ls_input.path.as_deref(), depth, &ls_input.ignore) {
+            Ok(listing) => Ok(ToolResult::success(listing)),
+            Err(e) => Ok(ToolResult::error(format!("LS failed: {}", e))),
+        }
+    }
+
+    fn requires_permission(&self) -> bool {
+        false // Read-only operation
+    }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
+
+    fn is_cacheable(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_list_tree() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("src/nested")).unwrap();
+        fs::create_dir_all(root.join("target")).unwrap();
+        fs::write(root.join("Cargo.toml"), "[package]").unwrap();
+        fs::write(root.join("src/lib.rs"), "x".repeat(2048)).unwrap();
+        fs::write(root.join("src/nested/deep.rs"), "").unwrap();
+
+        let tool = ListDirTool::with_base_dir(root.to_string_lossy());
+        let base = root.to_string_lossy();
+        let listing = tool.list(&base, None, 2, &["target".to_string()]).unwrap();
+        assert_eq!(
+            listing,
+            format!(
+                "{}/\n  src/\n    nested/\n    lib.rs (2.0 KB)\n  Cargo.toml (9 B)\n",
+                root.display()
+            )
+        );
+
+        let listing = tool.list(&base, Some("src"), 5, &[]).unwrap();
+        assert!(listing.ends_with("/src/\n  nested/\n    deep.rs (0 B)\n  lib.rs (2.0 KB)\n"));
+    }
+
+    #[test]
+    fn test_respect_gitignore() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("target/debug")).unwrap();
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::write(root.join(".gitignore"), "target/\n*.log\n").unwrap();
+        fs::write(root.join("run.log"), "").unwrap();
+        fs::write(root.join("main.rs"), "").unwrap();
+
+        let base = root.to_string_lossy();
+        let tool = ListDirTool::with_base_dir(root.to_string_lossy()).with_respect_gitignore(true);
+        let listing = tool.list(&base, None, 2, &[]).unwrap();
+        assert_eq!(
+            listing,
+            format!("{}/\n  .git/ (skipped)\n  .gitignore (14 B)\n  main.rs (0 B)\n", root.display())
+        );
+    }
+
+    #[test]
+    fn test_confined_to_base_dir() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("project")).unwrap();
+        let base = dir.path().join("project").to_string_lossy().to_string();
+
+        let confined = ListDirTool::with_base_dir(&base);
+        assert!(confined.list(&base, Some(".."), 1, &[]).is_err());
+        assert!(confined.list(&base, Some("missing"), 1, &[]).is_err());
+
+        let unconfined = ListDirTool::default();
+        assert!(unconfined.list(&base, Some(".."), 1, &[]).is_ok());
+    }
+}
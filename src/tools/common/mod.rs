@@ -6,27 +6,41 @@
 //! - `ReadTool` - Read file contents
 //! - `WriteTool` - Write files
 //! - `EditTool` - Edit files with string replacement
+//! - `FileOpsTool` - Delete and rename files
+//! - `ApplyPatchTool` - Edit files with a unified diff
 //! - `GlobTool` - Find files by pattern
+//! - `ListDirTool` - List a directory tree (`LS`)
 //! - `GrepTool` - Search file contents
 //! - `TodoWriteTool` - Manage todo lists
 //! - `PresentFileTool` - Present files to the user
+//! - `SessionQueryTool` - List and read other sessions
 
+pub mod apply_patch;
 pub mod ask_user_question;
 pub mod bash;
 pub mod edit_tool;
+pub mod file_ops;
 pub mod glob_tool;
 pub mod grep_tool;
+pub mod list_dir;
+mod outline;
+mod patch;
 pub mod present_file;
 pub mod read_tool;
+pub mod session_query;
 pub mod todo;
 pub mod write_tool;
 
+pub use apply_patch::ApplyPatchTool;
 pub use ask_user_question::AskUserQuestionTool;
-pub use bash::BashTool;
+pub use bash::{BashOutputMode, BashTool};
 pub use edit_tool::EditTool;
+pub use file_ops::FileOpsTool;
 pub use glob_tool::GlobTool;
 pub use grep_tool::GrepTool;
+pub use list_dir::ListDirTool;
 pub use present_file::PresentFileTool;
 pub use read_tool::ReadTool;
+pub use session_query::SessionQueryTool;
 pub use todo::TodoWriteTool;
 pub use write_tool::WriteTool;
@@ -0,0 +1,350 @@
+//! Source file outlines for the Read tool
+//!
+//! Extracts declarations (functions, types, classes, modules) with their line
+//! numbers so the model can see a file's structure without reading all of it.
+//!
+//! Detection is line-based: a line counts as a symbol when it is indented at
+//! most one level and starts with a declaration keyword for the language,
+//! after visibility and other modifiers. This catches top-level items and
+//! the methods directly inside them, without needing a parser per language.
+//! In JavaScript and TypeScript, `const`/`let`/`var` bindings whose value is
+//! a function or arrow function count as well.
+//!
+//! Comments and docstrings are skipped, so prose that happens to start with
+//! a keyword is not reported. Block comments are recognized when they start
+//! a line, Python's triple-quoted strings wherever they open.
+
+/// Maximum indentation (in columns) for a line to be considered a symbol
+const MAX_INDENT: usize = 4;
+/// Maximum characters kept from a symbol's declaration line
+const MAX_SIGNATURE_LENGTH: usize = 160;
+
+/// Modifiers skipped before looking for a declaration keyword
+const MODIFIERS: &[&str] = &[
+    "pub", "public", "private", "protected", "internal", "static", "final", "abstract",
+    "sealed", "data", "open", "override", "export", "default", "async", "declare", "unsafe",
+    "extern", "inline", "suspend",
+];
+
+/// A declaration found in a source file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    /// 1-indexed line number
+    pub line: usize,
+    /// The declaration line, trimmed of its body opener
+    pub signature: String,
+}
+
+/// How declarations and comments look in a language
+struct Language {
+    /// Keywords that start a declaration
+    keywords: &'static [&'static str],
+    /// Keywords that bind a name, counted when the value is a function
+    bindings: &'static [&'static str],
+    /// Line comment prefixes
+    line_comments: &'static [&'static str],
+    /// Block comment and docstring delimiters, as (open, close)
+    blocks: &'static [(&'static str, &'static str)],
+}
+
+impl Language {
+    /// A language with C-style comments
+    const fn c_style(keywords: &'static [&'static str]) -> Self {
+        Self {
+            keywords,
+            bindings: &[],
+            line_comments: &["//"],
+            blocks: &[("/*", "*/")],
+        }
+    }
+}
+
+/// Language rules for a file extension, or `None` if the language is not recognized
+fn language_for(extension: &str) -> Option<Language> {
+    let language = match extension {
+        "rs" => Language::c_style(&[
+            "fn", "struct", "enum", "trait", "impl", "mod", "type", "const", "static", "union",
+            "macro_rules!",
+        ]),
+        "py" | "pyi" => Language {
+            keywords: &["def", "class"],
+            bindings: &[],
+            line_comments: &["#"],
+            blocks: &[("\"\"\"", "\"\"\""), ("'''", "'''")],
+        },
+        "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "mts" | "cts" => Language {
+            bindings: &["const", "let", "var"],
+            ..Language::c_style(&["function", "function*", "class", "interface", "type", "enum", "namespace"])
+        },
+        "go" => Language::c_style(&["func", "type"]),
+        "java" | "cs" => Language::c_style(&["class", "interface", "enum", "record", "struct", "namespace"]),
+        "kt" | "kts" => Language::c_style(&["fun", "class", "interface", "object", "enum", "typealias"]),
+        "swift" => Language::c_style(&["func", "class", "struct", "enum", "protocol", "extension"]),
+        "rb" => Language {
+            keywords: &["def", "class", "module"],
+            bindings: &[],
+            line_comments: &["#"],
+            blocks: &[("=begin", "=end")],
+        },
+        "php" => Language {
+            line_comments: &["//", "#"],
+            ..Language::c_style(&["function", "class", "interface", "trait", "enum"])
+        },
+        _ => return None,
+    };
+    Some(language)
+}
+
+/// Build an outline of `content`, or `None` if the extension is not recognized
+pub fn outline(content: &str, extension: &str) -> Option<Vec<Symbol>> {
+    let language = language_for(extension)?;
+
+    let mut symbols = Vec::new();
+    // Closing delimiter of the block comment or docstring being skipped
+    let mut inside_block: Option<&str> = None;
+    for (i, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+
+        if let Some(close) = inside_block {
+            if trimmed.contains(close) {
+                inside_block = None;
+            }
+            continue;
+        }
+        if language.line_comments.iter().any(|prefix| trimmed.starts_with(prefix)) {
+            continue;
+        }
+        inside_block = opened_block(trimmed, language.blocks);
+        if language.blocks.iter().any(|(open, _)| trimmed.starts_with(open)) {
+            continue;
+        }
+
+        if indent_width(line) > MAX_INDENT {
+            continue;
+        }
+        let rest = strip_modifiers(trimmed, language.keywords);
+        if is_declaration(rest, language.keywords) || is_function_binding(rest, language.bindings) {
+            symbols.push(Symbol {
+                line: i + 1,
+                signature: signature(line),
+            });
+        }
+    }
+
+    Some(symbols)
+}
+
+/// The closing delimiter of a block comment or docstring left open by a line
+///
+/// Delimiters that also close the block (Python's `"""`) count wherever they
+/// appear and leave a block open when they occur an odd number of times.
+/// Others only count at the start of the line, since `/*` also turns up in
+/// strings such as glob patterns.
+fn opened_block(trimmed: &str, blocks: &[(&'static str, &'static str)]) -> Option<&'static str> {
+    blocks.iter().find_map(|&(open, close)| {
+        let left_open = if open == close {
+            trimmed.matches(open).count() % 2 == 1
+        } else {
+            trimmed
+                .strip_prefix(open)
+                .is_some_and(|rest| !rest.contains(close))
+        };
+        left_open.then_some(close)
+    })
+}
+
+/// Width of a line's leading whitespace, counting tabs as four columns
+fn indent_width(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum()
+}
+
+/// Skip leading modifiers such as `pub(crate)`, `export default` or `extern "C"`
+///
+/// A modifier that is also one of the language's keywords (Rust's `static`)
+/// is kept, since it starts the declaration.
+fn strip_modifiers<'a>(mut rest: &'a str, keywords: &[&str]) -> &'a str {
+    loop {
+        let word_end = rest
+            .find(|c: char| c.is_whitespace() || c == '(')
+            .unwrap_or(rest.len());
+        let word = &rest[..word_end];
+        if !MODIFIERS.contains(&word) || keywords.contains(&word) {
+            return rest;
+        }
+        rest = &rest[word_end..];
+
+        // Visibility scope, e.g. `pub(crate)`
+        if rest.starts_with('(') {
+            match rest.find(')') {
+                Some(close) => rest = &rest[close + 1..],
+                None => return rest,
+            }
+        }
+        rest = rest.trim_start();
+
+        // ABI string, e.g. `extern "C"`
+        if rest.starts_with('"') {
+            match rest[1..].find('"') {
+                Some(close) => rest = rest[close + 2..].trim_start(),
+                None => return rest,
+            }
+        }
+    }
+}
+
+/// Whether a line (with modifiers stripped) starts with one of the keywords
+fn is_declaration(rest: &str, keywords: &[&str]) -> bool {
+    keywords.iter().any(|keyword| {
+        rest.strip_prefix(keyword).is_some_and(|after| {
+            keyword.ends_with('!')
+                || after.starts_with(|c: char| c.is_whitespace() || c == '<' || c == '(')
+        })
+    })
+}
+
+/// Whether a line binds a name to a function, e.g. `const load = async (id) => {`
+fn is_function_binding(rest: &str, bindings: &[&str]) -> bool {
+    let Some(after) = bindings.iter().find_map(|binding| {
+        rest.strip_prefix(binding)
+            .filter(|after| after.starts_with(char::is_whitespace))
+    }) else {
+        return false;
+    };
+
+    // The assignment, skipping `=>` in a type annotation and `==`
+    let Some((eq, _)) = after
+        .char_indices()
+        .find(|&(i, c)| c == '=' && !after[i + 1..].starts_with(['>', '=']))
+    else {
+        return false;
+    };
+    let value = after[eq + 1..].trim_start();
+    let value = value.strip_prefix("async").unwrap_or(value).trim_start();
+
+    if value.starts_with("function") {
+        return true;
+    }
+    // Arrow function: `(params) =>` or `param =>`
+    match value.split_once("=>") {
+        Some((params, _)) => {
+            let params = params.trim();
+            params.starts_with('(')
+                || (!params.is_empty()
+                    && params.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '$'))
+        }
+        None => false,
+    }
+}
+
+/// The declaration text shown in the outline
+fn signature(line: &str) -> String {
+    let trimmed = line.trim_end();
+    let trimmed = trimmed.strip_suffix('{').unwrap_or(trimmed).trim_end();
+    if trimmed.chars().count() > MAX_SIGNATURE_LENGTH {
+        let kept: String = trimmed.chars().take(MAX_SIGNATURE_LENGTH).collect();
+        format!("{}...", kept)
+    } else {
+        trimmed.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rust_outline() {
+        let source = r#"use std::fs;
+
+pub(crate) struct Config {
+    name: String,
+}
+
+impl Config {
+    pub fn new() -> Self {
+        let inner = || {
+            fn nested() {}
+        };
+        Self { name: String::new() }
+    }
+}
+
+pub extern "C" fn callback() {}
+
+const LIMIT: usize = 8;
+pub static NAME: &str = "config";
+static mut COUNTER: u32 = 0;
+"#;
+
+        let symbols = outline(source, "rs").unwrap();
+        let lines: Vec<usize> = symbols.iter().map(|s| s.line).collect();
+        assert_eq!(lines, vec![3, 7, 8, 16, 18, 19, 20]);
+        assert_eq!(symbols[0].signature, "pub(crate) struct Config");
+        assert_eq!(symbols[2].signature, "    pub fn new() -> Self");
+        assert_eq!(symbols[6].signature, "static mut COUNTER: u32 = 0;");
+    }
+
+    #[test]
+    fn test_python_outline_and_unknown_extension() {
+        let source = "import os\n\nclass Foo:\n    def bar(self):\n        pass\n\nasync def main():\n    pass\n";
+        let symbols = outline(source, "py").unwrap();
+        let lines: Vec<usize> = symbols.iter().map(|s| s.line).collect();
+        assert_eq!(lines, vec![3, 4, 7]);
+
+        assert!(outline(source, "txt").is_none());
+    }
+
+    #[test]
+    fn test_js_function_bindings() {
+        let source = r#"import { api } from "./api";
+
+export const fetchUser = async (id) => {
+  return api.get(id);
+};
+const Button = function (props) {};
+let onClick = e => e.preventDefault();
+const handler: Handler = (req, res) => res.end();
+export const LIMIT = 10;
+const doubled = items.map(x => x * 2);
+export function main() {}
+"#;
+
+        let symbols = outline(source, "ts").unwrap();
+        let lines: Vec<usize> = symbols.iter().map(|s| s.line).collect();
+        assert_eq!(lines, vec![3, 6, 7, 8, 11]);
+        assert_eq!(symbols[0].signature, "export const fetchUser = async (id) =>");
+    }
+
+    #[test]
+    fn test_comments_and_docstrings_skipped() {
+        let source = r#"class ValidationError(Exception):
+    """Raised when input is invalid.
+
+    class of errors that the caller can fix
+    def of done: every check passes
+    """
+
+    # def commented_out(self):
+    def message(self):
+        return '''
+class inside a string'''
+"#;
+        let symbols = outline(source, "py").unwrap();
+        let lines: Vec<usize> = symbols.iter().map(|s| s.line).collect();
+        assert_eq!(lines, vec![1, 9]);
+
+        let source = r#"/*
+struct Disabled;
+ */
+// fn commented_out() {}
+const PATTERN: &str = "src/**/*.rs";
+fn main() {}
+"#;
+        let symbols = outline(source, "rs").unwrap();
+        let lines: Vec<usize> = symbols.iter().map(|s| s.line).collect();
+        assert_eq!(lines, vec![5, 6]);
+    }
+}
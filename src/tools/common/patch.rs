@@ -0,0 +1,385 @@
+//! Unified diff parsing and application for the ApplyPatch tool
+//!
+//! Parses `diff -u` / `git diff` output into per-file hunks and applies them
+//! to file contents in memory. Hunk line counts in `@@` headers are ignored,
+//! since models often get them wrong; a hunk runs until the next header.
+//!
+//! Each hunk is located near the line its header names, searching outward.
+//! If the exact text isn't found, the search is repeated ignoring trailing
+//! whitespace, then ignoring indentation. Context lines keep the file's
+//! version of the text, so a whitespace-insensitive match only changes the
+//! added and removed lines.
+
+/// Path used in diff headers for a file that doesn't exist on one side
+const DEV_NULL: &str = "/dev/null";
+
+/// One line of a hunk
+#[derive(Debug, Clone, PartialEq)]
+pub enum HunkLine {
+    /// Unchanged line (` `)
+    Context(String),
+    /// Removed line (`-`)
+    Remove(String),
+    /// Added line (`+`)
+    Add(String),
+}
+
+/// A `@@` section of a file diff
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hunk {
+    /// 1-indexed line where the hunk starts in the original file (0 for an empty file)
+    pub old_start: usize,
+    /// Lines of the hunk, in order
+    pub lines: Vec<HunkLine>,
+}
+
+/// The changes to one file
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilePatch {
+    /// Path before the change (`None` for a new file)
+    pub old_path: Option<String>,
+    /// Path after the change (`None` for a deleted file)
+    pub new_path: Option<String>,
+    /// Hunks, in file order
+    pub hunks: Vec<Hunk>,
+}
+
+impl FilePatch {
+    /// The path to report for this file
+    pub fn path(&self) -> &str {
+        self.new_path
+            .as_deref()
+            .or(self.old_path.as_deref())
+            .unwrap_or(DEV_NULL)
+    }
+}
+
+/// How closely a hunk's context matched the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MatchKind {
+    /// Identical text
+    Exact,
+    /// Identical apart from trailing whitespace
+    TrailingWhitespace,
+    /// Identical apart from leading and trailing whitespace
+    Whitespace,
+}
+
+/// Where a hunk was applied
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placement {
+    /// 1-indexed line in the original file where the hunk matched
+    pub line: usize,
+    /// Lines between the match and where the header said the hunk starts
+    pub offset: isize,
+    /// How closely the context matched
+    pub kind: MatchKind,
+}
+
+/// Result of applying the hunks of one file
+#[derive(Debug, Clone)]
+pub struct PatchedFile {
+    /// The new contents
+    pub content: String,
+    /// One result per hunk: where it applied, or why it didn't
+    pub hunks: Vec<Result<Placement, String>>,
+}
+
+impl PatchedFile {
+    /// Number of hunks that could not be applied
+    pub fn failed(&self) -> usize {
+        self.hunks.iter().filter(|hunk| hunk.is_err()).count()
+    }
+}
+
+/// Parse a unified diff that may span several files
+///
+/// `diff --git`, `index` and similar git lines are skipped, and the `a/` and
+/// `b/` prefixes of git paths are removed.
+pub fn parse_patch(patch: &str) -> Result<Vec<FilePatch>, String> {
+    let lines: Vec<&str> = patch.lines().map(|line| line.strip_suffix('\r').unwrap_or(line)).collect();
+    let mut files: Vec<FilePatch> = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        if let (Some(old), Some(new)) = (
+            line.strip_prefix("--- "),
+            lines.get(i + 1).and_then(|next| next.strip_prefix("+++ ")),
+        ) {
+            files.push(FilePatch {
+                old_path: header_path(old, "a/"),
+                new_path: header_path(new, "b/"),
+                hunks: Vec::new(),
+            });
+            i += 2;
+            continue;
+        }
+
+        if line.starts_with("@@") {
+            let Some(file) = files.last_mut() else {
+                return Err(format!("line {}: hunk before any '---'/'+++' file header", i + 1));
+            };
+            let old_start = parse_hunk_start(line).ok_or_else(|| format!("line {}: invalid hunk header '{}'", i + 1, line))?;
+            let mut hunk = Hunk {
+                old_start,
+                lines: Vec::new(),
+            };
+            i += 1;
+            while i < lines.len() && !is_section_start(&lines, i) {
+                let line = lines[i];
+                match line.chars().next() {
+                    Some(' ') => hunk.lines.push(HunkLine::Context(line[1..].to_string())),
+                    Some('-') => hunk.lines.push(HunkLine::Remove(line[1..].to_string())),
+                    Some('+') => hunk.lines.push(HunkLine::Add(line[1..].to_string())),
+                    // "\ No newline at end of file"
+                    Some('\\') => {}
+                    // Blank context lines often lose their leading space
+                    None => hunk.lines.push(HunkLine::Context(String::new())),
+                    Some(_) => break,
+                }
+                i += 1;
+            }
+            // Trailing blank lines are usually the end of the patch, not context
+            while hunk.lines.last() == Some(&HunkLine::Context(String::new())) {
+                hunk.lines.pop();
+            }
+            if hunk.lines.is_empty() {
+                return Err(format!("hunk at line {} of {} has no changes", old_start, file.path()));
+            }
+            file.hunks.push(hunk);
+            continue;
+        }
+
+        i += 1;
+    }
+
+    if files.is_empty() {
+        return Err("no file headers found; expected '--- old' and '+++ new' lines".to_string());
+    }
+    if let Some(file) = files.iter().find(|file| file.hunks.is_empty()) {
+        return Err(format!("no hunks for {}", file.path()));
+    }
+    Ok(files)
+}
+
+/// Apply `hunks` to `content`
+///
+/// Hunks that can't be placed are reported and skipped; the others are
+/// still applied. Line endings (`\n` or `\r\n`) and the presence of a final
+/// newline are kept.
+pub fn apply_hunks(content: &str, hunks: &[Hunk]) -> PatchedFile {
+    let eol = if content.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let ends_with_newline = content.is_empty() || content.ends_with('\n');
+
+    let mut results = Vec::with_capacity(hunks.len());
+    // Lines added minus lines removed by the hunks applied so far
+    let mut delta: isize = 0;
+    // Offset of the previous hunk, likely shared by the next one
+    let mut drift: isize = 0;
+    // Hunks must not overlap an earlier hunk
+    let mut min_pos = 0;
+
+    for hunk in hunks {
+        let old: Vec<&str> = hunk
+            .lines
+            .iter()
+            .filter_map(|line| match line {
+                HunkLine::Context(text) | HunkLine::Remove(text) => Some(text.as_str()),
+                HunkLine::Add(_) => None,
+            })
+            .collect();
+        // A hunk with nothing to match inserts after its start line
+        let expected = if old.is_empty() {
+            hunk.old_start
+        } else {
+            hunk.old_start.saturating_sub(1)
+        } as isize;
+        let hint = (expected + delta + drift).max(min_pos as isize) as usize;
+
+        let Some((pos, kind)) = find_hunk(&lines, &old, hint, min_pos) else {
+            results.push(Err(format!(
+                "context not found near line {}; the file doesn't contain:\n{}",
+                hunk.old_start,
+                old.join("\n")
+            )));
+            continue;
+        };
+
+        let mut file_lines = lines[pos..pos + old.len()].iter();
+        let mut replacement = Vec::new();
+        for line in &hunk.lines {
+            match line {
+                HunkLine::Context(_) => replacement.extend(file_lines.next().cloned()),
+                HunkLine::Remove(_) => {
+                    file_lines.next();
+                }
+                HunkLine::Add(text) => replacement.push(text.clone()),
+            }
+        }
+        let added = replacement.len();
+        lines.splice(pos..pos + old.len(), replacement);
+
+        let original_pos = pos as isize - delta;
+        drift = original_pos - expected;
+        results.push(Ok(Placement {
+            line: original_pos as usize + 1,
+            offset: drift,
+            kind,
+        }));
+        delta += added as isize - old.len() as isize;
+        min_pos = pos + added;
+    }
+
+    let mut content = lines.join(eol);
+    if ends_with_newline && !lines.is_empty() {
+        content.push_str(eol);
+    }
+    PatchedFile {
+        content,
+        hunks: results,
+    }
+}
+
+/// Find where `old` occurs, preferring the closest position to `hint` at the strictest match
+fn find_hunk(lines: &[String], old: &[&str], hint: usize, min_pos: usize) -> Option<(usize, MatchKind)> {
+    if old.is_empty() {
+        return Some((hint.min(lines.len()), MatchKind::Exact));
+    }
+    if old.len() > lines.len() {
+        return None;
+    }
+    let last = lines.len() - old.len();
+    if min_pos > last {
+        return None;
+    }
+
+    for kind in [MatchKind::Exact, MatchKind::TrailingWhitespace, MatchKind::Whitespace] {
+        let matches_at = |pos: usize| {
+            lines[pos..pos + old.len()]
+                .iter()
+                .zip(old)
+                .all(|(line, old)| lines_match(line, old, kind))
+        };
+        let hint = hint.clamp(min_pos, last);
+        for distance in 0..=(last - min_pos) {
+            let after = hint + distance;
+            if after <= last && matches_at(after) {
+                return Some((after, kind));
+            }
+            if distance > 0 && distance <= hint - min_pos && matches_at(hint - distance) {
+                return Some((hint - distance, kind));
+            }
+        }
+    }
+    None
+}
+
+fn lines_match(line: &str, old: &str, kind: MatchKind) -> bool {
+    match kind {
+        MatchKind::Exact => line == old,
+        MatchKind::TrailingWhitespace => line.trim_end() == old.trim_end(),
+        MatchKind::Whitespace => line.trim() == old.trim(),
+    }
+}
+
+/// Whether line `i` starts a new hunk or file
+fn is_section_start(lines: &[&str], i: usize) -> bool {
+    lines[i].starts_with("@@")
+        || lines[i].starts_with("diff --git ")
+        || (lines[i].starts_with("--- ") && lines.get(i + 1).is_some_and(|next| next.starts_with("+++ ")))
+}
+
+/// Path from a `---`/`+++` header, without timestamp or git prefix
+fn header_path(header: &str, git_prefix: &str) -> Option<String> {
+    let path = header.split('\t').next().unwrap_or(header).trim();
+    if path == DEV_NULL {
+        return None;
+    }
+    Some(path.strip_prefix(git_prefix).unwrap_or(path).to_string())
+}
+
+/// Old start line from `@@ -start[,count] +start[,count] @@`
+fn parse_hunk_start(header: &str) -> Option<usize> {
+    let old = header.strip_prefix("@@")?.trim_start().strip_prefix('-')?;
+    let end = old.find(|c: char| !c.is_ascii_digit()).unwrap_or(old.len());
+    old[..end].parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATCH: &str = "diff --git a/src/lib.rs b/src/lib.rs
+index 1234567..89abcde 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -2,3 +2,3 @@ fn main() {
+     let a = 1;
+-    let b = 2;
++    let b = 3;
+     println!(\"{}\", a + b);
+@@ -10,2 +10,3 @@
+ fn helper() {
++    // added
+ }
+--- /dev/null
++++ b/NOTES.md
+@@ -0,0 +1,2 @@
++# Notes
++
+";
+
+    #[test]
+    fn test_parse_multi_file_patch() {
+        let files = parse_patch(PATCH).unwrap();
+        assert_eq!(files.len(), 2);
+
+        assert_eq!(files[0].old_path.as_deref(), Some("src/lib.rs"));
+        assert_eq!(files[0].hunks.len(), 2);
+        assert_eq!(files[0].hunks[1].old_start, 10);
+        assert_eq!(files[0].hunks[1].lines[1], HunkLine::Add("    // added".to_string()));
+
+        assert_eq!(files[1].old_path, None);
+        assert_eq!(files[1].path(), "NOTES.md");
+        // The blank added line is kept, the trailing blank line is not
+        assert_eq!(files[1].hunks[0].lines.len(), 2);
+
+        assert!(parse_patch("just some text").is_err());
+    }
+
+    #[test]
+    fn test_apply_with_offset_and_whitespace() {
+        let files = parse_patch(PATCH).unwrap();
+        // Two lines were inserted above the first hunk, and the helper is indented with tabs
+        let content = "fn main() {\n    // new\n    // lines\n    let a = 1;\n    let b = 2;\n    println!(\"{}\", a + b);\n}\n\n\
+            \tfn helper() {\n\t}\n";
+
+        let patched = apply_hunks(content, &files[0].hunks);
+        assert_eq!(patched.failed(), 0);
+        let first = patched.hunks[0].as_ref().unwrap();
+        assert_eq!((first.line, first.offset, first.kind), (4, 2, MatchKind::Exact));
+        let second = patched.hunks[1].as_ref().unwrap();
+        assert_eq!(second.kind, MatchKind::Whitespace);
+        assert_eq!(second.line, 9);
+
+        assert!(patched.content.contains("    let b = 3;\n"));
+        // Context keeps the file's indentation
+        assert!(patched.content.ends_with("\tfn helper() {\n    // added\n\t}\n"));
+    }
+
+    #[test]
+    fn test_failed_hunk_is_reported() {
+        let files = parse_patch(PATCH).unwrap();
+        let content = "fn main() {\n    let a = 1;\n    let b = 5;\n}\nfn helper() {\n}\n";
+
+        let patched = apply_hunks(content, &files[0].hunks);
+        assert_eq!(patched.failed(), 1);
+        assert!(patched.hunks[0].as_ref().unwrap_err().contains("let b = 2;"));
+        assert!(patched.hunks[1].is_ok());
+
+        let created = apply_hunks("", &files[1].hunks);
+        assert_eq!(created.content, "# Notes\n\n");
+    }
+}
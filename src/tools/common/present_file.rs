@@ -115,4 +115,8 @@ impl Tool for PresentFileTool {
     fn requires_permission(&self) -> bool {
         false // Read-only presentation doesn't need permission
     }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
 }
@@ -1,15 +1,21 @@
 //! Read tool for reading files
 //!
-//! Reads files from the local filesystem with line numbers.
+//! Reads files from the local filesystem with line numbers, or returns an
+//! outline of the declarations in a source file.
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use serde::Deserialize;
 use serde_json::{json, Value};
+use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
+use std::sync::Mutex;
+use std::time::SystemTime;
 
 use super::super::tool::{Tool, ToolInfo, ToolResult};
+use super::outline::outline;
+use crate::helpers::LongLines;
 use crate::llm::{ToolDefinition, ToolInputSchema};
 use crate::runtime::AgentInternals;
 
@@ -21,11 +27,21 @@ const MAX_LINE_LENGTH: usize = 2000;
 const MAX_IMAGE_SIZE: u64 = 5 * 1024 * 1024;
 /// Maximum file size for PDFs (32MB per user requirement)
 const MAX_PDF_SIZE: u64 = 32 * 1024 * 1024;
+/// Maximum number of cached reads before the cache is reset
+const MAX_CACHE_ENTRIES: usize = 128;
+
+/// Cache key for a text read: path, modification time, size, offset, and limit
+type CacheKey = (String, SystemTime, u64, Option<usize>, Option<usize>);
 
 /// Read tool for reading files
+#[derive(Default)]
 pub struct ReadTool {
-    /// Base directory for file operations
-    base_dir: String,
+    /// Base directory for file operations (None = the session's working directory)
+    base_dir: Option<String>,
+    /// Cached text reads (None = caching disabled)
+    cache: Option<Mutex<HashMap<CacheKey, ToolResult>>>,
+    /// How lines over `MAX_LINE_LENGTH` characters are shown
+    long_lines: LongLines,
 }
 
 /// Input for the read tool
@@ -37,32 +53,63 @@ struct ReadInput {
     offset: Option<usize>,
     /// The number of lines to read
     limit: Option<usize>,
+    /// Return an outline of the file's symbols instead of its contents
+    #[serde(default)]
+    outline: bool,
 }
 
 impl ReadTool {
-    /// Create a new Read tool with the current directory as base
+    /// Create a new Read tool that resolves paths against the session's working directory
+    ///
+    /// Falls back to the current directory when the session has none recorded.
     pub fn new() -> Result<Self> {
-        let base_dir = std::env::current_dir()?
-            .to_string_lossy()
-            .to_string();
-
-        Ok(Self { base_dir })
+        Ok(Self {
+            base_dir: None,
+            cache: None,
+            long_lines: LongLines::default(),
+        })
     }
 
     /// Create a new Read tool with a specific base directory
     pub fn with_base_dir(base_dir: impl Into<String>) -> Self {
         Self {
-            base_dir: base_dir.into(),
+            base_dir: Some(base_dir.into()),
+            cache: None,
+            long_lines: LongLines::default(),
         }
     }
 
+    /// Cache text reads for the lifetime of the tool
+    ///
+    /// Repeated reads of the same range are served from memory while the
+    /// file's modification time and size are unchanged. Off by default, since
+    /// a file rewritten within the filesystem's timestamp resolution without
+    /// changing size would return stale content.
+    pub fn with_cache(mut self) -> Self {
+        self.cache = Some(Mutex::new(HashMap::new()));
+        self
+    }
+
+    /// Set how lines over 2000 characters are shown (default: truncate the end)
+    pub fn with_long_lines(mut self, long_lines: LongLines) -> Self {
+        self.long_lines = long_lines;
+        self
+    }
+
+    /// Get the base directory, defaulting to the session's working directory
+    fn base_dir(&self, internals: &AgentInternals) -> String {
+        self.base_dir
+            .clone()
+            .unwrap_or_else(|| internals.default_base_dir())
+    }
+
     /// Resolve a path (handle both absolute and relative)
-    fn resolve_path(&self, path: &str) -> String {
+    fn resolve_path(&self, base_dir: &str, path: &str) -> String {
         let path = Path::new(path);
         if path.is_absolute() {
             path.to_string_lossy().to_string()
         } else {
-            Path::new(&self.base_dir)
+            Path::new(base_dir)
                 .join(path)
                 .to_string_lossy()
                 .to_string()
@@ -70,8 +117,8 @@ impl ReadTool {
     }
 
     /// Read file contents - dispatches to appropriate handler based on file type
-    fn read_file(&self, file_path: &str, offset: Option<usize>, limit: Option<usize>) -> Result<ToolResult> {
-        let resolved_path = self.resolve_path(file_path);
+    fn read_file(&self, base_dir: &str, file_path: &str, offset: Option<usize>, limit: Option<usize>) -> Result<ToolResult> {
+        let resolved_path = self.resolve_path(base_dir, file_path);
         tracing::info!("Reading file: {}", resolved_path);
 
         // Get file extension to determine type
@@ -89,11 +136,39 @@ impl ReadTool {
             }
             _ => {
                 // Default to text reading for all other files
-                self.read_text_file(&resolved_path, offset, limit)
+                self.read_text_file_cached(&resolved_path, offset, limit)
             }
         }
     }
 
+    /// Read a text file, using the cache when enabled and the file is unchanged
+    fn read_text_file_cached(&self, resolved_path: &str, offset: Option<usize>, limit: Option<usize>) -> Result<ToolResult> {
+        let Some(cache) = &self.cache else {
+            return self.read_text_file(resolved_path, offset, limit);
+        };
+        let Ok((modified, size)) = fs::metadata(resolved_path).and_then(|m| Ok((m.modified()?, m.len()))) else {
+            return self.read_text_file(resolved_path, offset, limit);
+        };
+
+        let key = (resolved_path.to_string(), modified, size, offset, limit);
+        if let Some(cached) = cache.lock().unwrap().get(&key) {
+            tracing::debug!("[ReadTool] Cache hit: {}", resolved_path);
+            return Ok(cached.clone());
+        }
+
+        let result = self.read_text_file(resolved_path, offset, limit)?;
+
+        let mut cache = cache.lock().unwrap();
+        // Drop reads of older versions of this file
+        cache.retain(|(path, m, s, ..), _| path != resolved_path || (*m == modified && *s == size));
+        if cache.len() >= MAX_CACHE_ENTRIES {
+            cache.clear();
+        }
+        cache.insert(key, result.clone());
+
+        Ok(result)
+    }
+
     /// Read a text file with optional offset and limit
     fn read_text_file(&self, resolved_path: &str, offset: Option<usize>, limit: Option<usize>) -> Result<ToolResult> {
         let content = fs::read_to_string(resolved_path)
@@ -118,13 +193,8 @@ impl ReadTool {
 
         for (i, line) in lines[start..end].iter().enumerate() {
             let line_num = start + i + 1;
-            let display_line = if line.len() > MAX_LINE_LENGTH {
-                format!("{}...", &line[..MAX_LINE_LENGTH])
-            } else {
-                line.to_string()
-            };
             // Use cat -n format: right-aligned line number + tab + content
-            result.push_str(&format!("{:>6}\t{}\n", line_num, display_line));
+            self.long_lines.push_numbered(&mut result, line_num, line, MAX_LINE_LENGTH);
         }
 
         if end < total_lines {
@@ -137,6 +207,40 @@ impl ReadTool {
         Ok(ToolResult::success(result))
     }
 
+    /// List the declarations in a source file with their line numbers
+    fn outline_file(&self, base_dir: &str, file_path: &str) -> Result<ToolResult> {
+        let resolved_path = self.resolve_path(base_dir, file_path);
+        tracing::info!("Outlining file: {}", resolved_path);
+
+        let extension = Path::new(&resolved_path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|s| s.to_lowercase())
+            .unwrap_or_default();
+
+        let content = fs::read_to_string(&resolved_path)
+            .with_context(|| format!("Failed to read file: {}", resolved_path))?;
+
+        let Some(symbols) = outline(&content, &extension) else {
+            return Ok(ToolResult::error(format!(
+                "Outline is not supported for '.{}' files. Read the file with offset and limit instead.",
+                extension
+            )));
+        };
+
+        let total_lines = content.lines().count();
+        let mut result = format!("Outline: {} ({} lines)\n\n", resolved_path, total_lines);
+
+        if symbols.is_empty() {
+            result.push_str("No symbols found.\n");
+        }
+        for symbol in &symbols {
+            result.push_str(&format!("{:>6}\t{}\n", symbol.line, symbol.signature));
+        }
+
+        Ok(ToolResult::success(result))
+    }
+
     /// Read an image file
     fn read_image(&self, resolved_path: &str) -> Result<ToolResult> {
         // Check file size first
@@ -214,12 +318,6 @@ impl ReadTool {
     }
 }
 
-impl Default for ReadTool {
-    fn default() -> Self {
-        Self::with_base_dir(".")
-    }
-}
-
 #[async_trait]
 impl Tool for ReadTool {
     fn name(&self) -> &str {
@@ -242,6 +340,8 @@ impl Tool for ReadTool {
                 For TEXT FILES: \
                 - By default, reads up to 2000 lines with line numbers. \
                 - You can optionally specify offset and limit for long files. \
+                - For large source files, set outline to true to get the file's functions, \
+                types, and classes with line numbers, then read the ranges you need. \
                 \n\n\
                 For IMAGES (PNG, JPEG, GIF, WebP): \
                 - Reads and returns the image for vision analysis. \
@@ -270,6 +370,10 @@ impl Tool for ReadTool {
                     "limit": {
                         "type": "number",
                         "description": "The number of lines to read. Only provide if the file is too large."
+                    },
+                    "outline": {
+                        "type": "boolean",
+                        "description": "Return the file's symbols (functions, types, classes) with line numbers instead of its contents. Source code files only."
                     }
                 })),
                 required: Some(vec!["file_path".to_string()]),
@@ -285,18 +389,33 @@ impl Tool for ReadTool {
             .and_then(|v| v.as_str())
             .unwrap_or("?");
 
+        let outline = input
+            .get("outline")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
         ToolInfo {
             name: "Read".to_string(),
-            action_description: format!("Read file: {}", file_path),
+            action_description: if outline {
+                format!("Outline file: {}", file_path)
+            } else {
+                format!("Read file: {}", file_path)
+            },
             details: None,
         }
     }
 
-    async fn execute(&self, input: &Value, _internals: &mut AgentInternals) -> Result<ToolResult> {
+    async fn execute(&self, input: &Value, internals: &mut AgentInternals) -> Result<ToolResult> {
         let read_input: ReadInput = serde_json::from_value(input.clone())
             .map_err(|e| anyhow::anyhow!("Invalid read input: {}", e))?;
 
-        match self.read_file(&read_input.file_path, read_input.offset, read_input.limit) {
+        let base_dir = self.base_dir(internals);
+        let result = if read_input.outline {
+            self.outline_file(&base_dir, &read_input.file_path)
+        } else {
+            self.read_file(&base_dir, &read_input.file_path, read_input.offset, read_input.limit)
+        };
+        match result {
             Ok(result) => Ok(result),
             Err(e) => Ok(ToolResult::error(format!("{}", e))),
         }
@@ -305,6 +424,68 @@ impl Tool for ReadTool {
     fn requires_permission(&self) -> bool {
         false // Read-only operation
     }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::ToolResultData;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn text(result: ToolResult) -> String {
+        match result.content {
+            ToolResultData::Text(text) => text,
+            _ => panic!("expected text content"),
+        }
+    }
+
+    /// Rewrite a file with same-length content, keeping or moving its mtime
+    fn rewrite(path: &Path, content: &str, modified: SystemTime) {
+        fs::write(path, content).unwrap();
+        fs::File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+    }
+
+    #[test]
+    fn test_read_cache() {
+        let dir = TempDir::new().unwrap();
+        let base = dir.path().to_string_lossy().to_string();
+        let path = dir.path().join("notes.txt");
+        fs::write(&path, "first\n").unwrap();
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        let tool = ReadTool::with_base_dir(&base).with_cache();
+
+        assert!(text(tool.read_file(&base, "notes.txt", None, None).unwrap()).contains("first"));
+
+        // Unchanged mtime and size: served from the cache
+        rewrite(&path, "other\n", modified);
+        assert!(text(tool.read_file(&base, "notes.txt", None, None).unwrap()).contains("first"));
+
+        // A different range is a separate entry
+        assert!(text(tool.read_file(&base, "notes.txt", Some(1), Some(1)).unwrap()).contains("other"));
+
+        // A new mtime invalidates the cached read
+        rewrite(&path, "third\n", modified + Duration::from_secs(10));
+        assert!(text(tool.read_file(&base, "notes.txt", None, None).unwrap()).contains("third"));
+    }
+
+    #[test]
+    fn test_read_cache_disabled_by_default() {
+        let dir = TempDir::new().unwrap();
+        let base = dir.path().to_string_lossy().to_string();
+        let path = dir.path().join("notes.txt");
+        fs::write(&path, "first\n").unwrap();
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        let tool = ReadTool::with_base_dir(&base);
+
+        assert!(text(tool.read_file(&base, "notes.txt", None, None).unwrap()).contains("first"));
+        rewrite(&path, "other\n", modified);
+        assert!(text(tool.read_file(&base, "notes.txt", None, None).unwrap()).contains("other"));
+    }
 }
 
 // Tests temporarily disabled - require AgentInternals test helper
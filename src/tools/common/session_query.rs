@@ -0,0 +1,351 @@
+//! Session Query tool for reading other sessions
+//!
+//! Lets an agent list past sessions and read their transcripts, e.g. to
+//! summarize earlier runs or reuse what was learned in them. Access is limited
+//! to the sessions the tool is configured to allow.
+//!
+//! # Example
+//!
+//! ```ignore
+//! // Sessions of the same agent type (the default)
+//! let tool = SessionQueryTool::new();
+//!
+//! // Only specific agent types
+//! let tool = SessionQueryTool::new().with_allowed_agent_types(["coder", "reviewer"]);
+//! ```
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::super::tool::{Tool, ToolInfo, ToolResult};
+use crate::helpers::truncate_bytes_safe;
+use crate::llm::{ToolDefinition, ToolInputSchema};
+use crate::runtime::AgentInternals;
+use crate::session::{AgentSession, SessionMetadata, SessionStorage};
+
+/// Maximum bytes of transcript returned by a single read
+const MAX_TRANSCRIPT_BYTES: usize = 50_000;
+
+/// Which sessions the tool may read
+#[derive(Debug, Clone)]
+enum SessionAccess {
+    /// Sessions with the same agent type as the calling session
+    SameAgentType,
+    /// Sessions with one of these agent types
+    AgentTypes(Vec<String>),
+    /// Exactly these session IDs
+    Sessions(Vec<String>),
+    /// Every session in storage
+    All,
+}
+
+/// Session Query tool for listing and reading other sessions
+pub struct SessionQueryTool {
+    /// Storage to query (None = the calling session's storage)
+    storage: Option<SessionStorage>,
+    /// Which sessions may be read
+    access: SessionAccess,
+}
+
+/// Input for the session query tool
+#[derive(Debug, Deserialize)]
+struct SessionQueryInput {
+    /// "list" or "read"
+    action: String,
+    /// Session to read (required for "read")
+    session_id: Option<String>,
+    /// Include subagent sessions when listing
+    #[serde(default)]
+    include_subagents: bool,
+}
+
+impl SessionQueryTool {
+    /// Create a tool that can read sessions with the same agent type as the caller
+    pub fn new() -> Self {
+        Self {
+            storage: None,
+            access: SessionAccess::SameAgentType,
+        }
+    }
+
+    /// Query a specific storage instead of the calling session's
+    pub fn with_storage(mut self, storage: SessionStorage) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    /// Only allow sessions with one of these agent types
+    pub fn with_allowed_agent_types<I, S>(mut self, agent_types: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.access = SessionAccess::AgentTypes(agent_types.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Only allow these session IDs
+    pub fn with_allowed_sessions<I, S>(mut self, session_ids: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.access = SessionAccess::Sessions(session_ids.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Allow every session in storage
+    pub fn allow_all_sessions(mut self) -> Self {
+        self.access = SessionAccess::All;
+        self
+    }
+
+    /// Whether a session may be read by an agent of `caller_agent_type`
+    fn is_allowed(&self, metadata: &SessionMetadata, caller_agent_type: &str) -> bool {
+        match &self.access {
+            SessionAccess::SameAgentType => metadata.agent_type == caller_agent_type,
+            SessionAccess::AgentTypes(types) => types.contains(&metadata.agent_type),
+            SessionAccess::Sessions(ids) => ids.contains(&metadata.session_id),
+            SessionAccess::All => true,
+        }
+    }
+
+    /// List the allowed sessions, most recently updated first
+    fn list(&self, storage: &SessionStorage, caller: &str, current_id: &str, include_subagents: bool) -> Result<ToolResult> {
+        let mut sessions: Vec<SessionMetadata> = storage
+            .list_sessions_with_metadata(!include_subagents)?
+            .into_iter()
+            .map(|(_, metadata)| metadata)
+            .filter(|metadata| self.is_allowed(metadata, caller))
+            .collect();
+        sessions.sort_by_key(|metadata| std::cmp::Reverse(metadata.updated_at));
+
+        if sessions.is_empty() {
+            return Ok(ToolResult::success("No sessions available."));
+        }
+
+        let mut output = format!("{} sessions:\n", sessions.len());
+        for metadata in &sessions {
+            let title = metadata.conversation_name.as_deref().unwrap_or(&metadata.name);
+            let current = if metadata.session_id == current_id { " (current)" } else { "" };
+            output.push_str(&format!(
+                "- {}{} | {} | {} | updated {}\n",
+                metadata.session_id,
+                current,
+                title,
+                metadata.agent_type,
+                metadata.updated_at.format("%Y-%m-%d %H:%M UTC")
+            ));
+        }
+
+        Ok(ToolResult::success(output))
+    }
+
+    /// Render an allowed session as a Markdown transcript
+    fn read(&self, storage: &SessionStorage, caller: &str, session_id: &str) -> Result<ToolResult> {
+        // Report unknown and forbidden sessions the same way
+        let not_found = || ToolResult::error(format!("Session '{}' not found", session_id));
+
+        // The ID becomes a directory name, so it must not leave the storage
+        if !is_plain_session_id(session_id) {
+            tracing::warn!("[SessionQueryTool] Rejected session ID '{}'", session_id);
+            return Ok(not_found());
+        }
+
+        let Ok(metadata) = storage.load_metadata(session_id) else {
+            return Ok(not_found());
+        };
+        if !self.is_allowed(&metadata, caller) {
+            tracing::warn!("[SessionQueryTool] Denied read of session '{}'", session_id);
+            return Ok(not_found());
+        }
+
+        let session = AgentSession::load_with_storage(session_id, storage.clone())?;
+        let mut transcript = session.to_markdown();
+
+        if transcript.len() > MAX_TRANSCRIPT_BYTES {
+            let original_len = transcript.len();
+            let end = truncate_bytes_safe(&transcript, MAX_TRANSCRIPT_BYTES).len();
+            transcript.truncate(end);
+            return Ok(ToolResult::success_with_truncation(transcript, original_len));
+        }
+
+        Ok(ToolResult::success(transcript))
+    }
+}
+
+/// Whether `session_id` names a single directory inside the storage
+fn is_plain_session_id(session_id: &str) -> bool {
+    !session_id.is_empty() && session_id != "." && session_id != ".." && !session_id.contains(['/', '\\'])
+}
+
+impl Default for SessionQueryTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Tool for SessionQueryTool {
+    fn name(&self) -> &str {
+        "SessionQuery"
+    }
+
+    fn description(&self) -> &str {
+        "List past sessions and read their transcripts."
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        use crate::llm::types::CustomTool;
+
+        ToolDefinition::Custom(CustomTool {
+            name: "SessionQuery".to_string(),
+            description: Some(
+                "List past agent sessions and read their transcripts. \
+                Use action \"list\" to see available sessions (most recent first), \
+                then action \"read\" with a session_id to get that conversation as Markdown. \
+                Long transcripts are truncated."
+                    .to_string(),
+            ),
+            input_schema: ToolInputSchema {
+                schema_type: "object".to_string(),
+                properties: Some(json!({
+                    "action": {
+                        "type": "string",
+                        "enum": ["list", "read"],
+                        "description": "\"list\" to list sessions, \"read\" to read one session's transcript"
+                    },
+                    "session_id": {
+                        "type": "string",
+                        "description": "The session to read (required for \"read\")"
+                    },
+                    "include_subagents": {
+                        "type": "boolean",
+                        "description": "Include subagent sessions when listing (default: false)"
+                    }
+                })),
+                required: Some(vec!["action".to_string()]),
+            },
+            tool_type: None,
+            cache_control: None,
+        })
+    }
+
+    fn get_info(&self, input: &Value) -> ToolInfo {
+        let action_description = match input.get("session_id").and_then(|v| v.as_str()) {
+            Some(session_id) => format!("Read session: {}", session_id),
+            None => "List sessions".to_string(),
+        };
+
+        ToolInfo {
+            name: "SessionQuery".to_string(),
+            action_description,
+            details: None,
+        }
+    }
+
+    async fn execute(&self, input: &Value, internals: &mut AgentInternals) -> Result<ToolResult> {
+        let query: SessionQueryInput = serde_json::from_value(input.clone())
+            .map_err(|e| anyhow::anyhow!("Invalid session query input: {}", e))?;
+
+        let (storage, caller, current_id) = {
+            let session = internals.session.read().await;
+            (
+                self.storage.clone().unwrap_or_else(|| session.storage().clone()),
+                session.agent_type().to_string(),
+                session.session_id().to_string(),
+            )
+        };
+
+        let result = match (query.action.as_str(), query.session_id.as_deref()) {
+            ("list", _) => self.list(&storage, &caller, &current_id, query.include_subagents),
+            ("read", Some(session_id)) => self.read(&storage, &caller, session_id),
+            ("read", None) => return Ok(ToolResult::error("session_id is required for action \"read\"")),
+            (other, _) => return Ok(ToolResult::error(format!("Unknown action: {}", other))),
+        };
+
+        match result {
+            Ok(result) => Ok(result),
+            Err(e) => Ok(ToolResult::error(format!("{}", e))),
+        }
+    }
+
+    fn requires_permission(&self) -> bool {
+        true // Exposes other conversations
+    }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::ToolResultData;
+    use tempfile::TempDir;
+
+    /// Storage with sessions "coder-1" and "coder-2" (coder) and "review-1" (reviewer)
+    fn create_test_storage() -> (SessionStorage, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = SessionStorage::with_dir(temp_dir.path().join("sessions"));
+        for (id, agent_type) in [("coder-1", "coder"), ("coder-2", "coder"), ("review-1", "reviewer")] {
+            let mut session = AgentSession::new_with_storage(id, agent_type, id, "", "", storage.clone()).unwrap();
+            session.add_message(crate::llm::Message::user(format!("Hello from {}", id))).unwrap();
+        }
+        (storage, temp_dir)
+    }
+
+    fn listed(tool: &SessionQueryTool, storage: &SessionStorage, caller: &str) -> Vec<&'static str> {
+        let ToolResultData::Text(output) = tool.list(storage, caller, "coder-1", false).unwrap().content else {
+            panic!("Expected text output");
+        };
+        ["coder-1", "coder-2", "review-1"].into_iter().filter(|id| output.contains(id)).collect()
+    }
+
+    fn can_read(tool: &SessionQueryTool, storage: &SessionStorage, caller: &str, session_id: &str) -> bool {
+        !tool.read(storage, caller, session_id).unwrap().is_error
+    }
+
+    #[test]
+    fn test_session_access() {
+        let (storage, _temp) = create_test_storage();
+
+        let same_type = SessionQueryTool::new();
+        assert_eq!(listed(&same_type, &storage, "coder"), ["coder-1", "coder-2"]);
+        assert!(can_read(&same_type, &storage, "coder", "coder-2"));
+        assert!(!can_read(&same_type, &storage, "coder", "review-1"));
+
+        let types = SessionQueryTool::new().with_allowed_agent_types(["reviewer"]);
+        assert_eq!(listed(&types, &storage, "coder"), ["review-1"]);
+        assert!(can_read(&types, &storage, "coder", "review-1"));
+        assert!(!can_read(&types, &storage, "coder", "coder-2"));
+
+        let sessions = SessionQueryTool::new().with_allowed_sessions(["coder-2", "review-1"]);
+        assert_eq!(listed(&sessions, &storage, "coder"), ["coder-2", "review-1"]);
+        assert!(!can_read(&sessions, &storage, "coder", "coder-1"));
+
+        let all = SessionQueryTool::new().allow_all_sessions();
+        assert_eq!(listed(&all, &storage, "coder"), ["coder-1", "coder-2", "review-1"]);
+        assert!(can_read(&all, &storage, "coder", "review-1"));
+
+        // Denied and unknown sessions look the same
+        let denied = same_type.read(&storage, "coder", "review-1").unwrap();
+        assert!(matches!(denied.content, ToolResultData::Text(text) if text == "Session 'review-1' not found"));
+    }
+
+    #[test]
+    fn test_rejects_paths_outside_storage() {
+        let (storage, temp) = create_test_storage();
+        let outside = SessionStorage::with_dir(temp.path().join("other"));
+        AgentSession::new_with_storage("secret", "coder", "Secret", "", "", outside).unwrap();
+
+        let all = SessionQueryTool::new().allow_all_sessions();
+        assert!(can_read(&all, &storage, "coder", "coder-1"));
+        for session_id in ["../other/secret", "..\\other\\secret", "..", ".", "", "coder-1/../coder-2"] {
+            assert!(!can_read(&all, &storage, "coder", session_id), "{:?} was readable", session_id);
+        }
+    }
+}
@@ -154,14 +154,9 @@ impl Tool for TodoWriteTool {
             .map_err(|e| anyhow::anyhow!("Invalid todo input: {}", e))?;
 
         // Look for TodoListManager in resources
-        let manager = match internals.context.get_resource::<TodoListManager>() {
-            Some(m) => m,
-            None => {
-                return Ok(ToolResult::error(
-                    "TodoListManager not found in agent resources. \
-                    Ensure TodoListManager is added to context before using TodoWriteTool."
-                ));
-            }
+        let manager = match internals.context.require_resource::<TodoListManager>() {
+            Ok(m) => m,
+            Err(e) => return Ok(ToolResult::error(e.to_string())),
         };
 
         // Convert input items to TodoItem
@@ -185,4 +180,8 @@ impl Tool for TodoWriteTool {
     fn requires_permission(&self) -> bool {
         false // Todo updates don't need permission
     }
+
+    fn is_read_only(&self) -> bool {
+        true // Only updates the agent's own todo list
+    }
 }
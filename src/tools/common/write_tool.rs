@@ -1,12 +1,14 @@
 //! Write tool for creating/writing files
 //!
-//! Writes content to files on the local filesystem.
+//! Writes content to files on the local filesystem, replacing them or
+//! appending to them.
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use serde::Deserialize;
 use serde_json::{json, Value};
-use std::fs;
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::Path;
 
 use super::super::tool::{Tool, ToolInfo, ToolResult};
@@ -14,9 +16,10 @@ use crate::llm::{ToolDefinition, ToolInputSchema};
 use crate::runtime::AgentInternals;
 
 /// Write tool for creating files
+#[derive(Default)]
 pub struct WriteTool {
-    /// Base directory for file operations
-    base_dir: String,
+    /// Base directory for file operations (None = the session's working directory)
+    base_dir: Option<String>,
 }
 
 /// Input for the write tool
@@ -26,41 +29,49 @@ struct WriteInput {
     file_path: String,
     /// The content to write to the file (required)
     content: String,
+    /// Add the content to the end of the file instead of replacing it (optional)
+    #[serde(default)]
+    append: bool,
 }
 
 impl WriteTool {
-    /// Create a new Write tool with the current directory as base
+    /// Create a new Write tool that resolves paths against the session's working directory
+    ///
+    /// Falls back to the current directory when the session has none recorded.
     pub fn new() -> Result<Self> {
-        let base_dir = std::env::current_dir()?
-            .to_string_lossy()
-            .to_string();
-
-        Ok(Self { base_dir })
+        Ok(Self { base_dir: None })
     }
 
     /// Create a new Write tool with a specific base directory
     pub fn with_base_dir(base_dir: impl Into<String>) -> Self {
         Self {
-            base_dir: base_dir.into(),
+            base_dir: Some(base_dir.into()),
         }
     }
 
+    /// Get the base directory, defaulting to the session's working directory
+    fn base_dir(&self, internals: &AgentInternals) -> String {
+        self.base_dir
+            .clone()
+            .unwrap_or_else(|| internals.default_base_dir())
+    }
+
     /// Resolve a path (handle both absolute and relative)
-    fn resolve_path(&self, path: &str) -> String {
+    fn resolve_path(&self, base_dir: &str, path: &str) -> String {
         let path = Path::new(path);
         if path.is_absolute() {
             path.to_string_lossy().to_string()
         } else {
-            Path::new(&self.base_dir)
+            Path::new(base_dir)
                 .join(path)
                 .to_string_lossy()
                 .to_string()
         }
     }
 
-    /// Write content to a file
-    fn write_file(&self, file_path: &str, content: &str) -> Result<String> {
-        let resolved_path = self.resolve_path(file_path);
+    /// Write content to a file, or append it with `append`
+    fn write_file(&self, base_dir: &str, file_path: &str, content: &str, append: bool) -> Result<String> {
+        let resolved_path = self.resolve_path(base_dir, file_path);
         tracing::info!("Writing file: {}", resolved_path);
 
         // Create parent directories if needed
@@ -71,6 +82,31 @@ impl WriteTool {
 
         let existed = Path::new(&resolved_path).exists();
 
+        if append {
+            let mut file = OpenOptions::new()
+                .create(true)
+                .read(true)
+                .append(true)
+                .open(&resolved_path)
+                .with_context(|| format!("Failed to open file: {}", resolved_path))?;
+            // Start on a new line if the file doesn't end with one
+            let mut last = [b'\n'];
+            if file.metadata()?.len() > 0 {
+                file.seek(SeekFrom::End(-1))?;
+                file.read_exact(&mut last)?;
+            }
+            if last[0] != b'\n' {
+                file.write_all(b"\n")?;
+            }
+            file.write_all(content.as_bytes())
+                .with_context(|| format!("Failed to append to file: {}", resolved_path))?;
+            return Ok(if existed {
+                format!("Appended to file successfully: {}", file_path)
+            } else {
+                format!("File created successfully: {}", file_path)
+            });
+        }
+
         fs::write(&resolved_path, content)
             .with_context(|| format!("Failed to write file: {}", resolved_path))?;
 
@@ -82,12 +118,6 @@ impl WriteTool {
     }
 }
 
-impl Default for WriteTool {
-    fn default() -> Self {
-        Self::with_base_dir(".")
-    }
-}
-
 #[async_trait]
 impl Tool for WriteTool {
     fn name(&self) -> &str {
@@ -105,7 +135,7 @@ impl Tool for WriteTool {
             name: "Write".to_string(),
             description: Some(
                 "Writes a file to the local filesystem. \
-                This will overwrite the existing file if there is one. \
+                This will overwrite the existing file if there is one, unless `append` is set. \
                 ALWAYS prefer editing existing files. NEVER write new files unless explicitly required."
                     .to_string(),
             ),
@@ -119,6 +149,10 @@ impl Tool for WriteTool {
                     "content": {
                         "type": "string",
                         "description": "The content to write to the file"
+                    },
+                    "append": {
+                        "type": "boolean",
+                        "description": "Add the content to the end of the file (creating it if missing) instead of replacing it. A newline is inserted first if the file doesn't end with one."
                     }
                 })),
                 required: Some(vec!["file_path".to_string(), "content".to_string()]),
@@ -134,18 +168,25 @@ impl Tool for WriteTool {
             .and_then(|v| v.as_str())
             .unwrap_or("?");
 
+        let append = input.get("append").and_then(|v| v.as_bool()).unwrap_or(false);
+
         ToolInfo {
             name: "Write".to_string(),
-            action_description: format!("Write file: {}", file_path),
+            action_description: if append {
+                format!("Append to file: {}", file_path)
+            } else {
+                format!("Write file: {}", file_path)
+            },
             details: None,
         }
     }
 
-    async fn execute(&self, input: &Value, _internals: &mut AgentInternals) -> Result<ToolResult> {
+    async fn execute(&self, input: &Value, internals: &mut AgentInternals) -> Result<ToolResult> {
         let write_input: WriteInput = serde_json::from_value(input.clone())
             .map_err(|e| anyhow::anyhow!("Invalid write input: {}", e))?;
 
-        match self.write_file(&write_input.file_path, &write_input.content) {
+        let base_dir = self.base_dir(internals);
+        match self.write_file(&base_dir, &write_input.file_path, &write_input.content, write_input.append) {
             Ok(output) => Ok(ToolResult::success(output)),
             Err(e) => Ok(ToolResult::error(format!("{}", e))),
         }
@@ -156,5 +197,24 @@ impl Tool for WriteTool {
     }
 }
 
-// Tests temporarily disabled - require AgentInternals test helper
-// TODO: Create test infrastructure for tools that need AgentInternals
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_append() {
+        let dir = TempDir::new().unwrap();
+        let base = dir.path().to_string_lossy().to_string();
+        let tool = WriteTool::with_base_dir(&base);
+        let path = dir.path().join("logs/changes.md");
+
+        tool.write_file(&base, "logs/changes.md", "- first", true).unwrap();
+        tool.write_file(&base, "logs/changes.md", "- second\n", true).unwrap();
+        tool.write_file(&base, "logs/changes.md", "- third\n", true).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "- first\n- second\n- third\n");
+
+        tool.write_file(&base, "logs/changes.md", "reset", false).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "reset");
+    }
+}
@@ -0,0 +1,207 @@
+//! Closure-backed tools
+//!
+//! `FnTool` builds a complete `Tool` from a name, description, input schema,
+//! and an async closure, for tools that don't need access to the agent's
+//! internals.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let weather = FnTool::new(
+//!     "GetWeather",
+//!     "Get the current weather for a city",
+//!     json!({ "city": { "type": "string", "description": "City name" } }),
+//!     vec!["city".into()],
+//!     |input| async move {
+//!         let city = input["city"].as_str().unwrap_or_default().to_string();
+//!         Ok(ToolResult::success(format!("Sunny in {}", city)))
+//!     },
+//! )
+//! .with_permission(false);
+//!
+//! registry.register(weather);
+//! ```
+
+use std::future::Future;
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use serde_json::Value;
+
+use super::tool::{Tool, ToolInfo, ToolResult};
+use crate::llm::{define_tool, ToolDefinition};
+use crate::runtime::AgentInternals;
+
+/// Async handler called with the tool input
+type Handler = Arc<dyn Fn(Value) -> BoxFuture<'static, Result<ToolResult>> + Send + Sync>;
+
+/// Describes an invocation for permission prompts
+type InfoFn = Arc<dyn Fn(&Value) -> String + Send + Sync>;
+
+/// A tool implemented by an async closure
+#[derive(Clone)]
+pub struct FnTool {
+    name: String,
+    description: String,
+    definition: ToolDefinition,
+    handler: Handler,
+    info: Option<InfoFn>,
+    requires_permission: bool,
+    read_only: bool,
+    cacheable: bool,
+}
+
+impl FnTool {
+    /// Create a tool from its schema and handler
+    ///
+    /// `properties` and `required` are the input schema, as in [`define_tool`].
+    /// The tool requires permission by default.
+    pub fn new<F, Fut>(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        properties: Value,
+        required: Vec<String>,
+        handler: F,
+    ) -> Self
+    where
+        F: Fn(Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<ToolResult>> + Send + 'static,
+    {
+        let name = name.into();
+        let description = description.into();
+        let definition = define_tool(&name, &description, properties, required);
+
+        Self {
+            name,
+            description,
+            definition,
+            handler: Arc::new(move |input| Box::pin(handler(input))),
+            info: None,
+            requires_permission: true,
+            read_only: false,
+            cacheable: false,
+        }
+    }
+
+    /// Set whether the tool asks for permission before running (default: true)
+    pub fn with_permission(mut self, required: bool) -> Self {
+        self.requires_permission = required;
+        self
+    }
+
+    /// Mark the tool as only reading state (default: false)
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Allow the registry's result cache to reuse results (default: false)
+    pub fn with_cacheable(mut self, cacheable: bool) -> Self {
+        self.cacheable = cacheable;
+        self
+    }
+
+    /// Describe each invocation in permission prompts
+    ///
+    /// Without this, prompts show the tool name and its raw input.
+    pub fn with_info<F>(mut self, describe: F) -> Self
+    where
+        F: Fn(&Value) -> String + Send + Sync + 'static,
+    {
+        self.info = Some(Arc::new(describe));
+        self
+    }
+}
+
+impl std::fmt::Debug for FnTool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FnTool")
+            .field("name", &self.name)
+            .field("requires_permission", &self.requires_permission)
+            .field("read_only", &self.read_only)
+            .field("cacheable", &self.cacheable)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Tool for FnTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        self.definition.clone()
+    }
+
+    fn get_info(&self, input: &Value) -> ToolInfo {
+        match self.info {
+            Some(ref describe) => ToolInfo {
+                name: self.name.clone(),
+                action_description: describe(input),
+                details: None,
+            },
+            None => ToolInfo {
+                name: self.name.clone(),
+                action_description: format!("Run {}", self.name),
+                details: Some(input.to_string()),
+            },
+        }
+    }
+
+    async fn execute(&self, input: &Value, _internals: &mut AgentInternals) -> Result<ToolResult> {
+        (self.handler)(input.clone()).await
+    }
+
+    fn requires_permission(&self) -> bool {
+        self.requires_permission
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    fn is_cacheable(&self) -> bool {
+        self.cacheable
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_fn_tool() {
+        let tool = FnTool::new(
+            "Shout",
+            "Uppercase the input",
+            json!({ "text": { "type": "string" } }),
+            vec!["text".to_string()],
+            |input| async move {
+                let text = input["text"].as_str().unwrap_or_default().to_uppercase();
+                Ok(ToolResult::success(text))
+            },
+        )
+        .with_permission(false)
+        .with_read_only(true)
+        .with_info(|input| format!("Shout {}", input["text"]));
+
+        assert_eq!(tool.name(), "Shout");
+        assert_eq!(tool.definition().name(), "Shout");
+        assert!(!tool.requires_permission());
+        assert!(tool.is_read_only());
+
+        let input = json!({ "text": "hi" });
+        assert_eq!(tool.get_info(&input).action_description, "Shout \"hi\"");
+
+        let result = (tool.handler)(input).await.unwrap();
+        assert!(matches!(result.content, crate::tools::ToolResultData::Text(ref t) if t == "HI"));
+    }
+}
@@ -2,11 +2,13 @@
 //!
 //! This module provides:
 //! - `Tool` trait - Interface for implementing tools
+//! - `FnTool` - A `Tool` built from a schema and an async closure
 //! - `ToolResult` - Result type for tool execution
 //! - `ToolRegistry` - Registry for managing available tools
 //! - `ToolProvider` trait - Interface for dynamic tool sources (MCP, OpenAPI, etc.)
-//! - `common` - Built-in tools (Bash, Read, Write, Edit, Glob, Grep, Todo)
+//! - `common` - Built-in tools (Bash, Read, Write, Edit, Glob, Grep, Todo, SessionQuery)
 
+mod fn_tool;
 mod provider;
 mod registry;
 mod tool;
@@ -15,12 +17,13 @@ mod tool;
 pub mod common;
 
 // Core exports
+pub use fn_tool::FnTool;
 pub use provider::ToolProvider;
 pub use registry::ToolRegistry;
-pub use tool::{Tool, ToolInfo, ToolResult, ToolResultData};
+pub use tool::{SuggestedToolCall, Tool, ToolInfo, ToolResult, ToolResultData};
 
 // Re-export common tools for convenience
 pub use common::{
-    AskUserQuestionTool, BashTool, EditTool, GlobTool, GrepTool, PresentFileTool, ReadTool,
-    TodoWriteTool, WriteTool,
+    ApplyPatchTool, AskUserQuestionTool, BashOutputMode, BashTool, EditTool, FileOpsTool, GlobTool,
+    GrepTool, ListDirTool, PresentFileTool, ReadTool, SessionQueryTool, TodoWriteTool, WriteTool,
 };
@@ -3,11 +3,18 @@
 //! The registry holds all tools that are available to the agent.
 //! It supports both static tools (registered directly) and dynamic tools
 //! from providers (like MCP servers).
+//!
+//! An optional result cache (`with_result_cache`) reuses the results of
+//! cacheable tools called again with identical input within a session.
 
+use std::any::Any;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
+use futures::FutureExt;
 use serde_json::Value;
 
 use super::provider::ToolProvider;
@@ -22,6 +29,45 @@ pub struct ToolRegistry {
 
     /// Dynamic tool providers (MCP, etc.)
     providers: Vec<Arc<dyn ToolProvider>>,
+
+    /// Results of cacheable tools, when enabled
+    result_cache: Option<ResultCache>,
+}
+
+/// A cacheable call: session ID, tool name and serialized input
+///
+/// Object keys serialize sorted, so equal input gives an equal key.
+type CacheKey = (String, String, String);
+
+/// Cached tool results keyed by session, tool name and input
+struct ResultCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<CacheKey, (Instant, ToolResult)>>,
+}
+
+impl ResultCache {
+    /// Cache key for a call
+    fn key(session_id: &str, name: &str, input: &Value) -> CacheKey {
+        (session_id.to_string(), name.to_string(), input.to_string())
+    }
+
+    fn get(&self, key: &CacheKey) -> Option<ToolResult> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(key)
+            .filter(|(stored, _)| stored.elapsed() < self.ttl)
+            .map(|(_, result)| result.clone())
+    }
+
+    fn insert(&self, key: CacheKey, result: ToolResult) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, (stored, _)| stored.elapsed() < self.ttl);
+        entries.insert(key, (Instant::now(), result));
+    }
+
+    fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
 }
 
 impl ToolRegistry {
@@ -30,6 +76,29 @@ impl ToolRegistry {
         Self {
             tools: HashMap::new(),
             providers: Vec::new(),
+            result_cache: None,
+        }
+    }
+
+    /// Reuse results of cacheable tools for up to `ttl`
+    ///
+    /// A call to a tool whose `is_cacheable()` is true returns the stored
+    /// result when the same session called it with identical input less than
+    /// `ttl` ago. Error results are not stored. Running any tool that is not
+    /// read-only clears the cache, since it may have changed what the cached
+    /// calls read.
+    pub fn with_result_cache(mut self, ttl: Duration) -> Self {
+        self.result_cache = Some(ResultCache {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        });
+        self
+    }
+
+    /// Drop all cached tool results
+    pub fn clear_result_cache(&self) {
+        if let Some(ref cache) = self.result_cache {
+            cache.clear();
         }
     }
 
@@ -37,6 +106,9 @@ impl ToolRegistry {
     pub fn register<T: Tool + 'static>(&mut self, tool: T) {
         let name = tool.name().to_string();
         tracing::info!("Registering tool: {}", name);
+        if cfg!(debug_assertions) {
+            check_schema(&tool);
+        }
         self.tools.insert(name, Arc::new(tool));
     }
 
@@ -70,6 +142,9 @@ impl ToolRegistry {
                 name,
                 provider.name()
             );
+            if cfg!(debug_assertions) {
+                check_schema(tool.as_ref());
+            }
             self.tools.insert(name, tool);
         }
 
@@ -103,6 +178,9 @@ impl ToolRegistry {
 
             for tool in tools {
                 let name = tool.name().to_string();
+                if cfg!(debug_assertions) {
+                    check_schema(tool.as_ref());
+                }
                 self.tools.insert(name, tool);
             }
         }
@@ -128,6 +206,9 @@ impl ToolRegistry {
     }
 
     /// Execute a tool by name
+    ///
+    /// A panic inside the tool is caught and returned as an error result, so
+    /// a buggy tool fails its call instead of taking down the agent.
     pub async fn execute(
         &self,
         name: &str,
@@ -139,10 +220,36 @@ impl ToolRegistry {
             .get(name)
             .with_context(|| format!("Tool not found: {}", name))?;
 
+        let cache_key = match self.result_cache {
+            Some(_) if tool.is_cacheable() => Some(ResultCache::key(internals.session_id(), name, input)),
+            _ => None,
+        };
+        if let (Some(cache), Some(key)) = (&self.result_cache, &cache_key) {
+            if let Some(result) = cache.get(key) {
+                tracing::info!("[ToolRegistry] Reusing cached result for tool: {}", name);
+                return Ok(result);
+            }
+        }
+
         tracing::info!("Executing tool: {}", name);
         tracing::debug!("Input: {:?}", input);
 
-        let result = tool.execute(input, internals).await?;
+        let result = match AssertUnwindSafe(tool.execute(input, internals))
+            .catch_unwind()
+            .await
+        {
+            Ok(result) => result?,
+            Err(panic) => {
+                let message = panic_message(panic.as_ref());
+                tracing::error!(
+                    "[ToolRegistry] Tool '{}' panicked: {} (input: {})",
+                    name,
+                    message,
+                    input
+                );
+                ToolResult::error(format!("Tool '{}' failed unexpectedly: {}", name, message))
+            }
+        };
 
         tracing::debug!(
             "Tool {} completed. Is error: {}",
@@ -150,6 +257,15 @@ impl ToolRegistry {
             result.is_error
         );
 
+        if let Some(ref cache) = self.result_cache {
+            if !tool.is_read_only() {
+                cache.clear();
+            }
+            if let (Some(key), false) = (cache_key, result.is_error) {
+                cache.insert(key, result.clone());
+            }
+        }
+
         Ok(result)
     }
 
@@ -161,11 +277,42 @@ impl ToolRegistry {
             .unwrap_or(true)
     }
 
+    /// Check if a tool only reads state (false for unknown tools)
+    pub fn is_read_only(&self, name: &str) -> bool {
+        self.tools
+            .get(name)
+            .map(|t| t.is_read_only())
+            .unwrap_or(false)
+    }
+
+    /// Names of the tools that only read state
+    pub fn read_only_tool_names(&self) -> Vec<&str> {
+        self.tools
+            .iter()
+            .filter(|(_, tool)| tool.is_read_only())
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
     /// Get the list of tool names
     pub fn tool_names(&self) -> Vec<&str> {
         self.tools.keys().map(|s| s.as_str()).collect()
     }
 
+    /// Name and short description of every tool, sorted by name
+    ///
+    /// Suitable for a `/help` listing; use `get(name)` for the full
+    /// definition of a single tool.
+    pub fn describe_all(&self) -> Vec<(&str, &str)> {
+        let mut tools: Vec<(&str, &str)> = self
+            .tools
+            .iter()
+            .map(|(name, tool)| (name.as_str(), tool.description()))
+            .collect();
+        tools.sort_by_key(|(name, _)| *name);
+        tools
+    }
+
     /// Get the number of registered tools
     pub fn len(&self) -> usize {
         self.tools.len()
@@ -177,6 +324,60 @@ impl ToolRegistry {
     }
 }
 
+/// Text of a panic payload (`panic!` with a message yields a `&str` or `String`)
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "panic with a non-string payload".to_string()
+    }
+}
+
+/// Log a warning for each problem in a tool's input schema
+///
+/// Runs in debug builds only. A malformed schema is accepted by the API but
+/// makes the model call the tool erratically, so it's worth flagging early.
+fn check_schema(tool: &dyn Tool) {
+    for problem in schema_problems(&tool.definition()) {
+        tracing::warn!(
+            "[ToolRegistry] Tool '{}' has an invalid input schema: {}",
+            tool.name(),
+            problem
+        );
+    }
+}
+
+/// Find inconsistencies in a custom tool's input schema
+///
+/// Checks that `properties` is an object and that every `required` field
+/// is declared in it. Built-in tool definitions have no schema to check.
+fn schema_problems(definition: &ToolDefinition) -> Vec<String> {
+    let ToolDefinition::Custom(custom) = definition else {
+        return Vec::new();
+    };
+    let schema = &custom.input_schema;
+    let mut problems = Vec::new();
+
+    let properties = match &schema.properties {
+        Some(Value::Object(map)) => Some(map),
+        Some(other) => {
+            problems.push(format!("`properties` must be an object, got {}", other));
+            None
+        }
+        None => None,
+    };
+
+    for field in schema.required.iter().flatten() {
+        if !properties.is_some_and(|props| props.contains_key(field)) {
+            problems.push(format!("required field '{}' is not in `properties`", field));
+        }
+    }
+
+    problems
+}
+
 impl Default for ToolRegistry {
     fn default() -> Self {
         Self::new()
@@ -186,6 +387,9 @@ impl Default for ToolRegistry {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::llm::define_tool;
+    use crate::runtime::internals::test_internals;
+    use serde_json::json;
 
     #[test]
     fn test_empty_registry() {
@@ -194,4 +398,121 @@ mod tests {
         assert_eq!(registry.len(), 0);
         assert!(registry.get("nonexistent").is_none());
     }
+
+    #[test]
+    fn test_describe_all() {
+        use crate::tools::FnTool;
+
+        let mut registry = ToolRegistry::new();
+        for (name, description) in [("Write", "Write a file"), ("Read", "Read a file")] {
+            registry.register(FnTool::new(name, description, json!({}), vec![], |_| async {
+                Ok(ToolResult::success(""))
+            }));
+        }
+
+        assert_eq!(
+            registry.describe_all(),
+            vec![("Read", "Read a file"), ("Write", "Write a file")]
+        );
+    }
+
+    #[test]
+    fn test_read_only_tools() {
+        use crate::tools::FnTool;
+
+        let mut registry = ToolRegistry::new();
+        for (name, read_only) in [("Read", true), ("Write", false)] {
+            registry.register(
+                FnTool::new(name, name, json!({}), vec![], |_| async { Ok(ToolResult::success("")) })
+                    .with_read_only(read_only),
+            );
+        }
+
+        assert!(registry.is_read_only("Read"));
+        assert!(!registry.is_read_only("Write"));
+        assert!(!registry.is_read_only("Missing"));
+        assert_eq!(registry.read_only_tool_names(), vec!["Read"]);
+    }
+
+    #[test]
+    fn test_schema_problems() {
+        let valid = define_tool(
+            "Valid",
+            "A valid tool",
+            json!({"path": {"type": "string"}}),
+            vec!["path".into()],
+        );
+        assert!(schema_problems(&valid).is_empty());
+
+        let missing = define_tool(
+            "Missing",
+            "Requires an undeclared field",
+            json!({"path": {"type": "string"}}),
+            vec!["path".into(), "pattern".into()],
+        );
+        let problems = schema_problems(&missing);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("'pattern'"));
+
+        let not_object = define_tool("Array", "Bad properties", json!(["path"]), vec![]);
+        assert_eq!(schema_problems(&not_object).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_tool_panic_becomes_error_result() {
+        use crate::tools::{FnTool, ToolResultData};
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (mut internals, _, _) = test_internals(temp_dir.path());
+
+        let mut registry = ToolRegistry::new();
+        registry.register(FnTool::new("Buggy", "Always panics", json!({}), vec![], |input| async move {
+            let count = input["count"].as_u64().expect("no value");
+            Ok(ToolResult::success(count.to_string()))
+        }));
+
+        let result = registry.execute("Buggy", &json!({}), &mut internals).await.unwrap();
+        assert!(result.is_error);
+        assert!(matches!(result.content, ToolResultData::Text(ref t) if t.contains("no value")));
+    }
+
+    #[tokio::test]
+    async fn test_result_cache() {
+        use crate::tools::{FnTool, ToolResultData};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (mut internals, _, _) = test_internals(temp_dir.path());
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let mut registry = ToolRegistry::new().with_result_cache(Duration::from_secs(60));
+        registry.register(
+            FnTool::new("Count", "Count calls", json!({}), vec![], move |_| {
+                let n = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                async move { Ok(ToolResult::success(n.to_string())) }
+            })
+            .with_read_only(true)
+            .with_cacheable(true),
+        );
+        registry.register(FnTool::new("Write", "Write", json!({}), vec![], |_| async {
+            Ok(ToolResult::success(""))
+        }));
+
+        let text = |result: ToolResult| match result.content {
+            ToolResultData::Text(t) => t,
+            other => panic!("unexpected content: {:?}", other),
+        };
+        let input = json!({"a": 1, "b": 2});
+        assert_eq!(text(registry.execute("Count", &input, &mut internals).await.unwrap()), "1");
+        // Same input (key order aside) reuses the result
+        let reordered = json!({"b": 2, "a": 1});
+        assert_eq!(text(registry.execute("Count", &reordered, &mut internals).await.unwrap()), "1");
+        assert_eq!(text(registry.execute("Count", &json!({}), &mut internals).await.unwrap()), "2");
+
+        // A tool that may change state invalidates the cache
+        registry.execute("Write", &json!({}), &mut internals).await.unwrap();
+        assert_eq!(text(registry.execute("Count", &input, &mut internals).await.unwrap()), "3");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
 }
@@ -25,13 +25,66 @@ pub enum ToolResultData {
     },
 }
 
+impl Default for ToolResultData {
+    fn default() -> Self {
+        ToolResultData::Text(String::new())
+    }
+}
+
+/// A tool call the model may want to make next
+///
+/// Only a hint: it is shown to the model with the tool result and never
+/// executed by the agent.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SuggestedToolCall {
+    /// Name of the tool to call
+    pub tool: String,
+    /// Input for the call
+    pub input: Value,
+    /// Why this call is a good next step (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl SuggestedToolCall {
+    /// Suggest calling `tool` with `input`
+    pub fn new(tool: impl Into<String>, input: Value) -> Self {
+        Self {
+            tool: tool.into(),
+            input,
+            reason: None,
+        }
+    }
+
+    /// Explain why the call is suggested
+    pub fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+}
+
 /// Result of executing a tool
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Build one with a constructor such as [`success`](Self::success) and the
+/// `with_*` setters; the default is an empty successful text result.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ToolResult {
     /// The content of the tool result
     pub content: ToolResultData,
     /// Whether the tool execution resulted in an error
     pub is_error: bool,
+    /// Whether the content was truncated by the tool
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    /// Length in bytes of the full output before truncation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_len: Option<usize>,
+    /// Tool calls suggested as next steps, shown to the model as a hint
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<SuggestedToolCall>,
+    /// Exit code of the process the tool ran, if any (e.g. Bash)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
 }
 
 impl ToolResult {
@@ -39,7 +92,7 @@ impl ToolResult {
     pub fn success(output: impl Into<String>) -> Self {
         Self {
             content: ToolResultData::Text(output.into()),
-            is_error: false,
+            ..Default::default()
         }
     }
 
@@ -48,7 +101,97 @@ impl ToolResult {
         Self {
             content: ToolResultData::Text(message.into()),
             is_error: true,
+            ..Default::default()
+        }
+    }
+
+    /// Create a successful text result whose output was truncated
+    ///
+    /// `original_len` is the length in bytes of the full output, which is
+    /// reported to the model so it can decide whether to fetch more.
+    pub fn success_with_truncation(output: impl Into<String>, original_len: usize) -> Self {
+        Self::success(output).with_truncation(original_len)
+    }
+
+    /// Mark this result as truncated from an output of `original_len` bytes
+    ///
+    /// Text content gets the [`truncation_note`](Self::truncation_note)
+    /// appended, so every consumer of the result sees the marker.
+    pub fn with_truncation(mut self, original_len: usize) -> Self {
+        self.truncated = true;
+        self.original_len = Some(original_len);
+        let note = self.truncation_note();
+        if let (ToolResultData::Text(text), Some(note)) = (&mut self.content, note) {
+            text.push('\n');
+            text.push_str(&note);
+        }
+        self
+    }
+
+    /// Replace the content of this result
+    pub fn with_content(mut self, content: ToolResultData) -> Self {
+        self.content = content;
+        self
+    }
+
+    /// Set whether this result is an error
+    pub fn with_error(mut self, is_error: bool) -> Self {
+        self.is_error = is_error;
+        self
+    }
+
+    /// Record the exit code of the process the tool ran
+    pub fn with_exit_code(mut self, exit_code: i32) -> Self {
+        self.exit_code = Some(exit_code);
+        self
+    }
+
+    /// Note telling the model that the output was truncated, if it was
+    pub fn truncation_note(&self) -> Option<String> {
+        if !self.truncated {
+            return None;
         }
+        Some(match self.original_len {
+            Some(len) => format!("[truncated; full output was {} bytes]", len),
+            None => "[truncated]".to_string(),
+        })
+    }
+
+    /// Suggest a tool call for the model to consider next
+    ///
+    /// Can be called multiple times. Suggestions are appended to text
+    /// results; the model decides whether to follow them.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// ToolResult::success(plan).with_suggestion(
+    ///     SuggestedToolCall::new("Read", json!({ "file_path": "src/lib.rs" }))
+    ///         .with_reason("defines the public API the plan changes"),
+    /// )
+    /// ```
+    pub fn with_suggestion(mut self, suggestion: SuggestedToolCall) -> Self {
+        self.suggestions.push(suggestion);
+        self
+    }
+
+    /// Block listing the suggested tool calls, if there are any
+    pub fn suggestions_note(&self) -> Option<String> {
+        if self.suggestions.is_empty() {
+            return None;
+        }
+        let calls: Vec<String> = self
+            .suggestions
+            .iter()
+            .map(|call| match call.reason {
+                Some(ref reason) => format!("- {} {} ({})", call.tool, call.input, reason),
+                None => format!("- {} {}", call.tool, call.input),
+            })
+            .collect();
+        Some(format!(
+            "<suggested-tool-calls>\nThe tool suggests these next steps. Use them only if they help:\n{}\n</suggested-tool-calls>",
+            calls.join("\n")
+        ))
     }
 
     /// Create a successful image result
@@ -58,7 +201,7 @@ impl ToolResult {
                 data,
                 media_type: media_type.into(),
             },
-            is_error: false,
+            ..Default::default()
         }
     }
 
@@ -74,7 +217,7 @@ impl ToolResult {
                 media_type: media_type.into(),
                 description: description.into(),
             },
-            is_error: false,
+            ..Default::default()
         }
     }
 }
@@ -121,6 +264,25 @@ pub trait Tool: Send + Sync {
     fn requires_permission(&self) -> bool {
         true
     }
+
+    /// Check if this tool only reads state
+    ///
+    /// Read-only tools do not modify files, run commands or change anything
+    /// outside the agent's own session. Unlike `requires_permission`, this is
+    /// a property of the tool rather than a policy, so features such as plan
+    /// mode can rely on it. Default is false.
+    fn is_read_only(&self) -> bool {
+        false
+    }
+
+    /// Check if this tool's results may be reused for identical input
+    ///
+    /// Only consulted when the registry has a result cache
+    /// (`ToolRegistry::with_result_cache`). Return true only for
+    /// deterministic, read-only tools. Default is false.
+    fn is_cacheable(&self) -> bool {
+        false
+    }
 }
 
 #[cfg(test)]
@@ -147,6 +309,56 @@ mod tests {
         assert!(result.is_error);
     }
 
+    #[test]
+    fn test_tool_result_truncation() {
+        let result = ToolResult::success("partial");
+        assert!(!result.truncated);
+        assert!(result.truncation_note().is_none());
+
+        let result = ToolResult::success_with_truncation("partial", 50_000);
+        assert!(result.truncated);
+        assert_eq!(result.original_len, Some(50_000));
+        assert_eq!(
+            result.truncation_note().as_deref(),
+            Some("[truncated; full output was 50000 bytes]")
+        );
+        match result.content {
+            ToolResultData::Text(text) => assert_eq!(text, "partial\n[truncated; full output was 50000 bytes]"),
+            _ => panic!("Expected text content"),
+        }
+    }
+
+    #[test]
+    fn test_tool_result_builders() {
+        let result = ToolResult::default()
+            .with_content(ToolResultData::Text("exit 2".into()))
+            .with_error(true)
+            .with_exit_code(2);
+        assert!(result.is_error);
+        assert_eq!(result.exit_code, Some(2));
+        assert!(!result.truncated);
+        assert!(result.suggestions.is_empty());
+    }
+
+    #[test]
+    fn test_tool_result_suggestions() {
+        let result = ToolResult::success("plan");
+        assert!(result.suggestions_note().is_none());
+
+        let result = ToolResult::success("plan")
+            .with_suggestion(
+                SuggestedToolCall::new("Read", serde_json::json!({"file_path": "src/lib.rs"}))
+                    .with_reason("entry point"),
+            )
+            .with_suggestion(SuggestedToolCall::new("Glob", serde_json::json!({"pattern": "*.rs"})));
+        assert_eq!(
+            result.suggestions_note().unwrap(),
+            "<suggested-tool-calls>\nThe tool suggests these next steps. Use them only if they help:\n\
+             - Read {\"file_path\":\"src/lib.rs\"} (entry point)\n\
+             - Glob {\"pattern\":\"*.rs\"}\n</suggested-tool-calls>"
+        );
+    }
+
     #[test]
     fn test_tool_result_image() {
         let data = vec![1, 2, 3, 4];